@@ -0,0 +1,161 @@
+//! Measures the throughput of the tokenizer and of the parser building the
+//! syntax tree over a large generated source, on one thread and on several
+//! at once, as the parallel parse workers run them.
+//!
+//! Run with `cargo bench -p shigure-parser --bench tokenizer`. To compare two
+//! versions, save the results of one as a named baseline and pass the name
+//! when running the other:
+//!
+//! ```text
+//! git switch main
+//! cargo bench -p shigure-parser --bench tokenizer -- --save-baseline main
+//! git switch -
+//! cargo bench -p shigure-parser --bench tokenizer -- --baseline main
+//! ```
+
+use std::{collections::HashMap, env, fs, hint::black_box, path::PathBuf, thread, time::Instant};
+
+use shigure_parser::lang::{
+    ast::TokenContent,
+    parser::{module::ModuleParser, Parser},
+    tokenizer::Tokenizer,
+};
+
+const MODULE: &str = "import { Button as B, Link } from \"./button\"
+use std::list as l
+
+type Shape<T> = Circle(T) | Rect({ width: T, height: T })
+
+effect Log {
+  fn log(message: String) -> ()
+}
+
+pub fn area(shape: Shape<Float>, scale: Float) -> Float emits Log {
+  log(\"computing the area of a shape\")
+  if is_circle(shape) { 3.14159 * shape.radius * shape.radius } else { shape.width * scale }
+}
+
+pub view Counter(start: Int, label: String) {
+  let count: Int = start
+  effect { log(label) }
+  count = count + 1
+  <div#root class=\"counter\" aria-label=\"counter\">
+    <button#increment onclick={increment} disabled>\"Add one\" {count}</button>
+  </div>
+}
+";
+
+const COPIES: usize = 2_000;
+const RUNS: usize = 10;
+const THREADS: usize = 4;
+
+/// A benchmark run over a source, returning the number of things it produced.
+type Run = fn(&str) -> usize;
+
+/// Tokenizes `source`, reading the text of every identifier, and returns the
+/// number of tokens.
+fn tokenize(source: &str) -> usize {
+    let mut tokens = 0;
+    for token in Tokenizer::new(source) {
+        let token = token.expect("failed to tokenize");
+        if let TokenContent::Identifier(name) = &token.con {
+            black_box(name.as_str());
+        }
+        black_box(token);
+        tokens += 1;
+    }
+    tokens
+}
+
+/// Parses `source` with its syntax tree, and returns the number of items.
+fn parse(source: &str) -> usize {
+    let mut parser = ModuleParser::from_str(source);
+    let module = parser.parse_all().expect("failed to parse");
+    black_box(parser.syntax());
+    module.nodes.len()
+}
+
+/// Returns the best of [`RUNS`] runs of `run` over `source` on `threads`
+/// threads, each running it over the whole source, in seconds, and the
+/// count `run` returns.
+fn measure(source: &str, threads: usize, run: Run) -> (f64, usize) {
+    let mut count = 0;
+    let mut best = f64::MAX;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        count = thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads).map(|_| scope.spawn(|| run(source))).collect();
+            workers.into_iter().map(|w| w.join().unwrap()).sum()
+        });
+        best = best.min(start.elapsed().as_secs_f64());
+    }
+    (best, count)
+}
+
+/// The file a baseline named `name` is saved in.
+fn baseline_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("tokenizer-{name}.txt"))
+}
+
+/// Reads the best times of the baseline `name`, by benchmark.
+fn load_baseline(name: &str) -> HashMap<String, f64> {
+    let path = baseline_path(name);
+    let text = fs::read_to_string(&path)
+        .unwrap_or_else(|err| panic!("cannot read baseline {}: {}", path.display(), err));
+    text.lines()
+        .filter_map(|line| {
+            let (bench, best) = line.rsplit_once('\t')?;
+            Some((bench.to_string(), best.parse().ok()?))
+        })
+        .collect()
+}
+
+fn main() {
+    let mut save = None;
+    let mut baseline = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--save-baseline" => save = args.next(),
+            "--baseline" => baseline = args.next().map(|name| load_baseline(&name)),
+            _ => {}
+        }
+    }
+
+    let source = MODULE.repeat(COPIES);
+    let mib = source.len() as f64 / (1024.0 * 1024.0);
+    let benches: [(&str, Run, &str); 2] =
+        [("tokenize", tokenize, "tokens"), ("parse", parse, "items")];
+    let mut saved = String::new();
+    for (name, run, unit) in benches {
+        for threads in [1, THREADS] {
+            let bench = format!("{name}, {threads} threads");
+            let (best, count) = measure(&source, threads, run);
+            let total = mib * threads as f64;
+            print!(
+                "{}: {} {} in {:.2} MiB, best of {} runs {:.2} ms ({:.1} MiB/s)",
+                bench,
+                count,
+                unit,
+                total,
+                RUNS,
+                best * 1000.0,
+                total / best,
+            );
+            match baseline.as_ref().and_then(|baseline| baseline.get(&bench)) {
+                Some(base) => println!(
+                    ", {:+.1}% against the baseline",
+                    (best / base - 1.0) * 100.0
+                ),
+                None => println!(),
+            }
+            saved.push_str(&format!("{bench}\t{best}\n"));
+        }
+    }
+
+    if let Some(name) = save {
+        let path = baseline_path(&name);
+        fs::write(&path, saved)
+            .unwrap_or_else(|err| panic!("cannot save baseline {}: {}", path.display(), err));
+    }
+}
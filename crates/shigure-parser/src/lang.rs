@@ -1,3 +1,22 @@
 pub mod ast;
+pub mod config;
+pub mod consteval;
+pub mod diagnostic;
+pub mod effects;
+pub mod format;
+pub mod html;
+pub mod lint;
+pub mod modules;
+pub mod mono;
+pub mod node_index;
 pub mod parser;
+pub mod project;
+pub mod reactivity;
+pub mod resolve;
+#[cfg(feature = "serde")]
+pub mod schema;
+pub mod symbol;
+pub mod syntax;
 pub mod tokenizer;
+pub mod typecheck;
+pub mod visit;
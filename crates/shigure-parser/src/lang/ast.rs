@@ -1,42 +1,1276 @@
-/// A location information for AST nodes.
+//! The abstract syntax tree built by the parsers.
+//!
+//! Every node has a public `new` constructor taking its location followed by
+//! its fields, so tools such as code generators can build trees without the
+//! parser. Nodes which do not come from source text use
+//! [`ASTLoc::default()`]. The location of a node is read through [`Spanned`];
+//! all of its other fields are public.
+//!
+//! The parameters of a constructor follow the order of the fields of the
+//! node, except for the fields of items most declarations leave out: the
+//! attributes, visibility and generic parameters of an item and whether a
+//! function is `const` start out empty, private or `false`, and are set with
+//! the `with_*` methods of the item. Node structs are `#[non_exhaustive]`, so
+//! they are only built through their constructors and only matched with
+//! `..`. A field added with a default and a `with_*` method therefore breaks
+//! no caller, while a new required field, node or variant still does.
+//!
+//! ```
+//! use shigure_parser::lang::{
+//!     ast::{
+//!         ASTExprKind, ASTItemConst, ASTLoc, ASTNodeExpr, ASTNodeModule, ASTNodeScoped,
+//!         ASTVisibility, Spanned, TokenLiteral,
+//!     },
+//!     parser::{module::ModuleParser, Parser},
+//!     symbol::Symbol,
+//! };
+//!
+//! let value = ASTNodeExpr::new(
+//!     ASTLoc::default(),
+//!     ASTExprKind::Literal(TokenLiteral::NumberLiteral(Symbol::intern("1"))),
+//! );
+//! let item = ASTItemConst::new(ASTLoc::default(), "ONE".to_string(), None, value)
+//!     .with_visibility(ASTVisibility::Public);
+//! let mut module = ASTNodeModule::new(ASTLoc::default(), String::new(), Vec::new());
+//! module.push(ASTNodeScoped::Const(item));
+//!
+//! let parsed = ModuleParser::from_str("pub const ONE = 1").parse_all().unwrap();
+//! assert_eq!(parsed.nodes[0].loc(), ASTLoc { start: 0, end: 17 });
+//! assert_eq!(parsed.nodes[0].name(), module.nodes[0].name());
+//! ```
+
+use crate::lang::symbol::Symbol;
+
+/// A location information for AST nodes, as a range of byte offsets into the
+/// source.
+///
+/// The default location is the empty range at the start of the source, for
+/// nodes which are synthesized rather than parsed.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ASTLoc {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl From<TokenLoc> for ASTLoc {
+    fn from(loc: TokenLoc) -> Self {
+        ASTLoc {
+            start: loc.starts_at,
+            end: loc.starts_at + loc.len,
+        }
+    }
+}
+
+impl ASTLoc {
+    /// Returns the smallest location covering both `self` and `other`.
+    pub fn to(&self, other: ASTLoc) -> ASTLoc {
+        ASTLoc {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+}
+
+/// A node that has [`ASTLoc`] in own member.
+pub trait Spanned {
+    fn loc(&self) -> ASTLoc;
+}
+
+/// An attribute of a view element, e.g. `href="/"`, `value={count}` or a bare
+/// `disabled`.
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ASTNodeViewAttribute {
+    loc: ASTLoc,
+    pub name: String,
+    pub value: Option<ASTNodeExpr>,
+}
+
+impl ASTNodeViewAttribute {
+    pub fn new(loc: ASTLoc, name: String, value: Option<ASTNodeExpr>) -> Self {
+        Self { loc, name, value }
+    }
+}
+
+impl Spanned for ASTNodeViewAttribute {
+    fn loc(&self) -> ASTLoc {
+        self.loc
+    }
+}
+
+/// Content placed between the tags of a view element.
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ASTNodeViewChild {
+    Element(ASTNodeViewElement),
+    /// `"text"`
+    Text(ASTNodeExpr),
+    /// `{expression}`
+    Expr(ASTNodeExpr),
+}
+
+impl Spanned for ASTNodeViewChild {
+    fn loc(&self) -> ASTLoc {
+        match self {
+            ASTNodeViewChild::Element(e) => e.loc(),
+            ASTNodeViewChild::Text(e) => e.loc(),
+            ASTNodeViewChild::Expr(e) => e.loc(),
+        }
+    }
+}
+
+/// `<Element#anchor name="value">children</Element>`
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ASTNodeViewElement {
+    loc: ASTLoc,
+    pub name: String,
+    pub anchor: Option<String>,
+    pub attributes: Vec<ASTNodeViewAttribute>,
+    pub children: Vec<ASTNodeViewChild>,
+}
+
+impl ASTNodeViewElement {
+    pub fn new(
+        loc: ASTLoc,
+        name: String,
+        anchor: Option<String>,
+        attributes: Vec<ASTNodeViewAttribute>,
+        children: Vec<ASTNodeViewChild>,
+    ) -> Self {
+        Self {
+            loc,
+            name,
+            anchor,
+            attributes,
+            children,
+        }
+    }
+}
+
+impl Spanned for ASTNodeViewElement {
+    fn loc(&self) -> ASTLoc {
+        self.loc
+    }
+}
+
+/// Whether an item is visible from other modules.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ASTVisibility {
+    #[default]
+    Private,
+    /// `pub`
+    Public,
+}
+
+impl ASTVisibility {
+    pub fn is_public(&self) -> bool {
+        matches!(self, ASTVisibility::Public)
+    }
+}
+
+/// `@name(arg, ...)` before a module level item, e.g. `@allow(unused)`. The
+/// arguments may be left out.
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ASTNodeItemAttribute {
+    loc: ASTLoc,
+    pub name: String,
+    pub args: Vec<ASTNodeAttributeArg>,
+}
+
+impl ASTNodeItemAttribute {
+    pub fn new(loc: ASTLoc, name: String, args: Vec<ASTNodeAttributeArg>) -> Self {
+        Self { loc, name, args }
+    }
+}
+
+impl Spanned for ASTNodeItemAttribute {
+    fn loc(&self) -> ASTLoc {
+        self.loc
+    }
+}
+
+/// A name passed to an [`ASTNodeItemAttribute`], e.g. `unused`.
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ASTNodeAttributeArg {
+    loc: ASTLoc,
+    pub name: String,
+}
+
+impl ASTNodeAttributeArg {
+    pub fn new(loc: ASTLoc, name: String) -> Self {
+        Self { loc, name }
+    }
+}
+
+impl Spanned for ASTNodeAttributeArg {
+    fn loc(&self) -> ASTLoc {
+        self.loc
+    }
+}
+
+/// `const NAME: Type = value`, at module level or in a block.
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ASTItemConst {
+    loc: ASTLoc,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub attributes: Vec<ASTNodeItemAttribute>,
+    pub visibility: ASTVisibility,
+    pub name: String,
+    pub ty: Option<ASTNodeType>,
+    pub value: ASTNodeExpr,
+}
+
+impl ASTItemConst {
+    pub fn new(loc: ASTLoc, name: String, ty: Option<ASTNodeType>, value: ASTNodeExpr) -> Self {
+        Self {
+            loc,
+            attributes: Vec::new(),
+            visibility: ASTVisibility::default(),
+            name,
+            ty,
+            value,
+        }
+    }
+
+    pub fn with_attributes(mut self, attributes: Vec<ASTNodeItemAttribute>) -> Self {
+        self.attributes = attributes;
+        self
+    }
+
+    pub fn with_visibility(mut self, visibility: ASTVisibility) -> Self {
+        self.visibility = visibility;
+        self
+    }
+}
+
+impl Spanned for ASTItemConst {
+    fn loc(&self) -> ASTLoc {
+        self.loc
+    }
+}
+
+/// A parameter of a function, handler operation or view, e.g. `msg: String`.
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ASTNodeParam {
+    loc: ASTLoc,
+    pub name: String,
+    pub ty: Option<ASTNodeType>,
+}
+
+impl ASTNodeParam {
+    pub fn new(loc: ASTLoc, name: String, ty: Option<ASTNodeType>) -> Self {
+        Self { loc, name, ty }
+    }
+}
+
+impl Spanned for ASTNodeParam {
+    fn loc(&self) -> ASTLoc {
+        self.loc
+    }
+}
+
+/// `view Name<T>(props) { body }`
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ASTItemView {
+    loc: ASTLoc,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub attributes: Vec<ASTNodeItemAttribute>,
+    pub visibility: ASTVisibility,
+    pub name: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub type_params: Vec<ASTNodeTypeParam>,
+    pub params: Vec<ASTNodeParam>,
+    pub body: ASTNodeBlock,
+}
+
+impl ASTItemView {
+    pub fn new(loc: ASTLoc, name: String, params: Vec<ASTNodeParam>, body: ASTNodeBlock) -> Self {
+        Self {
+            loc,
+            attributes: Vec::new(),
+            visibility: ASTVisibility::default(),
+            name,
+            type_params: Vec::new(),
+            params,
+            body,
+        }
+    }
+
+    pub fn with_attributes(mut self, attributes: Vec<ASTNodeItemAttribute>) -> Self {
+        self.attributes = attributes;
+        self
+    }
+
+    pub fn with_visibility(mut self, visibility: ASTVisibility) -> Self {
+        self.visibility = visibility;
+        self
+    }
+
+    pub fn with_type_params(mut self, type_params: Vec<ASTNodeTypeParam>) -> Self {
+        self.type_params = type_params;
+        self
+    }
+}
+
+impl Spanned for ASTItemView {
+    fn loc(&self) -> ASTLoc {
+        self.loc
+    }
+}
+
+/// `fn name<T>(params) -> Ret emits Effect { body }`
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ASTItemFn {
+    loc: ASTLoc,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub attributes: Vec<ASTNodeItemAttribute>,
+    pub visibility: ASTVisibility,
+    /// Whether it is a `const fn`, which constants may call.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub is_const: bool,
+    pub name: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub type_params: Vec<ASTNodeTypeParam>,
+    pub params: Vec<ASTNodeParam>,
+    pub ret: Option<ASTNodeType>,
+    /// The effect row listed after `emits`.
+    pub effects: Vec<ASTNodeType>,
+    pub body: ASTNodeBlock,
+}
+
+impl ASTItemFn {
+    /// Unlike the other constructors, takes the parameters, return type and
+    /// effect row together as the signature following the name.
+    pub fn new(
+        loc: ASTLoc,
+        name: String,
+        signature: ASTNodeFnSignature,
+        body: ASTNodeBlock,
+    ) -> Self {
+        Self {
+            loc,
+            attributes: Vec::new(),
+            visibility: ASTVisibility::default(),
+            is_const: false,
+            name,
+            type_params: Vec::new(),
+            params: signature.params,
+            ret: signature.ret,
+            effects: signature.effects,
+            body,
+        }
+    }
+
+    pub fn with_attributes(mut self, attributes: Vec<ASTNodeItemAttribute>) -> Self {
+        self.attributes = attributes;
+        self
+    }
+
+    pub fn with_visibility(mut self, visibility: ASTVisibility) -> Self {
+        self.visibility = visibility;
+        self
+    }
+
+    pub fn with_const(mut self, is_const: bool) -> Self {
+        self.is_const = is_const;
+        self
+    }
+
+    pub fn with_type_params(mut self, type_params: Vec<ASTNodeTypeParam>) -> Self {
+        self.type_params = type_params;
+        self
+    }
+}
+
+impl Spanned for ASTItemFn {
+    fn loc(&self) -> ASTLoc {
+        self.loc
+    }
+}
+
+/// The parts of a function declaration following its name.
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ASTNodeFnSignature {
+    loc: ASTLoc,
+    pub params: Vec<ASTNodeParam>,
+    pub ret: Option<ASTNodeType>,
+    pub effects: Vec<ASTNodeType>,
+}
+
+impl ASTNodeFnSignature {
+    pub fn new(
+        loc: ASTLoc,
+        params: Vec<ASTNodeParam>,
+        ret: Option<ASTNodeType>,
+        effects: Vec<ASTNodeType>,
+    ) -> Self {
+        Self {
+            loc,
+            params,
+            ret,
+            effects,
+        }
+    }
+}
+
+impl Spanned for ASTNodeFnSignature {
+    fn loc(&self) -> ASTLoc {
+        self.loc
+    }
+}
+
+/// An operation of an effect, e.g. `fn log(msg: String)`.
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ASTNodeEffectOp {
+    loc: ASTLoc,
+    pub name: String,
+    pub params: Vec<ASTNodeParam>,
+    pub ret: Option<ASTNodeType>,
+}
+
+impl ASTNodeEffectOp {
+    pub fn new(
+        loc: ASTLoc,
+        name: String,
+        params: Vec<ASTNodeParam>,
+        ret: Option<ASTNodeType>,
+    ) -> Self {
+        Self {
+            loc,
+            name,
+            params,
+            ret,
+        }
+    }
+}
+
+impl Spanned for ASTNodeEffectOp {
+    fn loc(&self) -> ASTLoc {
+        self.loc
+    }
+}
+
+/// `effect Log { fn log(msg: String) }`
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ASTItemEffect {
+    loc: ASTLoc,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub attributes: Vec<ASTNodeItemAttribute>,
+    pub visibility: ASTVisibility,
+    pub name: String,
+    pub ops: Vec<ASTNodeEffectOp>,
+}
+
+impl ASTItemEffect {
+    pub fn new(loc: ASTLoc, name: String, ops: Vec<ASTNodeEffectOp>) -> Self {
+        Self {
+            loc,
+            attributes: Vec::new(),
+            visibility: ASTVisibility::default(),
+            name,
+            ops,
+        }
+    }
+
+    pub fn with_attributes(mut self, attributes: Vec<ASTNodeItemAttribute>) -> Self {
+        self.attributes = attributes;
+        self
+    }
+
+    pub fn with_visibility(mut self, visibility: ASTVisibility) -> Self {
+        self.visibility = visibility;
+        self
+    }
+}
+
+impl Spanned for ASTItemEffect {
+    fn loc(&self) -> ASTLoc {
+        self.loc
+    }
+}
+
+/// An implementation of an effect operation inside a `with` block.
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ASTNodeHandlerOp {
+    loc: ASTLoc,
+    pub name: String,
+    pub params: Vec<ASTNodeParam>,
+    pub body: ASTNodeBlock,
+}
+
+impl ASTNodeHandlerOp {
+    pub fn new(loc: ASTLoc, name: String, params: Vec<ASTNodeParam>, body: ASTNodeBlock) -> Self {
+        Self {
+            loc,
+            name,
+            params,
+            body,
+        }
+    }
+}
+
+impl Spanned for ASTNodeHandlerOp {
+    fn loc(&self) -> ASTLoc {
+        self.loc
+    }
+}
+
+/// `with Log { fn log(msg: String) { .. } }` installs a handler for the
+/// rest of the enclosing block.
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ASTNodeWith {
+    loc: ASTLoc,
+    pub effect: ASTNodeType,
+    pub ops: Vec<ASTNodeHandlerOp>,
+}
+
+impl ASTNodeWith {
+    pub fn new(loc: ASTLoc, effect: ASTNodeType, ops: Vec<ASTNodeHandlerOp>) -> Self {
+        Self { loc, effect, ops }
+    }
+}
+
+impl Spanned for ASTNodeWith {
+    fn loc(&self) -> ASTLoc {
+        self.loc
+    }
+}
+
+/// `effect { .. }` in a view body runs its block again whenever the state it
+/// reads changes.
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ASTNodeEffectBlock {
+    loc: ASTLoc,
+    pub body: ASTNodeBlock,
+}
+
+impl ASTNodeEffectBlock {
+    pub fn new(loc: ASTLoc, body: ASTNodeBlock) -> Self {
+        Self { loc, body }
+    }
+}
+
+impl Spanned for ASTNodeEffectBlock {
+    fn loc(&self) -> ASTLoc {
+        self.loc
+    }
+}
+
+/// `target = value`, where the target is a name or a member of one.
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ASTNodeAssign {
+    loc: ASTLoc,
+    pub target: ASTNodeExpr,
+    pub value: ASTNodeExpr,
+}
+
+impl ASTNodeAssign {
+    pub fn new(loc: ASTLoc, target: ASTNodeExpr, value: ASTNodeExpr) -> Self {
+        Self { loc, target, value }
+    }
+
+    /// Returns the name of the binding the assignment writes to.
+    pub fn target_name(&self) -> Option<&str> {
+        let mut target = &self.target;
+        loop {
+            match &target.kind {
+                ASTExprKind::Identifier(name) => return Some(name),
+                ASTExprKind::Member { object, .. } => target = object,
+                _ => return None,
+            }
+        }
+    }
+}
+
+impl Spanned for ASTNodeAssign {
+    fn loc(&self) -> ASTLoc {
+        self.loc
+    }
+}
+
+/// `let name: Type = value`. In a view body, `let` declares reactive state.
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ASTNodeLet {
+    loc: ASTLoc,
+    pub name: String,
+    pub ty: Option<ASTNodeType>,
+    pub value: ASTNodeExpr,
+}
+
+impl ASTNodeLet {
+    pub fn new(loc: ASTLoc, name: String, ty: Option<ASTNodeType>, value: ASTNodeExpr) -> Self {
+        Self {
+            loc,
+            name,
+            ty,
+            value,
+        }
+    }
+}
+
+impl Spanned for ASTNodeLet {
+    fn loc(&self) -> ASTLoc {
+        self.loc
+    }
+}
+
+/// A statement of a block.
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ASTNodeStmt {
+    Let(ASTNodeLet),
+    Const(ASTItemConst),
+    With(ASTNodeWith),
+    Effect(ASTNodeEffectBlock),
+    Assign(ASTNodeAssign),
+    Element(ASTNodeViewElement),
+    Expr(ASTNodeExpr),
+}
+
+impl Spanned for ASTNodeStmt {
+    fn loc(&self) -> ASTLoc {
+        match self {
+            ASTNodeStmt::Let(s) => s.loc(),
+            ASTNodeStmt::Const(s) => s.loc(),
+            ASTNodeStmt::With(s) => s.loc(),
+            ASTNodeStmt::Effect(s) => s.loc(),
+            ASTNodeStmt::Assign(s) => s.loc(),
+            ASTNodeStmt::Element(s) => s.loc(),
+            ASTNodeStmt::Expr(s) => s.loc(),
+        }
+    }
+}
+
+/// `{ statements }`
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ASTNodeBlock {
+    loc: ASTLoc,
+    pub stmts: Vec<ASTNodeStmt>,
+}
+
+impl ASTNodeBlock {
+    pub fn new(loc: ASTLoc, stmts: Vec<ASTNodeStmt>) -> Self {
+        Self { loc, stmts }
+    }
+}
+
+impl Spanned for ASTNodeBlock {
+    fn loc(&self) -> ASTLoc {
+        self.loc
+    }
+}
+
 #[derive(Eq, PartialEq, Clone, Copy, Debug)]
-pub struct ASTLoc {
-    pub start: u32,
-    pub end: u32,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ASTBinaryOp {
+    /// `+`
+    Add,
+    /// `-`
+    Sub,
+    /// `*`
+    Mul,
+    /// `/`
+    Div,
+    /// `%`
+    Rem,
+    /// `==`
+    Eq,
+    /// `!=`
+    NotEq,
+    /// `<`
+    Lt,
+    /// `<=`
+    LtEq,
+    /// `>`
+    Gt,
+    /// `>=`
+    GtEq,
+    /// `&&`
+    And,
+    /// `||`
+    Or,
 }
 
-/// A node that has [`ASTLoc`] in own member.
-pub(crate) trait ASTHasLoc {
-    fn loc(&self) -> ASTLoc;
+impl ASTBinaryOp {
+    /// The binding power of the operator; a higher precedence binds tighter.
+    /// All binary operators are left associative.
+    pub fn precedence(self) -> u8 {
+        match self {
+            ASTBinaryOp::Or => 1,
+            ASTBinaryOp::And => 2,
+            ASTBinaryOp::Eq | ASTBinaryOp::NotEq => 3,
+            ASTBinaryOp::Lt | ASTBinaryOp::LtEq | ASTBinaryOp::Gt | ASTBinaryOp::GtEq => 4,
+            ASTBinaryOp::Add | ASTBinaryOp::Sub => 5,
+            ASTBinaryOp::Mul | ASTBinaryOp::Div | ASTBinaryOp::Rem => 6,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ASTBinaryOp::Add => "+",
+            ASTBinaryOp::Sub => "-",
+            ASTBinaryOp::Mul => "*",
+            ASTBinaryOp::Div => "/",
+            ASTBinaryOp::Rem => "%",
+            ASTBinaryOp::Eq => "==",
+            ASTBinaryOp::NotEq => "!=",
+            ASTBinaryOp::Lt => "<",
+            ASTBinaryOp::LtEq => "<=",
+            ASTBinaryOp::Gt => ">",
+            ASTBinaryOp::GtEq => ">=",
+            ASTBinaryOp::And => "&&",
+            ASTBinaryOp::Or => "||",
+        }
+    }
 }
 
-pub struct ASTNodeViewElement {
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ASTUnaryOp {
+    /// `-`
+    Neg,
+    /// `!`
+    Not,
+}
+
+impl ASTUnaryOp {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ASTUnaryOp::Neg => "-",
+            ASTUnaryOp::Not => "!",
+        }
+    }
+}
+
+/// A field of a record literal, e.g. `x: 1` in `{ x: 1 }`.
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ASTNodeRecordField {
     loc: ASTLoc,
+    pub name: String,
+    pub value: ASTNodeExpr,
+}
+
+impl ASTNodeRecordField {
+    pub fn new(loc: ASTLoc, name: String, value: ASTNodeExpr) -> Self {
+        Self { loc, name, value }
+    }
 }
 
-impl ASTHasLoc for ASTNodeViewElement {
+impl Spanned for ASTNodeRecordField {
     fn loc(&self) -> ASTLoc {
         self.loc
     }
 }
 
+/// A pattern matched against a value by a `when` arm or a `for` loop.
 #[derive(Eq, PartialEq, Clone, Debug)]
-pub struct ASTItemConst {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ASTNodePattern {
     loc: ASTLoc,
+    pub kind: ASTPatternKind,
+}
+
+impl ASTNodePattern {
+    pub fn new(loc: ASTLoc, kind: ASTPatternKind) -> Self {
+        Self { loc, kind }
+    }
+
+    /// The names the pattern binds with their locations, in source order.
+    pub fn bindings(&self) -> Vec<(&str, ASTLoc)> {
+        let mut bindings = Vec::new();
+        let mut stack = vec![self];
+        while let Some(pattern) = stack.pop() {
+            match &pattern.kind {
+                ASTPatternKind::Binding(name) => bindings.push((name.as_str(), pattern.loc)),
+                ASTPatternKind::Variant { fields: items, .. } | ASTPatternKind::Tuple(items) => {
+                    stack.extend(items.iter().rev())
+                }
+                ASTPatternKind::Wildcard | ASTPatternKind::Literal(_) | ASTPatternKind::Nil => {}
+            }
+        }
+        bindings
+    }
 }
 
-impl ASTHasLoc for ASTItemConst {
+impl Spanned for ASTNodePattern {
     fn loc(&self) -> ASTLoc {
         self.loc
     }
 }
 
 #[derive(Eq, PartialEq, Clone, Debug)]
-pub struct ASTItemView {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ASTPatternKind {
+    /// `_`
+    Wildcard,
+    /// `name`, which matches anything and binds it. A capitalized name
+    /// without fields is a [`ASTPatternKind::Variant`] instead.
+    Binding(String),
+    /// `1`, `"text"`
+    Literal(TokenLiteral),
+    /// `nil`
+    Nil,
+    /// `Circle(r)`, `None`
+    Variant {
+        name: String,
+        fields: Vec<ASTNodePattern>,
+    },
+    /// `(a, b)`
+    Tuple(Vec<ASTNodePattern>),
+}
+
+/// `pattern -> body`, an arm of a `when` expression.
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ASTNodeWhenArm {
+    loc: ASTLoc,
+    pub pattern: ASTNodePattern,
+    pub body: ASTNodeExpr,
+}
+
+impl ASTNodeWhenArm {
+    pub fn new(loc: ASTLoc, pattern: ASTNodePattern, body: ASTNodeExpr) -> Self {
+        Self { loc, pattern, body }
+    }
+}
+
+impl Spanned for ASTNodeWhenArm {
+    fn loc(&self) -> ASTLoc {
+        self.loc
+    }
+}
+
+/// An expression.
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ASTNodeExpr {
+    loc: ASTLoc,
+    pub kind: ASTExprKind,
+}
+
+impl ASTNodeExpr {
+    pub fn new(loc: ASTLoc, kind: ASTExprKind) -> Self {
+        Self { loc, kind }
+    }
+}
+
+impl Spanned for ASTNodeExpr {
+    fn loc(&self) -> ASTLoc {
+        self.loc
+    }
+}
+
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ASTExprKind {
+    /// `1`, `1.5`, `"text"`
+    Literal(TokenLiteral),
+    /// `nil`
+    Nil,
+    /// `name`
+    Identifier(String),
+    /// `#name`, a reference to the element of the enclosing view with this
+    /// anchor.
+    Anchor(String),
+    /// `callee(args)`
+    Call {
+        callee: Box<ASTNodeExpr>,
+        args: Vec<ASTNodeExpr>,
+    },
+    /// `object.name`
+    Member {
+        object: Box<ASTNodeExpr>,
+        name: String,
+    },
+    Binary {
+        op: ASTBinaryOp,
+        lhs: Box<ASTNodeExpr>,
+        rhs: Box<ASTNodeExpr>,
+    },
+    Unary {
+        op: ASTUnaryOp,
+        expr: Box<ASTNodeExpr>,
+    },
+    /// `if cond { .. } else { .. }`; the else branch is a block or another
+    /// `if` expression.
+    If {
+        cond: Box<ASTNodeExpr>,
+        then: ASTNodeBlock,
+        els: Option<Box<ASTNodeExpr>>,
+    },
+    Block(ASTNodeBlock),
+    /// `for item in items { .. }`
+    For {
+        pattern: ASTNodePattern,
+        iter: Box<ASTNodeExpr>,
+        body: ASTNodeBlock,
+    },
+    /// `when subject { pattern -> body .. }`; the first matching arm is
+    /// taken.
+    When {
+        subject: Box<ASTNodeExpr>,
+        arms: Vec<ASTNodeWhenArm>,
+    },
+    /// `{ x: 1, y: 2 }`
+    Record(Vec<ASTNodeRecordField>),
+    /// `[1, 2, 3]`
+    List(Vec<ASTNodeExpr>),
+    /// `()`, `(1, "a")`
+    Tuple(Vec<ASTNodeExpr>),
+}
+
+/// A type expression such as `Int`, `[String]`, `fn(Int) -> Bool` or `Point?`.
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ASTNodeType {
+    loc: ASTLoc,
+    pub kind: ASTTypeKind,
+}
+
+impl ASTNodeType {
+    pub fn new(loc: ASTLoc, kind: ASTTypeKind) -> Self {
+        Self { loc, kind }
+    }
+}
+
+impl Spanned for ASTNodeType {
+    fn loc(&self) -> ASTLoc {
+        self.loc
+    }
+}
+
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ASTTypeKind {
+    /// `Point`, `Result<T, E>`
+    Named {
+        name: String,
+        args: Vec<ASTNodeType>,
+    },
+    /// `fn(Int, Int) -> Int`, `fn(String) -> () emits Log`
+    Fn {
+        params: Vec<ASTNodeType>,
+        ret: Box<ASTNodeType>,
+        effects: Vec<ASTNodeType>,
+    },
+    /// `T?`
+    Optional(Box<ASTNodeType>),
+    /// `[T]`
+    List(Box<ASTNodeType>),
+    /// `()`, `(Int, String)`
+    Tuple(Vec<ASTNodeType>),
+    /// `{ x: Float, y: Float }`
+    Record(Vec<ASTNodeTypeField>),
+}
+
+/// A named field of a record type.
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ASTNodeTypeField {
+    loc: ASTLoc,
+    pub name: String,
+    pub ty: ASTNodeType,
+}
+
+impl ASTNodeTypeField {
+    pub fn new(loc: ASTLoc, name: String, ty: ASTNodeType) -> Self {
+        Self { loc, name, ty }
+    }
+}
+
+impl Spanned for ASTNodeTypeField {
+    fn loc(&self) -> ASTLoc {
+        self.loc
+    }
+}
+
+/// A variant of a tagged union, e.g. `Circle(Float)`.
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ASTNodeTypeVariant {
+    loc: ASTLoc,
+    pub name: String,
+    pub fields: Vec<ASTNodeType>,
+}
+
+impl ASTNodeTypeVariant {
+    pub fn new(loc: ASTLoc, name: String, fields: Vec<ASTNodeType>) -> Self {
+        Self { loc, name, fields }
+    }
+}
+
+impl Spanned for ASTNodeTypeVariant {
+    fn loc(&self) -> ASTLoc {
+        self.loc
+    }
+}
+
+/// A generic parameter of a declaration, e.g. `T` in `type List<T> = ...`
+/// or `T: Num` in `fn sum<T: Num>(xs: [T]) -> T`.
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ASTNodeTypeParam {
+    loc: ASTLoc,
+    pub name: String,
+    /// The bounds listed after `:`, which every type argument must satisfy.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub bounds: Vec<ASTNodeTypeBound>,
+}
+
+impl ASTNodeTypeParam {
+    pub fn new(loc: ASTLoc, name: String, bounds: Vec<ASTNodeTypeBound>) -> Self {
+        Self { loc, name, bounds }
+    }
+}
+
+impl Spanned for ASTNodeTypeParam {
+    fn loc(&self) -> ASTLoc {
+        self.loc
+    }
+}
+
+/// A bound of an [`ASTNodeTypeParam`], e.g. `Ord` in `T: Eq + Ord`.
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ASTNodeTypeBound {
+    loc: ASTLoc,
+    pub name: String,
+}
+
+impl ASTNodeTypeBound {
+    pub fn new(loc: ASTLoc, name: String) -> Self {
+        Self { loc, name }
+    }
+}
+
+impl Spanned for ASTNodeTypeBound {
+    fn loc(&self) -> ASTLoc {
+        self.loc
+    }
+}
+
+/// The right hand side of a `type` declaration.
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ASTTypeBody {
+    /// `type Id = Int`
+    Alias(ASTNodeType),
+    /// `type Point = { x: Float, y: Float }`
+    Record(Vec<ASTNodeTypeField>),
+    /// `type Shape = Circle(Float) | Rect(Point, Point)`
+    Union(Vec<ASTNodeTypeVariant>),
+}
+
+/// `type Name<T> = ...`
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ASTItemType {
+    loc: ASTLoc,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub attributes: Vec<ASTNodeItemAttribute>,
+    pub visibility: ASTVisibility,
+    pub name: String,
+    pub params: Vec<ASTNodeTypeParam>,
+    pub body: ASTTypeBody,
+}
+
+impl ASTItemType {
+    pub fn new(loc: ASTLoc, name: String, body: ASTTypeBody) -> Self {
+        Self {
+            loc,
+            attributes: Vec::new(),
+            visibility: ASTVisibility::default(),
+            name,
+            params: Vec::new(),
+            body,
+        }
+    }
+
+    pub fn with_attributes(mut self, attributes: Vec<ASTNodeItemAttribute>) -> Self {
+        self.attributes = attributes;
+        self
+    }
+
+    pub fn with_visibility(mut self, visibility: ASTVisibility) -> Self {
+        self.visibility = visibility;
+        self
+    }
+
+    pub fn with_params(mut self, params: Vec<ASTNodeTypeParam>) -> Self {
+        self.params = params;
+        self
+    }
+}
+
+impl Spanned for ASTItemType {
+    fn loc(&self) -> ASTLoc {
+        self.loc
+    }
+}
+
+/// A name listed in an import, e.g. `B as C` in `import { B as C } from "./b"`.
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ASTNodeImportName {
+    loc: ASTLoc,
+    pub name: String,
+    pub alias: Option<String>,
+}
+
+impl ASTNodeImportName {
+    pub fn new(loc: ASTLoc, name: String, alias: Option<String>) -> Self {
+        Self { loc, name, alias }
+    }
+
+    /// The name the import binds in the importing module.
+    pub fn local_name(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.name)
+    }
+}
+
+impl Spanned for ASTNodeImportName {
+    fn loc(&self) -> ASTLoc {
+        self.loc
+    }
+}
+
+/// `import { A, B as C } from "./widgets"`
+///
+/// A `pub import` re-exports the imported names.
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ASTItemImport {
+    loc: ASTLoc,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub attributes: Vec<ASTNodeItemAttribute>,
+    pub visibility: ASTVisibility,
+    pub names: Vec<ASTNodeImportName>,
+    /// The module specifier without quotes.
+    pub source: String,
+}
+
+impl ASTItemImport {
+    pub fn new(loc: ASTLoc, names: Vec<ASTNodeImportName>, source: String) -> Self {
+        Self {
+            loc,
+            attributes: Vec::new(),
+            visibility: ASTVisibility::default(),
+            names,
+            source,
+        }
+    }
+
+    pub fn with_attributes(mut self, attributes: Vec<ASTNodeItemAttribute>) -> Self {
+        self.attributes = attributes;
+        self
+    }
+
+    pub fn with_visibility(mut self, visibility: ASTVisibility) -> Self {
+        self.visibility = visibility;
+        self
+    }
+}
+
+impl Spanned for ASTItemImport {
+    fn loc(&self) -> ASTLoc {
+        self.loc
+    }
+}
+
+/// `use std::list` or `use std::list as l`
+///
+/// A `pub use` re-exports the used module or item.
+#[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ASTItemUse {
     loc: ASTLoc,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub attributes: Vec<ASTNodeItemAttribute>,
+    pub visibility: ASTVisibility,
+    pub path: Vec<String>,
+    pub alias: Option<String>,
+}
+
+impl ASTItemUse {
+    pub fn new(loc: ASTLoc, path: Vec<String>, alias: Option<String>) -> Self {
+        Self {
+            loc,
+            attributes: Vec::new(),
+            visibility: ASTVisibility::default(),
+            path,
+            alias,
+        }
+    }
+
+    pub fn with_attributes(mut self, attributes: Vec<ASTNodeItemAttribute>) -> Self {
+        self.attributes = attributes;
+        self
+    }
+
+    pub fn with_visibility(mut self, visibility: ASTVisibility) -> Self {
+        self.visibility = visibility;
+        self
+    }
+
+    /// The name the item binds in the using module.
+    pub fn local_name(&self) -> &str {
+        match &self.alias {
+            Some(alias) => alias,
+            None => self.path.last().map(String::as_str).unwrap_or_default(),
+        }
+    }
 }
 
-impl ASTHasLoc for ASTItemView {
+impl Spanned for ASTItemUse {
     fn loc(&self) -> ASTLoc {
         self.loc
     }
@@ -44,15 +1278,66 @@ impl ASTHasLoc for ASTItemView {
 
 /// AST nodes that possibly placement in a block
 #[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ASTNodeScoped {
     Const(ASTItemConst),
+    Effect(ASTItemEffect),
+    Fn(ASTItemFn),
+    Import(ASTItemImport),
+    Type(ASTItemType),
+    Use(ASTItemUse),
     View(ASTItemView),
 }
 
-impl ASTHasLoc for ASTNodeScoped {
+impl ASTNodeScoped {
+    /// The name the node declares, if it declares one.
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            ASTNodeScoped::Const(i) => Some(&i.name),
+            ASTNodeScoped::Effect(i) => Some(&i.name),
+            ASTNodeScoped::Fn(i) => Some(&i.name),
+            ASTNodeScoped::Type(i) => Some(&i.name),
+            ASTNodeScoped::View(i) => Some(&i.name),
+            ASTNodeScoped::Import(_) | ASTNodeScoped::Use(_) => None,
+        }
+    }
+
+    pub fn visibility(&self) -> ASTVisibility {
+        match self {
+            ASTNodeScoped::Const(i) => i.visibility,
+            ASTNodeScoped::Effect(i) => i.visibility,
+            ASTNodeScoped::Fn(i) => i.visibility,
+            ASTNodeScoped::Import(i) => i.visibility,
+            ASTNodeScoped::Type(i) => i.visibility,
+            ASTNodeScoped::Use(i) => i.visibility,
+            ASTNodeScoped::View(i) => i.visibility,
+        }
+    }
+
+    /// The attributes written before the item, which its location does not
+    /// cover.
+    pub fn attributes(&self) -> &[ASTNodeItemAttribute] {
+        match self {
+            ASTNodeScoped::Const(i) => &i.attributes,
+            ASTNodeScoped::Effect(i) => &i.attributes,
+            ASTNodeScoped::Fn(i) => &i.attributes,
+            ASTNodeScoped::Import(i) => &i.attributes,
+            ASTNodeScoped::Type(i) => &i.attributes,
+            ASTNodeScoped::Use(i) => &i.attributes,
+            ASTNodeScoped::View(i) => &i.attributes,
+        }
+    }
+}
+
+impl Spanned for ASTNodeScoped {
     fn loc(&self) -> ASTLoc {
         match self {
             ASTNodeScoped::Const(i) => i.loc(),
+            ASTNodeScoped::Effect(i) => i.loc(),
+            ASTNodeScoped::Fn(i) => i.loc(),
+            ASTNodeScoped::Import(i) => i.loc(),
+            ASTNodeScoped::Type(i) => i.loc(),
+            ASTNodeScoped::Use(i) => i.loc(),
             ASTNodeScoped::View(i) => i.loc(),
         }
     }
@@ -60,47 +1345,83 @@ impl ASTHasLoc for ASTNodeScoped {
 
 /// A module node
 #[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub struct ASTNodeModule {
     loc: ASTLoc,
     pub name: String,
     pub nodes: Vec<ASTNodeScoped>,
 }
 
-impl ASTHasLoc for ASTNodeModule {
+impl ASTNodeModule {
+    pub fn new(loc: ASTLoc, name: String, nodes: Vec<ASTNodeScoped>) -> Self {
+        Self { loc, name, nodes }
+    }
+
+    /// Appends a node and extends the module location to cover it.
+    pub fn push(&mut self, node: ASTNodeScoped) {
+        self.loc = self.loc.to(node.loc());
+        self.nodes.push(node);
+    }
+}
+
+impl Spanned for ASTNodeModule {
     fn loc(&self) -> ASTLoc {
         self.loc
     }
 }
 
 #[derive(Eq, PartialEq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TokenLoc {
     pub starts_at: u32,
     pub len: u32,
 }
 
 #[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TokenLiteral {
-    NumberLiteral(String),
-    StringLiteral(String),
+    NumberLiteral(Symbol),
+    /// The literal including its quotes.
+    StringLiteral(Symbol),
 }
 
 impl TokenLiteral {
-    pub(crate) fn content(&self) -> &String {
+    /// The source text of the literal.
+    pub fn content(&self) -> &'static str {
+        match self {
+            TokenLiteral::NumberLiteral(s) => s.as_str(),
+            TokenLiteral::StringLiteral(s) => s.as_str(),
+        }
+    }
+
+    /// Returns the content of a string literal without the surrounding quotes.
+    pub(crate) fn unquoted(&self) -> &str {
+        let content = self.content();
         match self {
-            TokenLiteral::NumberLiteral(s) => s,
-            TokenLiteral::StringLiteral(s) => s,
+            TokenLiteral::StringLiteral(_) => content
+                .strip_prefix('"')
+                .and_then(|s| s.strip_suffix('"'))
+                .unwrap_or(content),
+            TokenLiteral::NumberLiteral(_) => content,
         }
     }
 }
 
 #[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TokenContent {
     /// `#anchor`
-    Anchor(String),
+    Anchor(Symbol),
     /// `variable_name, function_name, CONSTANT_VALUE, ObjectName`
-    Identifier(String),
+    Identifier(Symbol),
     /// `"hello, world", 1, 0xdeadbeef`
     Literal(TokenLiteral),
+    /// Spaces and line breaks. Whitespace and comments only appear in the
+    /// syntax tree.
+    Whitespace,
+    /// `// comment`, `/* comment */`
+    Comment,
     /// `(`
     ParenthesisLeft,
     /// `)`
@@ -121,12 +1442,53 @@ pub enum TokenContent {
     TagAngleSelfClosingRight,
     /// `>`
     TagAngleBracketRight,
+    /// `->`
+    Arrow,
+    /// `:`
+    Colon,
+    /// `::`
+    DoubleColon,
+    /// `,`
+    Comma,
+    /// `.`
+    Dot,
+    /// `?`
+    QuestionMark,
+    /// `@`
+    At,
     /// `+`
     AddOp,
     /// `=`
     AssignmentOp,
     /// `&`
     BitwiseAndOp,
+    /// `|`
+    BitwiseOrOp,
+    /// `/`
+    DivOp,
+    /// `==`
+    EqualOp,
+    /// `>=`
+    GreaterEqualOp,
+    /// `<=`
+    LessEqualOp,
+    /// `<` followed by whitespace; `<` directly followed by a name is
+    /// [`TokenContent::TagAngleBracketLeft`]
+    LessThanOp,
+    /// `&&`
+    LogicalAndOp,
+    /// `||`
+    LogicalOrOp,
+    /// `*`
+    MulOp,
+    /// `!=`
+    NotEqualOp,
+    /// `!`
+    NotOp,
+    /// `%`
+    RemOp,
+    /// `-`
+    SubOp,
     /// as
     As,
     /// `const`
@@ -147,6 +1509,8 @@ pub enum TokenContent {
     If,
     /// `import`
     Import,
+    /// `in`
+    In,
     /// `let`
     Let,
     /// `nil`
@@ -165,6 +1529,40 @@ pub enum TokenContent {
     Pub,
 }
 
+impl TokenContent {
+    /// Whether the token is whitespace or a comment.
+    pub fn is_trivia(&self) -> bool {
+        matches!(self, TokenContent::Whitespace | TokenContent::Comment)
+    }
+
+    /// The text of the token if it is a keyword, e.g. `"type"`.
+    pub fn keyword(&self) -> Option<&'static str> {
+        let keyword = match self {
+            Self::As => "as",
+            Self::Const => "const",
+            Self::Effect => "effect",
+            Self::Else => "else",
+            Self::Emits => "emits",
+            Self::FnKeyword => "fn",
+            Self::For => "for",
+            Self::FromKeyword => "from",
+            Self::If => "if",
+            Self::Import => "import",
+            Self::In => "in",
+            Self::Let => "let",
+            Self::Nil => "nil",
+            Self::Type => "type",
+            Self::Use => "use",
+            Self::View => "view",
+            Self::When => "when",
+            Self::With => "with",
+            Self::Pub => "pub",
+            _ => return None,
+        };
+        Some(keyword)
+    }
+}
+
 impl TryFrom<&str> for TokenContent {
     type Error = ();
     fn try_from(word: &str) -> Result<Self, Self::Error> {
@@ -181,6 +1579,7 @@ impl TryFrom<&str> for TokenContent {
             "from" => Ok(Self::FromKeyword),
             "if" => Ok(Self::If),
             "import" => Ok(Self::Import),
+            "in" => Ok(Self::In),
             "let" => Ok(Self::Let),
             "nil" => Ok(Self::Nil),
             "type" => Ok(Self::Type),
@@ -203,12 +1602,20 @@ impl TryFrom<char> for TokenContent {
             '{' => Ok(Self::BraceLeft),
             '}' => Ok(Self::BraceRight),
             '=' => Ok(Self::AssignmentOp),
+            ',' => Ok(Self::Comma),
+            '.' => Ok(Self::Dot),
+            '?' => Ok(Self::QuestionMark),
+            '@' => Ok(Self::At),
+            '+' => Ok(Self::AddOp),
+            '*' => Ok(Self::MulOp),
+            '%' => Ok(Self::RemOp),
             _ => Err(()),
         }
     }
 }
 
 #[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Token {
     pub loc: TokenLoc,
     pub con: TokenContent,
@@ -0,0 +1,448 @@
+use crate::lang::{format::FormatConfig, lint::LintConfig, modules::FileLoader};
+
+/// The name of the project configuration file, relative to the project root.
+pub const CONFIG_FILE: &str = "ribbon.toml";
+
+/// Project settings read from [`CONFIG_FILE`].
+///
+/// ```toml
+/// [fmt]
+/// max_width = 80
+/// indent_width = 4
+/// trailing_comma = "always"
+/// single_attribute_per_line = true
+///
+/// [lints]
+/// img-alt = "deny"
+/// ```
+///
+/// Sections which are not used by the compiler are skipped without being
+/// checked, so the file can be shared with other tools.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Config {
+    /// The `[fmt]` section.
+    pub fmt: FormatConfig,
+    /// The `[lints]` section.
+    pub lints: LintConfig,
+    /// Problems which did not stop the file from being read, e.g. a lint
+    /// which does not exist, as for the `@allow` attribute.
+    pub warnings: Vec<ConfigError>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ConfigError {
+    /// The 1-based line the error was found on, or 0 if the file could not
+    /// be read.
+    pub line: u32,
+    pub message: String,
+}
+
+impl ConfigError {
+    fn new(line: u32, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+/// A value of a `key = value` pair.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) enum ConfigValue {
+    Bool(bool),
+    Int(i64),
+    Str(String),
+}
+
+impl ConfigValue {
+    fn type_name(&self) -> &'static str {
+        match self {
+            ConfigValue::Bool(_) => "a boolean",
+            ConfigValue::Int(_) => "an integer",
+            ConfigValue::Str(_) => "a string",
+        }
+    }
+}
+
+/// A `key = value` pair with the section it appeared in.
+struct Entry {
+    line: u32,
+    section: String,
+    key: String,
+    value: ConfigValue,
+}
+
+impl Config {
+    /// Reads the configuration of the project served by `loader`. A project
+    /// without [`CONFIG_FILE`] uses the default configuration, but a
+    /// configuration file which cannot be read is an error.
+    pub fn load(loader: &dyn FileLoader) -> Result<Config, ConfigError> {
+        match loader.load(CONFIG_FILE) {
+            Ok(Some(input)) => Config::from_toml(&input),
+            Ok(None) => Ok(Config::default()),
+            Err(err) => Err(ConfigError::new(
+                0,
+                format!("cannot read `{}`: {}", CONFIG_FILE, err),
+            )),
+        }
+    }
+
+    /// Parses the content of a configuration file.
+    ///
+    /// Only the subset of TOML the settings need is supported in the
+    /// sections the compiler reads: `#` comments and `key = value` pairs whose
+    /// values are booleans, integers or basic strings. Other sections may use
+    /// any value, including arrays, tables and multi-line strings.
+    pub fn from_toml(input: &str) -> Result<Config, ConfigError> {
+        let mut config = Config::default();
+        for entry in parse_entries(input)? {
+            let res = match entry.section.as_str() {
+                "fmt" => config.fmt.set(&entry.key, &entry.value).map(|()| None),
+                "lints" => config.lints.set(&entry.key, &entry.value),
+                _ => Ok(None),
+            };
+            let warning = res.map_err(|message| ConfigError::new(entry.line, message))?;
+            if let Some(message) = warning {
+                config.warnings.push(ConfigError::new(entry.line, message));
+            }
+        }
+        Ok(config)
+    }
+}
+
+/// Returns an error message for a value of the wrong type.
+pub(crate) fn expected(key: &str, ty: &str, value: &ConfigValue) -> String {
+    format!("`{}` must be {}, found {}", key, ty, value.type_name())
+}
+
+/// Returns a non-negative integer setting, or an error message.
+pub(crate) fn expect_usize(key: &str, value: &ConfigValue) -> Result<usize, String> {
+    match value {
+        ConfigValue::Int(n) => {
+            usize::try_from(*n).map_err(|_| format!("`{key}` must not be negative"))
+        }
+        _ => Err(expected(key, "an integer", value)),
+    }
+}
+
+pub(crate) fn expect_bool(key: &str, value: &ConfigValue) -> Result<bool, String> {
+    match value {
+        ConfigValue::Bool(b) => Ok(*b),
+        _ => Err(expected(key, "a boolean", value)),
+    }
+}
+
+pub(crate) fn expect_str<'v>(key: &str, value: &'v ConfigValue) -> Result<&'v str, String> {
+    match value {
+        ConfigValue::Str(s) => Ok(s),
+        _ => Err(expected(key, "a string", value)),
+    }
+}
+
+/// Whether the compiler reads the settings of `section`.
+fn is_read(section: &str) -> bool {
+    matches!(section, "fmt" | "lints")
+}
+
+fn parse_entries(input: &str) -> Result<Vec<Entry>, ConfigError> {
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut section = String::new();
+    let mut skipped = SkippedValue::default();
+    for (idx, line) in input.lines().enumerate() {
+        let line_no = idx as u32 + 1;
+        if skipped.is_open() {
+            skipped.scan(line);
+            continue;
+        }
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if line.starts_with('[') {
+            section = parse_header(line).map_err(|message| ConfigError::new(line_no, message))?;
+            continue;
+        }
+
+        if !is_read(&section) {
+            if let Some((_, value)) = line.split_once('=') {
+                skipped.scan(value);
+            }
+            continue;
+        }
+
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| ConfigError::new(line_no, "expected `key = value`"))?;
+        let key = key.trim();
+        if !is_bare_key(key) {
+            return Err(ConfigError::new(line_no, format!("invalid key `{key}`")));
+        }
+        if entries.iter().any(|e| e.section == section && e.key == key) {
+            return Err(ConfigError::new(line_no, format!("duplicate key `{key}`")));
+        }
+        let value = parse_value(value.trim())
+            .ok_or_else(|| ConfigError::new(line_no, format!("invalid value for `{key}`")))?;
+        entries.push(Entry {
+            line: line_no,
+            section: section.clone(),
+            key: key.to_string(),
+            value,
+        });
+    }
+    Ok(entries)
+}
+
+/// Returns the name of the table a `[table]` or `[[table]]` header opens,
+/// e.g. `tool.other`, or an error message.
+fn parse_header(line: &str) -> Result<String, String> {
+    let (body, close) = match line.strip_prefix("[[") {
+        Some(body) => (body, "]]"),
+        None => (&line[1..], "]"),
+    };
+    let name = body
+        .strip_suffix(close)
+        .ok_or("unterminated section header")?
+        .trim();
+    if name.is_empty() {
+        return Err("expected a section name".to_string());
+    }
+    Ok(name.to_string())
+}
+
+/// Tracks a value of a section which is not read, which may span several
+/// lines, e.g. an array or a multi-line string. Its syntax is not checked.
+#[derive(Default)]
+struct SkippedValue {
+    /// The number of arrays and inline tables left open.
+    depth: usize,
+    /// The delimiter of the multi-line string left open, if any.
+    string: Option<&'static str>,
+}
+
+impl SkippedValue {
+    fn is_open(&self) -> bool {
+        self.depth > 0 || self.string.is_some()
+    }
+
+    /// Scans the next line of the value.
+    fn scan(&mut self, mut text: &str) {
+        loop {
+            if let Some(delimiter) = self.string {
+                let Some(end) = text.find(delimiter) else {
+                    return;
+                };
+                self.string = None;
+                text = &text[end + delimiter.len()..];
+            }
+
+            let Some(idx) = text.find(['"', '\'', '[', ']', '{', '}', '#']) else {
+                return;
+            };
+            let rest = &text[idx..];
+            text = match rest.as_bytes()[0] {
+                b'#' => return,
+                b'[' | b'{' => {
+                    self.depth += 1;
+                    &rest[1..]
+                }
+                b']' | b'}' => {
+                    self.depth = self.depth.saturating_sub(1);
+                    &rest[1..]
+                }
+                _ if rest.starts_with("\"\"\"") || rest.starts_with("'''") => {
+                    self.string = Some(if rest.starts_with('"') {
+                        "\"\"\""
+                    } else {
+                        "'''"
+                    });
+                    &rest[3..]
+                }
+                quote => {
+                    let end = skip_string(&rest[1..], quote == b'"');
+                    &rest[1 + end..]
+                }
+            };
+        }
+    }
+}
+
+/// Returns the length of a single-line string up to and including its
+/// closing quote, or of the whole of `text` if it is not closed.
+fn skip_string(text: &str, basic: bool) -> usize {
+    let quote = if basic { '"' } else { '\'' };
+    let mut escaped = false;
+    for (idx, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if basic => escaped = true,
+            c if c == quote => return idx + 1,
+            _ => {}
+        }
+    }
+    text.len()
+}
+
+/// Removes a `#` comment which is not inside a string.
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (idx, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..idx],
+            _ => {}
+        }
+    }
+    line
+}
+
+fn is_bare_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+fn parse_value(value: &str) -> Option<ConfigValue> {
+    match value {
+        "true" => return Some(ConfigValue::Bool(true)),
+        "false" => return Some(ConfigValue::Bool(false)),
+        _ => {}
+    }
+
+    if let Some(body) = value.strip_prefix('"') {
+        let body = body.strip_suffix('"')?;
+        let mut s = String::new();
+        let mut chars = body.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => s.push(match chars.next()? {
+                    'n' => '\n',
+                    't' => '\t',
+                    c @ ('"' | '\\') => c,
+                    _ => return None,
+                }),
+                '"' => return None,
+                c => s.push(c),
+            }
+        }
+        return Some(ConfigValue::Str(s));
+    }
+
+    value.replace('_', "").parse().ok().map(ConfigValue::Int)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lang::{format::TrailingComma, modules::MemoryFileLoader};
+    use std::io;
+
+    #[test]
+    fn reads_fmt_section() {
+        let config = Config::from_toml(
+            "# project settings
+[package]
+name = \"app\"
+
+[fmt]
+max_width = 80 # columns
+trailing_comma = \"never\"
+single_attribute_per_line = true
+",
+        )
+        .unwrap();
+        assert_eq!(config.fmt.max_width, 80);
+        assert_eq!(
+            config.fmt.indent_width,
+            FormatConfig::default().indent_width
+        );
+        assert_eq!(config.fmt.trailing_comma, TrailingComma::Never);
+        assert!(config.fmt.single_attribute_per_line);
+    }
+
+    #[test]
+    fn reports_errors() {
+        let error = |input: &str| Config::from_toml(input).unwrap_err();
+        assert_eq!(
+            error("[fmt]\nmax_width = \"80\""),
+            ConfigError::new(2, "`max_width` must be an integer, found a string")
+        );
+        assert_eq!(
+            error("[fmt]\nwidth = 80"),
+            ConfigError::new(2, "unknown setting `width`")
+        );
+        assert_eq!(
+            error("[fmt]\n\ntrailing_comma = \"sometimes\""),
+            ConfigError::new(
+                3,
+                "`trailing_comma` must be one of \"always\", \"never\" or \"vertical\""
+            )
+        );
+        assert_eq!(
+            error("[fmt\n"),
+            ConfigError::new(1, "unterminated section header")
+        );
+        assert_eq!(
+            error("[fmt]\nmax_width = 1\nmax_width = 2"),
+            ConfigError::new(3, "duplicate key `max_width`")
+        );
+    }
+
+    #[test]
+    fn skips_unread_sections() {
+        let config = Config::from_toml(
+            "authors = [\"a\"]
+[package]
+authors = [\"a\", 'b]']
+ratio = 1.5
+released = 2024-01-01
+description = \"\"\"
+[fmt]
+max_width = 1
+\"\"\"
+dependencies = [
+  \"x\", # ]
+  [1, 2],
+]
+
+[tool.other]
+[tool.\"quoted\".x]
+\"quoted key\" = { a = 1, b = [true] }
+
+[[bin]]
+name = \"app\"
+
+[fmt]
+max_width = 90
+",
+        )
+        .unwrap();
+        assert_eq!(config.fmt.max_width, 90);
+    }
+
+    #[test]
+    fn loads_from_project() {
+        let mut loader = MemoryFileLoader::new();
+        assert_eq!(Config::load(&loader), Ok(Config::default()));
+
+        loader.insert(CONFIG_FILE, "[fmt]\nindent_width = 4\n");
+        assert_eq!(Config::load(&loader).unwrap().fmt.indent_width, 4);
+
+        let err = Config::load(&LockedLoader).unwrap_err();
+        assert_eq!(err.line, 0);
+        assert!(err.message.starts_with("cannot read `ribbon.toml`: "));
+    }
+
+    /// Fails to read every file.
+    struct LockedLoader;
+
+    impl FileLoader for LockedLoader {
+        fn load(&self, _path: &str) -> io::Result<Option<String>> {
+            Err(io::Error::from(io::ErrorKind::PermissionDenied))
+        }
+    }
+}
@@ -0,0 +1,936 @@
+//! Compile-time evaluation of constants.
+//!
+//! [`ConstEvaluator`] evaluates the initializer of every `const` of a
+//! resolved module. Initializers may use literals, arithmetic, comparisons,
+//! string concatenation, records, lists, tuples, union variants, other
+//! constants and calls to `const fn`s. Integer overflow, division by zero and
+//! anything which cannot run at compile time, such as calling a function
+//! which is not `const` or performing an effect, are errors.
+//!
+//! The evaluated values are turned back into literal expressions with
+//! [`ConstValue::to_expr`], so code generation can emit them in place of the
+//! initializers.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, mem,
+};
+
+use crate::lang::{
+    ast::{
+        ASTBinaryOp, ASTExprKind, ASTItemFn, ASTLoc, ASTNodeBlock, ASTNodeExpr, ASTNodePattern,
+        ASTNodeRecordField, ASTNodeStmt, ASTPatternKind, ASTUnaryOp, Spanned, TokenLiteral,
+    },
+    diagnostic::Diagnostic,
+    node_index::{Ast, NodeId, NodeRef},
+    resolve::{DefId, DefKind, Resolution},
+    symbol::Symbol,
+};
+
+/// How deeply calls to `const fn`s may nest.
+const MAX_CALL_DEPTH: usize = 128;
+
+/// How many expressions the evaluation of one module may evaluate.
+const MAX_STEPS: usize = 1_000_000;
+
+/// A value known at compile time.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstValue {
+    Int(i64),
+    /// Always finite.
+    Float(f64),
+    /// The content of a string literal, with its escapes as written.
+    String(String),
+    Bool(bool),
+    Nil,
+    Tuple(Vec<ConstValue>),
+    List(Vec<ConstValue>),
+    /// A record, with its fields in the order they were written.
+    Record(Vec<(String, ConstValue)>),
+    /// A value of a union type, e.g. `Some(1)`.
+    Variant {
+        name: String,
+        fields: Vec<ConstValue>,
+    },
+}
+
+impl ConstValue {
+    /// The value as a literal expression at `loc`, which evaluates to the
+    /// value again.
+    pub fn to_expr(&self, loc: ASTLoc) -> ASTNodeExpr {
+        let expr = |kind| ASTNodeExpr::new(loc, kind);
+        let number = |text: String| {
+            expr(ASTExprKind::Literal(TokenLiteral::NumberLiteral(
+                Symbol::intern(&text),
+            )))
+        };
+        let negated = |value: ASTNodeExpr| {
+            expr(ASTExprKind::Unary {
+                op: ASTUnaryOp::Neg,
+                expr: Box::new(value),
+            })
+        };
+        let items = |items: &[ConstValue]| items.iter().map(|item| item.to_expr(loc)).collect();
+
+        match self {
+            ConstValue::Int(value) if *value < 0 => {
+                negated(number(value.unsigned_abs().to_string()))
+            }
+            ConstValue::Int(value) => number(value.to_string()),
+            ConstValue::Float(value) if value.is_sign_negative() => {
+                negated(ConstValue::Float(-value).to_expr(loc))
+            }
+            ConstValue::Float(value) => {
+                let text = value.to_string();
+                match text.contains('.') {
+                    true => number(text),
+                    false => number(format!("{}.0", text)),
+                }
+            }
+            ConstValue::String(value) => expr(ASTExprKind::Literal(TokenLiteral::StringLiteral(
+                Symbol::intern(&format!("\"{}\"", value)),
+            ))),
+            ConstValue::Bool(value) => expr(ASTExprKind::Identifier(value.to_string())),
+            ConstValue::Nil => expr(ASTExprKind::Nil),
+            ConstValue::Tuple(values) => expr(ASTExprKind::Tuple(items(values))),
+            ConstValue::List(values) => expr(ASTExprKind::List(items(values))),
+            ConstValue::Record(fields) => expr(ASTExprKind::Record(
+                fields
+                    .iter()
+                    .map(|(name, value)| {
+                        ASTNodeRecordField::new(loc, name.clone(), value.to_expr(loc))
+                    })
+                    .collect(),
+            )),
+            ConstValue::Variant { name, fields } if fields.is_empty() => {
+                expr(ASTExprKind::Identifier(name.clone()))
+            }
+            ConstValue::Variant { name, fields } => expr(ASTExprKind::Call {
+                callee: Box::new(expr(ASTExprKind::Identifier(name.clone()))),
+                args: items(fields),
+            }),
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            ConstValue::Int(_) => "an integer",
+            ConstValue::Float(_) => "a float",
+            ConstValue::String(_) => "a string",
+            ConstValue::Bool(_) => "a boolean",
+            ConstValue::Nil => "nil",
+            ConstValue::Tuple(_) => "a tuple",
+            ConstValue::List(_) => "a list",
+            ConstValue::Record(_) => "a record",
+            ConstValue::Variant { .. } => "a variant",
+        }
+    }
+}
+
+impl fmt::Display for ConstValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn list(f: &mut fmt::Formatter<'_>, items: &[ConstValue]) -> fmt::Result {
+            for (idx, item) in items.iter().enumerate() {
+                if idx > 0 {
+                    f.write_str(", ")?;
+                }
+                write!(f, "{}", item)?;
+            }
+            Ok(())
+        }
+
+        match self {
+            ConstValue::Int(value) => write!(f, "{}", value),
+            ConstValue::Float(value) if value.fract() == 0.0 => write!(f, "{:.1}", value),
+            ConstValue::Float(value) => write!(f, "{}", value),
+            ConstValue::String(value) => write!(f, "\"{}\"", value),
+            ConstValue::Bool(value) => write!(f, "{}", value),
+            ConstValue::Nil => f.write_str("nil"),
+            ConstValue::Tuple(items) => {
+                f.write_str("(")?;
+                list(f, items)?;
+                f.write_str(")")
+            }
+            ConstValue::List(items) => {
+                f.write_str("[")?;
+                list(f, items)?;
+                f.write_str("]")
+            }
+            ConstValue::Record(fields) => {
+                f.write_str("{ ")?;
+                for (idx, (name, value)) in fields.iter().enumerate() {
+                    if idx > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}: {}", name, value)?;
+                }
+                f.write_str(" }")
+            }
+            ConstValue::Variant { name, fields } if fields.is_empty() => f.write_str(name),
+            ConstValue::Variant { name, fields } => {
+                write!(f, "{}(", name)?;
+                list(f, fields)?;
+                f.write_str(")")
+            }
+        }
+    }
+}
+
+/// The values of the constants of a module.
+#[derive(Debug, Clone, Default)]
+pub struct ConstValues {
+    /// The value of every constant which could be evaluated.
+    pub values: HashMap<DefId, ConstValue>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ConstValues {
+    pub fn value(&self, def: DefId) -> Option<&ConstValue> {
+        self.values.get(&def)
+    }
+
+    /// The value of the constant `def` as a literal expression at `loc`.
+    pub fn literal(&self, def: DefId, loc: ASTLoc) -> Option<ASTNodeExpr> {
+        Some(self.value(def)?.to_expr(loc))
+    }
+}
+
+/// The evaluation of an expression stopped by an error, which has been
+/// reported already.
+struct Stop;
+
+type Eval = Result<ConstValue, Stop>;
+
+/// Evaluates the constants of a resolved module.
+pub struct ConstEvaluator<'a, 'ast> {
+    path: String,
+    ast: &'a Ast<'ast>,
+    res: &'a Resolution,
+    /// The definition declared by each declaring node.
+    decls: HashMap<NodeId, DefId>,
+    /// The constants being evaluated, to find cycles.
+    evaluating: HashSet<DefId>,
+    /// The constants which failed to evaluate, reported once.
+    failed: HashSet<DefId>,
+    /// The parameters and bindings of each `const fn` call being evaluated,
+    /// innermost last.
+    frames: Vec<HashMap<DefId, ConstValue>>,
+    /// The call sites of the `const fn` calls being evaluated.
+    calls: Vec<ASTLoc>,
+    steps: usize,
+    values: ConstValues,
+}
+
+impl<'a, 'ast> ConstEvaluator<'a, 'ast> {
+    pub fn new(path: impl Into<String>, ast: &'a Ast<'ast>, res: &'a Resolution) -> Self {
+        let decls = res
+            .definition_ids()
+            .filter_map(|(id, def)| Some((def.node?, id)))
+            .collect();
+        Self {
+            path: path.into(),
+            ast,
+            res,
+            decls,
+            evaluating: HashSet::new(),
+            failed: HashSet::new(),
+            frames: Vec::new(),
+            calls: Vec::new(),
+            steps: 0,
+            values: ConstValues::default(),
+        }
+    }
+
+    /// Evaluates every constant of the module, including the ones declared
+    /// in blocks.
+    pub fn evaluate(mut self) -> ConstValues {
+        for id in self.ast.ids() {
+            if let NodeRef::Const(item) = self.ast.get(id) {
+                if let Some(&def) = self.decls.get(&id) {
+                    let _ = self.constant(def, item.loc());
+                }
+            }
+        }
+        self.values
+    }
+
+    fn id(&self, node: impl Into<NodeRef<'ast>>) -> NodeId {
+        self.ast.id(node).expect("node of the evaluated module")
+    }
+
+    /// Reports an error at `loc`. An error inside a `const fn` is labelled
+    /// with the call it was evaluated for.
+    fn error(&mut self, code: &'static str, loc: ASTLoc, message: impl Into<String>) -> Stop {
+        let mut diagnostic = Diagnostic::error(code, &self.path, loc, message);
+        if let Some(&call) = self.calls.first() {
+            diagnostic = diagnostic.with_label(&self.path, call, "while evaluating this call");
+        }
+        self.values.diagnostics.push(diagnostic);
+        Stop
+    }
+
+    /// The value of the constant `def`, used at `loc`.
+    fn constant(&mut self, def: DefId, loc: ASTLoc) -> Eval {
+        if let Some(value) = self.values.values.get(&def) {
+            return Ok(value.clone());
+        }
+        if self.failed.contains(&def) {
+            return Err(Stop);
+        }
+        let definition = self.res.definition(def);
+        let Some(NodeRef::Const(item)) = definition.node.map(|node| self.ast.get(node)) else {
+            return Err(Stop);
+        };
+        if !self.evaluating.insert(def) {
+            let message = format!("the value of `{}` depends on itself", definition.name);
+            return Err(self.error("const-cycle", loc, message));
+        }
+
+        // An initializer only sees constants and its own bindings, not the
+        // bindings of the calls it is evaluated in.
+        let frames = mem::replace(&mut self.frames, vec![HashMap::new()]);
+        let calls = mem::take(&mut self.calls);
+        let value = self.eval(&item.value);
+        self.frames = frames;
+        self.calls = calls;
+        self.evaluating.remove(&def);
+
+        match &value {
+            Ok(value) => {
+                self.values.values.insert(def, value.clone());
+            }
+            Err(_) => {
+                self.failed.insert(def);
+            }
+        }
+        value
+    }
+
+    fn eval(&mut self, expr: &'ast ASTNodeExpr) -> Eval {
+        self.steps += 1;
+        if self.steps == MAX_STEPS {
+            let message = "evaluating the constants of the module takes too long";
+            return Err(self.error("const-eval-limit", expr.loc(), message));
+        }
+
+        match &expr.kind {
+            ASTExprKind::Literal(literal) => self.literal(literal, expr.loc()),
+            ASTExprKind::Nil => Ok(ConstValue::Nil),
+            ASTExprKind::Identifier(name) => self.name(expr, name),
+            ASTExprKind::Anchor(name) => Err(self.error(
+                "not-const",
+                expr.loc(),
+                format!("the anchor `#{}` cannot be used in a constant", name),
+            )),
+            ASTExprKind::Call { callee, args } => self.call(expr, callee, args),
+            ASTExprKind::Member { object, name } => match self.eval(object)? {
+                ConstValue::Record(fields) => {
+                    match fields.into_iter().find(|(field, _)| field == name) {
+                        Some((_, value)) => Ok(value),
+                        None => Err(self.invalid(expr.loc(), format!("no field `{}`", name))),
+                    }
+                }
+                other => {
+                    let message = format!("cannot access `{}` on {}", name, other.describe());
+                    Err(self.invalid(expr.loc(), message))
+                }
+            },
+            ASTExprKind::Binary { op, lhs, rhs } => self.binary(expr, *op, lhs, rhs),
+            ASTExprKind::Unary { op, expr: operand } => match (op, self.eval(operand)?) {
+                (ASTUnaryOp::Neg, ConstValue::Int(value)) => match value.checked_neg() {
+                    Some(value) => Ok(ConstValue::Int(value)),
+                    None => Err(self.overflow(expr.loc(), "negate")),
+                },
+                (ASTUnaryOp::Neg, ConstValue::Float(value)) => Ok(ConstValue::Float(-value)),
+                (ASTUnaryOp::Not, ConstValue::Bool(value)) => Ok(ConstValue::Bool(!value)),
+                (op, value) => {
+                    let message = format!("cannot apply `{}` to {}", op.as_str(), value.describe());
+                    Err(self.invalid(expr.loc(), message))
+                }
+            },
+            ASTExprKind::If { cond, then, els } => match self.condition(cond)? {
+                true => self.block(then),
+                false => match els {
+                    Some(els) => self.eval(els),
+                    None => Ok(ConstValue::Tuple(Vec::new())),
+                },
+            },
+            ASTExprKind::Block(block) => self.block(block),
+            ASTExprKind::For {
+                pattern,
+                iter,
+                body,
+            } => {
+                let ConstValue::List(items) = self.eval(iter)? else {
+                    return Err(self.invalid(iter.loc(), "can only iterate over a list"));
+                };
+                for item in items {
+                    if !self.bind_pattern(pattern, &item) {
+                        return Err(self.invalid(pattern.loc(), "the pattern does not match"));
+                    }
+                    self.block(body)?;
+                }
+                Ok(ConstValue::Tuple(Vec::new()))
+            }
+            ASTExprKind::When { subject, arms } => {
+                let subject = self.eval(subject)?;
+                for arm in arms {
+                    if self.bind_pattern(&arm.pattern, &subject) {
+                        return self.eval(&arm.body);
+                    }
+                }
+                let message = format!("no arm matches `{}`", subject);
+                Err(self.invalid(expr.loc(), message))
+            }
+            ASTExprKind::Record(fields) => {
+                let mut values = Vec::new();
+                for field in fields {
+                    values.push((field.name.clone(), self.eval(&field.value)?));
+                }
+                Ok(ConstValue::Record(values))
+            }
+            ASTExprKind::List(items) => Ok(ConstValue::List(self.eval_all(items)?)),
+            ASTExprKind::Tuple(items) => Ok(ConstValue::Tuple(self.eval_all(items)?)),
+        }
+    }
+
+    fn eval_all(&mut self, exprs: &'ast [ASTNodeExpr]) -> Result<Vec<ConstValue>, Stop> {
+        exprs.iter().map(|expr| self.eval(expr)).collect()
+    }
+
+    fn literal(&mut self, literal: &TokenLiteral, loc: ASTLoc) -> Eval {
+        let text = literal.content();
+        match literal {
+            TokenLiteral::StringLiteral(_) => Ok(ConstValue::String(literal.unquoted().into())),
+            TokenLiteral::NumberLiteral(_) if text.contains('.') => match text.parse::<f64>() {
+                Ok(value) if value.is_finite() => Ok(ConstValue::Float(value)),
+                _ => Err(self.error(
+                    "const-overflow",
+                    loc,
+                    format!("the literal `{}` does not fit in a float", text),
+                )),
+            },
+            TokenLiteral::NumberLiteral(_) => match text.parse::<i64>() {
+                Ok(value) => Ok(ConstValue::Int(value)),
+                Err(_) => Err(self.error(
+                    "const-overflow",
+                    loc,
+                    format!("the literal `{}` does not fit in an integer", text),
+                )),
+            },
+        }
+    }
+
+    /// The value of the name used by `expr`.
+    fn name(&mut self, expr: &'ast ASTNodeExpr, name: &str) -> Eval {
+        let Some(&def) = self.res.uses.get(self.id(expr)) else {
+            // Reported by the resolver.
+            return Err(Stop);
+        };
+        let definition = self.res.definition(def);
+        match definition.kind {
+            DefKind::Const => self.constant(def, expr.loc()),
+            DefKind::Variant => Ok(ConstValue::Variant {
+                name: name.to_string(),
+                fields: Vec::new(),
+            }),
+            DefKind::Builtin if matches!(name, "true" | "false") => {
+                Ok(ConstValue::Bool(name == "true"))
+            }
+            DefKind::Param | DefKind::Let | DefKind::Binding => {
+                match self.frames.last().and_then(|frame| frame.get(&def)) {
+                    Some(value) => Ok(value.clone()),
+                    None => Err(self.error(
+                        "not-const",
+                        expr.loc(),
+                        format!("`{}` is not known at compile time", name),
+                    )),
+                }
+            }
+            kind => Err(self.error(
+                "not-const",
+                expr.loc(),
+                format!(
+                    "the {} `{}` cannot be used in a constant",
+                    kind.describe(),
+                    name
+                ),
+            )),
+        }
+    }
+
+    fn call(
+        &mut self,
+        expr: &'ast ASTNodeExpr,
+        callee: &'ast ASTNodeExpr,
+        args: &'ast [ASTNodeExpr],
+    ) -> Eval {
+        let def = match &callee.kind {
+            ASTExprKind::Identifier(_) => self.res.uses.get(self.id(callee)).copied(),
+            _ => None,
+        };
+        let Some(def) = def else {
+            let message = "only `const fn`s and variants can be called in a constant";
+            return Err(self.error("not-const", callee.loc(), message));
+        };
+
+        let definition = self.res.definition(def);
+        let item = match (definition.kind, definition.node.map(|n| self.ast.get(n))) {
+            (DefKind::Variant, _) => {
+                let fields = self.eval_all(args)?;
+                let name = definition.name.clone();
+                return Ok(ConstValue::Variant { name, fields });
+            }
+            (DefKind::Fn, Some(NodeRef::Fn(item))) if item.is_const => item,
+            (DefKind::Fn, _) => {
+                let message = format!(
+                    "cannot call `{}` in a constant, as it is not a `const fn`",
+                    definition.name
+                );
+                return Err(self.error("not-const", callee.loc(), message));
+            }
+            _ => {
+                let message = "only `const fn`s and variants can be called in a constant";
+                return Err(self.error("not-const", callee.loc(), message));
+            }
+        };
+
+        let args = self.eval_all(args)?;
+        self.call_fn(item, args, expr.loc())
+    }
+
+    fn call_fn(&mut self, item: &'ast ASTItemFn, args: Vec<ConstValue>, loc: ASTLoc) -> Eval {
+        if self.calls.len() == MAX_CALL_DEPTH {
+            let message = format!("calls to `const fn`s nest deeper than {}", MAX_CALL_DEPTH);
+            return Err(self.error("const-eval-limit", loc, message));
+        }
+        if args.len() != item.params.len() {
+            let message = format!(
+                "`{}` takes {} arguments, found {}",
+                item.name,
+                item.params.len(),
+                args.len()
+            );
+            return Err(self.invalid(loc, message));
+        }
+
+        let frame = item
+            .params
+            .iter()
+            .zip(args)
+            .filter_map(|(param, arg)| Some((*self.decls.get(&self.id(param))?, arg)))
+            .collect();
+        self.frames.push(frame);
+        self.calls.push(loc);
+        let value = self.block(&item.body);
+        self.calls.pop();
+        self.frames.pop();
+        value
+    }
+
+    fn block(&mut self, block: &'ast ASTNodeBlock) -> Eval {
+        let mut value = ConstValue::Tuple(Vec::new());
+        for stmt in &block.stmts {
+            value = ConstValue::Tuple(Vec::new());
+            match stmt {
+                ASTNodeStmt::Let(stmt) => {
+                    let bound = self.eval(&stmt.value)?;
+                    self.bind(self.id(stmt), bound, stmt.loc())?;
+                }
+                ASTNodeStmt::Const(item) => {
+                    if let Some(&def) = self.decls.get(&self.id(item)) {
+                        self.constant(def, item.loc())?;
+                    }
+                }
+                ASTNodeStmt::Assign(assign) => {
+                    let assigned = self.eval(&assign.value)?;
+                    let target = match &assign.target.kind {
+                        ASTExprKind::Identifier(_) => {
+                            self.res.uses.get(self.id(&assign.target)).copied()
+                        }
+                        _ => None,
+                    };
+                    let Some(frame) = self.frames.last_mut() else {
+                        return Err(self.not_const(stmt.loc()));
+                    };
+                    match target {
+                        Some(def) if frame.contains_key(&def) => {
+                            frame.insert(def, assigned);
+                        }
+                        _ => return Err(self.not_const(stmt.loc())),
+                    }
+                }
+                ASTNodeStmt::Expr(expr) => value = self.eval(expr)?,
+                ASTNodeStmt::With(_) | ASTNodeStmt::Effect(_) | ASTNodeStmt::Element(_) => {
+                    return Err(self.not_const(stmt.loc()));
+                }
+            }
+        }
+        Ok(value)
+    }
+
+    /// Binds the value of the `let` statement `node` in the current call.
+    fn bind(&mut self, node: NodeId, value: ConstValue, loc: ASTLoc) -> Result<(), Stop> {
+        let def = self.decls.get(&node).copied();
+        match (self.frames.last_mut(), def) {
+            (Some(frame), Some(def)) => {
+                frame.insert(def, value);
+                Ok(())
+            }
+            _ => Err(self.not_const(loc)),
+        }
+    }
+
+    /// Matches `value` against `pattern`, binding its names in the current
+    /// call. Returns whether it matched.
+    fn bind_pattern(&mut self, pattern: &'ast ASTNodePattern, value: &ConstValue) -> bool {
+        match (&pattern.kind, value) {
+            (ASTPatternKind::Wildcard, _) => true,
+            (ASTPatternKind::Binding(_), _) => {
+                let def = self.decls.get(&self.id(pattern)).copied();
+                if let (Some(frame), Some(def)) = (self.frames.last_mut(), def) {
+                    frame.insert(def, value.clone());
+                }
+                true
+            }
+            (ASTPatternKind::Literal(literal), value) => {
+                let loc = pattern.loc();
+                self.literal(literal, loc)
+                    .is_ok_and(|literal| literal == *value)
+            }
+            (ASTPatternKind::Nil, value) => *value == ConstValue::Nil,
+            (
+                ASTPatternKind::Variant { name, fields },
+                ConstValue::Variant {
+                    name: actual,
+                    fields: values,
+                },
+            ) => {
+                name == actual
+                    && fields.len() == values.len()
+                    && fields
+                        .iter()
+                        .zip(values)
+                        .all(|(field, value)| self.bind_pattern(field, value))
+            }
+            (ASTPatternKind::Tuple(items), ConstValue::Tuple(values)) => {
+                items.len() == values.len()
+                    && items
+                        .iter()
+                        .zip(values)
+                        .all(|(item, value)| self.bind_pattern(item, value))
+            }
+            _ => false,
+        }
+    }
+
+    fn condition(&mut self, cond: &'ast ASTNodeExpr) -> Result<bool, Stop> {
+        match self.eval(cond)? {
+            ConstValue::Bool(value) => Ok(value),
+            other => {
+                let message = format!("expected a boolean condition, found {}", other.describe());
+                Err(self.invalid(cond.loc(), message))
+            }
+        }
+    }
+
+    fn binary(
+        &mut self,
+        expr: &'ast ASTNodeExpr,
+        op: ASTBinaryOp,
+        lhs: &'ast ASTNodeExpr,
+        rhs: &'ast ASTNodeExpr,
+    ) -> Eval {
+        let loc = expr.loc();
+        match op {
+            ASTBinaryOp::And => {
+                return Ok(ConstValue::Bool(
+                    self.condition(lhs)? && self.condition(rhs)?,
+                ))
+            }
+            ASTBinaryOp::Or => {
+                return Ok(ConstValue::Bool(
+                    self.condition(lhs)? || self.condition(rhs)?,
+                ))
+            }
+            _ => {}
+        }
+
+        let (lhs, rhs) = (self.eval(lhs)?, self.eval(rhs)?);
+        let value = match (op, &lhs, &rhs) {
+            (ASTBinaryOp::Eq, _, _) => ConstValue::Bool(lhs == rhs),
+            (ASTBinaryOp::NotEq, _, _) => ConstValue::Bool(lhs != rhs),
+            (ASTBinaryOp::Add, ConstValue::String(a), ConstValue::String(b)) => {
+                ConstValue::String(format!("{}{}", a, b))
+            }
+            (_, ConstValue::Int(a), ConstValue::Int(b)) => {
+                let (a, b) = (*a, *b);
+                let (value, verb) = match op {
+                    ASTBinaryOp::Add => (a.checked_add(b), "add"),
+                    ASTBinaryOp::Sub => (a.checked_sub(b), "subtract"),
+                    ASTBinaryOp::Mul => (a.checked_mul(b), "multiply"),
+                    ASTBinaryOp::Div | ASTBinaryOp::Rem if b == 0 => {
+                        return Err(self.division_by_zero(loc, op));
+                    }
+                    ASTBinaryOp::Div => (a.checked_div(b), "divide"),
+                    ASTBinaryOp::Rem => (a.checked_rem(b), "take the remainder"),
+                    _ => return Ok(ConstValue::Bool(compare(op, a.cmp(&b)))),
+                };
+                match value {
+                    Some(value) => ConstValue::Int(value),
+                    None => return Err(self.overflow(loc, verb)),
+                }
+            }
+            (_, ConstValue::Float(a), ConstValue::Float(b)) => {
+                let (a, b) = (*a, *b);
+                let (value, verb) = match op {
+                    ASTBinaryOp::Add => (a + b, "add"),
+                    ASTBinaryOp::Sub => (a - b, "subtract"),
+                    ASTBinaryOp::Mul => (a * b, "multiply"),
+                    ASTBinaryOp::Div | ASTBinaryOp::Rem if b == 0.0 => {
+                        return Err(self.division_by_zero(loc, op));
+                    }
+                    ASTBinaryOp::Div => (a / b, "divide"),
+                    ASTBinaryOp::Rem => (a % b, "take the remainder"),
+                    _ => {
+                        let ordering = a.partial_cmp(&b).expect("floats are finite");
+                        return Ok(ConstValue::Bool(compare(op, ordering)));
+                    }
+                };
+                match value.is_finite() {
+                    true => ConstValue::Float(value),
+                    false => return Err(self.overflow(loc, verb)),
+                }
+            }
+            (_, ConstValue::String(a), ConstValue::String(b))
+                if !matches!(
+                    op,
+                    ASTBinaryOp::Sub | ASTBinaryOp::Mul | ASTBinaryOp::Div | ASTBinaryOp::Rem
+                ) =>
+            {
+                ConstValue::Bool(compare(op, a.cmp(b)))
+            }
+            _ => {
+                let message = format!(
+                    "cannot apply `{}` to {} and {}",
+                    op.as_str(),
+                    lhs.describe(),
+                    rhs.describe()
+                );
+                return Err(self.invalid(loc, message));
+            }
+        };
+        Ok(value)
+    }
+
+    fn overflow(&mut self, loc: ASTLoc, verb: &str) -> Stop {
+        let message = format!("attempt to {} with overflow", verb);
+        self.error("const-overflow", loc, message)
+    }
+
+    fn division_by_zero(&mut self, loc: ASTLoc, op: ASTBinaryOp) -> Stop {
+        let message = match op {
+            ASTBinaryOp::Rem => "attempt to take the remainder of a division by zero",
+            _ => "attempt to divide by zero",
+        };
+        self.error("const-division-by-zero", loc, message)
+    }
+
+    fn not_const(&mut self, loc: ASTLoc) -> Stop {
+        let message = "this statement cannot be evaluated at compile time";
+        self.error("not-const", loc, message)
+    }
+
+    /// Reports an operation on values of the wrong kind, which the type
+    /// checker reports as well.
+    fn invalid(&mut self, loc: ASTLoc, message: impl Into<String>) -> Stop {
+        self.error("invalid-const", loc, message)
+    }
+}
+
+/// Applies the comparison operator `op` to the ordering of its operands.
+fn compare(op: ASTBinaryOp, ordering: std::cmp::Ordering) -> bool {
+    match op {
+        ASTBinaryOp::Lt => ordering.is_lt(),
+        ASTBinaryOp::LtEq => ordering.is_le(),
+        ASTBinaryOp::Gt => ordering.is_gt(),
+        ASTBinaryOp::GtEq => ordering.is_ge(),
+        _ => unreachable!("`{}` is not a comparison", op.as_str()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lang::resolve::test::with_resolution;
+
+    /// The value of each module constant by name, and the diagnostic codes.
+    fn evaluate(source: &str) -> (HashMap<String, String>, Vec<(&'static str, String)>) {
+        with_resolution(source, |ast, resolution| {
+            assert_eq!(resolution.diagnostics, [], "failed to resolve");
+            let values = ConstEvaluator::new("main.rbn", ast, resolution).evaluate();
+
+            let named = values
+                .values
+                .iter()
+                .map(|(&def, value)| (resolution.definition(def).name.clone(), value.to_string()))
+                .collect();
+            let diagnostics = values
+                .diagnostics
+                .into_iter()
+                .map(|diagnostic| (diagnostic.code, diagnostic.message))
+                .collect();
+            (named, diagnostics)
+        })
+    }
+
+    #[test]
+    fn evaluates_constants() {
+        let (values, diagnostics) = evaluate(
+            r#"
+            type Shape = Circle(Float) | Empty
+            const fn square(x: Int) -> Int { x * x }
+            const fn sum(items: [Int]) -> Int {
+                let total = 0
+                for item in items { total = total + item }
+                total
+            }
+            const fn area(shape: Shape) -> Float {
+                when shape { Circle(r) -> r * r * 3.0, Empty -> 0.0 }
+            }
+            const SIZE = square(4) + 2 % 3
+            const GREETING = "Hello, " + "world"
+            const THEME = { color: "red", size: SIZE }
+            const TOTAL = sum([1, 2, SIZE])
+            const BIG = SIZE > 10 && !false
+            const AREA = area(Circle(2.0))
+            const SHAPES = (Circle(-1.5), Empty)
+            const LABEL = if BIG { THEME.color } else { "none" }
+            const BLOCK = if true {
+                let a = 2
+                a * -3
+            } else {
+                0
+            }
+            "#,
+        );
+        assert_eq!(diagnostics, []);
+
+        let expected = [
+            ("SIZE", "18"),
+            ("GREETING", "\"Hello, world\""),
+            ("THEME", "{ color: \"red\", size: 18 }"),
+            ("TOTAL", "21"),
+            ("BIG", "true"),
+            ("AREA", "12.0"),
+            ("SHAPES", "(Circle(-1.5), Empty)"),
+            ("LABEL", "\"red\""),
+            ("BLOCK", "-6"),
+        ];
+        for (name, value) in expected {
+            assert_eq!(
+                values.get(name).map(String::as_str),
+                Some(value),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn reports_errors() {
+        let (values, diagnostics) = evaluate(
+            r#"
+            fn now() -> Int { 0 }
+            const fn div(a: Int, b: Int) -> Int { a / b }
+            const MAX = 9223372036854775807
+            const OVERFLOW = MAX + 1
+            const DEPENDENT = OVERFLOW * 2
+            const ZERO = div(1, 0)
+            const HALF = 1.0 % 0.0
+            const TIME = now()
+            const A = B
+            const B = A
+            "#,
+        );
+        assert_eq!(
+            diagnostics,
+            [
+                ("const-overflow", "attempt to add with overflow".to_string()),
+                (
+                    "const-division-by-zero",
+                    "attempt to divide by zero".to_string()
+                ),
+                (
+                    "const-division-by-zero",
+                    "attempt to take the remainder of a division by zero".to_string()
+                ),
+                (
+                    "not-const",
+                    "cannot call `now` in a constant, as it is not a `const fn`".to_string()
+                ),
+                (
+                    "const-cycle",
+                    "the value of `A` depends on itself".to_string()
+                ),
+            ]
+        );
+        assert_eq!(values.len(), 1);
+        assert_eq!(values["MAX"], "9223372036854775807");
+    }
+
+    #[test]
+    fn labels_calls() {
+        let source = "const fn f(a: Int) -> Int { a / 0 }\nconst X = f(1)";
+        let values = with_resolution(source, |ast, resolution| {
+            ConstEvaluator::new("main.rbn", ast, resolution).evaluate()
+        });
+        let [diagnostic] = &values.diagnostics[..] else {
+            panic!("expected one diagnostic: {:?}", values.diagnostics);
+        };
+        assert_eq!(diagnostic.labels[0].message, "while evaluating this call");
+        assert_eq!(
+            diagnostic.labels[0].loc.start,
+            source.find("f(1)").unwrap() as u32
+        );
+    }
+
+    #[test]
+    fn values_as_literals() {
+        let values = [
+            ConstValue::Int(-3),
+            ConstValue::Float(2.0),
+            ConstValue::String("a\\n".into()),
+            ConstValue::Record(vec![
+                ("on".into(), ConstValue::Bool(true)),
+                ("items".into(), ConstValue::List(vec![ConstValue::Nil])),
+            ]),
+            ConstValue::Variant {
+                name: "Some".into(),
+                fields: vec![ConstValue::Tuple(vec![
+                    ConstValue::Float(-0.5),
+                    ConstValue::Int(1),
+                ])],
+            },
+        ];
+        let source: String = values
+            .iter()
+            .enumerate()
+            .map(|(idx, value)| format!("const V{} = {}\n", idx, value))
+            .collect();
+        let (evaluated, diagnostics) = evaluate(&format!("type Opt<T> = Some(T)\n{}", source));
+        assert_eq!(diagnostics, []);
+        for (idx, value) in values.iter().enumerate() {
+            assert_eq!(evaluated[&format!("V{}", idx)], value.to_string());
+        }
+
+        let loc = ASTLoc::default();
+        let ASTExprKind::Unary { op, expr } = ConstValue::Int(-3).to_expr(loc).kind else {
+            panic!("negative integers are negated literals");
+        };
+        assert_eq!(op, ASTUnaryOp::Neg);
+        assert!(matches!(expr.kind, ASTExprKind::Literal(literal) if literal.content() == "3"));
+        let float = ConstValue::Float(2.0).to_expr(loc).kind;
+        assert!(matches!(float, ASTExprKind::Literal(literal) if literal.content() == "2.0"));
+    }
+}
@@ -0,0 +1,176 @@
+use crate::lang::{ast::ASTLoc, parser::incremental::TextEdit};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// A secondary location attached to a [`Diagnostic`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DiagnosticLabel {
+    pub module: String,
+    pub loc: ASTLoc,
+    pub message: String,
+}
+
+/// A change to the module of a [`Diagnostic`] which resolves it, and which a
+/// tool may apply without asking.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Fix {
+    /// Describes the change, e.g. "add an empty `alt`".
+    pub message: String,
+    /// Non-overlapping edits of the source.
+    pub edits: Vec<TextEdit>,
+}
+
+impl Fix {
+    /// Returns `source` with the edits applied.
+    pub fn apply(&self, source: &str) -> String {
+        let mut edits: Vec<_> = self.edits.iter().collect();
+        edits.sort_by_key(|edit| edit.loc.start);
+        let mut output = String::with_capacity(source.len());
+        let mut last = 0;
+        for edit in edits {
+            output.push_str(&source[last..edit.loc.start as usize]);
+            output.push_str(&edit.text);
+            last = edit.loc.end as usize;
+        }
+        output.push_str(&source[last..]);
+        output
+    }
+}
+
+/// A problem found in a module by one of the semantic passes.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// A stable identifier of the kind of problem, e.g. `unresolved-module`.
+    pub code: &'static str,
+    pub message: String,
+    /// The path of the module the problem was found in.
+    pub module: String,
+    pub loc: ASTLoc,
+    pub labels: Vec<DiagnosticLabel>,
+    pub fixes: Vec<Fix>,
+}
+
+impl Diagnostic {
+    pub fn error(
+        code: &'static str,
+        module: impl Into<String>,
+        loc: ASTLoc,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            severity: Severity::Error,
+            code,
+            message: message.into(),
+            module: module.into(),
+            loc,
+            labels: Vec::new(),
+            fixes: Vec::new(),
+        }
+    }
+
+    pub fn warning(
+        code: &'static str,
+        module: impl Into<String>,
+        loc: ASTLoc,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(code, module, loc, message)
+        }
+    }
+
+    pub fn with_label(
+        mut self,
+        module: impl Into<String>,
+        loc: ASTLoc,
+        message: impl Into<String>,
+    ) -> Self {
+        self.labels.push(DiagnosticLabel {
+            module: module.into(),
+            loc,
+            message: message.into(),
+        });
+        self
+    }
+
+    pub fn with_fix(mut self, message: impl Into<String>, edits: Vec<TextEdit>) -> Self {
+        self.fixes.push(Fix {
+            message: message.into(),
+            edits,
+        });
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+/// The candidate closest to `name`, to suggest for a misspelled name, if one
+/// is close enough to be a likely correction.
+pub fn suggest<S: AsRef<str>>(name: &str, candidates: impl IntoIterator<Item = S>) -> Option<S> {
+    let max = (name.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .map(|candidate| (distance(name, candidate.as_ref()), candidate))
+        .filter(|(distance, _)| *distance <= max)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// The number of edits turning `a` into `b`, ignoring case, where an edit
+/// inserts, removes or replaces a character or swaps two adjacent ones.
+fn distance(a: &str, b: &str) -> usize {
+    let a: Vec<_> = a.chars().flat_map(char::to_lowercase).collect();
+    let b: Vec<_> = b.chars().flat_map(char::to_lowercase).collect();
+    // `d[i][j]` is the distance between the first `i` characters of `a` and
+    // the first `j` of `b`.
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    d[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn suggestions() {
+        assert_eq!(distance("kitten", "sitting"), 3);
+        assert_eq!(suggest("dvi", ["div", "span"]), Some("div"));
+        assert_eq!(suggest("onClick", ["onclick", "onclose"]), Some("onclick"));
+        assert_eq!(suggest("xyz", ["div", "span"]), None);
+    }
+
+    #[test]
+    fn applies_fixes() {
+        let fix = Fix {
+            message: "fix".to_string(),
+            edits: vec![
+                TextEdit::insert(4, " alt=\"\""),
+                TextEdit::new(ASTLoc { start: 1, end: 4 }, "image"),
+            ],
+        };
+        assert_eq!(fix.apply("<img/>"), "<image alt=\"\"/>");
+    }
+}
@@ -0,0 +1,868 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::lang::{
+    ast::{
+        ASTExprKind, ASTItemEffect, ASTLoc, ASTNodeBlock, ASTNodeExpr, ASTNodeModule,
+        ASTNodeScoped, ASTNodeStmt, ASTNodeType, ASTNodeViewChild, ASTNodeViewElement, ASTNodeWith,
+        ASTTypeKind, Spanned,
+    },
+    diagnostic::Diagnostic,
+    modules::{ImportTarget, ModuleGraph, ModuleId},
+    node_index::{Ast, NodeId, NodeRef},
+    resolve::{DefId, DefKind, Resolution},
+};
+
+/// The name of the function treated as the entry point of a program.
+pub const MAIN_FN: &str = "main";
+
+/// The name of the view mounted as the root of a program. The runtime
+/// renders it, so no other view needs to.
+pub const ENTRY_VIEW: &str = "App";
+
+/// The code that runs outside of any function, whose effects must all be
+/// handled before they leave it.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Boundary<'ast> {
+    View(&'ast str),
+    Main,
+    Const(&'ast str),
+    /// A function which may only perform the effects of its row.
+    Fn(&'ast str),
+}
+
+/// An effect performed, handled or declared in the checked module.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+enum Effect<'a> {
+    /// An effect declared in the checked module, or an imported one whose
+    /// declaration is not known.
+    Local(DefId),
+    /// The effect declared with this name in another module of the graph.
+    /// It is the same effect in every module importing it.
+    Imported(ModuleId, &'a str),
+}
+
+/// Checks that every effect performed in a resolved module is either handled
+/// by an enclosing `with` block or declared in the effect row of the
+/// enclosing function.
+///
+/// Views and the `main` function are entry points and must handle all of
+/// their effects. Calling a parameter or a binding of a function type
+/// performs the effects of its type, and a function may only be passed or
+/// bound where that type declares its effects.
+///
+/// Effects and functions imported from other modules are only known when the
+/// checker is given the module graph with [`EffectChecker::with_imports`]:
+/// calling an imported function then performs the effects of its row, and a
+/// handler of an imported effect is checked against its declaration.
+/// Otherwise an imported effect may be named in rows and handled, but
+/// calling an imported function performs nothing.
+pub struct EffectChecker<'a, 'ast> {
+    path: String,
+    ast: &'a Ast<'ast>,
+    res: &'a Resolution,
+    /// The module graph and the checked module in it.
+    imports: Option<(&'a ModuleGraph, ModuleId)>,
+    /// The definition declared by each declaring node.
+    decls: HashMap<NodeId, DefId>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a, 'ast> EffectChecker<'a, 'ast> {
+    pub fn new(path: impl Into<String>, ast: &'a Ast<'ast>, res: &'a Resolution) -> Self {
+        let decls = res
+            .definition_ids()
+            .filter_map(|(id, def)| Some((def.node?, id)))
+            .collect();
+        Self {
+            path: path.into(),
+            ast,
+            res,
+            imports: None,
+            decls,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Resolves the imports of the checked module, which is `module` in
+    /// `graph`, to the effects and functions they import.
+    pub fn with_imports(mut self, graph: &'a ModuleGraph, module: ModuleId) -> Self {
+        self.imports = Some((graph, module));
+        self
+    }
+
+    pub fn check(mut self) -> Vec<Diagnostic> {
+        let NodeRef::Module(module) = self.ast.get(self.ast.root()) else {
+            unreachable!("the root of a node index is a module");
+        };
+        self.check_module(module);
+        self.diagnostics
+    }
+
+    fn check_module(&mut self, module: &'ast ASTNodeModule) {
+        for node in &module.nodes {
+            match node {
+                ASTNodeScoped::Fn(item) => {
+                    let boundary = match item.name == MAIN_FN {
+                        true => Boundary::Main,
+                        false => Boundary::Fn(&item.name),
+                    };
+                    let handled = item
+                        .effects
+                        .iter()
+                        .filter_map(|effect| self.effect(effect))
+                        .collect();
+                    self.check_block(&item.body, boundary, &handled);
+                }
+                ASTNodeScoped::View(view) => {
+                    self.check_block(&view.body, Boundary::View(&view.name), &HashSet::new());
+                }
+                ASTNodeScoped::Const(item) => {
+                    let boundary = Boundary::Const(&item.name);
+                    self.check_expr(&item.value, boundary, &HashSet::new());
+                    if let Some(ty) = &item.ty {
+                        self.check_flow(&item.value, ty, &item.name);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn id(&self, node: impl Into<NodeRef<'ast>>) -> NodeId {
+        self.ast.id(node).expect("node of the checked module")
+    }
+
+    fn name(&self, effect: Effect<'a>) -> &'a str {
+        match effect {
+            Effect::Local(def) => &self.res.definition(def).name,
+            Effect::Imported(_, name) => name,
+        }
+    }
+
+    /// The item of the graph imported as `id`, and the module declaring it.
+    fn imported(&self, id: DefId) -> Option<(ModuleId, &'a ASTNodeScoped)> {
+        let (graph, module) = self.imports?;
+        let def = self.res.definition(id);
+        if !matches!(def.kind, DefKind::Import | DefKind::Use) {
+            return None;
+        }
+        let binding = (graph.module(module).bindings.iter()).find(|b| b.local == def.name)?;
+        let ImportTarget::Item(name) = &binding.target else {
+            return None;
+        };
+        self.exported(binding.module, name)
+    }
+
+    /// The item `module` of the graph exports as `name`, and the module
+    /// declaring it.
+    fn exported(&self, module: ModuleId, name: &str) -> Option<(ModuleId, &'a ASTNodeScoped)> {
+        let (graph, _) = self.imports?;
+        let export = graph.module(module).exports.get(name)?;
+        let ImportTarget::Item(name) = &export.target else {
+            return None;
+        };
+        let item = (graph.module(export.origin).ast.nodes.iter())
+            .find(|node| node.name() == Some(name.as_str()))?;
+        Some((export.origin, item))
+    }
+
+    /// The effect defined as `id`, following an import to the declaration of
+    /// the effect it imports.
+    fn canonical(&self, id: DefId) -> Effect<'a> {
+        match self.imported(id) {
+            Some((module, ASTNodeScoped::Effect(effect))) => Effect::Imported(module, &effect.name),
+            _ => Effect::Local(id),
+        }
+    }
+
+    /// The effects named by the row of a function declared in `module`, an
+    /// effect declared there or imported into it.
+    fn imported_row(&self, module: ModuleId, effects: &'a [ASTNodeType]) -> Vec<Effect<'a>> {
+        let Some((graph, _)) = self.imports else {
+            return Vec::new();
+        };
+        let node = graph.module(module);
+        let find = |name: &str| {
+            let declared = (node.ast.nodes.iter()).find(|item| item.name() == Some(name));
+            let item = match declared {
+                Some(item) => (module, item),
+                None => {
+                    let binding = (node.bindings.iter()).find(|b| b.local == name)?;
+                    let ImportTarget::Item(name) = &binding.target else {
+                        return None;
+                    };
+                    self.exported(binding.module, name)?
+                }
+            };
+            match item {
+                (module, ASTNodeScoped::Effect(effect)) => {
+                    Some(Effect::Imported(module, &effect.name))
+                }
+                _ => None,
+            }
+        };
+        effects
+            .iter()
+            .filter_map(|ty| match &ty.kind {
+                ASTTypeKind::Named { name, .. } => find(name),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the effect referred to by `ty`, reporting it if it names
+    /// something other than an effect or an import. Names which do not
+    /// resolve are reported by the resolver.
+    fn effect(&mut self, ty: &'ast ASTNodeType) -> Option<Effect<'a>> {
+        let ASTTypeKind::Named { .. } = &ty.kind else {
+            self.diagnostics.push(Diagnostic::error(
+                "unknown-effect",
+                &self.path,
+                ty.loc(),
+                "expected the name of an effect",
+            ));
+            return None;
+        };
+
+        let id = *self.res.uses.get(self.id(ty))?;
+        let def = self.res.definition(id);
+        match def.kind {
+            DefKind::Effect | DefKind::Import | DefKind::Use => Some(self.canonical(id)),
+            kind => {
+                let mut diagnostic = Diagnostic::error(
+                    "unknown-effect",
+                    &self.path,
+                    ty.loc(),
+                    format!(
+                        "expected an effect, found the {} `{}`",
+                        kind.describe(),
+                        def.name
+                    ),
+                );
+                if def.node.is_some() {
+                    let message = format!("`{}` is declared here", def.name);
+                    diagnostic = diagnostic.with_label(&self.path, def.loc, message);
+                }
+                self.diagnostics.push(diagnostic);
+                None
+            }
+        }
+    }
+
+    /// The effects named by an effect row, without reporting the ones that
+    /// are not effects again.
+    fn row(&self, effects: &'ast [ASTNodeType]) -> Vec<Effect<'a>> {
+        effects
+            .iter()
+            .filter_map(|ty| self.res.uses.get(self.id(ty)).copied())
+            .filter(|&id| {
+                matches!(
+                    self.res.definition(id).kind,
+                    DefKind::Effect | DefKind::Import | DefKind::Use
+                )
+            })
+            .map(|id| self.canonical(id))
+            .collect()
+    }
+
+    /// The effects of a function type, or `None` if `ty` is not one.
+    fn fn_type_row(&self, ty: &'ast ASTNodeType) -> Option<Vec<Effect<'a>>> {
+        match &ty.kind {
+            ASTTypeKind::Fn { effects, .. } => Some(self.row(effects)),
+            _ => None,
+        }
+    }
+
+    /// The declaration of `effect`, if it is known, and the path of the
+    /// module declaring it.
+    fn declaration(&self, effect: Effect<'a>) -> Option<(&str, &'a ASTItemEffect)> {
+        match effect {
+            Effect::Local(id) => match self.ast.get(self.res.definition(id).node?) {
+                NodeRef::Effect(item) => Some((&self.path, item)),
+                _ => None,
+            },
+            Effect::Imported(module, name) => {
+                let (graph, _) = self.imports?;
+                let module = graph.module(module);
+                module.ast.nodes.iter().find_map(|node| match node {
+                    ASTNodeScoped::Effect(item) if item.name == name => {
+                        Some((module.path.as_str(), item))
+                    }
+                    _ => None,
+                })
+            }
+        }
+    }
+
+    /// Returns the effects performed by calling the value defined as `id`:
+    /// the effect of an operation, the effect row of a function, or the row
+    /// of the function type of a parameter or binding, or the effect row of
+    /// an imported function.
+    fn performed_by_value(&self, id: DefId) -> Vec<Effect<'a>> {
+        let def = self.res.definition(id);
+        let Some(node) = def.node else {
+            return Vec::new();
+        };
+        match (def.kind, self.ast.get(node)) {
+            (DefKind::EffectOp, _) => (self.ast.parent(node))
+                .and_then(|effect| self.decls.get(&effect).copied())
+                .map(Effect::Local)
+                .into_iter()
+                .collect(),
+            (DefKind::Import | DefKind::Use, _) => match self.imported(id) {
+                Some((module, ASTNodeScoped::Fn(item))) => self.imported_row(module, &item.effects),
+                _ => Vec::new(),
+            },
+            (DefKind::Fn, NodeRef::Fn(item)) => self.row(&item.effects),
+            (DefKind::Param, NodeRef::Param(param)) => (param.ty.as_ref())
+                .and_then(|ty| self.fn_type_row(ty))
+                .unwrap_or_default(),
+            (DefKind::Const, NodeRef::Const(item)) => (item.ty.as_ref())
+                .and_then(|ty| self.fn_type_row(ty))
+                .unwrap_or_default(),
+            (DefKind::Let, NodeRef::Let(stmt)) => match &stmt.ty {
+                Some(ty) => self.fn_type_row(ty).unwrap_or_default(),
+                None => self.performed_by(&stmt.value),
+            },
+            _ => Vec::new(),
+        }
+    }
+
+    /// Checks the statements of a block. A `with` statement handles its effect
+    /// for the remaining statements of the block.
+    fn check_block(
+        &mut self,
+        block: &'ast ASTNodeBlock,
+        boundary: Boundary<'ast>,
+        handled: &HashSet<Effect<'a>>,
+    ) {
+        let mut handled = handled.clone();
+        for stmt in &block.stmts {
+            match stmt {
+                ASTNodeStmt::Let(stmt) => {
+                    self.check_expr(&stmt.value, boundary, &handled);
+                    if let Some(ty) = &stmt.ty {
+                        self.check_flow(&stmt.value, ty, &stmt.name);
+                    }
+                }
+                ASTNodeStmt::Const(item) => self.check_expr(&item.value, boundary, &handled),
+                ASTNodeStmt::With(with) => {
+                    if let Some(effect) = self.check_with(with, boundary, &handled) {
+                        handled.insert(effect);
+                    }
+                }
+                ASTNodeStmt::Effect(effect) => self.check_block(&effect.body, boundary, &handled),
+                ASTNodeStmt::Assign(assign) => {
+                    self.check_expr(&assign.target, boundary, &handled);
+                    self.check_expr(&assign.value, boundary, &handled);
+                }
+                ASTNodeStmt::Element(element) => self.check_element(element, boundary, &handled),
+                ASTNodeStmt::Expr(expr) => self.check_expr(expr, boundary, &handled),
+            }
+        }
+    }
+
+    /// Checks the operations of a handler against its effect, and returns the
+    /// handled effect. The bodies of the operations run in the context the
+    /// handler is installed in.
+    fn check_with(
+        &mut self,
+        with: &'ast ASTNodeWith,
+        boundary: Boundary<'ast>,
+        handled: &HashSet<Effect<'a>>,
+    ) -> Option<Effect<'a>> {
+        for op in &with.ops {
+            self.check_block(&op.body, boundary, handled);
+        }
+
+        let id = self.effect(&with.effect)?;
+        let Some((path, effect)) = self.declaration(id) else {
+            return Some(id);
+        };
+        let path = path.to_string();
+        let name = &effect.name;
+
+        for op in &with.ops {
+            if !effect.ops.iter().any(|declared| declared.name == op.name) {
+                self.diagnostics.push(
+                    Diagnostic::error(
+                        "unknown-operation",
+                        &self.path,
+                        op.loc(),
+                        format!("effect `{}` has no operation `{}`", name, op.name),
+                    )
+                    .with_label(
+                        &path,
+                        effect.loc(),
+                        format!("`{}` is declared here", name),
+                    ),
+                );
+            }
+        }
+        for declared in &effect.ops {
+            if !with.ops.iter().any(|op| op.name == declared.name) {
+                self.diagnostics.push(
+                    Diagnostic::error(
+                        "missing-operation",
+                        &self.path,
+                        with.loc(),
+                        format!(
+                            "handler of `{}` does not implement `{}`",
+                            name, declared.name
+                        ),
+                    )
+                    .with_label(
+                        &path,
+                        declared.loc(),
+                        "operation declared here",
+                    ),
+                );
+            }
+        }
+        Some(id)
+    }
+
+    fn check_element(
+        &mut self,
+        element: &'ast ASTNodeViewElement,
+        boundary: Boundary<'ast>,
+        handled: &HashSet<Effect<'a>>,
+    ) {
+        for attribute in &element.attributes {
+            if let Some(value) = &attribute.value {
+                self.check_expr(value, boundary, handled);
+            }
+        }
+        for child in &element.children {
+            match child {
+                ASTNodeViewChild::Element(element) => {
+                    self.check_element(element, boundary, handled)
+                }
+                ASTNodeViewChild::Text(expr) | ASTNodeViewChild::Expr(expr) => {
+                    self.check_expr(expr, boundary, handled)
+                }
+            }
+        }
+    }
+
+    fn check_expr(
+        &mut self,
+        expr: &'ast ASTNodeExpr,
+        boundary: Boundary<'ast>,
+        handled: &HashSet<Effect<'a>>,
+    ) {
+        match &expr.kind {
+            ASTExprKind::Call { callee, args } => {
+                for effect in self.performed_by(callee) {
+                    self.perform(effect, expr.loc(), boundary, handled);
+                }
+                self.check_member(callee);
+                if !matches!(callee.kind, ASTExprKind::Identifier(_)) {
+                    self.check_expr(callee, boundary, handled);
+                }
+                for arg in args {
+                    self.check_expr(arg, boundary, handled);
+                }
+                self.check_args(callee, args);
+            }
+            ASTExprKind::Member { object, .. } => self.check_expr(object, boundary, handled),
+            ASTExprKind::Binary { lhs, rhs, .. } => {
+                self.check_expr(lhs, boundary, handled);
+                self.check_expr(rhs, boundary, handled);
+            }
+            ASTExprKind::Unary { expr, .. } => self.check_expr(expr, boundary, handled),
+            ASTExprKind::If { cond, then, els } => {
+                self.check_expr(cond, boundary, handled);
+                self.check_block(then, boundary, handled);
+                if let Some(els) = els {
+                    self.check_expr(els, boundary, handled);
+                }
+            }
+            ASTExprKind::Block(block) => self.check_block(block, boundary, handled),
+            ASTExprKind::For { iter, body, .. } => {
+                self.check_expr(iter, boundary, handled);
+                self.check_block(body, boundary, handled);
+            }
+            ASTExprKind::When { subject, arms } => {
+                self.check_expr(subject, boundary, handled);
+                for arm in arms {
+                    self.check_expr(&arm.body, boundary, handled);
+                }
+            }
+            ASTExprKind::Record(fields) => {
+                for field in fields {
+                    self.check_expr(&field.value, boundary, handled);
+                }
+            }
+            ASTExprKind::List(items) | ASTExprKind::Tuple(items) => {
+                for item in items {
+                    self.check_expr(item, boundary, handled);
+                }
+            }
+            ASTExprKind::Literal(_)
+            | ASTExprKind::Nil
+            | ASTExprKind::Identifier(_)
+            | ASTExprKind::Anchor(_) => {}
+        }
+    }
+
+    /// Returns the effects performed by calling `callee`: an operation,
+    /// either by its name or qualified as `Effect.op`, a function, or a value
+    /// of a function type.
+    fn performed_by(&self, callee: &'ast ASTNodeExpr) -> Vec<Effect<'a>> {
+        match &callee.kind {
+            ASTExprKind::Identifier(_) => match self.res.uses.get(self.id(callee)) {
+                Some(&id) => self.performed_by_value(id),
+                None => Vec::new(),
+            },
+            ASTExprKind::Member { object, .. } => match self.qualified_effect(object) {
+                Some(id) => vec![id],
+                None => Vec::new(),
+            },
+            _ => Vec::new(),
+        }
+    }
+
+    /// The declared effect `object` refers to in `Effect.op`.
+    fn qualified_effect(&self, object: &'ast ASTNodeExpr) -> Option<Effect<'a>> {
+        let ASTExprKind::Identifier(_) = &object.kind else {
+            return None;
+        };
+        let effect = self.canonical(*self.res.uses.get(self.id(object))?);
+        self.declaration(effect).map(|_| effect)
+    }
+
+    /// Reports calls of an operation an effect does not declare.
+    fn check_member(&mut self, callee: &'ast ASTNodeExpr) {
+        let ASTExprKind::Member { object, name } = &callee.kind else {
+            return;
+        };
+        let Some((_, effect)) = self
+            .qualified_effect(object)
+            .and_then(|effect| self.declaration(effect))
+        else {
+            return;
+        };
+        if !effect.ops.iter().any(|op| &op.name == name) {
+            self.diagnostics.push(Diagnostic::error(
+                "unknown-operation",
+                &self.path,
+                callee.loc(),
+                format!("effect `{}` has no operation `{}`", effect.name, name),
+            ));
+        }
+    }
+
+    /// Checks the functions passed to the parameters of a local function.
+    fn check_args(&mut self, callee: &'ast ASTNodeExpr, args: &'ast [ASTNodeExpr]) {
+        let Some(&id) = self.res.uses.get(self.id(callee)) else {
+            return;
+        };
+        let Some(NodeRef::Fn(item)) = self.res.definition(id).node.map(|node| self.ast.get(node))
+        else {
+            return;
+        };
+        for (param, arg) in item.params.iter().zip(args) {
+            if let Some(ty) = &param.ty {
+                self.check_flow(arg, ty, &param.name);
+            }
+        }
+    }
+
+    /// Reports the effects of a function `value` that are missing from the
+    /// row of the function type `ty` it is bound to as `name`. Calling it
+    /// through `name` would perform them unchecked.
+    fn check_flow(&mut self, value: &'ast ASTNodeExpr, ty: &'ast ASTNodeType, name: &str) {
+        let Some(row) = self.fn_type_row(ty) else {
+            return;
+        };
+        for effect in self.performed_by(value) {
+            if !row.contains(&effect) {
+                self.diagnostics.push(
+                    Diagnostic::error(
+                        "undeclared-effect",
+                        &self.path,
+                        value.loc(),
+                        format!(
+                            "effect `{}` is performed by this function but not declared in the type of `{}`",
+                            self.name(effect),
+                            name
+                        ),
+                    )
+                    .with_label(&self.path, ty.loc(), "function type declared here"),
+                );
+            }
+        }
+    }
+
+    fn perform(
+        &mut self,
+        effect: Effect<'a>,
+        loc: ASTLoc,
+        boundary: Boundary<'ast>,
+        handled: &HashSet<Effect<'a>>,
+    ) {
+        if handled.contains(&effect) {
+            return;
+        }
+
+        let effect = self.name(effect);
+        let diagnostic = match boundary {
+            Boundary::Fn(name) => Diagnostic::error(
+                "undeclared-effect",
+                &self.path,
+                loc,
+                format!(
+                    "effect `{}` is performed by `{}` but not declared in its effect row",
+                    effect, name
+                ),
+            ),
+            Boundary::View(name) => Diagnostic::error(
+                "unhandled-effect",
+                &self.path,
+                loc,
+                format!("effect `{}` is not handled in view `{}`", effect, name),
+            ),
+            Boundary::Main => Diagnostic::error(
+                "unhandled-effect",
+                &self.path,
+                loc,
+                format!("effect `{}` is not handled in `{}`", effect, MAIN_FN),
+            ),
+            Boundary::Const(name) => Diagnostic::error(
+                "unhandled-effect",
+                &self.path,
+                loc,
+                format!("effect `{}` is performed by constant `{}`", effect, name),
+            ),
+        };
+        self.diagnostics.push(diagnostic);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lang::{
+        modules::{MemoryFileLoader, ModuleResolver},
+        resolve::{test::with_resolution, Resolver},
+    };
+
+    fn check(input: &str) -> Vec<Diagnostic> {
+        with_resolution(input, |ast, res| {
+            assert_eq!(res.diagnostics, [], "failed to resolve");
+            EffectChecker::new("main.rbn", ast, res).check()
+        })
+    }
+
+    fn codes(diagnostics: &[Diagnostic]) -> Vec<&'static str> {
+        diagnostics.iter().map(|d| d.code).collect()
+    }
+
+    const LOG: &str = "effect Log { fn log(msg: String) }\n";
+
+    #[test]
+    fn handled_effects() {
+        let input = format!(
+            "{}fn greet(name: String) emits Log {{ log(name) }}
+            view App {{
+              with Log {{ fn log(msg) {{ print(msg) }} }}
+              greet(\"world\")
+              <p>{{Log.log(\"rendered\")}}</p>
+            }}",
+            LOG
+        );
+        assert_eq!(check(&input), []);
+    }
+
+    #[test]
+    fn unhandled_at_boundaries() {
+        let input = format!(
+            "{}fn main() {{ log(\"start\") }}
+            view App {{
+              log(\"before\")
+              with Log {{ fn log(msg) {{ log(msg) }} }}
+              <p>{{log(\"after\")}}</p>
+            }}",
+            LOG
+        );
+        let diagnostics = check(&input);
+        assert_eq!(codes(&diagnostics), ["unhandled-effect"; 3]);
+        assert_eq!(
+            diagnostics[0].message,
+            "effect `Log` is not handled in `main`"
+        );
+        assert_eq!(
+            diagnostics[1].message,
+            "effect `Log` is not handled in view `App`"
+        );
+        // The handler body runs outside of its own handler.
+        assert_eq!(
+            diagnostics[2].loc,
+            ASTLoc {
+                start: 152,
+                end: 160
+            }
+        );
+    }
+
+    #[test]
+    fn undeclared_effects() {
+        let input = format!(
+            "{}effect Http {{ fn get(url: String) -> String }}
+            fn fetch(url: String) -> String emits Http {{ log(url) get(url) }}
+            fn run() emits Log {{ fetch(\"/\") }}",
+            LOG
+        );
+        let diagnostics = check(&input);
+        assert_eq!(codes(&diagnostics), ["undeclared-effect"; 2]);
+        assert_eq!(
+            diagnostics[0].message,
+            "effect `Log` is performed by `fetch` but not declared in its effect row"
+        );
+        assert!(diagnostics[1]
+            .message
+            .contains("`Http` is performed by `run`"));
+    }
+
+    #[test]
+    fn shadowed_operations() {
+        let input = format!(
+            "{}fn greet(log: fn(String) -> ()) {{ log(\"hi\") }}
+            fn main() {{
+              let log = print
+              log(\"hi\")
+            }}",
+            LOG
+        );
+        assert_eq!(check(&input), []);
+    }
+
+    #[test]
+    fn function_values() {
+        let input = format!(
+            "{}fn noisy() emits Log {{ log(\"noise\") }}
+            fn pure(k: fn() -> ()) {{ k() }}
+            fn run(k: fn() -> () emits Log) {{ k() }}
+            fn main() {{
+              pure(noisy)
+              let f = noisy
+              f()
+              with Log {{ fn log(msg) {{ print(msg) }} }}
+              run(noisy)
+            }}",
+            LOG
+        );
+        let diagnostics = check(&input);
+        assert_eq!(
+            codes(&diagnostics),
+            ["undeclared-effect", "undeclared-effect", "unhandled-effect"]
+        );
+        assert_eq!(
+            diagnostics[0].message,
+            "effect `Log` is performed by `run` but not declared in its effect row"
+        );
+        assert_eq!(
+            diagnostics[1].message,
+            "effect `Log` is performed by this function but not declared in the type of `k`"
+        );
+        assert_eq!(
+            diagnostics[1].loc.start,
+            input.find("noisy)").unwrap() as u32
+        );
+        assert_eq!(diagnostics[2].loc.start, input.find("f()").unwrap() as u32);
+    }
+
+    #[test]
+    fn invalid_handlers() {
+        let input = format!(
+            "{}type Shape = {{ size: Int }}
+            fn main() {{
+              with Log {{ fn write(msg) {{ }} }}
+              with Shape {{ }}
+            }}",
+            LOG
+        );
+        let diagnostics = check(&input);
+        assert_eq!(
+            codes(&diagnostics),
+            ["unknown-operation", "missing-operation", "unknown-effect"]
+        );
+        assert_eq!(diagnostics[0].labels[0].loc, ASTLoc { start: 0, end: 34 });
+        assert_eq!(
+            diagnostics[1].message,
+            "handler of `Log` does not implement `log`"
+        );
+        assert_eq!(
+            diagnostics[2].message,
+            "expected an effect, found the type `Shape`"
+        );
+    }
+
+    #[test]
+    fn imported_effects() {
+        let input = "import { Http } from \"./http\"\nfn fetch() emits Http { Http.get(\"/\") }";
+        assert_eq!(check(input), []);
+
+        let diagnostics = check("import { Http } from \"./http\"\nfn fetch() emits Int { }");
+        assert_eq!(codes(&diagnostics), ["unknown-effect"]);
+        assert!(diagnostics[0].labels.is_empty());
+    }
+
+    #[test]
+    fn effects_across_modules() {
+        let mut loader = MemoryFileLoader::new();
+        loader.insert(
+            "log.rbn",
+            "pub effect Log { fn log(msg: String) }
+pub fn shout(msg: String) emits Log { log(msg) }",
+        );
+        let main = "import { Log as Logger, shout } from \"./log\"
+fn quiet() { shout(\"a\") }
+fn loud() emits Logger { shout(\"b\") }
+fn main() {
+  with Logger { fn log(msg) { print(msg) } }
+  shout(\"c\")
+  Logger.log(\"d\")
+}
+view App {
+  with Logger { fn write(msg) { } }
+  Logger.send(\"e\")
+}";
+        loader.insert("main.rbn", main);
+        let resolution = ModuleResolver::new(loader).resolve("main.rbn");
+        assert_eq!(resolution.diagnostics, []);
+
+        let entry = resolution.entry.unwrap();
+        let module = resolution.graph.module(entry);
+        let ast = Ast::new(&module.ast);
+        let res = Resolver::new(&module.path, &ast)
+            .with_globals(["print"])
+            .resolve();
+        let diagnostics = EffectChecker::new(&module.path, &ast, &res)
+            .with_imports(&resolution.graph, entry)
+            .check();
+        assert_eq!(
+            codes(&diagnostics),
+            [
+                "undeclared-effect",
+                "unknown-operation",
+                "missing-operation",
+                "unknown-operation",
+            ]
+        );
+        assert_eq!(
+            diagnostics[0].message,
+            "effect `Log` is performed by `quiet` but not declared in its effect row"
+        );
+        assert_eq!(
+            diagnostics[0].loc.start,
+            main.find("shout(\"a").unwrap() as u32
+        );
+        assert_eq!(diagnostics[1].labels[0].module, "log.rbn");
+        assert_eq!(
+            diagnostics[3].message,
+            "effect `Log` has no operation `send`"
+        );
+    }
+}
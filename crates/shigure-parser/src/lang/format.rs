@@ -0,0 +1,1258 @@
+//! Formats modules into canonical Ribbon source, as done by `ribbon fmt`.
+//!
+//! The formatter prints the AST of a module again, breaking the constructs
+//! which do not fit in [`FormatConfig::max_width`] columns. Comments are
+//! taken from the syntax tree and placed before the item, statement, element
+//! or list element following them, or after the one they trail on the same
+//! line. Blank lines between these are kept, but never more than one.
+//! Formatting the output of the formatter again leaves it unchanged.
+
+mod doc;
+
+use crate::lang::{
+    ast::{
+        ASTExprKind, ASTItemConst, ASTItemEffect, ASTItemFn, ASTItemImport, ASTItemType,
+        ASTItemUse, ASTItemView, ASTLoc, ASTNodeBlock, ASTNodeEffectOp, ASTNodeExpr,
+        ASTNodeHandlerOp, ASTNodeImportName, ASTNodeItemAttribute, ASTNodeModule, ASTNodeParam,
+        ASTNodePattern, ASTNodeRecordField, ASTNodeScoped, ASTNodeStmt, ASTNodeType,
+        ASTNodeTypeField, ASTNodeTypeParam, ASTNodeTypeVariant, ASTNodeViewAttribute,
+        ASTNodeViewChild, ASTNodeViewElement, ASTNodeWhenArm, ASTPatternKind, ASTTypeBody,
+        ASTTypeKind, Spanned, TokenContent, TokenLiteral,
+    },
+    config::{expect_bool, expect_str, expect_usize, ConfigValue},
+    parser::{module::ModuleParser, ParseError, ParseResult, Parser},
+    syntax::SyntaxNode,
+};
+use doc::Doc;
+
+/// Where comma separated lists get a comma after their last element.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum TrailingComma {
+    Always,
+    Never,
+    /// Only when the list is broken into one element per line.
+    #[default]
+    Vertical,
+}
+
+/// The settings of the formatter, read from the `[fmt]` section of
+/// `ribbon.toml`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FormatConfig {
+    /// The width lines are kept within where the syntax allows a break.
+    pub max_width: usize,
+    /// The number of spaces per level of indentation.
+    pub indent_width: usize,
+    pub trailing_comma: TrailingComma,
+    /// Puts each attribute of a view tag with more than one attribute on its
+    /// own line, even if the tag would fit on one line.
+    pub single_attribute_per_line: bool,
+}
+
+impl Default for FormatConfig {
+    fn default() -> Self {
+        Self {
+            max_width: 100,
+            indent_width: 2,
+            trailing_comma: TrailingComma::default(),
+            single_attribute_per_line: false,
+        }
+    }
+}
+
+impl FormatConfig {
+    /// Applies `key = value` of the `[fmt]` section, or returns an error
+    /// message.
+    pub(crate) fn set(&mut self, key: &str, value: &ConfigValue) -> Result<(), String> {
+        match key {
+            "max_width" => self.max_width = expect_usize(key, value)?,
+            "indent_width" => self.indent_width = expect_usize(key, value)?,
+            "trailing_comma" => {
+                self.trailing_comma = match expect_str(key, value)? {
+                    "always" => TrailingComma::Always,
+                    "never" => TrailingComma::Never,
+                    "vertical" => TrailingComma::Vertical,
+                    _ => {
+                        return Err(format!(
+                            "`{key}` must be one of \"always\", \"never\" or \"vertical\""
+                        ))
+                    }
+                }
+            }
+            "single_attribute_per_line" => {
+                self.single_attribute_per_line = expect_bool(key, value)?
+            }
+            _ => return Err(format!("unknown setting `{key}`")),
+        }
+        Ok(())
+    }
+}
+
+/// Parses `source` as a module and returns it formatted.
+pub fn format_source(source: &str, config: &FormatConfig) -> ParseResult<String> {
+    let mut parser = ModuleParser::from_str(source);
+    let module = parser.parse_all()?;
+    let syntax = parser.syntax().ok_or(ParseError::SyntaxError)?;
+
+    let mut formatter = Formatter::new(source, &syntax, config);
+    let doc = formatter.module(&module);
+    Ok(doc::print(&doc, config.max_width, config.indent_width))
+}
+
+#[derive(Debug, Clone)]
+struct Comment {
+    loc: ASTLoc,
+    text: String,
+    /// Whether only whitespace precedes the comment on its line.
+    own_line: bool,
+    /// Whether an empty line precedes the comment.
+    blank_before: bool,
+}
+
+impl Comment {
+    fn is_line(&self) -> bool {
+        self.text.starts_with("//")
+    }
+}
+
+/// Whether an empty line directly precedes `pos`.
+fn blank_before(source: &str, pos: u32) -> bool {
+    source[..pos as usize]
+        .chars()
+        .rev()
+        .take_while(|c| c.is_whitespace())
+        .filter(|&c| c == '\n')
+        .count()
+        >= 2
+}
+
+/// How a comma separated list ends.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum ListEnd {
+    /// As configured by [`FormatConfig::trailing_comma`].
+    Configured,
+    /// Always with a comma, as in the one element tuple type `(T,)`.
+    Comma,
+}
+
+struct Formatter<'a> {
+    source: &'a str,
+    config: &'a FormatConfig,
+    comments: Vec<Comment>,
+    /// The first comment which has not been printed yet.
+    next: usize,
+}
+
+impl<'a> Formatter<'a> {
+    fn new(source: &'a str, syntax: &SyntaxNode, config: &'a FormatConfig) -> Self {
+        let comments = syntax
+            .tokens()
+            .into_iter()
+            .filter(|token| token.con() == &TokenContent::Comment)
+            .map(|token| {
+                let loc = token.loc();
+                let line = source[..loc.start as usize].rsplit('\n').next();
+                Comment {
+                    loc,
+                    text: token.text().trim_end().to_string(),
+                    own_line: line.unwrap_or_default().trim().is_empty(),
+                    blank_before: blank_before(source, loc.start),
+                }
+            })
+            .collect();
+
+        Self {
+            source,
+            config,
+            comments,
+            next: 0,
+        }
+    }
+
+    /// Takes the next comment if it starts before `pos`.
+    fn take_comment(&mut self, pos: u32) -> Option<Comment> {
+        let comment = self.comments.get(self.next)?;
+        if comment.loc.start >= pos {
+            return None;
+        }
+        self.next += 1;
+        Some(comment.clone())
+    }
+
+    fn has_comment_before(&self, pos: u32) -> bool {
+        self.comments
+            .get(self.next)
+            .is_some_and(|comment| comment.loc.start < pos)
+    }
+
+    /// Prints the comments before a node starting at `start`, each on its
+    /// own line, and the empty lines before them and the node. Empty lines
+    /// are left out before the first node of a list.
+    fn leading(&mut self, start: u32, first: bool) -> Vec<Doc> {
+        let mut docs = Vec::new();
+        let mut first = first;
+        while let Some(comment) = self.take_comment(start) {
+            if comment.blank_before && !first {
+                docs.push(Doc::HardLine);
+            }
+            docs.push(Doc::Text(comment.text));
+            docs.push(Doc::HardLine);
+            first = false;
+        }
+        if !first && blank_before(self.source, start) {
+            docs.push(Doc::HardLine);
+        }
+        docs
+    }
+
+    /// Prints the comments following a node ending at `end` on the same line,
+    /// optionally after a comma.
+    fn trailing(&mut self, end: u32) -> Doc {
+        let mut docs = Vec::new();
+        let mut end = end;
+        while let Some(comment) = self.comments.get(self.next) {
+            if comment.own_line || comment.loc.start < end {
+                break;
+            }
+            let between = &self.source[end as usize..comment.loc.start as usize];
+            if !matches!(between.trim(), "" | ",") || between.contains('\n') {
+                break;
+            }
+
+            docs.push(Doc::text(" "));
+            docs.push(Doc::text(comment.text.clone()));
+            if comment.is_line() {
+                docs.push(Doc::BreakParent);
+            }
+            end = comment.loc.end;
+            self.next += 1;
+        }
+        Doc::Concat(docs)
+    }
+
+    /// Prints the comments left before `end`, the end of a list, each on its
+    /// own line unless it is the first thing in the list.
+    fn dangling(&mut self, end: u32, first: bool) -> Vec<Doc> {
+        let mut docs = Vec::new();
+        let mut first = first;
+        while let Some(comment) = self.take_comment(end) {
+            if !first {
+                docs.push(Doc::HardLine);
+                if comment.blank_before {
+                    docs.push(Doc::HardLine);
+                }
+            }
+            let is_line = comment.is_line();
+            docs.push(Doc::Text(comment.text));
+            if is_line {
+                docs.push(Doc::BreakParent);
+            }
+            first = false;
+        }
+        docs
+    }
+
+    /// Prints `items` on separate lines, followed by the comments left
+    /// before `end`. Returns `None` if there is nothing to print.
+    fn lines<T: Spanned>(
+        &mut self,
+        items: &[T],
+        end: u32,
+        f: impl Fn(&mut Self, &T) -> Doc,
+    ) -> Option<Doc> {
+        let mut docs = Vec::new();
+        for (idx, item) in items.iter().enumerate() {
+            if idx > 0 {
+                docs.push(Doc::HardLine);
+            }
+            docs.extend(self.leading(item.loc().start, idx == 0));
+            docs.push(f(self, item));
+            docs.push(self.trailing(item.loc().end));
+        }
+        docs.extend(self.dangling(end, items.is_empty()));
+        (!docs.is_empty()).then_some(Doc::Concat(docs))
+    }
+
+    /// Prints `{ items }` with one item per line.
+    fn braced_lines<T: Spanned>(
+        &mut self,
+        items: &[T],
+        end: u32,
+        f: impl Fn(&mut Self, &T) -> Doc,
+    ) -> Doc {
+        match self.lines(items, end, f) {
+            Some(doc) => Doc::concat([
+                Doc::text("{"),
+                Doc::nest(Doc::concat([Doc::HardLine, doc])),
+                Doc::HardLine,
+                Doc::text("}"),
+            ]),
+            None => Doc::text("{}"),
+        }
+    }
+
+    /// Prints a comma separated list, which is broken into one element per
+    /// line if it does not fit. A `padded` list has spaces inside of its
+    /// delimiters, as in `{ x: 1 }`. The comments left before `end` are
+    /// printed at the end of the list.
+    #[allow(clippy::too_many_arguments)]
+    fn delimited<T: Spanned>(
+        &mut self,
+        open: &str,
+        items: &[T],
+        close: &str,
+        padded: bool,
+        list_end: ListEnd,
+        end: Option<u32>,
+        f: impl Fn(&mut Self, &T) -> Doc,
+    ) -> Doc {
+        let line = match padded {
+            true => Doc::Line,
+            false => Doc::SoftLine,
+        };
+
+        let mut inner = Vec::new();
+        for (idx, item) in items.iter().enumerate() {
+            inner.push(if idx == 0 { line.clone() } else { Doc::Line });
+            inner.extend(self.leading(item.loc().start, idx == 0));
+            inner.push(f(self, item));
+            inner.push(match idx + 1 < items.len() {
+                true => Doc::text(","),
+                false => self.trailing_comma(list_end),
+            });
+            inner.push(self.trailing(item.loc().end));
+        }
+        if let Some(end) = end {
+            let dangling = self.dangling(end, items.is_empty());
+            if items.is_empty() && !dangling.is_empty() {
+                inner.push(line.clone());
+            }
+            inner.extend(dangling);
+        }
+
+        if inner.is_empty() {
+            return Doc::text(format!("{open}{close}"));
+        }
+        Doc::group(Doc::concat([
+            Doc::text(open),
+            Doc::nest(Doc::Concat(inner)),
+            line,
+            Doc::text(close),
+        ]))
+    }
+
+    fn trailing_comma(&self, list_end: ListEnd) -> Doc {
+        match (list_end, self.config.trailing_comma) {
+            (ListEnd::Comma, _) | (_, TrailingComma::Always) => Doc::text(","),
+            (_, TrailingComma::Never) => Doc::text(""),
+            (_, TrailingComma::Vertical) => Doc::if_break(Doc::text(","), Doc::text("")),
+        }
+    }
+
+    fn module(&mut self, module: &ASTNodeModule) -> Doc {
+        let end = self.source.len() as u32;
+        match self.lines(&module.nodes, end, Self::item) {
+            Some(doc) => Doc::concat([doc, Doc::HardLine]),
+            None => Doc::text(""),
+        }
+    }
+
+    fn item(&mut self, item: &ASTNodeScoped) -> Doc {
+        let doc = match item {
+            ASTNodeScoped::Const(i) => self.const_item(i),
+            ASTNodeScoped::Effect(i) => self.effect(i),
+            ASTNodeScoped::Fn(i) => self.fn_item(i),
+            ASTNodeScoped::Import(i) => self.import(i),
+            ASTNodeScoped::Type(i) => self.type_item(i),
+            ASTNodeScoped::Use(i) => self.use_item(i),
+            ASTNodeScoped::View(i) => self.view(i),
+        };
+        let doc = match item.visibility().is_public() {
+            true => Doc::concat([Doc::text("pub "), doc]),
+            false => doc,
+        };
+        let mut docs: Vec<_> = item
+            .attributes()
+            .iter()
+            .flat_map(|attribute| [Self::item_attribute(attribute), Doc::HardLine])
+            .collect();
+        docs.push(doc);
+        Doc::Concat(docs)
+    }
+
+    fn item_attribute(attribute: &ASTNodeItemAttribute) -> Doc {
+        let args: Vec<_> = attribute.args.iter().map(|arg| arg.name.as_str()).collect();
+        match args.is_empty() {
+            true => Doc::text(format!("@{}", attribute.name)),
+            false => Doc::text(format!("@{}({})", attribute.name, args.join(", "))),
+        }
+    }
+
+    fn const_item(&mut self, item: &ASTItemConst) -> Doc {
+        Doc::concat([
+            Doc::text(format!("const {}", item.name)),
+            self.annotation(&item.ty),
+            Doc::text(" = "),
+            self.expr(&item.value),
+        ])
+    }
+
+    fn effect(&mut self, item: &ASTItemEffect) -> Doc {
+        Doc::concat([
+            Doc::text(format!("effect {} ", item.name)),
+            self.braced_lines(&item.ops, item.loc().end, Self::effect_op),
+        ])
+    }
+
+    fn effect_op(&mut self, op: &ASTNodeEffectOp) -> Doc {
+        Doc::concat([
+            Doc::text(format!("fn {}", op.name)),
+            self.params(&op.params),
+            self.ret(&op.ret),
+        ])
+    }
+
+    fn fn_item(&mut self, item: &ASTItemFn) -> Doc {
+        Doc::concat([
+            Doc::text(match item.is_const {
+                true => format!("const fn {}", item.name),
+                false => format!("fn {}", item.name),
+            }),
+            Self::type_params(&item.type_params),
+            self.params(&item.params),
+            self.ret(&item.ret),
+            self.effect_row(&item.effects),
+            Doc::text(" "),
+            self.block(&item.body),
+        ])
+    }
+
+    fn import(&mut self, item: &ASTItemImport) -> Doc {
+        Doc::concat([
+            Doc::text("import "),
+            self.delimited(
+                "{",
+                &item.names,
+                "}",
+                true,
+                ListEnd::Configured,
+                None,
+                Self::import_name,
+            ),
+            Doc::text(format!(" from \"{}\"", item.source)),
+        ])
+    }
+
+    fn import_name(&mut self, name: &ASTNodeImportName) -> Doc {
+        match &name.alias {
+            Some(alias) => Doc::text(format!("{} as {}", name.name, alias)),
+            None => Doc::text(name.name.clone()),
+        }
+    }
+
+    fn type_item(&mut self, item: &ASTItemType) -> Doc {
+        let mut docs = vec![
+            Doc::text(format!("type {}", item.name)),
+            Self::type_params(&item.params),
+        ];
+        match &item.body {
+            ASTTypeBody::Alias(ty) => {
+                docs.push(Doc::text(" = "));
+                docs.push(self.ty(ty));
+            }
+            ASTTypeBody::Record(fields) => {
+                docs.push(Doc::text(" = "));
+                docs.push(self.delimited(
+                    "{",
+                    fields,
+                    "}",
+                    true,
+                    ListEnd::Configured,
+                    Some(item.loc().end),
+                    Self::type_field,
+                ));
+            }
+            ASTTypeBody::Union(variants) => {
+                docs.push(Doc::text(" ="));
+                docs.push(self.union(variants));
+            }
+        }
+        Doc::Concat(docs)
+    }
+
+    /// Prints generic parameters as `<T: Eq + Ord, U>`, or nothing if there
+    /// are none.
+    fn type_params(params: &[ASTNodeTypeParam]) -> Doc {
+        if params.is_empty() {
+            return Doc::text("");
+        }
+        let params: Vec<_> = params
+            .iter()
+            .map(|param| {
+                let bounds: Vec<_> = param.bounds.iter().map(|b| b.name.as_str()).collect();
+                match bounds.is_empty() {
+                    true => param.name.clone(),
+                    false => format!("{}: {}", param.name, bounds.join(" + ")),
+                }
+            })
+            .collect();
+        Doc::text(format!("<{}>", params.join(", ")))
+    }
+
+    /// Prints the variants of a union after `=`, either as `A | B` or with
+    /// each variant on its own line after a leading `|`.
+    fn union(&mut self, variants: &[ASTNodeTypeVariant]) -> Doc {
+        // `type A = B` would declare an alias.
+        let bare = matches!(variants, [variant] if variant.fields.is_empty());
+        let mut docs = Vec::new();
+        for (idx, variant) in variants.iter().enumerate() {
+            docs.push(Doc::Line);
+            docs.extend(self.leading(variant.loc().start, idx == 0));
+            docs.push(match idx == 0 && !bare {
+                true => Doc::if_break(Doc::text("| "), Doc::text("")),
+                false => Doc::text("| "),
+            });
+            docs.push(self.variant(variant));
+            // A comment after the last variant trails the whole item.
+            if idx + 1 < variants.len() {
+                docs.push(self.trailing(variant.loc().end));
+            }
+        }
+        Doc::group(Doc::nest(Doc::Concat(docs)))
+    }
+
+    fn variant(&mut self, variant: &ASTNodeTypeVariant) -> Doc {
+        if variant.fields.is_empty() {
+            return Doc::text(variant.name.clone());
+        }
+        Doc::concat([
+            Doc::text(variant.name.clone()),
+            self.delimited(
+                "(",
+                &variant.fields,
+                ")",
+                false,
+                ListEnd::Configured,
+                Some(variant.loc().end),
+                Self::ty,
+            ),
+        ])
+    }
+
+    fn type_field(&mut self, field: &ASTNodeTypeField) -> Doc {
+        Doc::concat([Doc::text(format!("{}: ", field.name)), self.ty(&field.ty)])
+    }
+
+    fn use_item(&mut self, item: &ASTItemUse) -> Doc {
+        let path = item.path.join("::");
+        match &item.alias {
+            Some(alias) => Doc::text(format!("use {path} as {alias}")),
+            None => Doc::text(format!("use {path}")),
+        }
+    }
+
+    fn view(&mut self, item: &ASTItemView) -> Doc {
+        let params = match item.params.is_empty() {
+            true => Doc::text(""),
+            false => self.params(&item.params),
+        };
+        Doc::concat([
+            Doc::text(format!("view {}", item.name)),
+            Self::type_params(&item.type_params),
+            params,
+            Doc::text(" "),
+            self.block(&item.body),
+        ])
+    }
+
+    fn params(&mut self, params: &[ASTNodeParam]) -> Doc {
+        self.delimited(
+            "(",
+            params,
+            ")",
+            false,
+            ListEnd::Configured,
+            None,
+            Self::param,
+        )
+    }
+
+    fn param(&mut self, param: &ASTNodeParam) -> Doc {
+        Doc::concat([Doc::text(param.name.clone()), self.annotation(&param.ty)])
+    }
+
+    fn annotation(&mut self, ty: &Option<ASTNodeType>) -> Doc {
+        match ty {
+            Some(ty) => Doc::concat([Doc::text(": "), self.ty(ty)]),
+            None => Doc::text(""),
+        }
+    }
+
+    fn ret(&mut self, ret: &Option<ASTNodeType>) -> Doc {
+        match ret {
+            Some(ty) => Doc::concat([Doc::text(" -> "), self.ty(ty)]),
+            None => Doc::text(""),
+        }
+    }
+
+    fn effect_row(&mut self, effects: &[ASTNodeType]) -> Doc {
+        let mut docs = Vec::new();
+        for (idx, effect) in effects.iter().enumerate() {
+            docs.push(Doc::text(if idx == 0 { " emits " } else { " + " }));
+            docs.push(self.ty(effect));
+        }
+        Doc::Concat(docs)
+    }
+
+    fn block(&mut self, block: &ASTNodeBlock) -> Doc {
+        self.braced_lines(&block.stmts, block.loc().end, Self::stmt)
+    }
+
+    /// Prints a block of an `if` expression, which stays on one line if it
+    /// only holds an expression.
+    fn inline_block(&mut self, block: &ASTNodeBlock) -> Doc {
+        match block.stmts.as_slice() {
+            [ASTNodeStmt::Expr(expr)] if !self.has_comment_before(block.loc().end) => {
+                Doc::concat([
+                    Doc::text("{"),
+                    Doc::nest(Doc::concat([Doc::Line, self.expr(expr)])),
+                    Doc::Line,
+                    Doc::text("}"),
+                ])
+            }
+            _ => self.block(block),
+        }
+    }
+
+    fn stmt(&mut self, stmt: &ASTNodeStmt) -> Doc {
+        match stmt {
+            ASTNodeStmt::Let(s) => Doc::concat([
+                Doc::text(format!("let {}", s.name)),
+                self.annotation(&s.ty),
+                Doc::text(" = "),
+                self.expr(&s.value),
+            ]),
+            ASTNodeStmt::Const(s) => self.const_item(s),
+            ASTNodeStmt::With(s) => Doc::concat([
+                Doc::text("with "),
+                self.ty(&s.effect),
+                Doc::text(" "),
+                self.braced_lines(&s.ops, s.loc().end, Self::handler_op),
+            ]),
+            ASTNodeStmt::Effect(s) => Doc::concat([Doc::text("effect "), self.block(&s.body)]),
+            ASTNodeStmt::Assign(s) => {
+                Doc::concat([self.expr(&s.target), Doc::text(" = "), self.expr(&s.value)])
+            }
+            ASTNodeStmt::Element(s) => self.element(s),
+            ASTNodeStmt::Expr(s) => self.expr(s),
+        }
+    }
+
+    fn handler_op(&mut self, op: &ASTNodeHandlerOp) -> Doc {
+        Doc::concat([
+            Doc::text(format!("fn {}", op.name)),
+            self.params(&op.params),
+            Doc::text(" "),
+            self.block(&op.body),
+        ])
+    }
+
+    fn element(&mut self, element: &ASTNodeViewElement) -> Doc {
+        let mut head = format!("<{}", element.name);
+        if let Some(anchor) = &element.anchor {
+            head = format!("{head}#{anchor}");
+        }
+
+        let mut attrs = Vec::new();
+        for (idx, attr) in element.attributes.iter().enumerate() {
+            attrs.push(Doc::Line);
+            attrs.extend(self.leading(attr.loc().start, idx == 0));
+            attrs.push(self.attribute(attr));
+            attrs.push(self.trailing(attr.loc().end));
+        }
+        if self.config.single_attribute_per_line && element.attributes.len() > 1 {
+            attrs.push(Doc::BreakParent);
+        }
+
+        if element.children.is_empty() {
+            return Doc::group(Doc::concat([
+                Doc::text(head),
+                Doc::nest(Doc::Concat(attrs)),
+                Doc::Line,
+                Doc::text("/>"),
+            ]));
+        }
+
+        let open = Doc::group(Doc::concat([
+            Doc::text(head),
+            Doc::nest(Doc::Concat(attrs)),
+            Doc::SoftLine,
+            Doc::text(">"),
+        ]));
+        let mut children = Vec::new();
+        for (idx, child) in element.children.iter().enumerate() {
+            children.push(if idx == 0 { Doc::SoftLine } else { Doc::Line });
+            children.extend(self.leading(child.loc().start, idx == 0));
+            children.push(self.view_child(child));
+            children.push(self.trailing(child.loc().end));
+        }
+        children.extend(self.dangling(element.loc().end, false));
+
+        Doc::group(Doc::concat([
+            open,
+            Doc::nest(Doc::Concat(children)),
+            Doc::SoftLine,
+            Doc::text(format!("</{}>", element.name)),
+        ]))
+    }
+
+    fn attribute(&mut self, attr: &ASTNodeViewAttribute) -> Doc {
+        match &attr.value {
+            None => Doc::text(attr.name.clone()),
+            Some(ASTNodeExpr {
+                kind: ASTExprKind::Literal(literal @ TokenLiteral::StringLiteral(_)),
+                ..
+            }) => Doc::text(format!("{}={}", attr.name, literal.content())),
+            Some(value) => Doc::concat([
+                Doc::text(format!("{}={{", attr.name)),
+                self.expr(value),
+                Doc::text("}"),
+            ]),
+        }
+    }
+
+    fn view_child(&mut self, child: &ASTNodeViewChild) -> Doc {
+        match child {
+            ASTNodeViewChild::Element(element) => self.element(element),
+            ASTNodeViewChild::Text(text) => self.expr(text),
+            ASTNodeViewChild::Expr(expr) => {
+                Doc::concat([Doc::text("{"), self.expr(expr), Doc::text("}")])
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &ASTNodeExpr) -> Doc {
+        let end = Some(expr.loc().end);
+        match &expr.kind {
+            ASTExprKind::Literal(literal) => Doc::text(literal.content()),
+            ASTExprKind::Nil => Doc::text("nil"),
+            ASTExprKind::Anchor(name) => Doc::text(format!("#{}", name)),
+            ASTExprKind::Identifier(name) => Doc::text(name.clone()),
+            ASTExprKind::Call { callee, args } => Doc::concat([
+                self.postfix_operand(callee),
+                self.delimited("(", args, ")", false, ListEnd::Configured, end, Self::expr),
+            ]),
+            ASTExprKind::Member { object, name } => {
+                Doc::concat([self.postfix_operand(object), Doc::text(format!(".{name}"))])
+            }
+            ASTExprKind::Binary { op, lhs, rhs } => {
+                let prec = op.precedence();
+                // Operators are left associative, so an operand on the right
+                // with the same precedence needs parentheses.
+                Doc::concat([
+                    self.binary_operand(lhs, |p| p < prec),
+                    Doc::text(format!(" {} ", op.as_str())),
+                    self.binary_operand(rhs, |p| p <= prec),
+                ])
+            }
+            ASTExprKind::Unary { op, expr } => {
+                // `--1` and `!!a` would not read back as nested operators.
+                let operand = match expr.kind {
+                    ASTExprKind::Binary { .. } | ASTExprKind::Unary { .. } => {
+                        self.parenthesized(expr)
+                    }
+                    _ => self.expr(expr),
+                };
+                Doc::concat([Doc::text(op.as_str()), operand])
+            }
+            ASTExprKind::If { .. } => Doc::group(self.if_chain(expr)),
+            ASTExprKind::Block(block) => self.block(block),
+            ASTExprKind::For {
+                pattern,
+                iter,
+                body,
+            } => Doc::concat([
+                Doc::text("for "),
+                self.pattern(pattern),
+                Doc::text(" in "),
+                self.expr(iter),
+                Doc::text(" "),
+                self.block(body),
+            ]),
+            ASTExprKind::When { subject, arms } => Doc::concat([
+                Doc::text("when "),
+                self.expr(subject),
+                Doc::text(" "),
+                self.braced_lines(arms, expr.loc().end, Self::when_arm),
+            ]),
+            ASTExprKind::Record(fields) => self.delimited(
+                "{",
+                fields,
+                "}",
+                true,
+                ListEnd::Configured,
+                end,
+                Self::record_field,
+            ),
+            ASTExprKind::List(items) => {
+                self.delimited("[", items, "]", false, ListEnd::Configured, end, Self::expr)
+            }
+            ASTExprKind::Tuple(items) => {
+                let list_end = match items.len() {
+                    1 => ListEnd::Comma,
+                    _ => ListEnd::Configured,
+                };
+                self.delimited("(", items, ")", false, list_end, end, Self::expr)
+            }
+        }
+    }
+
+    /// Prints an `if` expression and the `else if` branches following it,
+    /// whose blocks are all broken into lines together.
+    fn if_chain(&mut self, expr: &ASTNodeExpr) -> Doc {
+        let ASTExprKind::If { cond, then, els } = &expr.kind else {
+            return self.expr(expr);
+        };
+
+        let mut docs = vec![
+            Doc::text("if "),
+            self.expr(cond),
+            Doc::text(" "),
+            self.inline_block(then),
+        ];
+        if let Some(els) = els {
+            docs.push(Doc::text(" else "));
+            docs.push(match &els.kind {
+                ASTExprKind::If { .. } => self.if_chain(els),
+                ASTExprKind::Block(block) => self.inline_block(block),
+                _ => self.expr(els),
+            });
+        }
+        Doc::Concat(docs)
+    }
+
+    fn when_arm(&mut self, arm: &ASTNodeWhenArm) -> Doc {
+        Doc::concat([
+            self.pattern(&arm.pattern),
+            Doc::text(" -> "),
+            self.expr(&arm.body),
+        ])
+    }
+
+    fn pattern(&mut self, pattern: &ASTNodePattern) -> Doc {
+        let end = Some(pattern.loc().end);
+        match &pattern.kind {
+            ASTPatternKind::Wildcard => Doc::text("_"),
+            ASTPatternKind::Binding(name) => Doc::text(name.clone()),
+            ASTPatternKind::Literal(literal) => Doc::text(literal.content()),
+            ASTPatternKind::Nil => Doc::text("nil"),
+            // A capitalized name is a variant even without parentheses.
+            ASTPatternKind::Variant { name, fields }
+                if fields.is_empty() && name.starts_with(|c: char| c.is_ascii_uppercase()) =>
+            {
+                Doc::text(name.clone())
+            }
+            ASTPatternKind::Variant { name, fields } => Doc::concat([
+                Doc::text(name.clone()),
+                self.delimited(
+                    "(",
+                    fields,
+                    ")",
+                    false,
+                    ListEnd::Configured,
+                    end,
+                    Self::pattern,
+                ),
+            ]),
+            ASTPatternKind::Tuple(items) => self.delimited(
+                "(",
+                items,
+                ")",
+                false,
+                ListEnd::Configured,
+                end,
+                Self::pattern,
+            ),
+        }
+    }
+
+    fn record_field(&mut self, field: &ASTNodeRecordField) -> Doc {
+        Doc::concat([
+            Doc::text(format!("{}: ", field.name)),
+            self.expr(&field.value),
+        ])
+    }
+
+    fn parenthesized(&mut self, expr: &ASTNodeExpr) -> Doc {
+        Doc::concat([Doc::text("("), self.expr(expr), Doc::text(")")])
+    }
+
+    /// Prints an operand of a binary operator, in parentheses if it is a
+    /// binary expression whose precedence satisfies `needs_parens`.
+    fn binary_operand(&mut self, expr: &ASTNodeExpr, needs_parens: impl Fn(u8) -> bool) -> Doc {
+        match expr.kind {
+            ASTExprKind::Binary { op, .. } if needs_parens(op.precedence()) => {
+                self.parenthesized(expr)
+            }
+            _ => self.expr(expr),
+        }
+    }
+
+    /// Prints the callee of a call or the object of a member access. A number
+    /// is parenthesized, as the `.` of `2.5.x` would be read as part of it.
+    fn postfix_operand(&mut self, expr: &ASTNodeExpr) -> Doc {
+        match expr.kind {
+            ASTExprKind::Binary { .. }
+            | ASTExprKind::Unary { .. }
+            | ASTExprKind::Literal(TokenLiteral::NumberLiteral(_)) => self.parenthesized(expr),
+            _ => self.expr(expr),
+        }
+    }
+
+    fn ty(&mut self, ty: &ASTNodeType) -> Doc {
+        match &ty.kind {
+            ASTTypeKind::Named { name, args } => {
+                if args.is_empty() {
+                    return Doc::text(name.clone());
+                }
+                let mut docs = vec![Doc::text(format!("{name}<"))];
+                for (idx, arg) in args.iter().enumerate() {
+                    if idx > 0 {
+                        docs.push(Doc::text(", "));
+                    }
+                    docs.push(self.ty(arg));
+                }
+                docs.push(Doc::text(">"));
+                Doc::Concat(docs)
+            }
+            ASTTypeKind::Fn {
+                params,
+                ret,
+                effects,
+            } => {
+                // The effect row of `fn() -> fn() -> A emits E` belongs to
+                // the inner function type.
+                let ret = match (&ret.kind, effects.is_empty()) {
+                    (ASTTypeKind::Fn { .. }, false) => {
+                        Doc::concat([Doc::text("("), self.ty(ret), Doc::text(")")])
+                    }
+                    _ => self.ty(ret),
+                };
+                Doc::concat([
+                    Doc::text("fn"),
+                    self.delimited("(", params, ")", false, ListEnd::Configured, None, Self::ty),
+                    Doc::text(" -> "),
+                    ret,
+                    self.effect_row(effects),
+                ])
+            }
+            ASTTypeKind::Optional(inner) => match inner.kind {
+                ASTTypeKind::Fn { .. } => {
+                    Doc::concat([Doc::text("("), self.ty(inner), Doc::text(")?")])
+                }
+                _ => Doc::concat([self.ty(inner), Doc::text("?")]),
+            },
+            ASTTypeKind::List(inner) => {
+                Doc::concat([Doc::text("["), self.ty(inner), Doc::text("]")])
+            }
+            ASTTypeKind::Tuple(items) => {
+                let list_end = match items.len() {
+                    1 => ListEnd::Comma,
+                    _ => ListEnd::Configured,
+                };
+                self.delimited("(", items, ")", false, list_end, None, Self::ty)
+            }
+            ASTTypeKind::Record(fields) => self.delimited(
+                "{",
+                fields,
+                "}",
+                true,
+                ListEnd::Configured,
+                None,
+                Self::type_field,
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn format(input: &str, config: &FormatConfig) -> String {
+        let output = format_source(input, config).expect("failed to format");
+        assert_eq!(
+            format_source(&output, config).unwrap(),
+            output,
+            "formatting is not idempotent"
+        );
+        assert_eq!(shape(&output), shape(input), "formatting changed the AST");
+        output
+    }
+
+    /// Returns the AST of `source` without locations.
+    fn shape(source: &str) -> String {
+        let module = ModuleParser::from_str(source).parse_all().unwrap();
+        let mut debug = format!("{:?}", module);
+        while let Some(start) = debug.find("loc: ASTLoc {") {
+            let end = start + debug[start..].find("}, ").unwrap() + 3;
+            debug.replace_range(start..end, "");
+        }
+        debug
+    }
+
+    fn narrow(max_width: usize) -> FormatConfig {
+        FormatConfig {
+            max_width,
+            ..FormatConfig::default()
+        }
+    }
+
+    #[test]
+    fn canonical_layout() {
+        let input = "import {Button as B,Link} from \"./button\"
+use std::list as l
+type Shape<T:Num> = Circle(T)|Rect({w:T,h:T})
+type Single = | Only
+pub effect Log { fn log(msg:String)->()
+fn flush() }
+fn area(shape:Shape<Float>,f:fn(Int)->Int emits Log)->Float emits Log+Http {
+  if !done {[1,2.5]} else {(shape.w*-2,nil)}
+  x = (a - (b - c)) * -(d + e).f
+}
+pub  const   fn double<T:Num+Ord,U>(x:T)->T{x*2}
+view Counter<T>(start:Int){
+  let count:Int=start
+  with Log { fn log(msg:String){print(msg)} }
+  effect {log(count) #inc.focus( )}
+  <button#inc onclick={increment} disabled title={\"x\"}>\"Add\" {count}</button>
+  <br/>
+}
+@allow( unused,shadowed-name )  @deprecated()
+view Empty {}
+";
+        let expected = "import { Button as B, Link } from \"./button\"
+use std::list as l
+type Shape<T: Num> = Circle(T) | Rect({ w: T, h: T })
+type Single = | Only
+pub effect Log {
+  fn log(msg: String) -> ()
+  fn flush()
+}
+fn area(shape: Shape<Float>, f: fn(Int) -> Int emits Log) -> Float emits Log + Http {
+  if !done { [1, 2.5] } else { (shape.w * -2, nil) }
+  x = (a - (b - c)) * -(d + e).f
+}
+pub const fn double<T: Num + Ord, U>(x: T) -> T {
+  x * 2
+}
+view Counter<T>(start: Int) {
+  let count: Int = start
+  with Log {
+    fn log(msg: String) {
+      print(msg)
+    }
+  }
+  effect {
+    log(count)
+    #inc.focus()
+  }
+  <button#inc onclick={increment} disabled title=\"x\">\"Add\" {count}</button>
+  <br />
+}
+@allow(unused, shadowed-name)
+@deprecated
+view Empty {}
+";
+        assert_eq!(format(input, &FormatConfig::default()), expected);
+        assert_eq!(format("", &FormatConfig::default()), "");
+    }
+
+    #[test]
+    fn for_and_when() {
+        let input = "fn f() {
+  for (i,item) in items {draw(i,item)}
+  when shape {Circle(r)->r*r,Empty->0
+  nil->{log(\"none\") 0}
+  _->1}
+}
+";
+        let expected = "fn f() {
+  for (i, item) in items {
+    draw(i, item)
+  }
+  when shape {
+    Circle(r) -> r * r
+    Empty -> 0
+    nil -> {
+      log(\"none\")
+      0
+    }
+    _ -> 1
+  }
+}
+";
+        assert_eq!(format(input, &FormatConfig::default()), expected);
+    }
+
+    #[test]
+    fn unary_operands() {
+        let input = "const A = -(-1)\nconst B = -(2.5).x\nconst C = !(!a)\nconst D = -(1 + 2)\n";
+        assert_eq!(format(input, &FormatConfig::default()), input);
+    }
+
+    #[test]
+    fn breaks_long_lines() {
+        let input = "type Shape<T> = Circle(T) | Rect({ w: T, h: T }) | Polygon([Point], Color)
+fn f() {
+  call(first_argument, second_argument, { name: \"value\" }, [1, 2, 3])
+  let r = if a { 1 } else if b { long_function_name(argument) } else { 3 }
+}
+view V {
+  <ul class=\"list\" aria-label=\"a long label\" role=\"list\"><li>{item.name}</li></ul>
+}
+";
+        let expected = "type Shape<T> =
+  | Circle(T)
+  | Rect({ w: T, h: T })
+  | Polygon([Point], Color)
+fn f() {
+  call(
+    first_argument,
+    second_argument,
+    { name: \"value\" },
+    [1, 2, 3],
+  )
+  let r = if a {
+    1
+  } else if b {
+    long_function_name(argument)
+  } else {
+    3
+  }
+}
+view V {
+  <ul
+    class=\"list\"
+    aria-label=\"a long label\"
+    role=\"list\"
+  >
+    <li>{item.name}</li>
+  </ul>
+}
+";
+        assert_eq!(format(input, &narrow(40)), expected);
+    }
+
+    #[test]
+    fn preserves_comments() {
+        let input = "// header
+
+import { A } from \"./a\" // trailing
+
+
+// before the view
+view V { // after the brace
+  log(\"a\")
+  call(a, // first
+    b,
+    // before c
+    c
+    // dangling
+  )
+
+  <p title=\"x\" /* inline */ class=\"y\">\"text\" // child
+  </p>
+  /* last */
+}
+// end
+";
+        let expected = "// header
+
+import { A } from \"./a\" // trailing
+
+// before the view
+view V {
+  // after the brace
+  log(\"a\")
+  call(
+    a, // first
+    b,
+    // before c
+    c,
+    // dangling
+  )
+
+  <p title=\"x\" /* inline */ class=\"y\">
+    \"text\" // child
+  </p>
+  /* last */
+}
+// end
+";
+        assert_eq!(format(input, &FormatConfig::default()), expected);
+    }
+
+    #[test]
+    fn trailing_commas() {
+        let input = "fn f(first: Int, second: Int) { g(first, second) }\n";
+        let format_with = |trailing_comma| {
+            let config = FormatConfig {
+                trailing_comma,
+                ..narrow(20)
+            };
+            format(input, &config)
+        };
+
+        assert_eq!(
+            format_with(TrailingComma::Never),
+            "fn f(
+  first: Int,
+  second: Int
+) {
+  g(first, second)
+}
+"
+        );
+        assert_eq!(
+            format_with(TrailingComma::Always),
+            "fn f(
+  first: Int,
+  second: Int,
+) {
+  g(first, second,)
+}
+"
+        );
+        // A tuple type with one element keeps its comma.
+        assert_eq!(
+            format("type T = (Int,)", &FormatConfig::default()),
+            "type T = (Int,)\n"
+        );
+    }
+
+    #[test]
+    fn config_layout() {
+        let config = FormatConfig {
+            indent_width: 4,
+            single_attribute_per_line: true,
+            ..FormatConfig::default()
+        };
+        assert_eq!(
+            format(
+                "view V { <a href=\"/\" class=\"x\">\"home\"</a> <b x=\"1\" /> }",
+                &config
+            ),
+            "view V {
+    <a
+        href=\"/\"
+        class=\"x\"
+    >
+        \"home\"
+    </a>
+    <b x=\"1\" />
+}
+"
+        );
+    }
+}
@@ -0,0 +1,210 @@
+/// A document describing source text together with the places it may be
+/// broken into lines.
+///
+/// A [`Doc::Group`] is printed on one line if it fits in the remaining width,
+/// and otherwise every line of the group itself is broken. Groups containing
+/// a hard line break always break.
+#[derive(Debug, Clone)]
+pub(crate) enum Doc {
+    Text(String),
+    /// A space, or a line break if the enclosing group breaks.
+    Line,
+    /// Nothing, or a line break if the enclosing group breaks.
+    SoftLine,
+    /// A line break which breaks all enclosing groups.
+    HardLine,
+    /// Breaks all enclosing groups without printing anything.
+    BreakParent,
+    Concat(Vec<Doc>),
+    /// Indents the lines broken inside of the document by one level.
+    Nest(Box<Doc>),
+    Group {
+        doc: Box<Doc>,
+        /// Whether the group contains a hard line break.
+        broken: bool,
+    },
+    /// Prints `broken` if the enclosing group breaks, and `flat` otherwise.
+    IfBreak {
+        broken: Box<Doc>,
+        flat: Box<Doc>,
+    },
+}
+
+impl Doc {
+    pub(crate) fn text(text: impl Into<String>) -> Doc {
+        Doc::Text(text.into())
+    }
+
+    pub(crate) fn concat(docs: impl IntoIterator<Item = Doc>) -> Doc {
+        Doc::Concat(docs.into_iter().collect())
+    }
+
+    pub(crate) fn nest(doc: Doc) -> Doc {
+        Doc::Nest(Box::new(doc))
+    }
+
+    pub(crate) fn group(doc: Doc) -> Doc {
+        let broken = doc.forces_break();
+        Doc::Group {
+            doc: Box::new(doc),
+            broken,
+        }
+    }
+
+    pub(crate) fn if_break(broken: Doc, flat: Doc) -> Doc {
+        Doc::IfBreak {
+            broken: Box::new(broken),
+            flat: Box::new(flat),
+        }
+    }
+
+    fn forces_break(&self) -> bool {
+        match self {
+            Doc::HardLine | Doc::BreakParent => true,
+            Doc::Text(_) | Doc::Line | Doc::SoftLine => false,
+            Doc::Concat(docs) => docs.iter().any(Doc::forces_break),
+            Doc::Nest(doc) => doc.forces_break(),
+            Doc::Group { broken, .. } => *broken,
+            Doc::IfBreak { broken, flat } => broken.forces_break() || flat.forces_break(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+type Command<'d> = (usize, Mode, &'d Doc);
+
+/// Prints `doc` breaking groups which do not fit in `width` columns, and
+/// indenting each level by `indent` spaces.
+pub(crate) fn print(doc: &Doc, width: usize, indent: usize) -> String {
+    let mut out = Output {
+        text: String::new(),
+        column: 0,
+        pending_indent: None,
+        indent,
+    };
+    let mut stack: Vec<Command> = vec![(0, Mode::Break, doc)];
+
+    while let Some((level, mode, doc)) = stack.pop() {
+        match doc {
+            Doc::Text(text) => out.write(text),
+            Doc::Line if mode == Mode::Flat => out.write(" "),
+            Doc::SoftLine if mode == Mode::Flat => {}
+            Doc::Line | Doc::SoftLine | Doc::HardLine => out.newline(level),
+            Doc::BreakParent => {}
+            Doc::Concat(docs) => {
+                stack.extend(docs.iter().rev().map(|doc| (level, mode, doc)));
+            }
+            Doc::Nest(doc) => stack.push((level + 1, mode, doc)),
+            Doc::Group { doc, broken } => {
+                let flat = (level, Mode::Flat, &**doc);
+                let remaining = width.saturating_sub(out.column(level));
+                let mode = match !broken && fits(flat, &stack, remaining) {
+                    true => Mode::Flat,
+                    false => Mode::Break,
+                };
+                stack.push((level, mode, doc));
+            }
+            Doc::IfBreak { broken, flat } => {
+                let doc = match mode {
+                    Mode::Break => broken,
+                    Mode::Flat => flat,
+                };
+                stack.push((level, mode, doc));
+            }
+        }
+    }
+    out.text
+}
+
+struct Output {
+    text: String,
+    column: usize,
+    /// The indentation level of the current line, until its first text is
+    /// written. Empty lines therefore have no trailing whitespace.
+    pending_indent: Option<usize>,
+    indent: usize,
+}
+
+impl Output {
+    fn write(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        if let Some(level) = self.pending_indent.take() {
+            self.text
+                .extend(std::iter::repeat_n(' ', level * self.indent));
+            self.column = level * self.indent;
+        }
+        self.text.push_str(text);
+        self.column = match text.rfind('\n') {
+            Some(idx) => text[idx + 1..].chars().count(),
+            None => self.column + text.chars().count(),
+        };
+    }
+
+    fn newline(&mut self, level: usize) {
+        self.text.push('\n');
+        self.column = 0;
+        self.pending_indent = Some(level);
+    }
+
+    /// The column the next text is written at.
+    fn column(&self, level: usize) -> usize {
+        match self.pending_indent {
+            Some(_) => level * self.indent,
+            None => self.column,
+        }
+    }
+}
+
+/// Whether `next` printed flat, followed by the commands of `rest` up to
+/// their first line break, fits in `width` columns.
+fn fits(next: Command, rest: &[Command], width: usize) -> bool {
+    let mut width = width as isize;
+    let mut rest = rest.iter().rev();
+    let mut stack = vec![next];
+    loop {
+        let (level, mode, doc) = match stack.pop() {
+            Some(command) => command,
+            None => match rest.next() {
+                Some(&command) => command,
+                None => return true,
+            },
+        };
+        match doc {
+            Doc::Text(text) => {
+                width -= text.chars().count() as isize;
+                if width < 0 {
+                    return false;
+                }
+            }
+            Doc::Line if mode == Mode::Flat => {
+                width -= 1;
+                if width < 0 {
+                    return false;
+                }
+            }
+            Doc::SoftLine if mode == Mode::Flat => {}
+            Doc::Line | Doc::SoftLine | Doc::HardLine => return true,
+            Doc::BreakParent => {}
+            Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|doc| (level, mode, doc))),
+            Doc::Nest(doc) => stack.push((level + 1, mode, doc)),
+            Doc::Group { doc, broken } => {
+                let mode = if *broken { Mode::Break } else { mode };
+                stack.push((level, mode, doc));
+            }
+            Doc::IfBreak { broken, flat } => {
+                let doc = match mode {
+                    Mode::Break => broken,
+                    Mode::Flat => flat,
+                };
+                stack.push((level, mode, doc));
+            }
+        }
+    }
+}
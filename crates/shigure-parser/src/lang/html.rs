@@ -0,0 +1,732 @@
+//! The builtin HTML elements a view can render.
+//!
+//! Every lowercase element of a view is looked up in this schema, which
+//! knows the attributes each element accepts with the type of their values,
+//! and the content each element may have. It follows the HTML standard in a
+//! simplified form: an element either accepts any flow content, only
+//! phrasing content, only some elements, only text or nothing at all.
+//! Elements whose name contains a `-` are custom elements, which the schema
+//! does not describe.
+
+use AttrType::{Bool, Boolish, Enum, Float, Int, Text};
+use Content::{Flow, Foreign, Only, Phrasing, Transparent, Void};
+
+/// The type of the value of an attribute.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum AttrType {
+    /// Any string, e.g. `class`.
+    Text,
+    /// A boolean attribute, e.g. `disabled`, which may be given bare.
+    Bool,
+    /// An attribute taking `"true"` or `"false"`, e.g. `aria-hidden`, which
+    /// may also be given a boolean.
+    Boolish,
+    Int,
+    Float,
+    /// A string which must be one of the given keywords, e.g. the `type` of
+    /// a `button`.
+    Enum(&'static [&'static str]),
+    /// An event handler, e.g. `onclick`, which takes a function.
+    Handler,
+}
+
+/// What an element may contain.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Content {
+    /// Any flow content, e.g. `div`.
+    Flow,
+    /// Phrasing content only, e.g. `p` or `span`.
+    Phrasing,
+    /// The content allowed by its parent, e.g. `a`.
+    Transparent,
+    /// Only the given elements, e.g. `li` for `ul`.
+    Only(&'static [&'static str]),
+    /// Only text, e.g. `textarea`.
+    Text,
+    /// Nothing, e.g. `img`.
+    Void,
+    /// Elements of another vocabulary, which is not checked, e.g. `svg`.
+    Foreign,
+}
+
+/// A builtin element.
+#[derive(Debug, Clone, Copy)]
+pub struct Element {
+    pub name: &'static str,
+    pub content: Content,
+    /// Whether the element is phrasing content, which may appear in a
+    /// paragraph.
+    pub phrasing: bool,
+    /// Whether the element is interactive content, which may not appear in
+    /// a link or a button.
+    pub interactive: bool,
+    /// The elements which may be the parent of this one, or any if empty.
+    pub parents: &'static [&'static str],
+    /// The attributes of the element besides the global ones.
+    pub attributes: &'static [(&'static str, AttrType)],
+}
+
+impl Element {
+    const fn new(name: &'static str, content: Content) -> Self {
+        Self {
+            name,
+            content,
+            phrasing: false,
+            interactive: false,
+            parents: &[],
+            attributes: &[],
+        }
+    }
+
+    const fn phrasing(mut self) -> Self {
+        self.phrasing = true;
+        self
+    }
+
+    const fn interactive(mut self) -> Self {
+        self.interactive = true;
+        self
+    }
+
+    const fn parents(mut self, parents: &'static [&'static str]) -> Self {
+        self.parents = parents;
+        self
+    }
+
+    const fn attributes(mut self, attributes: &'static [(&'static str, AttrType)]) -> Self {
+        self.attributes = attributes;
+        self
+    }
+
+    /// The type of the attribute `name` of this element.
+    pub fn attribute(&self, name: &str) -> Option<AttrType> {
+        fn find(attributes: &[(&str, AttrType)], name: &str) -> Option<AttrType> {
+            attributes
+                .iter()
+                .find(|(attribute, _)| *attribute == name)
+                .map(|&(_, ty)| ty)
+        }
+
+        if let Some(ty) = find(self.attributes, name).or_else(|| find(GLOBAL_ATTRIBUTES, name)) {
+            return Some(ty);
+        }
+        if let Some(aria) = name.strip_prefix("aria-") {
+            return find(ARIA_ATTRIBUTES, aria);
+        }
+        if name.starts_with("data-") {
+            return Some(AttrType::Text);
+        }
+        match name.strip_prefix("on") {
+            Some(event) if EVENTS.contains(&event) => Some(AttrType::Handler),
+            _ => None,
+        }
+    }
+
+    /// The names of all attributes of this element, to suggest one for a
+    /// misspelled name.
+    pub fn attribute_names(&self) -> impl Iterator<Item = String> + '_ {
+        let plain = (self.attributes.iter())
+            .chain(GLOBAL_ATTRIBUTES)
+            .map(|(name, _)| name.to_string());
+        let aria = ARIA_ATTRIBUTES
+            .iter()
+            .map(|(name, _)| format!("aria-{}", name));
+        let events = EVENTS.iter().map(|event| format!("on{}", event));
+        plain.chain(aria).chain(events)
+    }
+}
+
+/// The builtin element named `name`.
+pub fn element(name: &str) -> Option<&'static Element> {
+    ELEMENTS.iter().find(|element| element.name == name)
+}
+
+/// The names of all builtin elements.
+pub fn element_names() -> impl Iterator<Item = &'static str> {
+    ELEMENTS.iter().map(|element| element.name)
+}
+
+/// Whether `name` is the name of a custom element, e.g. `my-widget`.
+pub fn is_custom(name: &str) -> bool {
+    name.contains('-')
+}
+
+/// Why `child` may not appear where it is, given the builtin elements
+/// enclosing it, innermost last.
+pub fn misplaced(ancestors: &[&Element], child: &Element) -> Option<String> {
+    let parent = ancestors.last()?;
+    if !child.parents.is_empty() && !child.parents.contains(&parent.name) {
+        return Some(format!(
+            "`<{}>` must be inside {}",
+            child.name,
+            list(child.parents)
+        ));
+    }
+
+    // A transparent element takes the content model of its parent.
+    let container = ancestors
+        .iter()
+        .rev()
+        .find(|ancestor| ancestor.content != Content::Transparent);
+    let allowed = match container.map(|container| container.content) {
+        Some(Content::Phrasing) => child.phrasing,
+        Some(Content::Only(children)) => children.contains(&child.name),
+        Some(Content::Text) => false,
+        _ => true,
+    };
+    if !allowed {
+        let container = container.expect("not allowed in some container");
+        return Some(format!(
+            "`<{}>` cannot be inside `<{}>`",
+            child.name, container.name
+        ));
+    }
+
+    // Links and buttons cannot contain interactive content, and forms and
+    // labels cannot contain one another.
+    let conflict = ancestors.iter().rev().find(|ancestor| match ancestor.name {
+        "a" | "button" => child.interactive,
+        "form" | "label" => child.name == ancestor.name,
+        _ => false,
+    });
+    conflict.map(|ancestor| format!("`<{}>` cannot be inside `<{}>`", child.name, ancestor.name))
+}
+
+/// `` `<a>`, `<b>` or `<c>` ``
+fn list(names: &[&str]) -> String {
+    let names: Vec<_> = names.iter().map(|name| format!("`<{}>`", name)).collect();
+    match names.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} or {}", rest.join(", "), last),
+        _ => names.concat(),
+    }
+}
+
+const INPUT_TYPES: &[&str] = &[
+    "button",
+    "checkbox",
+    "color",
+    "date",
+    "datetime-local",
+    "email",
+    "file",
+    "hidden",
+    "image",
+    "month",
+    "number",
+    "password",
+    "radio",
+    "range",
+    "reset",
+    "search",
+    "submit",
+    "tel",
+    "text",
+    "time",
+    "url",
+    "week",
+];
+
+const GLOBAL_ATTRIBUTES: &[(&str, AttrType)] = &[
+    ("accesskey", Text),
+    ("autofocus", Bool),
+    ("class", Text),
+    ("contenteditable", Boolish),
+    ("dir", Enum(&["ltr", "rtl", "auto"])),
+    ("draggable", Boolish),
+    ("hidden", Bool),
+    ("id", Text),
+    ("inert", Bool),
+    ("lang", Text),
+    ("role", Text),
+    ("slot", Text),
+    ("spellcheck", Boolish),
+    ("style", Text),
+    ("tabindex", Int),
+    ("title", Text),
+    ("translate", Enum(&["yes", "no"])),
+];
+
+/// The `aria-*` attributes, without their prefix.
+const ARIA_ATTRIBUTES: &[(&str, AttrType)] = &[
+    ("activedescendant", Text),
+    ("atomic", Boolish),
+    ("autocomplete", Enum(&["none", "inline", "list", "both"])),
+    ("busy", Boolish),
+    ("checked", Enum(&["true", "false", "mixed"])),
+    ("colcount", Int),
+    ("colindex", Int),
+    ("colspan", Int),
+    ("controls", Text),
+    (
+        "current",
+        Enum(&["page", "step", "location", "date", "time", "true", "false"]),
+    ),
+    ("describedby", Text),
+    ("description", Text),
+    ("details", Text),
+    ("disabled", Boolish),
+    ("errormessage", Text),
+    ("expanded", Boolish),
+    ("flowto", Text),
+    (
+        "haspopup",
+        Enum(&["false", "true", "menu", "listbox", "tree", "grid", "dialog"]),
+    ),
+    ("hidden", Boolish),
+    ("invalid", Enum(&["grammar", "false", "spelling", "true"])),
+    ("keyshortcuts", Text),
+    ("label", Text),
+    ("labelledby", Text),
+    ("level", Int),
+    ("live", Enum(&["assertive", "off", "polite"])),
+    ("modal", Boolish),
+    ("multiline", Boolish),
+    ("multiselectable", Boolish),
+    ("orientation", Enum(&["horizontal", "vertical"])),
+    ("owns", Text),
+    ("placeholder", Text),
+    ("posinset", Int),
+    ("pressed", Enum(&["true", "false", "mixed"])),
+    ("readonly", Boolish),
+    ("relevant", Text),
+    ("required", Boolish),
+    ("roledescription", Text),
+    ("rowcount", Int),
+    ("rowindex", Int),
+    ("rowspan", Int),
+    ("selected", Boolish),
+    ("setsize", Int),
+    ("sort", Enum(&["ascending", "descending", "none", "other"])),
+    ("valuemax", Float),
+    ("valuemin", Float),
+    ("valuenow", Float),
+    ("valuetext", Text),
+];
+
+/// The events an element can handle with an `on*` attribute.
+const EVENTS: &[&str] = &[
+    "animationend",
+    "blur",
+    "change",
+    "click",
+    "contextmenu",
+    "copy",
+    "cut",
+    "dblclick",
+    "drag",
+    "dragend",
+    "dragenter",
+    "dragleave",
+    "dragover",
+    "dragstart",
+    "drop",
+    "ended",
+    "error",
+    "focus",
+    "focusin",
+    "focusout",
+    "input",
+    "keydown",
+    "keyup",
+    "load",
+    "mousedown",
+    "mouseenter",
+    "mouseleave",
+    "mousemove",
+    "mouseout",
+    "mouseover",
+    "mouseup",
+    "paste",
+    "pause",
+    "play",
+    "pointerdown",
+    "pointerenter",
+    "pointerleave",
+    "pointermove",
+    "pointerup",
+    "reset",
+    "scroll",
+    "select",
+    "submit",
+    "timeupdate",
+    "toggle",
+    "touchend",
+    "touchmove",
+    "touchstart",
+    "transitionend",
+    "wheel",
+];
+
+const MEDIA: &[(&str, AttrType)] = &[
+    ("autoplay", Bool),
+    ("controls", Bool),
+    ("crossorigin", Enum(&["anonymous", "use-credentials"])),
+    ("height", Int),
+    ("loop", Bool),
+    ("muted", Bool),
+    ("poster", Text),
+    ("preload", Enum(&["none", "metadata", "auto"])),
+    ("src", Text),
+    ("width", Int),
+];
+
+const CELL: &[(&str, AttrType)] = &[
+    ("colspan", Int),
+    ("headers", Text),
+    ("rowspan", Int),
+    ("scope", Enum(&["row", "col", "rowgroup", "colgroup"])),
+];
+
+const ELEMENTS: &[Element] = &[
+    Element::new("a", Transparent)
+        .phrasing()
+        .interactive()
+        .attributes(&[
+            ("download", Text),
+            ("href", Text),
+            ("hreflang", Text),
+            ("rel", Text),
+            ("target", Enum(&["_self", "_blank", "_parent", "_top"])),
+            ("type", Text),
+        ]),
+    Element::new("abbr", Phrasing).phrasing(),
+    Element::new("address", Flow),
+    Element::new("article", Flow),
+    Element::new("aside", Flow),
+    Element::new("audio", Transparent)
+        .phrasing()
+        .attributes(MEDIA),
+    Element::new("b", Phrasing).phrasing(),
+    Element::new("blockquote", Flow).attributes(&[("cite", Text)]),
+    Element::new("br", Void).phrasing(),
+    Element::new("button", Phrasing)
+        .phrasing()
+        .interactive()
+        .attributes(&[
+            ("disabled", Bool),
+            ("form", Text),
+            ("name", Text),
+            ("type", Enum(&["submit", "reset", "button"])),
+            ("value", Text),
+        ]),
+    Element::new("canvas", Transparent)
+        .phrasing()
+        .attributes(&[("height", Int), ("width", Int)]),
+    Element::new("caption", Flow).parents(&["table"]),
+    Element::new("cite", Phrasing).phrasing(),
+    Element::new("code", Phrasing).phrasing(),
+    Element::new("col", Void)
+        .parents(&["colgroup"])
+        .attributes(&[("span", Int)]),
+    Element::new("colgroup", Only(&["col"]))
+        .parents(&["table"])
+        .attributes(&[("span", Int)]),
+    Element::new("data", Phrasing)
+        .phrasing()
+        .attributes(&[("value", Text)]),
+    Element::new("datalist", Only(&["option"])).phrasing(),
+    Element::new("dd", Flow).parents(&["dl", "div"]),
+    Element::new("del", Transparent)
+        .phrasing()
+        .attributes(&[("cite", Text), ("datetime", Text)]),
+    Element::new("details", Flow)
+        .interactive()
+        .attributes(&[("open", Bool)]),
+    Element::new("dfn", Phrasing).phrasing(),
+    Element::new("dialog", Flow).attributes(&[("open", Bool)]),
+    Element::new("div", Flow),
+    Element::new("dl", Only(&["dt", "dd", "div"])),
+    Element::new("dt", Flow).parents(&["dl", "div"]),
+    Element::new("em", Phrasing).phrasing(),
+    Element::new("fieldset", Flow).attributes(&[
+        ("disabled", Bool),
+        ("form", Text),
+        ("name", Text),
+    ]),
+    Element::new("figcaption", Flow).parents(&["figure"]),
+    Element::new("figure", Flow),
+    Element::new("footer", Flow),
+    Element::new("form", Flow).attributes(&[
+        ("action", Text),
+        ("autocomplete", Enum(&["on", "off"])),
+        ("enctype", Text),
+        ("method", Enum(&["get", "post", "dialog"])),
+        ("name", Text),
+        ("novalidate", Bool),
+        ("target", Text),
+    ]),
+    Element::new("h1", Phrasing),
+    Element::new("h2", Phrasing),
+    Element::new("h3", Phrasing),
+    Element::new("h4", Phrasing),
+    Element::new("h5", Phrasing),
+    Element::new("h6", Phrasing),
+    Element::new("header", Flow),
+    Element::new("hr", Void),
+    Element::new("i", Phrasing).phrasing(),
+    Element::new("iframe", Void)
+        .phrasing()
+        .interactive()
+        .attributes(&[
+            ("allow", Text),
+            ("height", Int),
+            ("loading", Enum(&["lazy", "eager"])),
+            ("name", Text),
+            ("referrerpolicy", Text),
+            ("sandbox", Text),
+            ("src", Text),
+            ("srcdoc", Text),
+            ("width", Int),
+        ]),
+    Element::new("img", Void).phrasing().attributes(&[
+        ("alt", Text),
+        ("crossorigin", Enum(&["anonymous", "use-credentials"])),
+        ("decoding", Enum(&["sync", "async", "auto"])),
+        ("height", Int),
+        ("loading", Enum(&["lazy", "eager"])),
+        ("sizes", Text),
+        ("src", Text),
+        ("srcset", Text),
+        ("width", Int),
+    ]),
+    Element::new("input", Void)
+        .phrasing()
+        .interactive()
+        .attributes(&[
+            ("accept", Text),
+            ("autocomplete", Text),
+            ("checked", Bool),
+            ("disabled", Bool),
+            ("form", Text),
+            ("list", Text),
+            ("max", Text),
+            ("maxlength", Int),
+            ("min", Text),
+            ("minlength", Int),
+            ("multiple", Bool),
+            ("name", Text),
+            ("pattern", Text),
+            ("placeholder", Text),
+            ("readonly", Bool),
+            ("required", Bool),
+            ("size", Int),
+            ("step", Text),
+            ("type", Enum(INPUT_TYPES)),
+            ("value", Text),
+        ]),
+    Element::new("ins", Transparent)
+        .phrasing()
+        .attributes(&[("cite", Text), ("datetime", Text)]),
+    Element::new("kbd", Phrasing).phrasing(),
+    Element::new("label", Phrasing)
+        .phrasing()
+        .interactive()
+        .attributes(&[("for", Text)]),
+    Element::new("legend", Phrasing).parents(&["fieldset"]),
+    Element::new("li", Flow)
+        .parents(&["ul", "ol", "menu"])
+        .attributes(&[("value", Int)]),
+    Element::new("main", Flow),
+    Element::new("mark", Phrasing).phrasing(),
+    Element::new("menu", Only(&["li"])),
+    Element::new("meter", Phrasing).phrasing().attributes(&[
+        ("high", Float),
+        ("low", Float),
+        ("max", Float),
+        ("min", Float),
+        ("optimum", Float),
+        ("value", Float),
+    ]),
+    Element::new("nav", Flow),
+    Element::new("ol", Only(&["li"])).attributes(&[
+        ("reversed", Bool),
+        ("start", Int),
+        ("type", Enum(&["1", "a", "A", "i", "I"])),
+    ]),
+    Element::new("optgroup", Only(&["option"]))
+        .parents(&["select"])
+        .attributes(&[("disabled", Bool), ("label", Text)]),
+    Element::new("option", Content::Text)
+        .parents(&["select", "optgroup", "datalist"])
+        .attributes(&[
+            ("disabled", Bool),
+            ("label", Text),
+            ("selected", Bool),
+            ("value", Text),
+        ]),
+    Element::new("output", Phrasing).phrasing().attributes(&[
+        ("for", Text),
+        ("form", Text),
+        ("name", Text),
+    ]),
+    Element::new("p", Phrasing),
+    Element::new("picture", Only(&["source", "img"])).phrasing(),
+    Element::new("pre", Phrasing),
+    Element::new("progress", Phrasing)
+        .phrasing()
+        .attributes(&[("max", Float), ("value", Float)]),
+    Element::new("q", Phrasing)
+        .phrasing()
+        .attributes(&[("cite", Text)]),
+    Element::new("s", Phrasing).phrasing(),
+    Element::new("samp", Phrasing).phrasing(),
+    Element::new("section", Flow),
+    Element::new("select", Only(&["option", "optgroup", "hr"]))
+        .phrasing()
+        .interactive()
+        .attributes(&[
+            ("autocomplete", Text),
+            ("disabled", Bool),
+            ("form", Text),
+            ("multiple", Bool),
+            ("name", Text),
+            ("required", Bool),
+            ("size", Int),
+        ]),
+    Element::new("small", Phrasing).phrasing(),
+    Element::new("source", Void)
+        .parents(&["audio", "video", "picture"])
+        .attributes(&[
+            ("media", Text),
+            ("sizes", Text),
+            ("src", Text),
+            ("srcset", Text),
+            ("type", Text),
+        ]),
+    Element::new("span", Phrasing).phrasing(),
+    Element::new("strong", Phrasing).phrasing(),
+    Element::new("sub", Phrasing).phrasing(),
+    Element::new("summary", Phrasing).parents(&["details"]),
+    Element::new("sup", Phrasing).phrasing(),
+    Element::new("svg", Foreign).phrasing(),
+    Element::new(
+        "table",
+        Only(&["caption", "colgroup", "thead", "tbody", "tfoot", "tr"]),
+    ),
+    Element::new("tbody", Only(&["tr"])).parents(&["table"]),
+    Element::new("td", Flow).parents(&["tr"]).attributes(CELL),
+    Element::new("textarea", Content::Text)
+        .phrasing()
+        .interactive()
+        .attributes(&[
+            ("autocomplete", Text),
+            ("cols", Int),
+            ("disabled", Bool),
+            ("form", Text),
+            ("maxlength", Int),
+            ("minlength", Int),
+            ("name", Text),
+            ("placeholder", Text),
+            ("readonly", Bool),
+            ("required", Bool),
+            ("rows", Int),
+            ("wrap", Enum(&["soft", "hard"])),
+        ]),
+    Element::new("tfoot", Only(&["tr"])).parents(&["table"]),
+    Element::new("th", Flow).parents(&["tr"]).attributes(CELL),
+    Element::new("thead", Only(&["tr"])).parents(&["table"]),
+    Element::new("time", Phrasing)
+        .phrasing()
+        .attributes(&[("datetime", Text)]),
+    Element::new("tr", Only(&["td", "th"])).parents(&["table", "thead", "tbody", "tfoot"]),
+    Element::new("track", Void)
+        .parents(&["audio", "video"])
+        .attributes(&[
+            ("default", Bool),
+            (
+                "kind",
+                Enum(&[
+                    "subtitles",
+                    "captions",
+                    "descriptions",
+                    "chapters",
+                    "metadata",
+                ]),
+            ),
+            ("label", Text),
+            ("src", Text),
+            ("srclang", Text),
+        ]),
+    Element::new("u", Phrasing).phrasing(),
+    Element::new("ul", Only(&["li"])),
+    Element::new("var", Phrasing).phrasing(),
+    Element::new("video", Transparent)
+        .phrasing()
+        .attributes(MEDIA),
+    Element::new("wbr", Void).phrasing(),
+];
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sorted_and_consistent() {
+        assert!(ELEMENTS.windows(2).all(|w| w[0].name < w[1].name));
+        assert!(EVENTS.windows(2).all(|w| w[0] < w[1]));
+        for element in ELEMENTS {
+            let names = (element.parents.iter()).chain(match &element.content {
+                Only(children) => *children,
+                _ => &[],
+            });
+            for name in names {
+                assert!(
+                    self::element(name).is_some(),
+                    "{} in {}",
+                    name,
+                    element.name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn attributes() {
+        let a = element("a").unwrap();
+        assert_eq!(a.attribute("href"), Some(Text));
+        assert_eq!(a.attribute("class"), Some(Text));
+        assert_eq!(a.attribute("data-id"), Some(Text));
+        assert_eq!(a.attribute("aria-hidden"), Some(Boolish));
+        assert_eq!(a.attribute("aria-foo"), None);
+        assert_eq!(a.attribute("onclick"), Some(AttrType::Handler));
+        assert_eq!(a.attribute("onclik"), None);
+        assert_eq!(a.attribute("checked"), None);
+        assert!(a.attribute_names().any(|name| name == "aria-label"));
+    }
+
+    #[test]
+    fn nesting() {
+        fn misplaced(ancestors: &[&str], child: &str) -> Option<String> {
+            let ancestors: Vec<_> = ancestors.iter().map(|n| element(n).unwrap()).collect();
+            super::misplaced(&ancestors, element(child).unwrap())
+        }
+        assert_eq!(misplaced(&["div", "p"], "span"), None);
+        assert_eq!(
+            misplaced(&["p"], "div").as_deref(),
+            Some("`<div>` cannot be inside `<p>`")
+        );
+        assert_eq!(
+            misplaced(&["p", "a"], "div").as_deref(),
+            Some("`<div>` cannot be inside `<p>`")
+        );
+        assert_eq!(misplaced(&["div", "a"], "div"), None);
+        assert_eq!(
+            misplaced(&["div"], "li").as_deref(),
+            Some("`<li>` must be inside `<ul>`, `<ol>` or `<menu>`")
+        );
+        assert_eq!(
+            misplaced(&["ul"], "div").as_deref(),
+            Some("`<div>` cannot be inside `<ul>`")
+        );
+        assert_eq!(
+            misplaced(&["a", "span"], "button").as_deref(),
+            Some("`<button>` cannot be inside `<a>`")
+        );
+        assert_eq!(
+            misplaced(&["form", "div"], "form").as_deref(),
+            Some("`<form>` cannot be inside `<form>`")
+        );
+        assert_eq!(misplaced(&["label"], "input"), None);
+        assert_eq!(misplaced(&[], "li"), None);
+    }
+}
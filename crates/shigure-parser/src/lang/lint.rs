@@ -0,0 +1,377 @@
+//! Lints: checks of code which is valid, but likely to be a mistake.
+//!
+//! Every lint has a stable name, which is also the code of its diagnostics,
+//! and a level deciding whether it is reported as a warning, an error or not
+//! at all. Most lints belong to a group, e.g. `unused`, whose name sets the
+//! level of all of them. The default level of a lint can be changed in the
+//! `[lints]` section of `ribbon.toml`:
+//!
+//! ```toml
+//! [lints]
+//! img-alt = "deny"
+//! unused = "allow"
+//! ```
+//!
+//! and for a single item with the `@allow`, `@warn` and `@deny` attributes,
+//! which take precedence over the configuration:
+//!
+//! ```text
+//! @allow(unused-let, shadowed-name)
+//! view Draft() { <p>"..."</p> }
+//! ```
+//!
+//! The lints are checked by [`LintPass`]es, which walk the module with a
+//! [`Visitor`](crate::lang::visit::Visitor) and report to a [`LintContext`].
+//! A new pass is added to [`PASSES`].
+
+mod a11y;
+mod shadow;
+mod unused;
+
+use std::collections::HashMap;
+
+use crate::lang::{
+    ast::{ASTLoc, ASTNodeModule, Spanned},
+    config::{expect_str, ConfigValue},
+    diagnostic::{suggest, Diagnostic},
+    node_index::{Ast, NodeId, NodeRef},
+    resolve::{DefId, Definition, Resolution},
+};
+
+/// How the problems found by a lint are reported.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Level {
+    /// Not reported.
+    Allow,
+    /// Reported as a warning.
+    Warn,
+    /// Reported as an error.
+    Deny,
+}
+
+impl Level {
+    /// The level set by `name` in the configuration, or by the attribute
+    /// `@name`.
+    fn from_name(name: &str) -> Option<Level> {
+        match name {
+            "allow" => Some(Level::Allow),
+            "warn" => Some(Level::Warn),
+            "deny" => Some(Level::Deny),
+            _ => None,
+        }
+    }
+}
+
+/// A kind of problem looked for by [`lint_module`].
+#[derive(Debug)]
+pub struct Lint {
+    /// The stable name of the lint, e.g. `img-alt`.
+    pub name: &'static str,
+    /// The group setting the level of this lint together with others.
+    pub group: Option<&'static str>,
+    pub default: Level,
+    pub description: &'static str,
+}
+
+/// Lints checked together in one walk over a module.
+pub struct LintPass {
+    /// The lints the pass may report.
+    pub lints: &'static [&'static Lint],
+    pub run: fn(&mut LintContext),
+}
+
+/// The passes run by [`lint_module`].
+pub const PASSES: &[LintPass] = &[a11y::PASS, shadow::PASS, unused::PASS];
+
+/// Every lint of [`PASSES`].
+pub fn lints() -> impl Iterator<Item = &'static Lint> {
+    PASSES.iter().flat_map(|pass| pass.lints.iter().copied())
+}
+
+/// Returns the lint named `name`.
+pub fn lint(name: &str) -> Option<&'static Lint> {
+    lints().find(|lint| lint.name == name)
+}
+
+/// The names of the lints and of their groups.
+fn names() -> impl Iterator<Item = &'static str> {
+    lints()
+        .flat_map(|lint| [Some(lint.name), lint.group])
+        .flatten()
+}
+
+/// Returns an error message for a name which is neither a lint nor a group.
+fn unknown_lint(name: &str) -> String {
+    let message = format!("unknown lint `{name}`");
+    match suggest(name, names()) {
+        Some(suggestion) => format!("{message}; did you mean `{suggestion}`?"),
+        None => message,
+    }
+}
+
+/// The levels of the lints, read from the `[lints]` section of `ribbon.toml`.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct LintConfig {
+    /// Lint or group names with their level, in the order they were set.
+    levels: Vec<(&'static str, Level)>,
+}
+
+impl LintConfig {
+    /// The level `lint` is reported at: the one set last for the lint or its
+    /// group, or else its default.
+    pub fn level(&self, lint: &Lint) -> Level {
+        self.levels
+            .iter()
+            .rev()
+            .find(|(name, _)| *name == lint.name || Some(*name) == lint.group)
+            .map_or(lint.default, |&(_, level)| level)
+    }
+
+    /// Sets the level of the lint or group `name`, or returns an error
+    /// message if there is none of that name.
+    pub fn set_level(&mut self, name: &str, level: Level) -> Result<(), String> {
+        let name = names()
+            .find(|&known| known == name)
+            .ok_or_else(|| unknown_lint(name))?;
+        self.levels.push((name, level));
+        Ok(())
+    }
+
+    /// Applies `key = value` of the `[lints]` section, or returns an error
+    /// message. A `key` which is not a lint is skipped with a warning
+    /// message, as in an attribute.
+    pub(crate) fn set(&mut self, key: &str, value: &ConfigValue) -> Result<Option<String>, String> {
+        let level = Level::from_name(expect_str(key, value)?)
+            .ok_or_else(|| format!("`{key}` must be one of \"allow\", \"warn\" or \"deny\""))?;
+        Ok(self.set_level(key, level).err())
+    }
+}
+
+/// What the passes can look at, and the diagnostics they reported.
+pub struct LintContext<'a, 'ast> {
+    path: String,
+    ast: &'a Ast<'ast>,
+    res: &'a Resolution,
+    config: &'a LintConfig,
+    /// The definition declared by each node which declares one.
+    definitions: HashMap<NodeId, DefId>,
+    /// The items with attributes setting levels, and the resulting levels.
+    items: Vec<(ASTLoc, LintConfig)>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a, 'ast> LintContext<'a, 'ast> {
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn ast(&self) -> &'a Ast<'ast> {
+        self.ast
+    }
+
+    pub fn module(&self) -> &'ast ASTNodeModule {
+        let NodeRef::Module(module) = self.ast.get(self.ast.root()) else {
+            unreachable!("the root of a node index is a module");
+        };
+        module
+    }
+
+    pub fn resolution(&self) -> &'a Resolution {
+        self.res
+    }
+
+    /// The definition declared by `node`, e.g. by a `let` statement.
+    pub fn definition(&self, node: impl Into<NodeRef<'ast>>) -> Option<(DefId, &'a Definition)> {
+        let id = self.definitions.get(&self.ast.id(node)?).copied()?;
+        Some((id, self.res.definition(id)))
+    }
+
+    /// Reports a problem found by `lint` at its level where it was found,
+    /// after passing the diagnostic to `extend` to attach labels and fixes.
+    pub fn report(
+        &mut self,
+        lint: &Lint,
+        loc: ASTLoc,
+        message: impl Into<String>,
+        extend: impl FnOnce(Diagnostic) -> Diagnostic,
+    ) {
+        let config = self
+            .items
+            .iter()
+            .find(|(item, _)| item.start <= loc.start && loc.end <= item.end)
+            .map_or(self.config, |(_, config)| config);
+        let diagnostic = match config.level(lint) {
+            Level::Allow => return,
+            Level::Warn => Diagnostic::warning(lint.name, &self.path, loc, message),
+            Level::Deny => Diagnostic::error(lint.name, &self.path, loc, message),
+        };
+        self.diagnostics.push(extend(diagnostic));
+    }
+
+    /// Reads the levels set by the attributes of the items, reporting the
+    /// attributes which are not understood.
+    fn read_attributes(&mut self) {
+        for item in &self.module().nodes {
+            if item.attributes().is_empty() {
+                continue;
+            }
+            let mut config = self.config.clone();
+            for attribute in item.attributes() {
+                let Some(level) = Level::from_name(&attribute.name) else {
+                    self.diagnostics.push(Diagnostic::error(
+                        "unknown-item-attribute",
+                        &self.path,
+                        attribute.loc(),
+                        format!("unknown attribute `@{}`", attribute.name),
+                    ));
+                    continue;
+                };
+                for arg in &attribute.args {
+                    if let Err(message) = config.set_level(&arg.name, level) {
+                        let diagnostic =
+                            Diagnostic::warning("unknown-lint", &self.path, arg.loc(), message);
+                        self.diagnostics.push(diagnostic);
+                    }
+                }
+            }
+            self.items.push((item.loc(), config));
+        }
+    }
+}
+
+/// Runs the lints over the module at `path`, which `ast` indexes and `res`
+/// resolves.
+pub fn lint_module(
+    path: &str,
+    ast: &Ast,
+    res: &Resolution,
+    config: &LintConfig,
+) -> Vec<Diagnostic> {
+    let mut cx = LintContext {
+        path: path.to_string(),
+        ast,
+        res,
+        config,
+        definitions: res
+            .definition_ids()
+            .filter_map(|(id, def)| Some((def.node?, id)))
+            .collect(),
+        items: Vec::new(),
+        diagnostics: Vec::new(),
+    };
+    cx.read_attributes();
+    for pass in PASSES {
+        (pass.run)(&mut cx);
+    }
+    cx.diagnostics
+}
+
+#[cfg(test)]
+pub(super) mod test {
+    use super::*;
+    use crate::lang::{
+        config::{Config, ConfigError},
+        resolve::test::with_resolution,
+    };
+
+    pub(super) fn lint_source(input: &str, config: &LintConfig) -> Vec<Diagnostic> {
+        with_resolution(input, |ast, res| lint_module("main.rbn", ast, res, config))
+    }
+
+    pub(super) fn codes(diagnostics: &[Diagnostic]) -> Vec<(&str, &str)> {
+        diagnostics
+            .iter()
+            .map(|d| (d.code, d.message.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn registry() {
+        let mut names: Vec<_> = lints().map(|lint| lint.name).collect();
+        let count = names.len();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), count, "lint names are unique");
+        assert!(lints().all(|lint| lint.group.is_none_or(|group| super::lint(group).is_none())));
+        assert_eq!(lint("img-alt").map(|lint| lint.default), Some(Level::Warn));
+        assert!(lint("unused").is_none());
+    }
+
+    #[test]
+    fn configures_levels() {
+        let config = Config::from_toml(
+            "[lints]\na11y = \"allow\"\nimg-alt = \"deny\"\nunused-let = \"allow\"\n",
+        )
+        .unwrap()
+        .lints;
+        let diagnostics = lint_source(
+            "pub view V() {\n  let a = 1\n  <div><img/><input/></div>\n}",
+            &config,
+        );
+        assert_eq!(
+            codes(&diagnostics),
+            [("img-alt", "`<img>` has no `alt` text")]
+        );
+        assert!(diagnostics[0].is_error());
+
+        let config = Config::from_toml("[lints]\nimg-alts = \"deny\"\nunused = \"deny\"").unwrap();
+        assert_eq!(
+            config.warnings,
+            [ConfigError {
+                line: 2,
+                message: "unknown lint `img-alts`; did you mean `img-alt`?".to_string()
+            }]
+        );
+        assert_eq!(config.lints.level(lint("unused-let").unwrap()), Level::Deny);
+
+        let error = |input: &str| Config::from_toml(input).unwrap_err();
+        assert_eq!(
+            error("[lints]\nimg-alts = \"error\""),
+            ConfigError {
+                line: 2,
+                message: "`img-alts` must be one of \"allow\", \"warn\" or \"deny\"".to_string()
+            }
+        );
+        assert_eq!(
+            error("[lints]\nimg-alt = \"error\""),
+            ConfigError {
+                line: 2,
+                message: "`img-alt` must be one of \"allow\", \"warn\" or \"deny\"".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn reads_attributes() {
+        let diagnostics = lint_source(
+            "@allow(unused)
+view A() {
+  let a = 1
+  <img/>
+}
+@deny(img-alt) @warn(unusd)
+pub view B() {
+  let b = 1
+  <img/>
+}
+@inline
+pub fn c() { 1 }",
+            &LintConfig::default(),
+        );
+        assert_eq!(
+            codes(&diagnostics),
+            [
+                (
+                    "unknown-lint",
+                    "unknown lint `unusd`; did you mean `unused`?"
+                ),
+                ("unknown-item-attribute", "unknown attribute `@inline`"),
+                ("img-alt", "`<img>` has no `alt` text"),
+                ("img-alt", "`<img>` has no `alt` text"),
+                ("unused-let", "`b` is never used"),
+            ]
+        );
+        assert!(!diagnostics[2].is_error());
+        assert!(diagnostics[3].is_error());
+    }
+}
@@ -0,0 +1,385 @@
+//! Accessibility lints of view markup.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::lang::{
+    ast::{
+        ASTExprKind, ASTLoc, ASTNodeScoped, ASTNodeViewAttribute, ASTNodeViewElement, Spanned,
+        TokenLiteral,
+    },
+    html,
+    lint::{Level, Lint, LintContext, LintPass},
+    parser::incremental::TextEdit,
+    resolve::is_component,
+    visit::{walk_element, walk_item, Visitor},
+};
+
+pub(super) const CLICKABLE_NON_INTERACTIVE: Lint = Lint {
+    name: "clickable-non-interactive",
+    group: Some("a11y"),
+    default: Level::Warn,
+    description: "A non-interactive element with a click handler, but without a `role` or \
+                  a key handler, which keyboard and screen reader users cannot operate.",
+};
+
+pub(super) const DUPLICATE_ID: Lint = Lint {
+    name: "duplicate-id",
+    group: Some("a11y"),
+    default: Level::Warn,
+    description: "An `id` used by more than one element of an item, either as an attribute \
+                  or as an anchor.",
+};
+
+pub(super) const IMG_ALT: Lint = Lint {
+    name: "img-alt",
+    group: Some("a11y"),
+    default: Level::Warn,
+    description: "An `<img>` without alternative text for screen readers.",
+};
+
+pub(super) const INPUT_LABEL: Lint = Lint {
+    name: "input-label",
+    group: Some("a11y"),
+    default: Level::Warn,
+    description: "A form field which is not labelled by a `<label>` or an `aria-label`.",
+};
+
+pub(super) const PASS: LintPass = LintPass {
+    lints: &[
+        &CLICKABLE_NON_INTERACTIVE,
+        &DUPLICATE_ID,
+        &IMG_ALT,
+        &INPUT_LABEL,
+    ],
+    run: |cx| {
+        let module = cx.module();
+        A11y::new(cx).visit_module(module);
+    },
+};
+
+/// The attributes handling the keyboard in place of a click.
+const KEY_HANDLERS: &[&str] = &["onkeydown", "onkeyup", "onkeypress"];
+
+/// The attributes naming an element for assistive technology.
+const ARIA_LABELS: &[&str] = &["aria-label", "aria-labelledby"];
+
+/// The `type`s of `<input>` which are not filled in, and so need no label.
+const UNLABELLED_INPUTS: &[&str] = &["hidden", "submit", "reset", "button", "image"];
+
+struct A11y<'cx, 'a, 'ast> {
+    cx: &'cx mut LintContext<'a, 'ast>,
+    /// The static `for` attributes of the labels of the current item.
+    label_targets: HashSet<&'ast str>,
+    /// The first element using each static `id` of the current item, and
+    /// whether it is used as an anchor.
+    ids: HashMap<&'ast str, (ASTLoc, bool)>,
+    /// The number of `<label>` elements around the current element.
+    labels: usize,
+}
+
+impl<'cx, 'a, 'ast> A11y<'cx, 'a, 'ast> {
+    fn new(cx: &'cx mut LintContext<'a, 'ast>) -> Self {
+        Self {
+            cx,
+            label_targets: HashSet::new(),
+            ids: HashMap::new(),
+            labels: 0,
+        }
+    }
+
+    fn check_ids(&mut self, element: &'ast ASTNodeViewElement) {
+        let anchor = element
+            .anchor
+            .as_deref()
+            .map(|anchor| (anchor, element.loc(), true));
+        let id = attribute(element, "id")
+            .and_then(|attr| Some((static_value(attr)?, attr.loc(), false)));
+        for (id, loc, is_anchor) in anchor.into_iter().chain(id) {
+            let Some(&(first, first_is_anchor)) = self.ids.get(id) else {
+                self.ids.insert(id, (loc, is_anchor));
+                continue;
+            };
+            // The resolver reports anchors used twice.
+            if is_anchor && first_is_anchor {
+                continue;
+            }
+            let path = self.cx.path().to_string();
+            self.cx
+                .report(&DUPLICATE_ID, loc, format!("duplicate id `{id}`"), |d| {
+                    d.with_label(path, first, "first used here")
+                });
+        }
+    }
+
+    fn check_img(&mut self, element: &ASTNodeViewElement) {
+        if element.name != "img" || has_any(element, &["alt", "aria-label", "aria-labelledby"]) {
+            return;
+        }
+        let at = tag_end(element);
+        self.cx
+            .report(&IMG_ALT, element.loc(), "`<img>` has no `alt` text", |d| {
+                d.with_fix(
+                    "add an empty `alt` to mark the image as decorative",
+                    vec![TextEdit::insert(at, " alt=\"\"")],
+                )
+            });
+    }
+
+    fn check_click(&mut self, element: &ASTNodeViewElement, schema: &html::Element) {
+        let Some(onclick) = attribute(element, "onclick") else {
+            return;
+        };
+        if schema.interactive {
+            return;
+        }
+        let has_role = attribute(element, "role").is_some();
+        let has_key_handler = has_any(element, KEY_HANDLERS);
+        let missing = match (has_role, has_key_handler) {
+            (true, true) => return,
+            (false, true) => "no `role`",
+            (true, false) => "no key handler",
+            (false, false) => "no `role` and no key handler",
+        };
+        let message = format!("`<{}>` handles clicks, but has {}", element.name, missing);
+        let mut edit = String::from(" role=\"button\"");
+        if attribute(element, "tabindex").is_none() {
+            edit.push_str(" tabindex=\"0\"");
+        }
+        let at = tag_end(element);
+        self.cx
+            .report(&CLICKABLE_NON_INTERACTIVE, onclick.loc(), message, |d| {
+                // Only a missing role can be added without knowing what the
+                // keys should do.
+                if has_key_handler {
+                    d.with_fix(
+                        "give it the `button` role",
+                        vec![TextEdit::insert(at, edit)],
+                    )
+                } else {
+                    d
+                }
+            });
+    }
+
+    fn check_label(&mut self, element: &ASTNodeViewElement) {
+        match element.name.as_str() {
+            "select" | "textarea" => {}
+            "input" => {
+                let ty = attribute(element, "type").and_then(static_value);
+                if ty.is_some_and(|ty| UNLABELLED_INPUTS.contains(&ty)) {
+                    return;
+                }
+            }
+            _ => return,
+        }
+        if self.labels > 0 || has_any(element, ARIA_LABELS) || has_any(element, &["title"]) {
+            return;
+        }
+        // An `id` computed at runtime may be the target of any label.
+        if let Some(id) = attribute(element, "id") {
+            if static_value(id).is_none_or(|id| self.label_targets.contains(id)) {
+                return;
+            }
+        }
+        let message = format!("`<{}>` has no label", element.name);
+        self.cx.report(&INPUT_LABEL, element.loc(), message, |d| d);
+    }
+}
+
+impl<'ast> Visitor<'ast> for A11y<'_, '_, 'ast> {
+    fn visit_item(&mut self, item: &'ast ASTNodeScoped) {
+        // Ids and labels are matched within the markup of one item.
+        let mut targets = LabelTargets(HashSet::new());
+        targets.visit_item(item);
+        self.label_targets = targets.0;
+        self.ids.clear();
+        walk_item(self, item);
+    }
+
+    fn visit_element(&mut self, element: &'ast ASTNodeViewElement) {
+        if !is_component(&element.name) {
+            self.check_ids(element);
+            self.check_img(element);
+            if let Some(schema) = html::element(&element.name) {
+                self.check_click(element, schema);
+            }
+            self.check_label(element);
+        }
+        let is_label = element.name == "label";
+        self.labels += usize::from(is_label);
+        walk_element(self, element);
+        self.labels -= usize::from(is_label);
+    }
+}
+
+/// Collects the static `for` attributes of `<label>` elements.
+struct LabelTargets<'a>(HashSet<&'a str>);
+
+impl<'a> Visitor<'a> for LabelTargets<'a> {
+    fn visit_element(&mut self, element: &'a ASTNodeViewElement) {
+        if element.name == "label" {
+            self.0
+                .extend(attribute(element, "for").and_then(static_value));
+        }
+        walk_element(self, element);
+    }
+}
+
+fn attribute<'e>(element: &'e ASTNodeViewElement, name: &str) -> Option<&'e ASTNodeViewAttribute> {
+    element.attributes.iter().find(|attr| attr.name == name)
+}
+
+fn has_any(element: &ASTNodeViewElement, names: &[&str]) -> bool {
+    element
+        .attributes
+        .iter()
+        .any(|attr| names.contains(&attr.name.as_str()))
+}
+
+/// The value of an attribute set to a string literal.
+fn static_value(attr: &ASTNodeViewAttribute) -> Option<&str> {
+    match &attr.value.as_ref()?.kind {
+        ASTExprKind::Literal(literal @ TokenLiteral::StringLiteral(_)) => Some(literal.unquoted()),
+        _ => None,
+    }
+}
+
+/// Where an attribute can be added to the opening tag of `element`: after its
+/// last attribute, or else after its name and anchor.
+fn tag_end(element: &ASTNodeViewElement) -> u32 {
+    match element.attributes.last() {
+        Some(attr) => attr.loc().end,
+        None => {
+            let anchor = element.anchor.as_ref().map_or(0, |anchor| anchor.len() + 1);
+            element.loc().start + (1 + element.name.len() + anchor) as u32
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::lang::{
+        diagnostic::Diagnostic,
+        lint::{
+            test::{codes, lint_source},
+            LintConfig,
+        },
+    };
+
+    /// The diagnostics of the accessibility lints.
+    fn lint(input: &str) -> Vec<Diagnostic> {
+        lint_source(input, &LintConfig::default())
+            .into_iter()
+            .filter(|d| super::PASS.lints.iter().any(|lint| lint.name == d.code))
+            .collect()
+    }
+
+    #[test]
+    fn checks_images() {
+        let source = "view Logo() {
+              <div>
+                <img src=\"/logo.png\"/>
+                <img src=\"/logo.png\" alt=\"Ribbon\"/>
+                <img#hero/>
+              </div>
+            }";
+        let diagnostics = lint(source);
+        assert_eq!(
+            codes(&diagnostics),
+            [
+                ("img-alt", "`<img>` has no `alt` text"),
+                ("img-alt", "`<img>` has no `alt` text"),
+            ]
+        );
+        let fixed = diagnostics[0].fixes[0].apply(source);
+        assert!(fixed.contains("<img src=\"/logo.png\" alt=\"\"/>"));
+        let fixed = diagnostics[1].fixes[0].apply(source);
+        assert!(fixed.contains("<img#hero alt=\"\"/>"));
+    }
+
+    #[test]
+    fn checks_click_handlers() {
+        let source = "view Menu() {
+              <div>
+                <div onclick={open}>\"a\"</div>
+                <span onclick={open} onkeydown={open}>\"b\"</span>
+                <li role=\"button\" onclick={open}>\"c\"</li>
+                <div role=\"button\" onclick={open} onkeydown={open}>\"d\"</div>
+                <button onclick={open}>\"e\"</button>
+              </div>
+            }";
+        let diagnostics = lint(source);
+        assert_eq!(
+            codes(&diagnostics),
+            [
+                (
+                    "clickable-non-interactive",
+                    "`<div>` handles clicks, but has no `role` and no key handler"
+                ),
+                (
+                    "clickable-non-interactive",
+                    "`<span>` handles clicks, but has no `role`"
+                ),
+                (
+                    "clickable-non-interactive",
+                    "`<li>` handles clicks, but has no key handler"
+                ),
+            ]
+        );
+        assert!(diagnostics[0].fixes.is_empty());
+        let fixed = diagnostics[1].fixes[0].apply(source);
+        assert!(
+            fixed.contains("<span onclick={open} onkeydown={open} role=\"button\" tabindex=\"0\">")
+        );
+    }
+
+    #[test]
+    fn checks_labels() {
+        let diagnostics = lint(
+            "view Form() {
+              <form>
+                <input type=\"text\"/>
+                <input type=\"submit\"/>
+                <label>\"Name\" <input/></label>
+                <label for=\"email\">\"Email\"</label>
+                <input id=\"email\"/>
+                <input id=\"phone\"/>
+                <select aria-label=\"Size\"></select>
+                <textarea></textarea>
+              </form>
+            }",
+        );
+        assert_eq!(
+            codes(&diagnostics),
+            [
+                ("input-label", "`<input>` has no label"),
+                ("input-label", "`<input>` has no label"),
+                ("input-label", "`<textarea>` has no label"),
+            ]
+        );
+    }
+
+    #[test]
+    fn checks_ids() {
+        let diagnostics = lint(
+            "view Page() {
+              <main>
+                <h1 id=\"top\">\"Title\"</h1>
+                <p#intro id=\"top\"></p>
+                <p id=\"intro\"></p>
+              </main>
+            }
+            view Other() {
+              <p id=\"top\"></p>
+            }",
+        );
+        assert_eq!(
+            codes(&diagnostics),
+            [
+                ("duplicate-id", "duplicate id `top`"),
+                ("duplicate-id", "duplicate id `intro`"),
+            ]
+        );
+        assert_eq!(diagnostics[0].labels[0].message, "first used here");
+    }
+}
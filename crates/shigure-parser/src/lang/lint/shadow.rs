@@ -0,0 +1,123 @@
+//! The lint for definitions hiding another one of the same name.
+
+use crate::lang::{
+    ast::{ASTItemConst, ASTNodeLet, ASTNodeParam, ASTNodePattern, ASTNodeTypeParam},
+    lint::{Level, Lint, LintContext, LintPass},
+    node_index::NodeRef,
+    visit::{walk_const, walk_let, walk_param, walk_pattern, Visitor},
+};
+
+pub(super) const SHADOWED_NAME: Lint = Lint {
+    name: "shadowed-name",
+    group: None,
+    default: Level::Warn,
+    description: "A definition hiding one of an enclosing scope with the same name, other \
+                  than a builtin.",
+};
+
+pub(super) const PASS: LintPass = LintPass {
+    lints: &[&SHADOWED_NAME],
+    run: |cx| {
+        let module = cx.module();
+        Shadow { cx }.visit_module(module);
+    },
+};
+
+struct Shadow<'cx, 'a, 'ast> {
+    cx: &'cx mut LintContext<'a, 'ast>,
+}
+
+impl<'ast> Shadow<'_, '_, 'ast> {
+    fn check(&mut self, node: impl Into<NodeRef<'ast>>) {
+        let Some((_, def)) = self.cx.definition(node) else {
+            return;
+        };
+        let Some(outer) = def.shadows else {
+            return;
+        };
+        let outer = self.cx.resolution().definition(outer);
+        let message = format!(
+            "`{}` shadows {} {} of the same name",
+            def.name,
+            outer.kind.article(),
+            outer.kind.describe()
+        );
+        let path = self.cx.path().to_string();
+        self.cx.report(&SHADOWED_NAME, def.loc, message, |d| {
+            d.with_label(path, outer.loc, "shadowed definition")
+        });
+    }
+}
+
+impl<'ast> Visitor<'ast> for Shadow<'_, '_, 'ast> {
+    fn visit_const(&mut self, item: &'ast ASTItemConst) {
+        self.check(item);
+        walk_const(self, item);
+    }
+
+    fn visit_type_param(&mut self, param: &'ast ASTNodeTypeParam) {
+        self.check(param);
+    }
+
+    fn visit_param(&mut self, param: &'ast ASTNodeParam) {
+        self.check(param);
+        walk_param(self, param);
+    }
+
+    fn visit_let(&mut self, stmt: &'ast ASTNodeLet) {
+        self.check(stmt);
+        walk_let(self, stmt);
+    }
+
+    fn visit_pattern(&mut self, pattern: &'ast ASTNodePattern) {
+        self.check(pattern);
+        walk_pattern(self, pattern);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::lang::lint::{
+        test::{codes, lint_source},
+        LintConfig,
+    };
+
+    #[test]
+    fn reports_shadowing() {
+        let diagnostics = lint_source(
+            "fn b(x: Int) {
+  let b = x
+  for x in [] { print(x) }
+  let print = b
+  print
+}",
+            &LintConfig::default(),
+        );
+        assert_eq!(
+            codes(&diagnostics),
+            [
+                ("shadowed-name", "`b` shadows a function of the same name"),
+                ("shadowed-name", "`x` shadows a parameter of the same name"),
+            ]
+        );
+        assert_eq!(diagnostics[0].labels[0].message, "shadowed definition");
+
+        let diagnostics = lint_source(
+            "import { Button } from \"./button\"
+effect Log { fn log(msg: String) }
+pub const B = Button
+fn f(Button: Int, Log: Int) { Button + Log }",
+            &LintConfig::default(),
+        );
+        assert_eq!(
+            codes(&diagnostics),
+            [
+                (
+                    "shadowed-name",
+                    "`Button` shadows an import of the same name"
+                ),
+                ("shadowed-name", "`Log` shadows an effect of the same name"),
+            ]
+        );
+    }
+}
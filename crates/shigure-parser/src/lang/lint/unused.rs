@@ -0,0 +1,158 @@
+//! Lints for definitions which are never used.
+
+use std::collections::HashSet;
+
+use crate::lang::{
+    ast::{ASTItemImport, ASTItemUse, ASTItemView, ASTLoc, ASTNodeLet, Spanned},
+    effects::ENTRY_VIEW,
+    lint::{Level, Lint, LintContext, LintPass},
+    node_index::NodeRef,
+    resolve::DefId,
+    visit::{walk_let, walk_view, Visitor},
+};
+
+pub(super) const UNUSED_IMPORT: Lint = Lint {
+    name: "unused-import",
+    group: Some("unused"),
+    default: Level::Warn,
+    description: "A name bound by a private `import` or `use` which the module never uses.",
+};
+
+pub(super) const UNUSED_LET: Lint = Lint {
+    name: "unused-let",
+    group: Some("unused"),
+    default: Level::Warn,
+    description: "A `let` binding which is never read nor assigned.",
+};
+
+pub(super) const UNUSED_VIEW: Lint = Lint {
+    name: "unused-view",
+    group: Some("unused"),
+    default: Level::Warn,
+    description: "A private view other than the entry view which is never rendered.",
+};
+
+pub(super) const PASS: LintPass = LintPass {
+    lints: &[&UNUSED_IMPORT, &UNUSED_LET, &UNUSED_VIEW],
+    run: |cx| {
+        let module = cx.module();
+        let used = cx.resolution().uses.iter().map(|(_, &def)| def).collect();
+        Unused { cx, used }.visit_module(module);
+    },
+};
+
+struct Unused<'cx, 'a, 'ast> {
+    cx: &'cx mut LintContext<'a, 'ast>,
+    /// The definitions referred to at least once.
+    used: HashSet<DefId>,
+}
+
+impl<'ast> Unused<'_, '_, 'ast> {
+    /// Reports the definition of `node` if nothing refers to it. Names
+    /// starting with `_` are meant to be unused.
+    fn check(
+        &mut self,
+        lint: &Lint,
+        node: impl Into<NodeRef<'ast>>,
+        loc: ASTLoc,
+        message: impl FnOnce(&str) -> String,
+    ) {
+        let Some((id, def)) = self.cx.definition(node) else {
+            return;
+        };
+        if self.used.contains(&id) || def.name.starts_with('_') {
+            return;
+        }
+        self.cx.report(lint, loc, message(&def.name), |d| d);
+    }
+}
+
+impl<'ast> Visitor<'ast> for Unused<'_, '_, 'ast> {
+    fn visit_import(&mut self, item: &'ast ASTItemImport) {
+        // Public imports are used by the modules importing them.
+        if item.visibility.is_public() {
+            return;
+        }
+        for name in &item.names {
+            self.check(&UNUSED_IMPORT, name, name.loc(), |name| {
+                format!("`{name}` is imported but never used")
+            });
+        }
+    }
+
+    fn visit_use(&mut self, item: &'ast ASTItemUse) {
+        if item.visibility.is_public() {
+            return;
+        }
+        self.check(&UNUSED_IMPORT, item, item.loc(), |name| {
+            format!("`{name}` is imported but never used")
+        });
+    }
+
+    fn visit_let(&mut self, stmt: &'ast ASTNodeLet) {
+        self.check(&UNUSED_LET, stmt, stmt.loc(), |name| {
+            format!("`{name}` is never used")
+        });
+        walk_let(self, stmt);
+    }
+
+    fn visit_view(&mut self, item: &'ast ASTItemView) {
+        // The entry view is rendered by the runtime.
+        if !item.visibility.is_public() && item.name != ENTRY_VIEW {
+            self.check(&UNUSED_VIEW, item, item.loc(), |name| {
+                format!("the view `{name}` is never rendered")
+            });
+        }
+        walk_view(self, item);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::lang::lint::{
+        test::{codes, lint_source},
+        LintConfig,
+    };
+
+    #[test]
+    fn reports_unused_names() {
+        let diagnostics = lint_source(
+            "import { Button, Link as L, Icon } from \"./ui\"
+pub import { Card } from \"./card\"
+use std::list
+view Item(label: String) {
+  let count = 0
+  let _ignored = 1
+  <li><Button/>{label}</li>
+}
+view Unused() { <p/> }
+pub view List() {
+  <ul><Item label=\"a\"/><Icon/></ul>
+}",
+            &LintConfig::default(),
+        );
+        assert_eq!(
+            codes(&diagnostics),
+            [
+                ("unused-import", "`L` is imported but never used"),
+                ("unused-import", "`list` is imported but never used"),
+                ("unused-let", "`count` is never used"),
+                ("unused-view", "the view `Unused` is never rendered"),
+            ]
+        );
+        assert!(diagnostics.iter().all(|d| !d.is_error()));
+    }
+
+    #[test]
+    fn allows_entry_view() {
+        let diagnostics = lint_source(
+            "view Header() { <h1/> }
+view App() { <main/> }",
+            &LintConfig::default(),
+        );
+        assert_eq!(
+            codes(&diagnostics),
+            [("unused-view", "the view `Header` is never rendered")]
+        );
+    }
+}
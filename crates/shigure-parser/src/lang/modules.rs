@@ -0,0 +1,834 @@
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    fs, io,
+    path::PathBuf,
+};
+
+use crate::lang::{
+    ast::{ASTLoc, ASTNodeModule, ASTNodeScoped, ASTVisibility, Spanned},
+    diagnostic::Diagnostic,
+    project::parse_module,
+};
+
+/// The file extension of Ribbon modules.
+pub const MODULE_EXTENSION: &str = ".rbn";
+
+/// Provides the sources of modules to a [`ModuleResolver`].
+pub trait FileLoader {
+    /// Returns the source of the module at `path`, which is relative to the
+    /// project root and uses `/` as separator, or `None` if there is no such
+    /// module. A module which exists but cannot be read is an error.
+    fn load(&self, path: &str) -> io::Result<Option<String>>;
+}
+
+/// A [`FileLoader`] serving modules from memory.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryFileLoader {
+    files: HashMap<String, String>,
+}
+
+impl MemoryFileLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, path: impl Into<String>, source: impl Into<String>) {
+        self.files.insert(path.into(), source.into());
+    }
+}
+
+impl FileLoader for MemoryFileLoader {
+    fn load(&self, path: &str) -> io::Result<Option<String>> {
+        Ok(self.files.get(path).cloned())
+    }
+}
+
+/// A [`FileLoader`] reading modules below a root directory.
+#[derive(Debug, Clone)]
+pub struct FsFileLoader {
+    root: PathBuf,
+}
+
+impl FsFileLoader {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl FileLoader for FsFileLoader {
+    fn load(&self, path: &str) -> io::Result<Option<String>> {
+        match fs::read_to_string(self.root.join(path)) {
+            Ok(source) => Ok(Some(source)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct ModuleId(usize);
+
+/// What an [`ImportBinding`] refers to in the imported module.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ImportTarget {
+    /// The module itself, e.g. `use std::list`.
+    Module,
+    /// An item declared in the module.
+    Item(String),
+}
+
+/// A name bound in a module by an `import` or `use` item.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ImportBinding {
+    pub local: String,
+    pub module: ModuleId,
+    pub target: ImportTarget,
+    pub loc: ASTLoc,
+    /// Public bindings are re-exported by the binding module.
+    pub visibility: ASTVisibility,
+}
+
+/// A name a module makes visible to other modules.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ModuleExport {
+    /// The module declaring the item, or the exported module itself when
+    /// `target` is [`ImportTarget::Module`].
+    pub origin: ModuleId,
+    pub target: ImportTarget,
+    /// The location of the declaration in `origin`.
+    pub loc: ASTLoc,
+}
+
+/// An edge of the module graph.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ModuleDependency {
+    pub module: ModuleId,
+    /// The location of the item introducing the dependency.
+    pub loc: ASTLoc,
+}
+
+#[derive(Debug, Clone)]
+pub struct ModuleNode {
+    pub path: String,
+    pub ast: ASTNodeModule,
+    pub dependencies: Vec<ModuleDependency>,
+    pub bindings: Vec<ImportBinding>,
+    /// Public items of the module and names re-exported by `pub use` and
+    /// `pub import`.
+    pub exports: BTreeMap<String, ModuleExport>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct ModuleGraph {
+    modules: Vec<ModuleNode>,
+    paths: HashMap<String, ModuleId>,
+}
+
+impl ModuleGraph {
+    pub fn module(&self, id: ModuleId) -> &ModuleNode {
+        &self.modules[id.0]
+    }
+
+    pub fn find(&self, path: &str) -> Option<ModuleId> {
+        self.paths.get(path).copied()
+    }
+
+    pub fn modules(&self) -> impl Iterator<Item = (ModuleId, &ModuleNode)> {
+        self.modules
+            .iter()
+            .enumerate()
+            .map(|(idx, module)| (ModuleId(idx), module))
+    }
+
+    pub fn len(&self) -> usize {
+        self.modules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.modules.is_empty()
+    }
+
+    fn insert(&mut self, path: String, ast: ASTNodeModule) -> ModuleId {
+        let id = ModuleId(self.modules.len());
+        self.paths.insert(path.clone(), id);
+        self.modules.push(ModuleNode {
+            path,
+            ast,
+            dependencies: Vec::new(),
+            bindings: Vec::new(),
+            exports: BTreeMap::new(),
+        });
+        id
+    }
+}
+
+pub struct ModuleResolution {
+    pub graph: ModuleGraph,
+    /// The entry module, unless it could not be loaded.
+    pub entry: Option<ModuleId>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Loads a module and everything it imports, and resolves imported names.
+pub struct ModuleResolver<L> {
+    loader: L,
+}
+
+/// A reference from an item to another module found while loading.
+struct PendingImport {
+    from: ModuleId,
+    loc: ASTLoc,
+    visibility: ASTVisibility,
+    kind: PendingImportKind,
+}
+
+enum PendingImportKind {
+    /// `import { .. } from "path"`; names with their aliases and locations.
+    Names(String, Vec<(String, String, ASTLoc)>),
+    /// `use a::b`; the candidate module paths of the whole path and of its
+    /// prefix, and the local name.
+    Use(String, Option<(String, String)>, String),
+}
+
+impl<L: FileLoader> ModuleResolver<L> {
+    pub fn new(loader: L) -> Self {
+        Self { loader }
+    }
+
+    pub fn resolve(&self, entry: &str) -> ModuleResolution {
+        let mut graph = ModuleGraph::default();
+        let mut diagnostics = Vec::new();
+        let entry = normalize_path("", entry);
+
+        let Some(entry_id) = self.load(&mut graph, &mut diagnostics, &entry) else {
+            diagnostics.push(Diagnostic::error(
+                "unresolved-module",
+                entry.as_str(),
+                ASTLoc { start: 0, end: 0 },
+                format!("cannot find module `{}`", entry),
+            ));
+            return ModuleResolution {
+                graph,
+                entry: None,
+                diagnostics,
+            };
+        };
+
+        let mut queue = VecDeque::from([entry_id]);
+        while let Some(id) = queue.pop_front() {
+            for import in collect_imports(&graph, id) {
+                self.link(&mut graph, &mut diagnostics, &mut queue, import);
+            }
+        }
+
+        compute_exports(&mut graph);
+        for (_, module) in graph.modules() {
+            for binding in &module.bindings {
+                check_binding(&graph, &mut diagnostics, &module.path, binding);
+            }
+        }
+        detect_cycles(&graph, &mut diagnostics);
+
+        ModuleResolution {
+            graph,
+            entry: Some(entry_id),
+            diagnostics,
+        }
+    }
+
+    /// Returns the module at `path`, loading and parsing it if it has not
+    /// been loaded yet. A module which cannot be read is reported and loaded
+    /// empty, like one which fails to parse.
+    fn load(
+        &self,
+        graph: &mut ModuleGraph,
+        diagnostics: &mut Vec<Diagnostic>,
+        path: &str,
+    ) -> Option<ModuleId> {
+        if let Some(id) = graph.find(path) {
+            return Some(id);
+        }
+
+        let source = match self.loader.load(path) {
+            Ok(source) => source?,
+            Err(err) => {
+                diagnostics.push(unreadable_module(path, &err));
+                String::new()
+            }
+        };
+        let (ast, _) = parse_module(path, &source, diagnostics);
+
+        Some(graph.insert(path.to_string(), ast))
+    }
+
+    fn load_queued(
+        &self,
+        graph: &mut ModuleGraph,
+        diagnostics: &mut Vec<Diagnostic>,
+        queue: &mut VecDeque<ModuleId>,
+        path: &str,
+    ) -> Option<ModuleId> {
+        let loaded = graph.find(path).is_some();
+        let id = self.load(graph, diagnostics, path)?;
+        if !loaded {
+            queue.push_back(id);
+        }
+        Some(id)
+    }
+
+    fn link(
+        &self,
+        graph: &mut ModuleGraph,
+        diagnostics: &mut Vec<Diagnostic>,
+        queue: &mut VecDeque<ModuleId>,
+        import: PendingImport,
+    ) {
+        let from_path = graph.module(import.from).path.clone();
+        let mut bindings = Vec::new();
+
+        let target = match import.kind {
+            PendingImportKind::Names(path, names) => {
+                let target = self.load_queued(graph, diagnostics, queue, &path);
+                if let Some(target) = target {
+                    for (name, local, loc) in names {
+                        bindings.push(ImportBinding {
+                            local,
+                            module: target,
+                            target: ImportTarget::Item(name),
+                            loc,
+                            visibility: import.visibility,
+                        });
+                    }
+                }
+                target.ok_or(path)
+            }
+            PendingImportKind::Use(path, prefix, local) => {
+                match self.load_queued(graph, diagnostics, queue, &path) {
+                    Some(target) => {
+                        bindings.push(ImportBinding {
+                            local,
+                            module: target,
+                            target: ImportTarget::Module,
+                            loc: import.loc,
+                            visibility: import.visibility,
+                        });
+                        Ok(target)
+                    }
+                    None => {
+                        let target = prefix.and_then(|(prefix, name)| {
+                            let target = self.load_queued(graph, diagnostics, queue, &prefix)?;
+                            bindings.push(ImportBinding {
+                                local,
+                                module: target,
+                                target: ImportTarget::Item(name),
+                                loc: import.loc,
+                                visibility: import.visibility,
+                            });
+                            Some(target)
+                        });
+                        target.ok_or(path)
+                    }
+                }
+            }
+        };
+
+        match target {
+            Ok(target) => {
+                let module = &mut graph.modules[import.from.0];
+                module.dependencies.push(ModuleDependency {
+                    module: target,
+                    loc: import.loc,
+                });
+                module.bindings.extend(bindings);
+            }
+            Err(path) => diagnostics.push(Diagnostic::error(
+                "unresolved-module",
+                from_path,
+                import.loc,
+                format!("cannot find module `{}`", path),
+            )),
+        }
+    }
+}
+
+/// Reports a module which exists but cannot be read.
+pub(crate) fn unreadable_module(path: &str, err: &io::Error) -> Diagnostic {
+    Diagnostic::error(
+        "unreadable-module",
+        path,
+        ASTLoc { start: 0, end: 0 },
+        format!("cannot read module `{}`: {}", path, err),
+    )
+}
+
+/// Collects the `import` and `use` items of a module.
+fn collect_imports(graph: &ModuleGraph, id: ModuleId) -> Vec<PendingImport> {
+    let module = graph.module(id);
+    let mut imports = Vec::new();
+    for node in &module.ast.nodes {
+        let kind = match node {
+            ASTNodeScoped::Import(import) => PendingImportKind::Names(
+                normalize_path(&module.path, &import.source),
+                import
+                    .names
+                    .iter()
+                    .map(|n| (n.name.clone(), n.local_name().to_string(), n.loc()))
+                    .collect(),
+            ),
+            ASTNodeScoped::Use(item) => {
+                let path = normalize_path("", &item.path.join("/"));
+                let prefix = match item.path.split_last() {
+                    Some((name, prefix)) if !prefix.is_empty() => {
+                        Some((normalize_path("", &prefix.join("/")), name.clone()))
+                    }
+                    _ => None,
+                };
+                PendingImportKind::Use(path, prefix, item.local_name().to_string())
+            }
+            _ => continue,
+        };
+        imports.push(PendingImport {
+            from: id,
+            loc: node.loc(),
+            visibility: node.visibility(),
+            kind,
+        });
+    }
+    imports
+}
+
+/// Fills the export tables of all modules, following re-exports across
+/// modules. Re-exports taking part in a cycle are left out.
+fn compute_exports(graph: &mut ModuleGraph) {
+    fn visit(
+        graph: &ModuleGraph,
+        id: ModuleId,
+        tables: &mut Vec<Option<BTreeMap<String, ModuleExport>>>,
+        visiting: &mut Vec<bool>,
+    ) {
+        if tables[id.0].is_some() || visiting[id.0] {
+            return;
+        }
+        visiting[id.0] = true;
+
+        let module = graph.module(id);
+        let mut exports = BTreeMap::new();
+        for node in &module.ast.nodes {
+            if let (Some(name), true) = (node.name(), node.visibility().is_public()) {
+                let export = ModuleExport {
+                    origin: id,
+                    target: ImportTarget::Item(name.to_string()),
+                    loc: node.loc(),
+                };
+                exports.insert(name.to_string(), export);
+            }
+        }
+
+        for binding in &module.bindings {
+            if !binding.visibility.is_public() {
+                continue;
+            }
+            let export = match &binding.target {
+                ImportTarget::Module => Some(ModuleExport {
+                    origin: binding.module,
+                    target: ImportTarget::Module,
+                    loc: graph.module(binding.module).ast.loc(),
+                }),
+                ImportTarget::Item(name) => {
+                    visit(graph, binding.module, tables, visiting);
+                    tables[binding.module.0]
+                        .as_ref()
+                        .and_then(|exports| exports.get(name))
+                        .cloned()
+                }
+            };
+            if let Some(export) = export {
+                exports.insert(binding.local.clone(), export);
+            }
+        }
+
+        visiting[id.0] = false;
+        tables[id.0] = Some(exports);
+    }
+
+    let mut tables = vec![None; graph.len()];
+    let mut visiting = vec![false; graph.len()];
+    for idx in 0..graph.len() {
+        visit(graph, ModuleId(idx), &mut tables, &mut visiting);
+    }
+
+    for (module, exports) in graph.modules.iter_mut().zip(tables) {
+        module.exports = exports.unwrap_or_default();
+    }
+}
+
+/// Reports a binding to an item its module does not export.
+fn check_binding(
+    graph: &ModuleGraph,
+    diagnostics: &mut Vec<Diagnostic>,
+    from: &str,
+    binding: &ImportBinding,
+) {
+    let ImportTarget::Item(name) = &binding.target else {
+        return;
+    };
+
+    let target = graph.module(binding.module);
+    if target.exports.contains_key(name) {
+        return;
+    }
+
+    let private = target
+        .ast
+        .nodes
+        .iter()
+        .filter(|node| node.name() == Some(name.as_str()))
+        .map(|node| node.loc())
+        .chain(
+            target
+                .bindings
+                .iter()
+                .filter(|b| &b.local == name)
+                .map(|b| b.loc),
+        )
+        .next();
+
+    let diagnostic = match private {
+        Some(declaration) => Diagnostic::error(
+            "private-item",
+            from,
+            binding.loc,
+            format!("`{}` is private to module `{}`", name, target.path),
+        )
+        .with_label(
+            target.path.as_str(),
+            declaration,
+            format!("`{}` is declared here without `pub`", name),
+        ),
+        None => Diagnostic::error(
+            "unexported-name",
+            from,
+            binding.loc,
+            format!("`{}` is not exported by module `{}`", name, target.path),
+        ),
+    };
+    diagnostics.push(diagnostic);
+}
+
+/// Reports every cycle in the graph once, at the dependency closing it.
+fn detect_cycles(graph: &ModuleGraph, diagnostics: &mut Vec<Diagnostic>) {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Mark {
+        Unvisited,
+        InProgress,
+        Done,
+    }
+
+    fn visit(
+        graph: &ModuleGraph,
+        id: ModuleId,
+        marks: &mut Vec<Mark>,
+        stack: &mut Vec<ModuleId>,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        marks[id.0] = Mark::InProgress;
+        stack.push(id);
+
+        let module = graph.module(id);
+        for dep in &module.dependencies {
+            match marks[dep.module.0] {
+                Mark::Unvisited => visit(graph, dep.module, marks, stack, diagnostics),
+                Mark::InProgress => {
+                    let start = stack.iter().position(|m| *m == dep.module).unwrap_or(0);
+                    let cycle: Vec<_> = stack[start..]
+                        .iter()
+                        .chain([&dep.module])
+                        .map(|m| graph.module(*m).path.as_str())
+                        .collect();
+                    diagnostics.push(Diagnostic::error(
+                        "import-cycle",
+                        module.path.as_str(),
+                        dep.loc,
+                        format!("import cycle: {}", cycle.join(" -> ")),
+                    ));
+                }
+                Mark::Done => {}
+            }
+        }
+
+        stack.pop();
+        marks[id.0] = Mark::Done;
+    }
+
+    let mut marks = vec![Mark::Unvisited; graph.len()];
+    for (id, _) in graph.modules() {
+        if marks[id.0] == Mark::Unvisited {
+            visit(graph, id, &mut marks, &mut Vec::new(), diagnostics);
+        }
+    }
+}
+
+/// Turns a module specifier into a path relative to the project root.
+///
+/// Specifiers starting with `./` or `../` are relative to the directory of
+/// `base`; anything else is relative to the root. [`MODULE_EXTENSION`] is
+/// appended when missing.
+pub fn normalize_path(base: &str, specifier: &str) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+    if specifier.starts_with("./") || specifier.starts_with("../") {
+        segments.extend(base.split('/'));
+        segments.pop();
+    }
+
+    for segment in specifier.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+
+    let mut path = segments.join("/");
+    if !path.ends_with(MODULE_EXTENSION) {
+        path.push_str(MODULE_EXTENSION);
+    }
+    path
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn resolve(files: &[(&str, &str)]) -> ModuleResolution {
+        let mut loader = MemoryFileLoader::new();
+        for (path, source) in files {
+            loader.insert(*path, *source);
+        }
+        ModuleResolver::new(loader).resolve(files[0].0)
+    }
+
+    fn codes(resolution: &ModuleResolution) -> Vec<&str> {
+        resolution.diagnostics.iter().map(|d| d.code).collect()
+    }
+
+    #[test]
+    fn normalizes_paths() {
+        assert_eq!(
+            normalize_path("app/main.rbn", "./widgets"),
+            "app/widgets.rbn"
+        );
+        assert_eq!(normalize_path("app/main.rbn", "../lib/a.rbn"), "lib/a.rbn");
+        assert_eq!(normalize_path("app/main.rbn", "std/list"), "std/list.rbn");
+        assert_eq!(normalize_path("", "main"), "main.rbn");
+    }
+
+    #[test]
+    fn builds_module_graph() {
+        let resolution = resolve(&[
+            (
+                "app/main.rbn",
+                "import { Button, Label as Text } from \"./widgets\"\nuse std::list",
+            ),
+            (
+                "app/widgets.rbn",
+                "use std::list::List\npub type Button = Int\npub type Label = String",
+            ),
+            ("std/list.rbn", "pub type List<T> = [T]"),
+        ]);
+        assert_eq!(codes(&resolution), Vec::<&str>::new());
+
+        let graph = &resolution.graph;
+        assert_eq!(graph.len(), 3);
+        let main = graph.module(resolution.entry.unwrap());
+        let widgets = graph.find("app/widgets.rbn").unwrap();
+        let list = graph.find("std/list.rbn").unwrap();
+
+        let deps: Vec<_> = main.dependencies.iter().map(|d| d.module).collect();
+        assert_eq!(deps, [widgets, list]);
+
+        let bindings: Vec<_> = main
+            .bindings
+            .iter()
+            .map(|b| (b.local.as_str(), b.module, b.target.clone()))
+            .collect();
+        assert_eq!(
+            bindings,
+            [
+                ("Button", widgets, ImportTarget::Item("Button".into())),
+                ("Text", widgets, ImportTarget::Item("Label".into())),
+                ("list", list, ImportTarget::Module),
+            ]
+        );
+
+        let widgets = graph.module(widgets);
+        assert_eq!(
+            widgets.bindings[0].target,
+            ImportTarget::Item("List".into())
+        );
+        assert_eq!(widgets.dependencies[0].module, list);
+    }
+
+    #[test]
+    fn reports_missing_modules() {
+        let resolution = resolve(&[(
+            "main.rbn",
+            "type A = Int\nimport { B } from \"./missing\"\nuse std::nothing::here",
+        )]);
+        assert_eq!(
+            codes(&resolution),
+            ["unresolved-module", "unresolved-module"]
+        );
+
+        let missing = &resolution.diagnostics[0];
+        assert_eq!(missing.module, "main.rbn");
+        assert_eq!(missing.loc, ASTLoc { start: 13, end: 42 });
+        assert!(missing.message.contains("missing.rbn"));
+
+        let resolution = ModuleResolver::new(MemoryFileLoader::new()).resolve("main");
+        assert!(resolution.entry.is_none());
+        assert_eq!(codes(&resolution), ["unresolved-module"]);
+    }
+
+    /// Serves `main.rbn`, and fails to read `locked.rbn`.
+    struct LockedLoader;
+
+    impl FileLoader for LockedLoader {
+        fn load(&self, path: &str) -> io::Result<Option<String>> {
+            match path {
+                "main.rbn" => Ok(Some("import { A } from \"./locked\"".to_string())),
+                "locked.rbn" => Err(io::Error::from(io::ErrorKind::PermissionDenied)),
+                _ => Ok(None),
+            }
+        }
+    }
+
+    #[test]
+    fn reports_unreadable_modules() {
+        let resolution = ModuleResolver::new(LockedLoader).resolve("main");
+        let unreadable = &resolution.diagnostics[0];
+        assert_eq!(unreadable.code, "unreadable-module");
+        assert_eq!(unreadable.module, "locked.rbn");
+        assert!(unreadable
+            .message
+            .starts_with("cannot read module `locked.rbn`: "));
+        assert!(!codes(&resolution).contains(&"unresolved-module"));
+
+        let root = env!("CARGO_MANIFEST_DIR");
+        let loader = FsFileLoader::new(root);
+        assert!(loader.load("Cargo.toml").unwrap().is_some());
+        assert!(loader.load("missing.rbn").unwrap().is_none());
+        // A directory exists but cannot be read as a module.
+        assert!(loader.load("src").is_err());
+    }
+
+    #[test]
+    fn reports_unexported_names() {
+        let resolution = resolve(&[
+            (
+                "main.rbn",
+                "import { A, Missing } from \"./a\"\nuse a::Other",
+            ),
+            ("a.rbn", "pub type A = Int"),
+        ]);
+        assert_eq!(codes(&resolution), ["unexported-name", "unexported-name"]);
+        assert_eq!(resolution.diagnostics[0].loc, ASTLoc { start: 12, end: 19 });
+        assert!(resolution.diagnostics[1].message.contains("`Other`"));
+    }
+
+    #[test]
+    fn rejects_private_items() {
+        let resolution = resolve(&[
+            ("main.rbn", "import { Secret } from \"./a\"\nuse a::Hidden"),
+            ("a.rbn", "type Secret = Int\nuse b::Hidden"),
+            ("b.rbn", "pub type Hidden = Int"),
+        ]);
+        assert_eq!(codes(&resolution), ["private-item", "private-item"]);
+
+        let secret = &resolution.diagnostics[0];
+        assert_eq!(secret.module, "main.rbn");
+        assert_eq!(secret.loc, ASTLoc { start: 9, end: 15 });
+        assert_eq!(secret.labels[0].module, "a.rbn");
+        assert_eq!(secret.labels[0].loc, ASTLoc { start: 0, end: 17 });
+
+        let hidden = &resolution.diagnostics[1];
+        assert_eq!(hidden.labels[0].loc, ASTLoc { start: 18, end: 31 });
+    }
+
+    #[test]
+    fn computes_export_tables() {
+        let resolution = resolve(&[
+            (
+                "main.rbn",
+                "import { Button, Widget } from \"./ui\"\nuse ui::list",
+            ),
+            (
+                "ui.rbn",
+                "pub use widgets::Button\npub import { Base as Widget } from \"./widgets\"\n\
+                 pub use std::list\ntype Internal = Int",
+            ),
+            (
+                "widgets.rbn",
+                "pub type Button = Int\npub type Base = String",
+            ),
+            ("std/list.rbn", "pub type List<T> = [T]"),
+        ]);
+        assert_eq!(codes(&resolution), Vec::<&str>::new());
+
+        let graph = &resolution.graph;
+        let widgets = graph.find("widgets.rbn").unwrap();
+        let ui = graph.module(graph.find("ui.rbn").unwrap());
+        let exports: Vec<_> = ui
+            .exports
+            .iter()
+            .map(|(name, export)| (name.as_str(), export.origin, export.target.clone()))
+            .collect();
+        assert_eq!(
+            exports,
+            [
+                ("Button", widgets, ImportTarget::Item("Button".into())),
+                ("Widget", widgets, ImportTarget::Item("Base".into())),
+                (
+                    "list",
+                    graph.find("std/list.rbn").unwrap(),
+                    ImportTarget::Module
+                ),
+            ]
+        );
+        assert_eq!(
+            ui.exports["Button"].loc,
+            graph.module(widgets).ast.nodes[0].loc()
+        );
+    }
+
+    #[test]
+    fn detects_cycles() {
+        let resolution = resolve(&[
+            ("a.rbn", "import { B } from \"./b\"\npub type A = Int"),
+            ("b.rbn", "import { C } from \"./c\"\npub type B = Int"),
+            ("c.rbn", "import { A } from \"./a\"\npub type C = Int"),
+        ]);
+        assert_eq!(codes(&resolution), ["import-cycle"]);
+
+        let cycle = &resolution.diagnostics[0];
+        assert_eq!(cycle.module, "c.rbn");
+        assert_eq!(cycle.loc, ASTLoc { start: 0, end: 23 });
+        assert_eq!(
+            cycle.message,
+            "import cycle: a.rbn -> b.rbn -> c.rbn -> a.rbn"
+        );
+    }
+
+    #[test]
+    fn reports_parse_errors() {
+        let resolution = resolve(&[("main.rbn", "import { A } from \"./a\""), ("a.rbn", "type")]);
+        assert_eq!(codes(&resolution), ["parse-error", "unexported-name"]);
+        assert_eq!(resolution.diagnostics[0].module, "a.rbn");
+        assert_eq!(resolution.diagnostics[0].loc, ASTLoc { start: 0, end: 4 });
+
+        let resolution = resolve(&[("main.rbn", "const A = 1\nconst S = \"open")]);
+        assert_eq!(codes(&resolution), ["parse-error"]);
+        assert_eq!(resolution.diagnostics[0].loc, ASTLoc { start: 22, end: 27 });
+    }
+}
@@ -0,0 +1,413 @@
+//! Monomorphization of generic functions and views.
+//!
+//! Code generation emits a copy of a generic function or view for every list
+//! of type arguments it is used with, e.g. `max$i` and `max$f` for `max(1, 2)`
+//! and `max(1.5, 2.0)`, so that the copies can use the operations of their
+//! concrete types. [`Monomorphizer`] finds these instances: starting from the
+//! items which are not generic, it follows the uses of generic items whose
+//! type arguments the [type checker](crate::lang::typecheck) inferred, and
+//! walks each new instance with its type arguments substituted for the type
+//! parameters of its item.
+//!
+//! Type arguments which were left unconstrained, e.g. the `T` of `first([])`,
+//! cannot change what the instance does, and are instantiated as `_`.
+//!
+//! An item which uses itself with ever larger type arguments, as in
+//! `fn nest<T>(x: T) { nest([x]) }`, would have infinitely many instances;
+//! this is reported once instances nest [`MAX_DEPTH`] deep.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::lang::{
+    ast::{ASTLoc, Spanned},
+    diagnostic::Diagnostic,
+    node_index::{Ast, NodeId, NodeRef},
+    resolve::{DefId, Resolution},
+    typecheck::{Type, Typing},
+};
+
+/// How deeply instances may be instantiated from each other.
+pub const MAX_DEPTH: usize = 32;
+
+/// A generic function or view together with its type arguments.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Instance {
+    /// The generic function or view.
+    pub item: DefId,
+    pub args: Vec<Type>,
+    /// The name of the instance as written, e.g. `max<Int>`.
+    pub name: String,
+    /// A name for the instance which is unique within the module and only
+    /// contains identifier characters and `$`, e.g. `max$i`.
+    pub symbol: String,
+}
+
+/// The instances of the generic items of a module.
+#[derive(Debug, Clone, Default)]
+pub struct Instances {
+    /// Every instance reachable from the items which are not generic, in the
+    /// order they were found.
+    pub instances: Vec<Instance>,
+    /// The instance each use of a generic item refers to, by the instance
+    /// the use is in, which is `None` outside generic items, and the
+    /// identifier or element using it.
+    pub uses: HashMap<(Option<usize>, NodeId), usize>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Instances {
+    /// The instance used by `node` in the code generated for the instance
+    /// `within`, or outside generic items if `within` is `None`.
+    pub fn used(&self, within: Option<usize>, node: NodeId) -> Option<&Instance> {
+        Some(&self.instances[*self.uses.get(&(within, node))?])
+    }
+
+    /// The instances of the generic item `item`.
+    pub fn of(&self, item: DefId) -> impl Iterator<Item = &Instance> {
+        self.instances.iter().filter(move |i| i.item == item)
+    }
+}
+
+/// A use of a generic item within an item.
+struct Use<'t> {
+    node: NodeId,
+    item: DefId,
+    /// The type arguments, in terms of the type parameters of the item the
+    /// use is in.
+    args: &'t [Type],
+}
+
+/// Finds the instances of the generic items of a resolved and type checked
+/// module.
+pub struct Monomorphizer<'a, 'ast> {
+    path: String,
+    ast: &'a Ast<'ast>,
+    res: &'a Resolution,
+    /// The definition declared by each declaring node.
+    decls: HashMap<NodeId, DefId>,
+    /// The uses of generic items within each item, by the node of the item.
+    uses: HashMap<NodeId, Vec<Use<'a>>>,
+    /// The index of each instance by its item and type arguments.
+    indices: HashMap<(DefId, Vec<Type>), usize>,
+    /// The instances still to walk, with how deeply they are nested.
+    pending: Vec<(usize, usize)>,
+    /// The items reported for exceeding [`MAX_DEPTH`].
+    exceeded: HashSet<DefId>,
+    instances: Instances,
+}
+
+impl<'a, 'ast> Monomorphizer<'a, 'ast> {
+    pub fn new(
+        path: impl Into<String>,
+        ast: &'a Ast<'ast>,
+        res: &'a Resolution,
+        typing: &'a Typing,
+    ) -> Self {
+        let mut uses: HashMap<NodeId, Vec<Use>> = HashMap::new();
+        for (node, args) in typing.instances.iter() {
+            let (Some(&item), Some(owner)) = (res.uses.get(node), top_level(ast, node)) else {
+                continue;
+            };
+            uses.entry(owner)
+                .or_default()
+                .push(Use { node, item, args });
+        }
+        let decls = res
+            .definition_ids()
+            .filter_map(|(id, def)| Some((def.node?, id)))
+            .collect();
+        Self {
+            path: path.into(),
+            ast,
+            res,
+            decls,
+            uses,
+            indices: HashMap::new(),
+            pending: Vec::new(),
+            exceeded: HashSet::new(),
+            instances: Instances::default(),
+        }
+    }
+
+    pub fn run(mut self) -> Instances {
+        let items = self.ast.children(self.ast.root()).to_vec();
+        for item in items {
+            let is_code = matches!(
+                self.ast.get(item),
+                NodeRef::Fn(_) | NodeRef::View(_) | NodeRef::Const(_)
+            );
+            if is_code && self.type_params(item).is_empty() {
+                self.walk(item, None, &[], 0);
+            }
+        }
+        while let Some((index, depth)) = self.pending.pop() {
+            let instance = &self.instances.instances[index];
+            let Some(node) = self.res.definition(instance.item).node else {
+                continue;
+            };
+            let args = instance.args.clone();
+            self.walk(node, Some(index), &args, depth);
+        }
+        self.instances
+    }
+
+    /// The type parameters of the function or view declared by `node`.
+    fn type_params(&self, node: NodeId) -> Vec<DefId> {
+        let params = match self.ast.get(node) {
+            NodeRef::Fn(item) => &item.type_params,
+            NodeRef::View(item) => &item.type_params,
+            _ => return Vec::new(),
+        };
+        (params.iter())
+            .filter_map(|param| self.decls.get(&self.ast.id(param)?).copied())
+            .collect()
+    }
+
+    /// Records the instances used by the item `node`, walked as the instance
+    /// `within` with `args` for its type parameters.
+    fn walk(&mut self, node: NodeId, within: Option<usize>, args: &[Type], depth: usize) {
+        let params = self.type_params(node);
+        let Some(uses) = self.uses.get(&node) else {
+            return;
+        };
+        let uses: Vec<_> = uses
+            .iter()
+            .map(|u| {
+                let args = u.args.iter().map(|t| t.substitute(&params, args));
+                (u.node, u.item, args.collect::<Vec<_>>())
+            })
+            .collect();
+        for (use_node, item, args) in uses {
+            let loc = self.ast.get(use_node).loc();
+            if let Some(index) = self.instance(item, args, depth + 1, loc) {
+                self.instances.uses.insert((within, use_node), index);
+            }
+        }
+    }
+
+    /// The index of the instance of `item` with `args`, used at `loc` from
+    /// an instance nested `depth - 1` deep. Returns `None` if the instance
+    /// would be nested too deeply.
+    fn instance(
+        &mut self,
+        item: DefId,
+        args: Vec<Type>,
+        depth: usize,
+        loc: ASTLoc,
+    ) -> Option<usize> {
+        let args: Vec<_> = args.iter().map(concrete).collect();
+        if let Some(&index) = self.indices.get(&(item, args.clone())) {
+            return Some(index);
+        }
+        let name = format!("{}<{}>", self.res.definition(item).name, list(&args));
+        if depth > MAX_DEPTH {
+            if self.exceeded.insert(item) {
+                self.instances.diagnostics.push(Diagnostic::error(
+                    "instantiation-limit",
+                    &self.path,
+                    loc,
+                    format!(
+                        "instantiating `{}` needs instances nested more than {} deep",
+                        name, MAX_DEPTH
+                    ),
+                ));
+            }
+            return None;
+        }
+
+        let mut symbol = format!("{}$", self.res.definition(item).name);
+        for arg in &args {
+            mangle(arg, &mut symbol);
+        }
+        let index = self.instances.instances.len();
+        self.instances.instances.push(Instance {
+            item,
+            args: args.clone(),
+            name,
+            symbol,
+        });
+        self.indices.insert((item, args), index);
+        self.pending.push((index, depth));
+        Some(index)
+    }
+}
+
+/// The item at the top of the module containing `node`.
+fn top_level(ast: &Ast, node: NodeId) -> Option<NodeId> {
+    let root = ast.root();
+    std::iter::once(node)
+        .chain(ast.ancestors(node))
+        .find(|&id| ast.parent(id) == Some(root))
+}
+
+/// Replaces the type variables left in `ty` by [`Type::Unknown`].
+fn concrete(ty: &Type) -> Type {
+    match ty {
+        Type::Var(_) => Type::Unknown,
+        Type::Tuple(items) => Type::Tuple(items.iter().map(concrete).collect()),
+        Type::List(item) => Type::List(Box::new(concrete(item))),
+        Type::Optional(item) => Type::Optional(Box::new(concrete(item))),
+        Type::Record(fields) => Type::Record(
+            (fields.iter())
+                .map(|(name, ty)| (name.clone(), concrete(ty)))
+                .collect(),
+        ),
+        Type::Fn {
+            params,
+            ret,
+            effects,
+        } => Type::Fn {
+            params: params.iter().map(concrete).collect(),
+            ret: Box::new(concrete(ret)),
+            effects: effects.clone(),
+        },
+        Type::Named { name, def, args } => Type::Named {
+            name: name.clone(),
+            def: *def,
+            args: args.iter().map(concrete).collect(),
+        },
+        _ => ty.clone(),
+    }
+}
+
+fn list(types: &[Type]) -> String {
+    let types: Vec<_> = types.iter().map(Type::to_string).collect();
+    types.join(", ")
+}
+
+/// Appends the mangled form of `ty` to `out`. No mangled type is a prefix
+/// of another, so the mangled arguments of an instance can be concatenated.
+fn mangle(ty: &Type, out: &mut String) {
+    let name = |out: &mut String, name: &str| out.push_str(&format!("{}{}", name.len(), name));
+    match ty {
+        Type::Int => out.push('i'),
+        Type::Float => out.push('f'),
+        Type::String => out.push('s'),
+        Type::Bool => out.push('b'),
+        Type::Tuple(items) => {
+            out.push_str(&format!("T{}", items.len()));
+            items.iter().for_each(|t| mangle(t, out));
+        }
+        Type::List(item) => {
+            out.push('L');
+            mangle(item, out);
+        }
+        Type::Optional(item) => {
+            out.push('O');
+            mangle(item, out);
+        }
+        Type::Record(fields) => {
+            out.push_str(&format!("R{}", fields.len()));
+            for (field, ty) in fields {
+                name(out, field);
+                mangle(ty, out);
+            }
+        }
+        Type::Fn {
+            params,
+            ret,
+            effects,
+        } => {
+            out.push_str(&format!("F{}_{}", params.len(), effects.len()));
+            params.iter().for_each(|t| mangle(t, out));
+            mangle(ret, out);
+            effects.iter().for_each(|t| mangle(t, out));
+        }
+        Type::Named {
+            name: type_name,
+            args,
+            ..
+        } => {
+            out.push('N');
+            name(out, type_name);
+            out.push_str(&args.len().to_string());
+            args.iter().for_each(|t| mangle(t, out));
+        }
+        Type::Param { name: param, .. } => {
+            out.push('P');
+            name(out, param);
+        }
+        Type::Effect { name: effect, .. } => {
+            out.push('E');
+            name(out, effect);
+        }
+        Type::Element(tag) => {
+            out.push('X');
+            name(out, tag);
+        }
+        Type::Var(_) | Type::Unknown => out.push('_'),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lang::{resolve::test::with_resolution, typecheck::TypeChecker};
+
+    fn instances(source: &str) -> (Vec<(String, String)>, Instances) {
+        let instances = with_resolution(source, |ast, res| {
+            assert_eq!(res.diagnostics, [], "failed to resolve");
+            let typing = TypeChecker::new("main.rbn", ast, res).check();
+            assert_eq!(typing.diagnostics, [], "failed to type check");
+            Monomorphizer::new("main.rbn", ast, res, &typing).run()
+        });
+        let names = (instances.instances.iter())
+            .map(|i| (i.name.clone(), i.symbol.clone()))
+            .collect();
+        (names, instances)
+    }
+
+    fn pair(name: &str, symbol: &str) -> (String, String) {
+        (name.to_string(), symbol.to_string())
+    }
+
+    #[test]
+    fn instantiates_reachable_items() {
+        let (names, instances) = instances(
+            "fn id<T>(x: T) -> T { x }
+fn pair<T>(x: T) -> (T, T) { (id(x), id(x)) }
+fn unused<T>(x: T) -> T { id(x) }
+view List<T>(items: [T]) { <ul></ul> }
+fn main() {
+  let a = pair(1)
+  let b = pair([\"a\"])
+  let c = id(2)
+}
+view Page() {
+  <List items={[{ done: true }]} />
+}
+const EMPTY = id([])",
+        );
+        assert_eq!(
+            names,
+            [
+                pair("pair<Int>", "pair$i"),
+                pair("pair<[String]>", "pair$Ls"),
+                pair("id<Int>", "id$i"),
+                pair("List<{ done: Bool }>", "List$R14doneb"),
+                pair("id<[_]>", "id$L_"),
+                pair("id<[String]>", "id$Ls"),
+            ]
+        );
+        assert_eq!(instances.diagnostics, []);
+        // Both uses of `id` in `pair<Int>` refer to `id<Int>`.
+        let in_pair: Vec<_> = (instances.uses.iter())
+            .filter(|((within, _), _)| *within == Some(0))
+            .map(|(_, &index)| index)
+            .collect();
+        assert_eq!(in_pair, [2, 2]);
+        assert_eq!(instances.of(instances.instances[2].item).count(), 3);
+    }
+
+    #[test]
+    fn limits_nesting() {
+        let (names, instances) = instances(
+            "fn nest<T>(x: T) -> Int { nest([x]) }
+fn main() { nest(1) }",
+        );
+        assert_eq!(names.len(), MAX_DEPTH);
+        assert_eq!(names[1], pair("nest<[Int]>", "nest$Li"));
+        let codes: Vec<_> = instances.diagnostics.iter().map(|d| d.code).collect();
+        assert_eq!(codes, ["instantiation-limit"]);
+    }
+}
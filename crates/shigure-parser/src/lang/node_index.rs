@@ -0,0 +1,654 @@
+//! An index of the nodes of a parsed module.
+//!
+//! [`Ast`] assigns every node of a module a [`NodeId`] and records its parent
+//! and children. Later passes attach their results to nodes with a
+//! [`SideTable`] instead of storing them in the AST, so they annotate the
+//! tree without mutating or cloning it:
+//!
+//! ```
+//! use shigure_parser::lang::{
+//!     node_index::{Ast, NodeRef, SideTable},
+//!     ast::ASTExprKind,
+//!     parser::{module::ModuleParser, Parser},
+//! };
+//!
+//! let module = ModuleParser::from_str("const A = b + 1").parse_all().unwrap();
+//! let ast = Ast::new(&module);
+//!
+//! let mut names = SideTable::new();
+//! for id in ast.ids() {
+//!     if let NodeRef::Expr(expr) = ast.get(id) {
+//!         if let ASTExprKind::Identifier(name) = &expr.kind {
+//!             names.insert(id, name.len());
+//!         }
+//!     }
+//! }
+//!
+//! let (b, len) = names.iter().next().unwrap();
+//! assert_eq!(*len, 1);
+//! assert!(matches!(ast.get(ast.parent(b).unwrap()), NodeRef::Expr(_)));
+//! ```
+//!
+//! The AST is not stored in an arena. The nodes stay in the module, which
+//! still owns its `String`s and `Vec`s, and the index borrows them. A node
+//! is found again by its address and kind, so [`Ast::id`] only knows the
+//! nodes of the tree the index was built from, and a clone of the module
+//! needs an index of its own.
+
+use std::{collections::HashMap, mem::Discriminant, ops::Index};
+
+use crate::lang::{
+    ast::{
+        ASTItemConst, ASTItemEffect, ASTItemFn, ASTItemImport, ASTItemType, ASTItemUse,
+        ASTItemView, ASTLoc, ASTNodeAssign, ASTNodeBlock, ASTNodeEffectBlock, ASTNodeEffectOp,
+        ASTNodeExpr, ASTNodeHandlerOp, ASTNodeImportName, ASTNodeLet, ASTNodeModule, ASTNodeParam,
+        ASTNodePattern, ASTNodeRecordField, ASTNodeType, ASTNodeTypeField, ASTNodeTypeParam,
+        ASTNodeTypeVariant, ASTNodeViewAttribute, ASTNodeViewElement, ASTNodeWhenArm, ASTNodeWith,
+        Spanned,
+    },
+    syntax::SyntaxKind,
+    visit::{self, Visitor},
+};
+
+/// A handle to a node of an [`Ast`].
+///
+/// Ids are assigned in source order, starting with the module as
+/// [`Ast::root`], so indexing a tree twice from the same tree yields the
+/// same ids.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy, Debug)]
+pub struct NodeId(u32);
+
+impl NodeId {
+    /// The position of the node in the index.
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// A borrowed node of the AST.
+///
+/// The wrapper enums [`ASTNodeScoped`](crate::lang::ast::ASTNodeScoped),
+/// [`ASTNodeStmt`](crate::lang::ast::ASTNodeStmt) and
+/// [`ASTNodeViewChild`](crate::lang::ast::ASTNodeViewChild) are not nodes of
+/// their own; the node they wrap is.
+#[derive(Clone, Copy, Debug)]
+pub enum NodeRef<'ast> {
+    Module(&'ast ASTNodeModule),
+    Const(&'ast ASTItemConst),
+    Effect(&'ast ASTItemEffect),
+    EffectOp(&'ast ASTNodeEffectOp),
+    Fn(&'ast ASTItemFn),
+    Import(&'ast ASTItemImport),
+    ImportName(&'ast ASTNodeImportName),
+    TypeItem(&'ast ASTItemType),
+    TypeParam(&'ast ASTNodeTypeParam),
+    TypeField(&'ast ASTNodeTypeField),
+    TypeVariant(&'ast ASTNodeTypeVariant),
+    Use(&'ast ASTItemUse),
+    View(&'ast ASTItemView),
+    Param(&'ast ASTNodeParam),
+    Block(&'ast ASTNodeBlock),
+    Let(&'ast ASTNodeLet),
+    With(&'ast ASTNodeWith),
+    HandlerOp(&'ast ASTNodeHandlerOp),
+    EffectBlock(&'ast ASTNodeEffectBlock),
+    Assign(&'ast ASTNodeAssign),
+    Element(&'ast ASTNodeViewElement),
+    Attribute(&'ast ASTNodeViewAttribute),
+    Expr(&'ast ASTNodeExpr),
+    RecordField(&'ast ASTNodeRecordField),
+    WhenArm(&'ast ASTNodeWhenArm),
+    Pattern(&'ast ASTNodePattern),
+    Type(&'ast ASTNodeType),
+}
+
+impl NodeRef<'_> {
+    /// The address of the node, which identifies it together with its kind.
+    /// A node may share its address with its first field.
+    fn addr(&self) -> usize {
+        match *self {
+            NodeRef::Module(n) => n as *const _ as usize,
+            NodeRef::Const(n) => n as *const _ as usize,
+            NodeRef::Effect(n) => n as *const _ as usize,
+            NodeRef::EffectOp(n) => n as *const _ as usize,
+            NodeRef::Fn(n) => n as *const _ as usize,
+            NodeRef::Import(n) => n as *const _ as usize,
+            NodeRef::ImportName(n) => n as *const _ as usize,
+            NodeRef::TypeItem(n) => n as *const _ as usize,
+            NodeRef::TypeParam(n) => n as *const _ as usize,
+            NodeRef::TypeField(n) => n as *const _ as usize,
+            NodeRef::TypeVariant(n) => n as *const _ as usize,
+            NodeRef::Use(n) => n as *const _ as usize,
+            NodeRef::View(n) => n as *const _ as usize,
+            NodeRef::Param(n) => n as *const _ as usize,
+            NodeRef::Block(n) => n as *const _ as usize,
+            NodeRef::Let(n) => n as *const _ as usize,
+            NodeRef::With(n) => n as *const _ as usize,
+            NodeRef::HandlerOp(n) => n as *const _ as usize,
+            NodeRef::EffectBlock(n) => n as *const _ as usize,
+            NodeRef::Assign(n) => n as *const _ as usize,
+            NodeRef::Element(n) => n as *const _ as usize,
+            NodeRef::Attribute(n) => n as *const _ as usize,
+            NodeRef::Expr(n) => n as *const _ as usize,
+            NodeRef::RecordField(n) => n as *const _ as usize,
+            NodeRef::WhenArm(n) => n as *const _ as usize,
+            NodeRef::Pattern(n) => n as *const _ as usize,
+            NodeRef::Type(n) => n as *const _ as usize,
+        }
+    }
+}
+
+impl NodeRef<'_> {
+    /// The kind of the node of the syntax tree the node was parsed from.
+    pub fn syntax_kind(&self) -> SyntaxKind {
+        match self {
+            NodeRef::Module(_) => SyntaxKind::Module,
+            NodeRef::Const(_) => SyntaxKind::Const,
+            NodeRef::Effect(_) => SyntaxKind::Effect,
+            NodeRef::EffectOp(_) => SyntaxKind::EffectOp,
+            NodeRef::Fn(_) => SyntaxKind::Fn,
+            NodeRef::Import(_) => SyntaxKind::Import,
+            NodeRef::ImportName(_) => SyntaxKind::ImportName,
+            NodeRef::TypeItem(_) => SyntaxKind::TypeItem,
+            NodeRef::TypeParam(_) => SyntaxKind::TypeParam,
+            NodeRef::TypeField(_) => SyntaxKind::TypeField,
+            NodeRef::TypeVariant(_) => SyntaxKind::Variant,
+            NodeRef::Use(_) => SyntaxKind::Use,
+            NodeRef::View(_) => SyntaxKind::View,
+            NodeRef::Param(_) => SyntaxKind::Param,
+            NodeRef::Block(_) => SyntaxKind::Block,
+            NodeRef::Let(_) => SyntaxKind::Let,
+            NodeRef::With(_) => SyntaxKind::With,
+            NodeRef::HandlerOp(_) => SyntaxKind::HandlerOp,
+            NodeRef::EffectBlock(_) => SyntaxKind::EffectBlock,
+            NodeRef::Assign(_) => SyntaxKind::Assign,
+            NodeRef::Element(_) => SyntaxKind::Element,
+            NodeRef::Attribute(_) => SyntaxKind::Attribute,
+            NodeRef::Expr(_) => SyntaxKind::Expr,
+            NodeRef::RecordField(_) => SyntaxKind::RecordField,
+            NodeRef::WhenArm(_) => SyntaxKind::WhenArm,
+            NodeRef::Pattern(_) => SyntaxKind::Pattern,
+            NodeRef::Type(_) => SyntaxKind::Type,
+        }
+    }
+}
+
+impl Spanned for NodeRef<'_> {
+    fn loc(&self) -> ASTLoc {
+        match self {
+            NodeRef::Module(n) => n.loc(),
+            NodeRef::Const(n) => n.loc(),
+            NodeRef::Effect(n) => n.loc(),
+            NodeRef::EffectOp(n) => n.loc(),
+            NodeRef::Fn(n) => n.loc(),
+            NodeRef::Import(n) => n.loc(),
+            NodeRef::ImportName(n) => n.loc(),
+            NodeRef::TypeItem(n) => n.loc(),
+            NodeRef::TypeParam(n) => n.loc(),
+            NodeRef::TypeField(n) => n.loc(),
+            NodeRef::TypeVariant(n) => n.loc(),
+            NodeRef::Use(n) => n.loc(),
+            NodeRef::View(n) => n.loc(),
+            NodeRef::Param(n) => n.loc(),
+            NodeRef::Block(n) => n.loc(),
+            NodeRef::Let(n) => n.loc(),
+            NodeRef::With(n) => n.loc(),
+            NodeRef::HandlerOp(n) => n.loc(),
+            NodeRef::EffectBlock(n) => n.loc(),
+            NodeRef::Assign(n) => n.loc(),
+            NodeRef::Element(n) => n.loc(),
+            NodeRef::Attribute(n) => n.loc(),
+            NodeRef::Expr(n) => n.loc(),
+            NodeRef::RecordField(n) => n.loc(),
+            NodeRef::WhenArm(n) => n.loc(),
+            NodeRef::Pattern(n) => n.loc(),
+            NodeRef::Type(n) => n.loc(),
+        }
+    }
+}
+
+macro_rules! node_ref_from {
+    ($($ty:ident => $variant:ident),* $(,)?) => {
+        $(
+            impl<'ast> From<&'ast $ty> for NodeRef<'ast> {
+                fn from(node: &'ast $ty) -> Self {
+                    NodeRef::$variant(node)
+                }
+            }
+        )*
+    };
+}
+
+node_ref_from! {
+    ASTNodeModule => Module,
+    ASTItemConst => Const,
+    ASTItemEffect => Effect,
+    ASTNodeEffectOp => EffectOp,
+    ASTItemFn => Fn,
+    ASTItemImport => Import,
+    ASTNodeImportName => ImportName,
+    ASTItemType => TypeItem,
+    ASTNodeTypeParam => TypeParam,
+    ASTNodeTypeField => TypeField,
+    ASTNodeTypeVariant => TypeVariant,
+    ASTItemUse => Use,
+    ASTItemView => View,
+    ASTNodeParam => Param,
+    ASTNodeBlock => Block,
+    ASTNodeLet => Let,
+    ASTNodeWith => With,
+    ASTNodeHandlerOp => HandlerOp,
+    ASTNodeEffectBlock => EffectBlock,
+    ASTNodeAssign => Assign,
+    ASTNodeViewElement => Element,
+    ASTNodeViewAttribute => Attribute,
+    ASTNodeExpr => Expr,
+    ASTNodeRecordField => RecordField,
+    ASTNodeWhenArm => WhenArm,
+    ASTNodePattern => Pattern,
+    ASTNodeType => Type,
+}
+
+struct NodeData<'ast> {
+    node: NodeRef<'ast>,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+}
+
+/// The nodes of a module with their ids, parents and children.
+pub struct Ast<'ast> {
+    nodes: Vec<NodeData<'ast>>,
+    ids: HashMap<(usize, Discriminant<NodeRef<'ast>>), NodeId>,
+}
+
+impl<'ast> Ast<'ast> {
+    /// Indexes every node of `module`.
+    pub fn new(module: &'ast ASTNodeModule) -> Self {
+        let mut builder = Builder {
+            ast: Ast {
+                nodes: Vec::new(),
+                ids: HashMap::new(),
+            },
+            parents: Vec::new(),
+        };
+        builder.visit_module(module);
+        builder.ast
+    }
+
+    /// The id of the module.
+    pub fn root(&self) -> NodeId {
+        NodeId(0)
+    }
+
+    /// The number of indexed nodes.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// The ids of all nodes in source order, parents before their children.
+    pub fn ids(&self) -> impl Iterator<Item = NodeId> {
+        (0..self.nodes.len() as u32).map(NodeId)
+    }
+
+    pub fn get(&self, id: NodeId) -> NodeRef<'ast> {
+        self.nodes[id.index()].node
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes[id.index()].parent
+    }
+
+    /// The children of the node in source order.
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        &self.nodes[id.index()].children
+    }
+
+    /// The parent of the node, its parent and so on up to the root.
+    pub fn ancestors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + use<'_, 'ast> {
+        std::iter::successors(self.parent(id), |&id| self.parent(id))
+    }
+
+    /// Returns the id of a node of the indexed module, or `None` if the node
+    /// belongs to another tree.
+    pub fn id(&self, node: impl Into<NodeRef<'ast>>) -> Option<NodeId> {
+        let node = node.into();
+        self.ids
+            .get(&(node.addr(), std::mem::discriminant(&node)))
+            .copied()
+    }
+
+    /// Returns the innermost node whose location contains `offset`.
+    pub fn node_at(&self, offset: u32) -> Option<NodeId> {
+        let contains = |id: &&NodeId| {
+            let loc = self.get(**id).loc();
+            loc.start <= offset && offset < loc.end
+        };
+        let mut found = Some(self.root()).filter(|id| contains(&id))?;
+        while let Some(&child) = self.children(found).iter().find(contains) {
+            found = child;
+        }
+        Some(found)
+    }
+}
+
+impl<'ast> Index<NodeId> for Ast<'ast> {
+    type Output = NodeRef<'ast>;
+
+    fn index(&self, id: NodeId) -> &Self::Output {
+        &self.nodes[id.index()].node
+    }
+}
+
+/// Values of type `T` attached to nodes of an [`Ast`], such as the type of
+/// each expression or the declaration each name resolves to.
+#[derive(Clone, Debug)]
+pub struct SideTable<T> {
+    values: Vec<Option<T>>,
+}
+
+impl<T> Default for SideTable<T> {
+    fn default() -> Self {
+        Self { values: Vec::new() }
+    }
+}
+
+impl<T> SideTable<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Attaches `value` to the node, returning the value it replaces.
+    pub fn insert(&mut self, id: NodeId, value: T) -> Option<T> {
+        if self.values.len() <= id.index() {
+            self.values.resize_with(id.index() + 1, || None);
+        }
+        self.values[id.index()].replace(value)
+    }
+
+    pub fn get(&self, id: NodeId) -> Option<&T> {
+        self.values.get(id.index())?.as_ref()
+    }
+
+    pub fn get_mut(&mut self, id: NodeId) -> Option<&mut T> {
+        self.values.get_mut(id.index())?.as_mut()
+    }
+
+    pub fn remove(&mut self, id: NodeId) -> Option<T> {
+        self.values.get_mut(id.index())?.take()
+    }
+
+    pub fn contains(&self, id: NodeId) -> bool {
+        self.get(id).is_some()
+    }
+
+    /// The nodes with a value and their values, in the order of the ids.
+    pub fn iter(&self) -> impl Iterator<Item = (NodeId, &T)> {
+        self.values
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, value)| Some((NodeId(idx as u32), value.as_ref()?)))
+    }
+}
+
+impl<T> Index<NodeId> for SideTable<T> {
+    type Output = T;
+
+    /// Panics if the node has no value.
+    fn index(&self, id: NodeId) -> &T {
+        self.get(id).expect("no value for the node")
+    }
+}
+
+struct Builder<'ast> {
+    ast: Ast<'ast>,
+    /// The ids of the nodes being visited, innermost last.
+    parents: Vec<NodeId>,
+}
+
+impl<'ast> Builder<'ast> {
+    fn enter(&mut self, node: NodeRef<'ast>, walk: impl FnOnce(&mut Self)) {
+        let id = NodeId(self.ast.nodes.len() as u32);
+        let parent = self.parents.last().copied();
+        if let Some(parent) = parent {
+            self.ast.nodes[parent.index()].children.push(id);
+        }
+        self.ast.nodes.push(NodeData {
+            node,
+            parent,
+            children: Vec::new(),
+        });
+        self.ast
+            .ids
+            .insert((node.addr(), std::mem::discriminant(&node)), id);
+
+        self.parents.push(id);
+        walk(self);
+        self.parents.pop();
+    }
+}
+
+impl<'ast> Visitor<'ast> for Builder<'ast> {
+    fn visit_module(&mut self, module: &'ast ASTNodeModule) {
+        self.enter(module.into(), |b| visit::walk_module(b, module));
+    }
+
+    fn visit_const(&mut self, item: &'ast ASTItemConst) {
+        self.enter(item.into(), |b| visit::walk_const(b, item));
+    }
+
+    fn visit_effect(&mut self, item: &'ast ASTItemEffect) {
+        self.enter(item.into(), |b| visit::walk_effect(b, item));
+    }
+
+    fn visit_effect_op(&mut self, op: &'ast ASTNodeEffectOp) {
+        self.enter(op.into(), |b| visit::walk_effect_op(b, op));
+    }
+
+    fn visit_fn(&mut self, item: &'ast ASTItemFn) {
+        self.enter(item.into(), |b| visit::walk_fn(b, item));
+    }
+
+    fn visit_import(&mut self, item: &'ast ASTItemImport) {
+        self.enter(item.into(), |b| visit::walk_import(b, item));
+    }
+
+    fn visit_import_name(&mut self, name: &'ast ASTNodeImportName) {
+        self.enter(name.into(), |_| {});
+    }
+
+    fn visit_type_item(&mut self, item: &'ast ASTItemType) {
+        self.enter(item.into(), |b| visit::walk_type_item(b, item));
+    }
+
+    fn visit_type_param(&mut self, param: &'ast ASTNodeTypeParam) {
+        self.enter(param.into(), |_| {});
+    }
+
+    fn visit_type_field(&mut self, field: &'ast ASTNodeTypeField) {
+        self.enter(field.into(), |b| visit::walk_type_field(b, field));
+    }
+
+    fn visit_type_variant(&mut self, variant: &'ast ASTNodeTypeVariant) {
+        self.enter(variant.into(), |b| visit::walk_type_variant(b, variant));
+    }
+
+    fn visit_use(&mut self, item: &'ast ASTItemUse) {
+        self.enter(item.into(), |_| {});
+    }
+
+    fn visit_view(&mut self, item: &'ast ASTItemView) {
+        self.enter(item.into(), |b| visit::walk_view(b, item));
+    }
+
+    fn visit_param(&mut self, param: &'ast ASTNodeParam) {
+        self.enter(param.into(), |b| visit::walk_param(b, param));
+    }
+
+    fn visit_block(&mut self, block: &'ast ASTNodeBlock) {
+        self.enter(block.into(), |b| visit::walk_block(b, block));
+    }
+
+    fn visit_let(&mut self, stmt: &'ast ASTNodeLet) {
+        self.enter(stmt.into(), |b| visit::walk_let(b, stmt));
+    }
+
+    fn visit_with(&mut self, stmt: &'ast ASTNodeWith) {
+        self.enter(stmt.into(), |b| visit::walk_with(b, stmt));
+    }
+
+    fn visit_handler_op(&mut self, op: &'ast ASTNodeHandlerOp) {
+        self.enter(op.into(), |b| visit::walk_handler_op(b, op));
+    }
+
+    fn visit_effect_block(&mut self, stmt: &'ast ASTNodeEffectBlock) {
+        self.enter(stmt.into(), |b| visit::walk_effect_block(b, stmt));
+    }
+
+    fn visit_assign(&mut self, stmt: &'ast ASTNodeAssign) {
+        self.enter(stmt.into(), |b| visit::walk_assign(b, stmt));
+    }
+
+    fn visit_element(&mut self, element: &'ast ASTNodeViewElement) {
+        self.enter(element.into(), |b| visit::walk_element(b, element));
+    }
+
+    fn visit_attribute(&mut self, attr: &'ast ASTNodeViewAttribute) {
+        self.enter(attr.into(), |b| visit::walk_attribute(b, attr));
+    }
+
+    fn visit_expr(&mut self, expr: &'ast ASTNodeExpr) {
+        self.enter(expr.into(), |b| visit::walk_expr(b, expr));
+    }
+
+    fn visit_record_field(&mut self, field: &'ast ASTNodeRecordField) {
+        self.enter(field.into(), |b| visit::walk_record_field(b, field));
+    }
+
+    fn visit_when_arm(&mut self, arm: &'ast ASTNodeWhenArm) {
+        self.enter(arm.into(), |b| visit::walk_when_arm(b, arm));
+    }
+
+    fn visit_pattern(&mut self, pattern: &'ast ASTNodePattern) {
+        self.enter(pattern.into(), |b| visit::walk_pattern(b, pattern));
+    }
+
+    fn visit_type(&mut self, ty: &'ast ASTNodeType) {
+        self.enter(ty.into(), |b| visit::walk_type(b, ty));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lang::{
+        ast::{ASTExprKind, ASTNodeScoped, ASTNodeStmt},
+        parser::{module::ModuleParser, Parser},
+    };
+
+    const SOURCE: &str = "type Point = { x: Float, y: Float }
+
+fn norm(p: Point) -> Float {
+  p.x * p.x + p.y
+}
+
+view Counter(start: Int) {
+  let count = start
+  <button onclick={increment}>{count}</button>
+}
+";
+
+    fn parse(input: &str) -> ASTNodeModule {
+        ModuleParser::from_str(input)
+            .parse_all()
+            .expect("failed to parse")
+    }
+
+    #[test]
+    fn indexes_every_node() {
+        let module = parse(SOURCE);
+        let ast = Ast::new(&module);
+
+        assert!(matches!(ast.get(ast.root()), NodeRef::Module(_)));
+        assert_eq!(ast.parent(ast.root()), None);
+        assert_eq!(ast.children(ast.root()).len(), 3);
+        assert_eq!(ast.len(), 29);
+
+        for id in ast.ids() {
+            assert_eq!(ast.id(ast[id]), Some(id));
+            for &child in ast.children(id) {
+                assert_eq!(ast.parent(child), Some(id));
+                assert!(child > id);
+            }
+        }
+
+        // The same tree always gets the same ids.
+        let other = Ast::new(&module);
+        assert!(ast.ids().all(|id| ast[id].loc() == other[id].loc()));
+    }
+
+    #[test]
+    fn finds_nodes() {
+        let module = parse(SOURCE);
+        let ast = Ast::new(&module);
+
+        let ASTNodeScoped::View(view) = &module.nodes[2] else {
+            panic!("expected a view");
+        };
+        let ASTNodeStmt::Let(stmt) = &view.body.stmts[0] else {
+            panic!("expected a let");
+        };
+        let value = ast.id(&stmt.value).unwrap();
+        let kinds: Vec<_> = ast
+            .ancestors(value)
+            .map(|id| std::mem::discriminant(&ast[id]))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                std::mem::discriminant(&NodeRef::Let(stmt)),
+                std::mem::discriminant(&NodeRef::Block(&view.body)),
+                std::mem::discriminant(&NodeRef::View(view)),
+                std::mem::discriminant(&NodeRef::Module(&module)),
+            ]
+        );
+
+        let offset = SOURCE.find("increment").unwrap() as u32 + 2;
+        let NodeRef::Expr(expr) = ast[ast.node_at(offset).unwrap()] else {
+            panic!("expected an expression");
+        };
+        assert_eq!(expr.kind, ASTExprKind::Identifier("increment".to_string()));
+        assert_eq!(ast.node_at(SOURCE.len() as u32), None);
+
+        // Nodes of another tree have no id.
+        let copy = module.clone();
+        assert_eq!(ast.id(&copy), None);
+    }
+
+    #[test]
+    fn side_tables() {
+        let module = parse(SOURCE);
+        let ast = Ast::new(&module);
+
+        let mut names: SideTable<&str> = SideTable::new();
+        for id in ast.ids() {
+            if let NodeRef::Expr(expr) = ast[id] {
+                if let ASTExprKind::Identifier(name) = &expr.kind {
+                    names.insert(id, name);
+                }
+            }
+        }
+        let found: Vec<&str> = names.iter().map(|(_, name)| *name).collect();
+        assert_eq!(found, ["p", "p", "p", "start", "increment", "count"]);
+
+        let (first, _) = names.iter().next().unwrap();
+        assert_eq!(names[first], "p");
+        assert_eq!(names.insert(first, "q"), Some("p"));
+        assert_eq!(names.remove(first), Some("q"));
+        assert!(!names.contains(first));
+        assert_eq!(names.get(ast.root()), None);
+    }
+}
@@ -1,11 +1,21 @@
-use crate::lang::ast::Token;
-use crate::lang::tokenizer::TokenizerErr;
+use std::fmt;
 
+use crate::lang::{
+    ast::{ASTLoc, Token, TokenContent},
+    parser::{incremental::EditError, stream::TokenStream},
+    syntax::{Checkpoint, SyntaxKind},
+    tokenizer::{TokenResult, TokenizerErr},
+};
+
+pub mod expr;
+pub mod incremental;
 pub mod module;
+pub mod stream;
+pub mod types;
 pub mod view;
 
 pub trait Parser<T> {
-    fn parse_all(&self) -> ParseResult<T>;
+    fn parse_all(&mut self) -> ParseResult<T>;
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -15,7 +25,115 @@ pub enum ParseError {
     /// Incorrect syntax
     SyntaxError,
     TokenizeError(TokenizerErr),
+    /// An edit passed to [`incremental::reparse`] does not fit the source.
+    InvalidEdit(EditError),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::UnexpectedToken => f.write_str("unexpected token"),
+            ParseError::SyntaxError => f.write_str("invalid syntax"),
+            ParseError::TokenizeError(err) => err.fmt(f),
+            ParseError::InvalidEdit(err) => write!(f, "invalid edit: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseError::TokenizeError(err) => Some(err),
+            ParseError::InvalidEdit(err) => Some(err),
+            _ => None,
+        }
+    }
 }
 
 pub type ParseResult<T> = Result<T, ParseError>;
 pub type TokenizeResult = Result<Token, ParseError>;
+
+/// Token access shared by the parsers reading from one [`TokenStream`].
+pub(crate) trait TokenConsumer<'a> {
+    fn stream(&mut self) -> &mut TokenStream<'a>;
+
+    fn consume_token(&mut self) -> Option<TokenResult> {
+        self.stream().next()
+    }
+
+    /// Consume token and handle tokenize error and returns it as [`ParseError`].
+    /// If the inner tokenizer has no consumable token, it returns
+    /// [`ParseError::SyntaxError`].
+    fn consume_token_or_err(&mut self) -> TokenizeResult {
+        match self.consume_token() {
+            Some(res) => res.map_err(ParseError::TokenizeError),
+            None => Err(ParseError::SyntaxError),
+        }
+    }
+
+    /// Returns the content of the token `n` tokens ahead, if any.
+    fn peek_content_nth<'s>(&'s mut self, n: usize) -> Option<&'s TokenContent>
+    where
+        'a: 's,
+    {
+        self.stream().peek_content_nth(n)
+    }
+
+    /// Returns a copy of the content of the next token, if any.
+    fn peek_content(&mut self) -> Option<TokenContent> {
+        self.peek_content_nth(0).cloned()
+    }
+
+    fn peek_is(&mut self, con: &TokenContent) -> bool {
+        self.peek_content_nth(0) == Some(con)
+    }
+
+    /// Consumes the next token only if it has the given content.
+    fn consume_if(&mut self, con: &TokenContent) -> Option<Token> {
+        if self.peek_is(con) {
+            self.consume_token().and_then(|res| res.ok())
+        } else {
+            None
+        }
+    }
+
+    fn expect_token(&mut self, con: &TokenContent) -> TokenizeResult {
+        let token = self.consume_token_or_err()?;
+        if &token.con == con {
+            Ok(token)
+        } else {
+            Err(ParseError::UnexpectedToken)
+        }
+    }
+
+    /// Marks the position before the next token, to wrap what is parsed from
+    /// there in a node of the syntax tree with [`TokenConsumer::wrap_node`].
+    fn checkpoint(&mut self) -> Checkpoint {
+        self.stream().node_checkpoint()
+    }
+
+    fn wrap_node(&mut self, checkpoint: Checkpoint, kind: SyntaxKind) {
+        self.stream().wrap_node(checkpoint, kind);
+    }
+
+    /// Runs `parse` and wraps the tokens it consumes in a node of the syntax
+    /// tree.
+    fn node<T>(
+        &mut self,
+        kind: SyntaxKind,
+        parse: impl FnOnce(&mut Self) -> ParseResult<T>,
+    ) -> ParseResult<T> {
+        let checkpoint = self.checkpoint();
+        let res = parse(self)?;
+        self.wrap_node(checkpoint, kind);
+        Ok(res)
+    }
+
+    fn expect_identifier(&mut self) -> ParseResult<(String, ASTLoc)> {
+        let token = self.consume_token_or_err()?;
+        match token.con {
+            TokenContent::Identifier(name) => Ok((name.to_string(), token.loc.into())),
+            _ => Err(ParseError::UnexpectedToken),
+        }
+    }
+}
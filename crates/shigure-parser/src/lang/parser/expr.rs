@@ -0,0 +1,501 @@
+use crate::lang::{
+    ast::{
+        ASTBinaryOp, ASTExprKind, ASTItemConst, ASTLoc, ASTNodeAssign, ASTNodeBlock,
+        ASTNodeEffectBlock, ASTNodeExpr, ASTNodeHandlerOp, ASTNodeItemAttribute, ASTNodeLet,
+        ASTNodePattern, ASTNodeRecordField, ASTNodeStmt, ASTNodeType, ASTNodeWhenArm, ASTNodeWith,
+        ASTPatternKind, ASTUnaryOp, ASTVisibility, Spanned, TokenContent,
+    },
+    parser::{
+        stream::TokenStream, types::TypeParser, view::ViewParser, ParseError, ParseResult, Parser,
+        TokenConsumer,
+    },
+    syntax::SyntaxKind,
+};
+
+/// Parses expressions, and the blocks and statements they contain.
+///
+/// ```text
+/// expr    := unary {binop unary}
+/// unary   := ("-" | "!") unary | postfix
+/// postfix := primary {"(" [expr {"," expr}] ")" | "." Name}
+/// primary := Literal | nil | Name | Anchor | if | for | when | "(" ... ")" | "[" ... "]"
+///          | "{" Name ":" expr ... "}"
+/// for     := "for" pattern "in" expr block
+/// when    := "when" expr "{" {pattern "->" (block | expr) [","]} "}"
+/// pattern := "_" | Name ["(" [pattern {"," pattern}] ")"] | Literal | nil | "(" ... ")"
+/// block   := "{" {stmt} "}"
+/// stmt    := let | const | with | effect | element | expr ["=" expr]
+/// ```
+///
+/// A `{` in expression position always starts a record literal; blocks only
+/// follow keywords such as `if` and `else`, and the arrow of a `when` arm.
+pub struct ExprParser<'s, 'a> {
+    stream: &'s mut TokenStream<'a>,
+}
+
+impl Parser<ASTNodeExpr> for ExprParser<'_, '_> {
+    fn parse_all(&mut self) -> ParseResult<ASTNodeExpr> {
+        self.parse_expr()
+    }
+}
+
+impl<'a> TokenConsumer<'a> for ExprParser<'_, 'a> {
+    fn stream(&mut self) -> &mut TokenStream<'a> {
+        self.stream
+    }
+}
+
+/// Returns the binary operator of a token.
+fn binary_op(con: &TokenContent) -> Option<ASTBinaryOp> {
+    let op = match con {
+        TokenContent::LogicalOrOp => ASTBinaryOp::Or,
+        TokenContent::LogicalAndOp => ASTBinaryOp::And,
+        TokenContent::EqualOp => ASTBinaryOp::Eq,
+        TokenContent::NotEqualOp => ASTBinaryOp::NotEq,
+        TokenContent::LessThanOp => ASTBinaryOp::Lt,
+        TokenContent::LessEqualOp => ASTBinaryOp::LtEq,
+        TokenContent::TagAngleBracketRight => ASTBinaryOp::Gt,
+        TokenContent::GreaterEqualOp => ASTBinaryOp::GtEq,
+        TokenContent::AddOp => ASTBinaryOp::Add,
+        TokenContent::SubOp => ASTBinaryOp::Sub,
+        TokenContent::MulOp => ASTBinaryOp::Mul,
+        TokenContent::DivOp => ASTBinaryOp::Div,
+        TokenContent::RemOp => ASTBinaryOp::Rem,
+        _ => return None,
+    };
+    Some(op)
+}
+
+impl<'s, 'a> ExprParser<'s, 'a> {
+    pub fn new(stream: &'s mut TokenStream<'a>) -> Self {
+        ExprParser { stream }
+    }
+
+    pub(crate) fn parse_expr(&mut self) -> ParseResult<ASTNodeExpr> {
+        self.parse_binary(0)
+    }
+
+    /// Parses a chain of binary operators binding tighter than `min_prec`.
+    fn parse_binary(&mut self, min_prec: u8) -> ParseResult<ASTNodeExpr> {
+        let checkpoint = self.checkpoint();
+        let mut lhs = self.parse_unary()?;
+        while let Some(op) = self.peek_content().as_ref().and_then(binary_op) {
+            let prec = op.precedence();
+            if prec <= min_prec {
+                break;
+            }
+            self.consume_token_or_err()?;
+            let rhs = self.parse_binary(prec)?;
+            lhs = ASTNodeExpr::new(
+                lhs.loc().to(rhs.loc()),
+                ASTExprKind::Binary {
+                    op,
+                    lhs: Box::new(lhs),
+                    rhs: Box::new(rhs),
+                },
+            );
+            self.wrap_node(checkpoint, SyntaxKind::Expr);
+        }
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> ParseResult<ASTNodeExpr> {
+        let op = match self.peek_content() {
+            Some(TokenContent::SubOp) => ASTUnaryOp::Neg,
+            Some(TokenContent::NotOp) => ASTUnaryOp::Not,
+            _ => return self.parse_postfix(),
+        };
+        let checkpoint = self.checkpoint();
+        let token = self.consume_token_or_err()?;
+        let expr = self.parse_unary()?;
+        self.wrap_node(checkpoint, SyntaxKind::Expr);
+        Ok(ASTNodeExpr::new(
+            ASTLoc::from(token.loc).to(expr.loc()),
+            ASTExprKind::Unary {
+                op,
+                expr: Box::new(expr),
+            },
+        ))
+    }
+
+    fn parse_postfix(&mut self) -> ParseResult<ASTNodeExpr> {
+        let checkpoint = self.checkpoint();
+        let mut expr = self.parse_primary()?;
+        loop {
+            if self.consume_if(&TokenContent::ParenthesisLeft).is_some() {
+                let (args, end) = self.parse_expr_list(&TokenContent::ParenthesisRight)?;
+                expr = ASTNodeExpr::new(
+                    expr.loc().to(end),
+                    ASTExprKind::Call {
+                        callee: Box::new(expr),
+                        args,
+                    },
+                );
+                self.wrap_node(checkpoint, SyntaxKind::Expr);
+            } else if self.consume_if(&TokenContent::Dot).is_some() {
+                let (name, end) = self.expect_identifier()?;
+                expr = ASTNodeExpr::new(
+                    expr.loc().to(end),
+                    ASTExprKind::Member {
+                        object: Box::new(expr),
+                        name,
+                    },
+                );
+                self.wrap_node(checkpoint, SyntaxKind::Expr);
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn parse_primary(&mut self) -> ParseResult<ASTNodeExpr> {
+        self.node(SyntaxKind::Expr, |p| p.parse_primary_expr())
+    }
+
+    fn parse_primary_expr(&mut self) -> ParseResult<ASTNodeExpr> {
+        let token = self.consume_token_or_err()?;
+        let loc = ASTLoc::from(token.loc);
+        let expr = match token.con {
+            TokenContent::Literal(literal) => ASTNodeExpr::new(loc, ASTExprKind::Literal(literal)),
+            TokenContent::Nil => ASTNodeExpr::new(loc, ASTExprKind::Nil),
+            TokenContent::Identifier(name) => {
+                ASTNodeExpr::new(loc, ASTExprKind::Identifier(name.to_string()))
+            }
+            TokenContent::Anchor(anchor) => {
+                let name = anchor.as_str().trim_start_matches('#').to_string();
+                ASTNodeExpr::new(loc, ASTExprKind::Anchor(name))
+            }
+            TokenContent::If => self.parse_if(loc)?,
+            TokenContent::For => self.parse_for(loc)?,
+            TokenContent::When => self.parse_when(loc)?,
+            TokenContent::ParenthesisLeft => {
+                let (mut items, end) = self.parse_expr_list(&TokenContent::ParenthesisRight)?;
+                if items.len() == 1 {
+                    // Spans the parentheses, like its node of the syntax tree.
+                    ASTNodeExpr::new(loc.to(end), items.remove(0).kind)
+                } else {
+                    ASTNodeExpr::new(loc.to(end), ASTExprKind::Tuple(items))
+                }
+            }
+            TokenContent::SquareBracketLeft => {
+                let (items, end) = self.parse_expr_list(&TokenContent::SquareBracketRight)?;
+                ASTNodeExpr::new(loc.to(end), ASTExprKind::List(items))
+            }
+            TokenContent::BraceLeft => self.parse_record(loc)?,
+            _ => return Err(ParseError::UnexpectedToken),
+        };
+        Ok(expr)
+    }
+
+    /// Parses comma separated expressions up to and including `close`, and
+    /// returns them with the location of `close`.
+    fn parse_expr_list(&mut self, close: &TokenContent) -> ParseResult<(Vec<ASTNodeExpr>, ASTLoc)> {
+        let mut items = Vec::new();
+        loop {
+            if let Some(end) = self.consume_if(close) {
+                return Ok((items, end.loc.into()));
+            }
+            items.push(self.parse_expr()?);
+            if self.consume_if(&TokenContent::Comma).is_none() {
+                let end = self.expect_token(close)?;
+                return Ok((items, end.loc.into()));
+            }
+        }
+    }
+
+    /// Parses a record literal after its opening brace.
+    fn parse_record(&mut self, start: ASTLoc) -> ParseResult<ASTNodeExpr> {
+        let mut fields = Vec::new();
+        loop {
+            if let Some(end) = self.consume_if(&TokenContent::BraceRight) {
+                return Ok(ASTNodeExpr::new(
+                    start.to(end.loc.into()),
+                    ASTExprKind::Record(fields),
+                ));
+            }
+
+            let field = self.node(SyntaxKind::RecordField, |p| {
+                let (name, loc) = p.expect_identifier()?;
+                p.expect_token(&TokenContent::Colon)?;
+                let value = p.parse_expr()?;
+                Ok(ASTNodeRecordField::new(loc.to(value.loc()), name, value))
+            })?;
+            fields.push(field);
+
+            if self.consume_if(&TokenContent::Comma).is_none() {
+                let end = self.expect_token(&TokenContent::BraceRight)?;
+                return Ok(ASTNodeExpr::new(
+                    start.to(end.loc.into()),
+                    ASTExprKind::Record(fields),
+                ));
+            }
+        }
+    }
+
+    /// Parses `cond { .. } else ..` after the `if` keyword.
+    fn parse_if(&mut self, start: ASTLoc) -> ParseResult<ASTNodeExpr> {
+        let cond = self.parse_expr()?;
+        let then = self.parse_block()?;
+        let mut end = then.loc();
+
+        let els = match self.consume_if(&TokenContent::Else) {
+            Some(_) => {
+                let els = self.node(SyntaxKind::Expr, |p| {
+                    match p.consume_if(&TokenContent::If) {
+                        Some(token) => p.parse_if(token.loc.into()),
+                        None => {
+                            let block = p.parse_block()?;
+                            Ok(ASTNodeExpr::new(block.loc(), ASTExprKind::Block(block)))
+                        }
+                    }
+                })?;
+                end = els.loc();
+                Some(Box::new(els))
+            }
+            None => None,
+        };
+
+        Ok(ASTNodeExpr::new(
+            start.to(end),
+            ASTExprKind::If {
+                cond: Box::new(cond),
+                then,
+                els,
+            },
+        ))
+    }
+
+    /// Parses `pattern in iter { .. }` after the `for` keyword.
+    fn parse_for(&mut self, start: ASTLoc) -> ParseResult<ASTNodeExpr> {
+        let pattern = self.parse_pattern()?;
+        self.expect_token(&TokenContent::In)?;
+        let iter = self.parse_expr()?;
+        let body = self.parse_block()?;
+
+        Ok(ASTNodeExpr::new(
+            start.to(body.loc()),
+            ASTExprKind::For {
+                pattern,
+                iter: Box::new(iter),
+                body,
+            },
+        ))
+    }
+
+    /// Parses `subject { pattern -> body .. }` after the `when` keyword.
+    fn parse_when(&mut self, start: ASTLoc) -> ParseResult<ASTNodeExpr> {
+        let subject = self.parse_expr()?;
+        self.expect_token(&TokenContent::BraceLeft)?;
+
+        let mut arms = Vec::new();
+        let end = loop {
+            if let Some(end) = self.consume_if(&TokenContent::BraceRight) {
+                break end;
+            }
+            let arm = self.node(SyntaxKind::WhenArm, |p| {
+                let pattern = p.parse_pattern()?;
+                p.expect_token(&TokenContent::Arrow)?;
+                let body = match p.peek_is(&TokenContent::BraceLeft) {
+                    true => p.node(SyntaxKind::Expr, |p| {
+                        let block = p.parse_block()?;
+                        Ok(ASTNodeExpr::new(block.loc(), ASTExprKind::Block(block)))
+                    })?,
+                    false => p.parse_expr()?,
+                };
+                Ok(ASTNodeWhenArm::new(
+                    pattern.loc().to(body.loc()),
+                    pattern,
+                    body,
+                ))
+            })?;
+            arms.push(arm);
+            self.consume_if(&TokenContent::Comma);
+        };
+
+        Ok(ASTNodeExpr::new(
+            start.to(end.loc.into()),
+            ASTExprKind::When {
+                subject: Box::new(subject),
+                arms,
+            },
+        ))
+    }
+
+    fn parse_pattern(&mut self) -> ParseResult<ASTNodePattern> {
+        self.node(SyntaxKind::Pattern, |p| p.parse_pattern_kind())
+    }
+
+    fn parse_pattern_kind(&mut self) -> ParseResult<ASTNodePattern> {
+        let token = self.consume_token_or_err()?;
+        let loc = ASTLoc::from(token.loc);
+        let pattern = match token.con {
+            TokenContent::Identifier(name) if name == "_" => {
+                ASTNodePattern::new(loc, ASTPatternKind::Wildcard)
+            }
+            TokenContent::Identifier(name) => {
+                let name = name.to_string();
+                if self.consume_if(&TokenContent::ParenthesisLeft).is_some() {
+                    let (fields, end) = self.parse_pattern_list()?;
+                    ASTNodePattern::new(loc.to(end), ASTPatternKind::Variant { name, fields })
+                } else if name.starts_with(|c: char| c.is_ascii_uppercase()) {
+                    let fields = Vec::new();
+                    ASTNodePattern::new(loc, ASTPatternKind::Variant { name, fields })
+                } else {
+                    ASTNodePattern::new(loc, ASTPatternKind::Binding(name))
+                }
+            }
+            TokenContent::Literal(literal) => {
+                ASTNodePattern::new(loc, ASTPatternKind::Literal(literal))
+            }
+            TokenContent::Nil => ASTNodePattern::new(loc, ASTPatternKind::Nil),
+            TokenContent::ParenthesisLeft => {
+                let (mut items, end) = self.parse_pattern_list()?;
+                if items.len() == 1 {
+                    // Spans the parentheses, like its node of the syntax tree.
+                    ASTNodePattern::new(loc.to(end), items.remove(0).kind)
+                } else {
+                    ASTNodePattern::new(loc.to(end), ASTPatternKind::Tuple(items))
+                }
+            }
+            _ => return Err(ParseError::UnexpectedToken),
+        };
+        Ok(pattern)
+    }
+
+    /// Parses comma separated patterns up to and including `)`, and returns
+    /// them with the location of `)`.
+    fn parse_pattern_list(&mut self) -> ParseResult<(Vec<ASTNodePattern>, ASTLoc)> {
+        let mut items = Vec::new();
+        loop {
+            if let Some(end) = self.consume_if(&TokenContent::ParenthesisRight) {
+                return Ok((items, end.loc.into()));
+            }
+            items.push(self.parse_pattern()?);
+            if self.consume_if(&TokenContent::Comma).is_none() {
+                let end = self.expect_token(&TokenContent::ParenthesisRight)?;
+                return Ok((items, end.loc.into()));
+            }
+        }
+    }
+
+    /// Parses `{ statements }`.
+    pub(crate) fn parse_block(&mut self) -> ParseResult<ASTNodeBlock> {
+        self.node(SyntaxKind::Block, |p| p.parse_stmts())
+    }
+
+    fn parse_stmts(&mut self) -> ParseResult<ASTNodeBlock> {
+        let start: ASTLoc = self.expect_token(&TokenContent::BraceLeft)?.loc.into();
+        let mut stmts = Vec::new();
+        loop {
+            if let Some(end) = self.consume_if(&TokenContent::BraceRight) {
+                return Ok(ASTNodeBlock::new(start.to(end.loc.into()), stmts));
+            }
+            stmts.push(self.parse_stmt()?);
+        }
+    }
+
+    fn parse_stmt(&mut self) -> ParseResult<ASTNodeStmt> {
+        match self.peek_content() {
+            Some(TokenContent::Let) => self.node(SyntaxKind::Let, |p| {
+                let token = p.consume_token_or_err()?;
+                p.parse_let(token.loc.into())
+            }),
+            Some(TokenContent::Const) => self.node(SyntaxKind::Const, |p| {
+                let token = p.consume_token_or_err()?;
+                let item = p.parse_const(token.loc.into(), Vec::new(), ASTVisibility::Private)?;
+                Ok(ASTNodeStmt::Const(item))
+            }),
+            Some(TokenContent::With) => self.node(SyntaxKind::With, |p| {
+                let token = p.consume_token_or_err()?;
+                p.parse_with(token.loc.into())
+            }),
+            Some(TokenContent::Effect) => self.node(SyntaxKind::EffectBlock, |p| {
+                let token = p.consume_token_or_err()?;
+                let body = p.parse_block()?;
+                let loc = ASTLoc::from(token.loc).to(body.loc());
+                Ok(ASTNodeStmt::Effect(ASTNodeEffectBlock::new(loc, body)))
+            }),
+            Some(TokenContent::TagAngleBracketLeft) => {
+                let element = ViewParser::new(self.stream()).parse_all()?;
+                Ok(ASTNodeStmt::Element(element))
+            }
+            _ => {
+                let checkpoint = self.checkpoint();
+                let expr = self.parse_expr()?;
+                if self.consume_if(&TokenContent::AssignmentOp).is_none() {
+                    return Ok(ASTNodeStmt::Expr(expr));
+                }
+                let value = self.parse_expr()?;
+                let assign = ASTNodeAssign::new(expr.loc().to(value.loc()), expr, value);
+                if assign.target_name().is_none() {
+                    return Err(ParseError::SyntaxError);
+                }
+                self.wrap_node(checkpoint, SyntaxKind::Assign);
+                Ok(ASTNodeStmt::Assign(assign))
+            }
+        }
+    }
+
+    /// Parses `name: Type = value` after the `let` keyword.
+    fn parse_let(&mut self, start: ASTLoc) -> ParseResult<ASTNodeStmt> {
+        let (name, ty, value) = self.parse_binding()?;
+        Ok(ASTNodeStmt::Let(ASTNodeLet::new(
+            start.to(value.loc()),
+            name,
+            ty,
+            value,
+        )))
+    }
+
+    /// Parses `NAME: Type = value` after the `const` keyword.
+    pub(crate) fn parse_const(
+        &mut self,
+        start: ASTLoc,
+        attributes: Vec<ASTNodeItemAttribute>,
+        vis: ASTVisibility,
+    ) -> ParseResult<ASTItemConst> {
+        let (name, ty, value) = self.parse_binding()?;
+        Ok(ASTItemConst::new(start.to(value.loc()), name, ty, value)
+            .with_attributes(attributes)
+            .with_visibility(vis))
+    }
+
+    fn parse_binding(&mut self) -> ParseResult<(String, Option<ASTNodeType>, ASTNodeExpr)> {
+        let (name, _) = self.expect_identifier()?;
+        let ty = match self.consume_if(&TokenContent::Colon) {
+            Some(_) => Some(TypeParser::new(self.stream()).parse_all()?),
+            None => None,
+        };
+        self.expect_token(&TokenContent::AssignmentOp)?;
+        let value = self.parse_expr()?;
+        Ok((name, ty, value))
+    }
+
+    /// Parses `Effect { fn op(params) { body } .. }` after the `with` keyword.
+    fn parse_with(&mut self, start: ASTLoc) -> ParseResult<ASTNodeStmt> {
+        let effect = TypeParser::new(self.stream()).parse_all()?;
+        self.expect_token(&TokenContent::BraceLeft)?;
+
+        let mut ops = Vec::new();
+        let end = loop {
+            if let Some(end) = self.consume_if(&TokenContent::BraceRight) {
+                break end;
+            }
+            let op = self.node(SyntaxKind::HandlerOp, |p| {
+                let keyword = p.expect_token(&TokenContent::FnKeyword)?;
+                let (name, _) = p.expect_identifier()?;
+                let (params, _) = TypeParser::new(p.stream()).parse_params()?;
+                let body = p.parse_block()?;
+                let loc = ASTLoc::from(keyword.loc).to(body.loc());
+                Ok(ASTNodeHandlerOp::new(loc, name, params, body))
+            })?;
+            ops.push(op);
+        };
+
+        Ok(ASTNodeStmt::With(ASTNodeWith::new(
+            start.to(end.loc.into()),
+            effect,
+            ops,
+        )))
+    }
+}
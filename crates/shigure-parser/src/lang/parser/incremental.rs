@@ -0,0 +1,375 @@
+use std::{fmt, sync::Arc};
+
+use crate::lang::{
+    ast::ASTLoc,
+    parser::{module::ModuleParser, ParseError, ParseResult, Parser},
+    syntax::{GreenElement, GreenNode, SyntaxElement, SyntaxNode},
+};
+
+/// Why a [`TextEdit`] cannot be applied to a source.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum EditError {
+    /// The range ends before it starts, or past the end of the source.
+    OutOfBounds,
+    /// An end of the range falls inside of a character.
+    NotCharBoundary,
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            EditError::OutOfBounds => "the edited range is out of bounds",
+            EditError::NotCharBoundary => "the edited range splits a character",
+        })
+    }
+}
+
+impl std::error::Error for EditError {}
+
+/// Replaces the text in `loc`, a byte range of the source, by `text`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TextEdit {
+    pub loc: ASTLoc,
+    pub text: String,
+}
+
+impl TextEdit {
+    pub fn new(loc: ASTLoc, text: impl Into<String>) -> Self {
+        Self {
+            loc,
+            text: text.into(),
+        }
+    }
+
+    pub fn insert(offset: u32, text: impl Into<String>) -> Self {
+        Self::new(
+            ASTLoc {
+                start: offset,
+                end: offset,
+            },
+            text,
+        )
+    }
+
+    pub fn delete(loc: ASTLoc) -> Self {
+        Self::new(loc, "")
+    }
+
+    /// Applies the edit to `source`.
+    pub fn apply(&self, source: &str) -> Result<String, EditError> {
+        let (start, end) = (self.loc.start as usize, self.loc.end as usize);
+        if start > end || end > source.len() {
+            return Err(EditError::OutOfBounds);
+        }
+        if !source.is_char_boundary(start) || !source.is_char_boundary(end) {
+            return Err(EditError::NotCharBoundary);
+        }
+        let mut edited = String::with_capacity(source.len() - (end - start) + self.text.len());
+        edited.push_str(&source[..start]);
+        edited.push_str(&self.text);
+        edited.push_str(&source[end..]);
+        Ok(edited)
+    }
+
+    /// Applies the edit to the text of `green`, copying the text of its
+    /// tokens around the edit rather than printing the tree first.
+    fn splice(&self, green: &GreenNode) -> Result<String, EditError> {
+        if self.loc.start > self.loc.end || self.loc.end > green.len() {
+            return Err(EditError::OutOfBounds);
+        }
+        let mut splice = Splice {
+            edit: self,
+            offset: 0,
+            inserted: false,
+            out: String::with_capacity(
+                (green.len() - (self.loc.end - self.loc.start)) as usize + self.text.len(),
+            ),
+        };
+        splice.node(green)?;
+        if !splice.inserted {
+            splice.out.push_str(&self.text);
+        }
+        Ok(splice.out)
+    }
+}
+
+/// The state of [`TextEdit::splice`] while walking the tokens of a tree.
+struct Splice<'e> {
+    edit: &'e TextEdit,
+    /// The offset of the next token in the tree.
+    offset: u32,
+    inserted: bool,
+    out: String,
+}
+
+impl Splice<'_> {
+    fn node(&mut self, green: &GreenNode) -> Result<(), EditError> {
+        for child in green.children() {
+            match child {
+                GreenElement::Node(node) => self.node(node)?,
+                GreenElement::Token(token) => self.token(token.text())?,
+            }
+        }
+        Ok(())
+    }
+
+    fn token(&mut self, text: &str) -> Result<(), EditError> {
+        let (start, end) = (self.offset, self.offset + text.len() as u32);
+        self.offset = end;
+        let loc = self.edit.loc;
+        // The parts of the token before and after the edited range.
+        let before = (loc.start.clamp(start, end) - start) as usize;
+        let after = (loc.end.clamp(start, end) - start) as usize;
+        if !text.is_char_boundary(before) || !text.is_char_boundary(after) {
+            return Err(EditError::NotCharBoundary);
+        }
+        self.out.push_str(&text[..before]);
+        if !self.inserted && loc.start <= end {
+            self.out.push_str(&self.edit.text);
+            self.inserted = true;
+        }
+        self.out.push_str(&text[after..]);
+        Ok(())
+    }
+}
+
+/// Returns the syntax tree of a module after applying `edit` to the module
+/// whose tree is `root`.
+///
+/// When the edit lies within a single item, only the text of that item is
+/// tokenized and parsed again, and the other items of the new tree share
+/// their green nodes with `root`. Otherwise the whole module is parsed again.
+/// Either way, the result is the tree a full parse of the edited source
+/// produces. The edited text is spliced from the tokens of `root`, so the
+/// source is never printed before being edited.
+///
+/// An edit whose range is not in the source or splits a character is
+/// [`ParseError::InvalidEdit`].
+pub fn reparse(root: &SyntaxNode, edit: &TextEdit) -> ParseResult<SyntaxNode> {
+    if let Some(root) = reparse_item(root, edit)? {
+        return Ok(root);
+    }
+
+    let edited = edit.splice(root.green()).map_err(ParseError::InvalidEdit)?;
+    let mut parser = ModuleParser::from_str(&edited);
+    parser.parse_all()?;
+    parser.syntax().ok_or(ParseError::SyntaxError)
+}
+
+/// Reparses the item containing `edit`, or returns `None` when the edit may
+/// affect the tokens around the item.
+fn reparse_item(root: &SyntaxNode, edit: &TextEdit) -> ParseResult<Option<SyntaxNode>> {
+    let Some(item) = root.children().into_iter().find(|item| {
+        let loc = item.loc();
+        loc.start <= edit.loc.start && edit.loc.end <= loc.end
+    }) else {
+        return Ok(None);
+    };
+
+    // Whitespace around the item keeps its first and last tokens from
+    // joining the text next to it.
+    let separated = |c: Option<char>| c.is_none_or(char::is_whitespace);
+    let (before, after) = root.green().children().split_at(item.index());
+    if !separated(last_char(before)) || !separated(first_char(&after[1..])) {
+        return Ok(None);
+    }
+
+    let loc = item.loc();
+    let relative = TextEdit::new(
+        ASTLoc {
+            start: edit.loc.start - loc.start,
+            end: edit.loc.end - loc.start,
+        },
+        edit.text.clone(),
+    );
+    let text = relative
+        .splice(item.green())
+        .map_err(ParseError::InvalidEdit)?;
+    let mut parser = ModuleParser::from_str(&text);
+    if parser.parse_all().is_err() {
+        return Ok(None);
+    }
+
+    // The new text has to be exactly one item without surrounding trivia;
+    // anything else changes the items around it.
+    let Some(new_root) = parser.syntax() else {
+        return Ok(None);
+    };
+    let elements = new_root.children_with_tokens();
+    let [SyntaxElement::Node(new_item)] = elements.as_slice() else {
+        return Ok(None);
+    };
+
+    let children = root
+        .green()
+        .children()
+        .iter()
+        .enumerate()
+        .map(|(index, child)| match index == item.index() {
+            true => GreenElement::Node(new_item.green().clone()),
+            false => child.clone(),
+        })
+        .collect();
+    let green = GreenNode::new(root.kind(), children);
+    Ok(Some(SyntaxNode::new_root(Arc::new(green))))
+}
+
+/// The first character of the text of `elements`.
+fn first_char(elements: &[GreenElement]) -> Option<char> {
+    elements.iter().find_map(|element| match element {
+        GreenElement::Node(node) => first_char(node.children()),
+        GreenElement::Token(token) => token.text().chars().next(),
+    })
+}
+
+/// The last character of the text of `elements`.
+fn last_char(elements: &[GreenElement]) -> Option<char> {
+    elements.iter().rev().find_map(|element| match element {
+        GreenElement::Node(node) => last_char(node.children()),
+        GreenElement::Token(token) => token.text().chars().next_back(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lang::{ast::TokenContent, syntax::SyntaxKind};
+
+    const SOURCE: &str = "type Id = Int
+
+// the first view
+view A(id: Id) {
+  let count = 0
+  <p>{count}</p>
+}
+
+view B { <div class=\"b\"/> }
+";
+
+    fn parse(input: &str) -> SyntaxNode {
+        let mut parser = ModuleParser::from_str(input);
+        parser.parse_all().expect("failed to parse");
+        parser.syntax().unwrap()
+    }
+
+    /// Applies `edit` to `root` incrementally, checks the result against a
+    /// full parse, and returns it.
+    fn check_with(root: &SyntaxNode, edit: TextEdit) -> SyntaxNode {
+        let edited = edit.apply(SOURCE).unwrap();
+        let incremental = reparse(root, &edit).expect("failed to reparse");
+        assert_eq!(incremental.to_string(), edited);
+        assert_eq!(incremental.green(), parse(&edited).green());
+        incremental
+    }
+
+    fn check(edit: TextEdit) -> SyntaxNode {
+        check_with(&parse(SOURCE), edit)
+    }
+
+    fn item_greens(root: &SyntaxNode) -> Vec<Arc<GreenNode>> {
+        root.children()
+            .iter()
+            .map(|node| node.green().clone())
+            .collect()
+    }
+
+    fn offset(pattern: &str) -> u32 {
+        SOURCE.find(pattern).unwrap() as u32
+    }
+
+    #[test]
+    fn reparses_enclosing_item() {
+        let root = parse(SOURCE);
+        let start = offset("count}") + 5;
+        let new = check_with(&root, TextEdit::insert(start, " + 1"));
+
+        let (old, new) = (item_greens(&root), item_greens(&new));
+        assert!(Arc::ptr_eq(&old[0], &new[0]));
+        assert!(!Arc::ptr_eq(&old[1], &new[1]));
+        assert!(Arc::ptr_eq(&old[2], &new[2]));
+
+        let start = offset("class");
+        check(TextEdit::new(
+            ASTLoc {
+                start,
+                end: start + 5,
+            },
+            "id",
+        ));
+        check(TextEdit::insert(offset("let"), "// note\n  "));
+    }
+
+    #[test]
+    fn falls_back_to_full_parse() {
+        // Splitting a view into two items.
+        let new = check(TextEdit::insert(offset("  <p>"), "}\nview C {\n"));
+        assert_eq!(new.children().len(), 4);
+
+        // Editing trivia between items, and joining two items.
+        check(TextEdit::insert(offset("// the"), "const X = 1\n"));
+        check(TextEdit::delete(ASTLoc {
+            start: offset("\n\nview B"),
+            end: offset("view B"),
+        }));
+    }
+
+    #[test]
+    fn comments_out_code() {
+        let new = check(TextEdit::insert(offset("<p>"), "// "));
+        let view = &new.children()[1];
+        assert_eq!(view.kind(), SyntaxKind::View);
+        assert!(view
+            .tokens()
+            .iter()
+            .all(|token| token.con() != &TokenContent::TagAngleBracketLeft));
+    }
+
+    #[test]
+    fn reports_errors() {
+        let root = parse(SOURCE);
+        let edit = TextEdit::insert(offset("let"), "let = ");
+        assert_eq!(reparse(&root, &edit), Err(ParseError::UnexpectedToken));
+    }
+
+    #[test]
+    fn rejects_invalid_edits() {
+        let source = "const S = \"é\"\nconst T = 1\n";
+        let root = parse(source);
+        let inside = source.find('é').unwrap() as u32 + 1;
+        let len = source.len() as u32;
+        for (loc, err) in [
+            (
+                ASTLoc {
+                    start: inside,
+                    end: inside,
+                },
+                EditError::NotCharBoundary,
+            ),
+            (
+                ASTLoc {
+                    start: 0,
+                    end: inside,
+                },
+                EditError::NotCharBoundary,
+            ),
+            (
+                ASTLoc {
+                    start: len,
+                    end: len + 1,
+                },
+                EditError::OutOfBounds,
+            ),
+            (ASTLoc { start: 3, end: 2 }, EditError::OutOfBounds),
+        ] {
+            let edit = TextEdit::new(loc, "x");
+            assert_eq!(edit.apply(source), Err(err));
+            assert_eq!(reparse(&root, &edit), Err(ParseError::InvalidEdit(err)));
+        }
+
+        // Appending at the end of the source.
+        let edit = TextEdit::insert(len, "const U = 2\n");
+        let new = reparse(&root, &edit).unwrap();
+        assert_eq!(new.to_string(), edit.apply(source).unwrap());
+        assert_eq!(new.children().len(), 3);
+    }
+}
@@ -1,39 +1,48 @@
-use crate::lang::ast::{ASTLoc, ASTNodeModule, ASTNodeScoped, ASTNodeViewElement, TokenContent};
-use crate::lang::parser::{view::ViewParser, ParseError, ParseResult, Parser, TokenizeResult};
+use crate::lang::ast::{
+    ASTItemEffect, ASTItemFn, ASTItemImport, ASTItemType, ASTItemUse, ASTItemView, ASTLoc,
+    ASTNodeAttributeArg, ASTNodeEffectOp, ASTNodeImportName, ASTNodeItemAttribute, ASTNodeModule,
+    ASTNodeScoped, ASTNodeTypeBound, ASTNodeTypeParam, ASTNodeTypeVariant, ASTTypeBody,
+    ASTTypeKind, ASTVisibility, Spanned, Token, TokenContent, TokenLiteral,
+};
+use crate::lang::parser::{
+    expr::ExprParser, stream::TokenStream, types::TypeParser, ParseError, ParseResult, Parser,
+    TokenConsumer,
+};
+use crate::lang::syntax::{Checkpoint, SyntaxKind, SyntaxNode};
 use crate::lang::tokenizer::{TokenResult, Tokenizer};
-use std::cell::RefCell;
-use std::rc::Rc;
+use std::mem;
 
 #[derive(Debug, Default, Clone, Eq, PartialEq)]
 enum ModuleParserState {
-    PendingToken(TokenResult),
+    /// The first token of an item, and the checkpoint before it.
+    PendingToken(TokenResult, Checkpoint),
     PendingParseError(ParseError),
-    EOF,
+    Eof,
     #[default]
     Ready,
 }
 
 impl ModuleParserState {
     pub fn is_ready(&self) -> bool {
-        match self {
-            ModuleParserState::Ready => true,
-            _ => false,
-        }
+        matches!(self, ModuleParserState::Ready)
     }
 
     #[allow(dead_code)]
     pub fn has_pending_token(&self) -> Option<&TokenResult> {
         match self {
-            ModuleParserState::PendingToken(token) => Some(token),
+            ModuleParserState::PendingToken(token, _) => Some(token),
             _ => None,
         }
     }
 }
 
+/// Parses a module into an [`ASTNodeModule`], and records its lossless
+/// syntax tree while doing so.
 pub struct ModuleParser<'a> {
-    tokenizer: Rc<RefCell<Tokenizer<'a>>>,
-    state: RefCell<ModuleParserState>,
-    pending: RefCell<Option<ASTNodeModule>>,
+    stream: TokenStream<'a>,
+    state: ModuleParserState,
+    pending: Option<ASTNodeModule>,
+    syntax: Option<SyntaxNode>,
 }
 
 pub enum ModuleParserResult {
@@ -43,7 +52,7 @@ pub enum ModuleParserResult {
 }
 
 impl<'a> Parser<ASTNodeModule> for ModuleParser<'a> {
-    fn parse_all(&self) -> ParseResult<ASTNodeModule> {
+    fn parse_all(&mut self) -> ParseResult<ASTNodeModule> {
         loop {
             match self.advance() {
                 ModuleParserResult::Done(ast) => return Ok(ast),
@@ -54,90 +63,893 @@ impl<'a> Parser<ASTNodeModule> for ModuleParser<'a> {
     }
 }
 
+impl<'a> TokenConsumer<'a> for ModuleParser<'a> {
+    fn stream(&mut self) -> &mut TokenStream<'a> {
+        &mut self.stream
+    }
+}
+
 impl<'a> ModuleParser<'a> {
-    pub fn new(tokenizer: Rc<RefCell<Tokenizer<'a>>>) -> Self {
+    pub fn new(tokenizer: Tokenizer<'a>) -> Self {
+        let module = ASTNodeModule::new(ASTLoc { start: 0, end: 0 }, String::new(), Vec::new());
+        let mut stream = TokenStream::new(tokenizer);
+        stream.start_node(SyntaxKind::Module);
         ModuleParser {
-            tokenizer,
-            state: RefCell::new(ModuleParserState::default()),
-            pending: None.into(),
+            stream,
+            state: ModuleParserState::default(),
+            pending: Some(module),
+            syntax: None,
         }
     }
 
+    /// Returns the syntax tree of the module once it has been parsed. Printing
+    /// the tree reproduces the source exactly.
+    pub fn syntax(&self) -> Option<SyntaxNode> {
+        self.syntax.clone()
+    }
+
+    /// The location of the token the parser stopped at, to report an error
+    /// returned by [`Parser::parse_all`] at.
+    pub fn error_loc(&self) -> ASTLoc {
+        self.stream.last_loc()
+    }
+
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(input: &'a str) -> Self {
-        Self::new(RefCell::new(Tokenizer::new(input)).into())
+        Self::new(Tokenizer::new(input))
     }
 
-    fn set_pending_err(&self, err: ParseError) {
-        assert!(self
-            .state
-            .replace(ModuleParserState::PendingParseError(err))
-            .is_ready());
+    fn set_pending_err(&mut self, err: ParseError) {
+        assert!(
+            mem::replace(&mut self.state, ModuleParserState::PendingParseError(err)).is_ready()
+        );
     }
 
-    fn set_state_from_parse_result<T>(&self, res: ParseResult<T>) {
-        if let Err(err) = res {
-            self.set_pending_err(err);
+    fn set_state_from_parse_result(&mut self, res: ParseResult<ASTNodeScoped>) {
+        match res {
+            Ok(node) => self.push_node(node),
+            Err(err) => self.set_pending_err(err),
         }
     }
 
-    /// Consume token and handle tokenize error and returns it as [`ParseError`].
-    /// If the inner tokenizer has no consumable token, it returns [`ParseError::SyntaxError`].
-    fn consume_token_or_err(&self) -> TokenizeResult {
-        match self.consume_token() {
-            Some(res) => match res {
-                Ok(tok) => Ok(tok),
-                Err(err) => Err(ParseError::TokenizeError(err)),
-            },
-            None => Err(ParseError::SyntaxError),
+    fn push_node(&mut self, node: ASTNodeScoped) {
+        self.pending.as_mut().expect("No pending result").push(node);
+    }
+
+    /// Parses `Name<T>(props) { body }` after the `view` keyword. The generic
+    /// parameters and the property list may be left out.
+    fn parse_view(
+        &mut self,
+        start: ASTLoc,
+        attributes: Vec<ASTNodeItemAttribute>,
+        vis: ASTVisibility,
+    ) -> ParseResult<ASTNodeScoped> {
+        let (name, _) = self.expect_identifier()?;
+        let type_params = self.parse_type_params()?;
+        let params = match self.peek_is(&TokenContent::ParenthesisLeft) {
+            true => TypeParser::new(self.stream()).parse_params()?.0,
+            false => Vec::new(),
+        };
+        let body = ExprParser::new(self.stream()).parse_block()?;
+
+        let item = ASTItemView::new(start.to(body.loc()), name, params, body)
+            .with_attributes(attributes)
+            .with_visibility(vis)
+            .with_type_params(type_params);
+        Ok(ASTNodeScoped::View(item))
+    }
+
+    /// Parses `name<T>(params) -> Ret emits Row { body }` after the `fn`
+    /// keyword, which follows `const` if `is_const`.
+    fn parse_fn(
+        &mut self,
+        start: ASTLoc,
+        attributes: Vec<ASTNodeItemAttribute>,
+        vis: ASTVisibility,
+        is_const: bool,
+    ) -> ParseResult<ASTNodeScoped> {
+        let (name, _) = self.expect_identifier()?;
+        let type_params = self.parse_type_params()?;
+        let signature = TypeParser::new(self.stream()).parse_fn_signature()?;
+        let body = ExprParser::new(self.stream()).parse_block()?;
+
+        let item = ASTItemFn::new(start.to(body.loc()), name, signature, body)
+            .with_attributes(attributes)
+            .with_visibility(vis)
+            .with_const(is_const)
+            .with_type_params(type_params);
+        Ok(ASTNodeScoped::Fn(item))
+    }
+
+    /// Parses `Name { fn op(params) -> Ret .. }` after the `effect` keyword.
+    fn parse_effect(
+        &mut self,
+        start: ASTLoc,
+        attributes: Vec<ASTNodeItemAttribute>,
+        vis: ASTVisibility,
+    ) -> ParseResult<ASTNodeScoped> {
+        let (name, _) = self.expect_identifier()?;
+        self.expect_token(&TokenContent::BraceLeft)?;
+
+        let mut ops = Vec::new();
+        let end = loop {
+            if let Some(end) = self.consume_if(&TokenContent::BraceRight) {
+                break end;
+            }
+            let checkpoint = self.checkpoint();
+            let keyword = self.expect_token(&TokenContent::FnKeyword)?;
+            let (op, _) = self.expect_identifier()?;
+            let (params, mut loc) = TypeParser::new(self.stream()).parse_params()?;
+            let ret = match self.consume_if(&TokenContent::Arrow) {
+                Some(_) => Some(TypeParser::new(self.stream()).parse_all()?),
+                None => None,
+            };
+            if let Some(ret) = &ret {
+                loc = loc.to(ret.loc());
+            }
+            let loc = ASTLoc::from(keyword.loc).to(loc);
+            ops.push(ASTNodeEffectOp::new(loc, op, params, ret));
+            self.wrap_node(checkpoint, SyntaxKind::EffectOp);
+        };
+
+        let item = ASTItemEffect::new(start.to(end.loc.into()), name, ops)
+            .with_attributes(attributes)
+            .with_visibility(vis);
+        Ok(ASTNodeScoped::Effect(item))
+    }
+
+    /// Parses `type Name<T, U> = body` after the `type` keyword.
+    fn parse_type_item(
+        &mut self,
+        start: ASTLoc,
+        attributes: Vec<ASTNodeItemAttribute>,
+        vis: ASTVisibility,
+    ) -> ParseResult<ASTNodeScoped> {
+        let (name, _) = self.expect_identifier()?;
+        let params = self.parse_type_params()?;
+        self.expect_token(&TokenContent::AssignmentOp)?;
+        let (body, end) = self.parse_type_body()?;
+
+        let item = ASTItemType::new(start.to(end), name, body)
+            .with_attributes(attributes)
+            .with_visibility(vis)
+            .with_params(params);
+        Ok(ASTNodeScoped::Type(item))
+    }
+
+    /// Parses optional generic parameters of a declaration with their bounds,
+    /// e.g. `<T: Eq + Ord, E>`.
+    fn parse_type_params(&mut self) -> ParseResult<Vec<ASTNodeTypeParam>> {
+        let mut params = Vec::new();
+        if self
+            .consume_if(&TokenContent::TagAngleBracketLeft)
+            .is_none()
+        {
+            return Ok(params);
+        }
+
+        loop {
+            let param = self.node(SyntaxKind::TypeParam, |p| {
+                let (name, mut loc) = p.expect_identifier()?;
+                let mut bounds = Vec::new();
+                if p.consume_if(&TokenContent::Colon).is_some() {
+                    loop {
+                        let (bound, bound_loc) = p.expect_identifier()?;
+                        loc = loc.to(bound_loc);
+                        bounds.push(ASTNodeTypeBound::new(bound_loc, bound));
+                        if p.consume_if(&TokenContent::AddOp).is_none() {
+                            break;
+                        }
+                    }
+                }
+                Ok(ASTNodeTypeParam::new(loc, name, bounds))
+            })?;
+            params.push(param);
+            if self.consume_if(&TokenContent::Comma).is_none() {
+                break;
+            }
         }
+        self.expect_token(&TokenContent::TagAngleBracketRight)?;
+
+        Ok(params)
     }
 
-    fn parse_view_elements(&self) -> ParseResult<ASTNodeViewElement> {
-        ViewParser::new(self.tokenizer.clone()).parse_all()
+    /// Parses the right hand side of a `type` declaration and returns it with
+    /// the location where it ends.
+    ///
+    /// A name followed by `(` or `|`, or a leading `|`, starts a tagged union;
+    /// a bare record type declares a record and anything else is an alias.
+    fn parse_type_body(&mut self) -> ParseResult<(ASTTypeBody, ASTLoc)> {
+        if self.consume_if(&TokenContent::BitwiseOrOp).is_some() {
+            return self.parse_union();
+        }
+        if let Some(TokenContent::Identifier(_)) = self.peek_content() {
+            if matches!(
+                self.peek_content_nth(1),
+                Some(TokenContent::ParenthesisLeft | TokenContent::BitwiseOrOp)
+            ) {
+                return self.parse_union();
+            }
+        }
+
+        let ty = TypeParser::new(self.stream()).parse_all()?;
+        let end = ty.loc();
+        match ty.kind {
+            ASTTypeKind::Record(fields) => Ok((ASTTypeBody::Record(fields), end)),
+            _ => Ok((ASTTypeBody::Alias(ty), end)),
+        }
     }
 
-    fn parse_view(&self) -> ParseResult<ASTNodeScoped> {
-        todo!()
+    /// Parses `A(T) | B | C(U, V)`.
+    fn parse_union(&mut self) -> ParseResult<(ASTTypeBody, ASTLoc)> {
+        let mut variants = Vec::new();
+
+        loop {
+            let checkpoint = self.checkpoint();
+            let (name, loc) = self.expect_identifier()?;
+
+            let mut fields = Vec::new();
+            let mut end = loc;
+            if self.consume_if(&TokenContent::ParenthesisLeft).is_some() {
+                loop {
+                    if let Some(close) = self.consume_if(&TokenContent::ParenthesisRight) {
+                        end = close.loc.into();
+                        break;
+                    }
+                    fields.push(TypeParser::new(self.stream()).parse_all()?);
+                    if self.consume_if(&TokenContent::Comma).is_none() {
+                        end = self
+                            .expect_token(&TokenContent::ParenthesisRight)?
+                            .loc
+                            .into();
+                        break;
+                    }
+                }
+            }
+            variants.push(ASTNodeTypeVariant::new(loc.to(end), name, fields));
+            self.wrap_node(checkpoint, SyntaxKind::Variant);
+
+            if self.consume_if(&TokenContent::BitwiseOrOp).is_none() {
+                return Ok((ASTTypeBody::Union(variants), end));
+            }
+        }
+    }
+
+    /// Parses `import { A, B as C } from "./path"` after the `import` keyword.
+    fn parse_import(
+        &mut self,
+        start: ASTLoc,
+        attributes: Vec<ASTNodeItemAttribute>,
+        vis: ASTVisibility,
+    ) -> ParseResult<ASTNodeScoped> {
+        self.expect_token(&TokenContent::BraceLeft)?;
+        let mut names = Vec::new();
+        loop {
+            if self.consume_if(&TokenContent::BraceRight).is_some() {
+                break;
+            }
+
+            let name = self.node(SyntaxKind::ImportName, |p| {
+                let (name, loc) = p.expect_identifier()?;
+                let (alias, loc) = match p.consume_if(&TokenContent::As) {
+                    Some(_) => {
+                        let (alias, alias_loc) = p.expect_identifier()?;
+                        (Some(alias), loc.to(alias_loc))
+                    }
+                    None => (None, loc),
+                };
+                Ok(ASTNodeImportName::new(loc, name, alias))
+            })?;
+            names.push(name);
+
+            if self.consume_if(&TokenContent::Comma).is_none() {
+                self.expect_token(&TokenContent::BraceRight)?;
+                break;
+            }
+        }
+
+        self.expect_token(&TokenContent::FromKeyword)?;
+        let source = self.consume_token_or_err()?;
+        let TokenContent::Literal(literal @ TokenLiteral::StringLiteral(_)) = &source.con else {
+            return Err(ParseError::UnexpectedToken);
+        };
+
+        let loc = start.to(source.loc.into());
+        let item = ASTItemImport::new(loc, names, literal.unquoted().to_string())
+            .with_attributes(attributes)
+            .with_visibility(vis);
+        Ok(ASTNodeScoped::Import(item))
     }
 
-    fn parse_token(&self, res: TokenResult) {
-        /*match res {
-            Ok(token) => match token.con {
-                TokenContent::Create => {
-                    let res = self.parse_create();
-                    self.set_state_from_parse_result(res);
+    /// Parses `use a::b::c` or `use a::b as c` after the `use` keyword.
+    fn parse_use(
+        &mut self,
+        start: ASTLoc,
+        attributes: Vec<ASTNodeItemAttribute>,
+        vis: ASTVisibility,
+    ) -> ParseResult<ASTNodeScoped> {
+        let mut path = Vec::new();
+        let mut end;
+        loop {
+            let (segment, loc) = self.expect_identifier()?;
+            path.push(segment);
+            end = loc;
+            if self.consume_if(&TokenContent::DoubleColon).is_none() {
+                break;
+            }
+        }
+
+        let alias = match self.consume_if(&TokenContent::As) {
+            Some(_) => {
+                let (alias, loc) = self.expect_identifier()?;
+                end = loc;
+                Some(alias)
+            }
+            None => None,
+        };
+
+        let item = ASTItemUse::new(start.to(end), path, alias)
+            .with_attributes(attributes)
+            .with_visibility(vis);
+        Ok(ASTNodeScoped::Use(item))
+    }
+
+    /// Parses `name(arg, ...)` after the `@` of an item attribute.
+    fn parse_item_attribute(&mut self, start: ASTLoc) -> ParseResult<ASTNodeItemAttribute> {
+        let (name, mut end) = self.expect_identifier()?;
+        let mut args = Vec::new();
+        if self.consume_if(&TokenContent::ParenthesisLeft).is_some() {
+            loop {
+                if let Some(token) = self.consume_if(&TokenContent::ParenthesisRight) {
+                    end = token.loc.into();
+                    break;
+                }
+                let (arg, loc) = self.expect_identifier()?;
+                args.push(ASTNodeAttributeArg::new(loc, arg));
+                if self.consume_if(&TokenContent::Comma).is_none() {
+                    end = self
+                        .expect_token(&TokenContent::ParenthesisRight)?
+                        .loc
+                        .into();
+                    break;
                 }
-                _ => self.set_pending_err(ParseError::UnexpectedToken),
-            },
+            }
+        }
+        Ok(ASTNodeItemAttribute::new(start.to(end), name, args))
+    }
+
+    /// Parses an item starting with `token`, which is either the keyword of
+    /// the item, `pub` or the `@` of an attribute, and wraps it in a node from
+    /// `checkpoint`.
+    fn parse_item(&mut self, token: Token, checkpoint: Checkpoint) -> ParseResult<ASTNodeScoped> {
+        let mut token = token;
+        let mut attributes = Vec::new();
+        let mut attribute_checkpoint = checkpoint;
+        while token.con == TokenContent::At {
+            attributes.push(self.parse_item_attribute(token.loc.into())?);
+            self.wrap_node(attribute_checkpoint, SyntaxKind::ItemAttribute);
+            attribute_checkpoint = self.checkpoint();
+            token = self.consume_token_or_err()?;
+        }
+
+        // Like its node of the syntax tree, an item starts at its attributes.
+        let start = attributes
+            .first()
+            .map_or(ASTLoc::from(token.loc), |attribute| attribute.loc());
+        let (vis, keyword) = match token.con {
+            TokenContent::Pub => (ASTVisibility::Public, self.consume_token_or_err()?),
+            _ => (ASTVisibility::Private, token),
+        };
+
+        let item = match keyword.con {
+            TokenContent::Const if self.consume_if(&TokenContent::FnKeyword).is_some() => {
+                self.parse_fn(start, attributes, vis, true)
+            }
+            TokenContent::Const => {
+                let item = ExprParser::new(self.stream()).parse_const(start, attributes, vis)?;
+                Ok(ASTNodeScoped::Const(item))
+            }
+            TokenContent::Effect => self.parse_effect(start, attributes, vis),
+            TokenContent::FnKeyword => self.parse_fn(start, attributes, vis, false),
+            TokenContent::Import => self.parse_import(start, attributes, vis),
+            TokenContent::Type => self.parse_type_item(start, attributes, vis),
+            TokenContent::Use => self.parse_use(start, attributes, vis),
+            TokenContent::View => self.parse_view(start, attributes, vis),
+            _ => Err(ParseError::UnexpectedToken),
+        }?;
+
+        let kind = match &item {
+            ASTNodeScoped::Const(_) => SyntaxKind::Const,
+            ASTNodeScoped::Effect(_) => SyntaxKind::Effect,
+            ASTNodeScoped::Fn(_) => SyntaxKind::Fn,
+            ASTNodeScoped::Import(_) => SyntaxKind::Import,
+            ASTNodeScoped::Type(_) => SyntaxKind::TypeItem,
+            ASTNodeScoped::Use(_) => SyntaxKind::Use,
+            ASTNodeScoped::View(_) => SyntaxKind::View,
+        };
+        self.wrap_node(checkpoint, kind);
+        Ok(item)
+    }
+
+    fn parse_token(&mut self, res: TokenResult, checkpoint: Checkpoint) {
+        match res {
+            Ok(token) => {
+                let res = self.parse_item(token, checkpoint);
+                self.set_state_from_parse_result(res);
+            }
             Err(err) => self.set_pending_err(ParseError::TokenizeError(err)),
-        }*/
-        todo!()
+        }
     }
 
-    fn advance(&self) -> ModuleParserResult {
-        match self.state.take() {
+    fn advance(&mut self) -> ModuleParserResult {
+        match mem::take(&mut self.state) {
             ModuleParserState::Ready => {
+                let checkpoint = self.checkpoint();
                 match self.consume_token() {
-                    Some(res) => assert!(self
-                        .state
-                        .replace(ModuleParserState::PendingToken(res))
-                        .is_ready()),
-                    None => assert!(self.state.replace(ModuleParserState::EOF).is_ready()),
+                    Some(res) => assert!(mem::replace(
+                        &mut self.state,
+                        ModuleParserState::PendingToken(res, checkpoint)
+                    )
+                    .is_ready()),
+                    None => {
+                        assert!(mem::replace(&mut self.state, ModuleParserState::Eof).is_ready())
+                    }
                 }
                 ModuleParserResult::Continue
             }
-            ModuleParserState::PendingToken(token) => {
-                self.parse_token(token);
+            ModuleParserState::PendingToken(token, checkpoint) => {
+                self.parse_token(token, checkpoint);
                 ModuleParserResult::Continue
             }
             ModuleParserState::PendingParseError(err) => ModuleParserResult::ParseError(err),
-            ModuleParserState::EOF => {
+            ModuleParserState::Eof => {
+                if let Some(green) = self.stream.finish_tree() {
+                    self.syntax = Some(SyntaxNode::new_root(green));
+                }
                 ModuleParserResult::Done(self.pending.take().expect("No pending result"))
             }
         }
     }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lang::ast::{
+        ASTBinaryOp, ASTExprKind, ASTItemType, ASTNodeStmt, ASTNodeType, ASTNodeViewChild,
+        ASTPatternKind, ASTUnaryOp,
+    };
+
+    fn parse(input: &str) -> ParseResult<ASTNodeModule> {
+        ModuleParser::from_str(input).parse_all()
+    }
+
+    fn parse_type_item(input: &str) -> ASTItemType {
+        let module = parse(input).expect("failed to parse");
+        assert_eq!(module.nodes.len(), 1);
+        match module.nodes.into_iter().next() {
+            Some(ASTNodeScoped::Type(item)) => item,
+            other => panic!("expected a type item, got {:?}", other),
+        }
+    }
+
+    fn named(ty: &ASTNodeType) -> (&str, &Vec<ASTNodeType>) {
+        match &ty.kind {
+            ASTTypeKind::Named { name, args } => (name, args),
+            other => panic!("expected a named type, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn empty_module() {
+        let module = parse("").unwrap();
+        assert!(module.nodes.is_empty());
+    }
+
+    #[test]
+    fn record_type() {
+        let item = parse_type_item("type Point = { x: Float, y: Float }");
+        assert_eq!(item.name, "Point");
+        assert_eq!(item.loc(), ASTLoc { start: 0, end: 35 });
+        match &item.body {
+            ASTTypeBody::Record(fields) => {
+                let names: Vec<_> = fields.iter().map(|f| f.name.as_str()).collect();
+                assert_eq!(names, ["x", "y"]);
+                assert_eq!(named(&fields[1].ty).0, "Float");
+            }
+            other => panic!("expected a record, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn union_type() {
+        let item = parse_type_item("type Shape = Circle(Float) | Rect(Point, Point) | Empty");
+        match &item.body {
+            ASTTypeBody::Union(variants) => {
+                let shapes: Vec<_> = variants
+                    .iter()
+                    .map(|v| (v.name.as_str(), v.fields.len()))
+                    .collect();
+                assert_eq!(shapes, [("Circle", 1), ("Rect", 2), ("Empty", 0)]);
+                assert_eq!(variants[0].loc(), ASTLoc { start: 13, end: 26 });
+            }
+            other => panic!("expected a union, got {:?}", other),
+        }
+
+        let item = parse_type_item("type Unit = | Unit");
+        assert!(matches!(&item.body, ASTTypeBody::Union(v) if v.len() == 1));
+    }
+
+    #[test]
+    fn alias_and_generics() {
+        let item = parse_type_item("type Id = Int");
+        assert!(matches!(&item.body, ASTTypeBody::Alias(ty) if named(ty).0 == "Int"));
+
+        let item = parse_type_item("type Result<T, E> = Ok(T) | Err(E)");
+        let params: Vec<_> = item.params.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(params, ["T", "E"]);
+
+        let item = parse_type_item("type Pair<T> = Map<T, [T]>");
+        match &item.body {
+            ASTTypeBody::Alias(ty) => {
+                let (name, args) = named(ty);
+                assert_eq!(name, "Map");
+                assert!(matches!(&args[1].kind, ASTTypeKind::List(_)));
+            }
+            other => panic!("expected an alias, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn type_expressions() {
+        let item = parse_type_item("type Handler = fn(String, Int?) -> (Bool, [Int])?");
+        let ASTTypeBody::Alias(ty) = &item.body else {
+            panic!("expected an alias");
+        };
+        let ASTTypeKind::Fn { params, ret, .. } = &ty.kind else {
+            panic!("expected a function type");
+        };
+        assert!(matches!(&params[1].kind, ASTTypeKind::Optional(_)));
+        let ASTTypeKind::Optional(ret) = &ret.kind else {
+            panic!("expected an optional return type");
+        };
+        assert!(matches!(&ret.kind, ASTTypeKind::Tuple(items) if items.len() == 2));
+
+        let item = parse_type_item("type Unit = ()");
+        assert!(matches!(&item.body, ASTTypeBody::Alias(ty)
+            if ty.kind == ASTTypeKind::Tuple(Vec::new())));
+
+        let item = parse_type_item("type Maybe = { name: String }?");
+        assert!(matches!(&item.body, ASTTypeBody::Alias(ty)
+            if matches!(ty.kind, ASTTypeKind::Optional(_))));
+    }
+
+    #[test]
+    fn multiple_items() {
+        let module = parse("type A = Int\ntype B = A?").unwrap();
+        assert_eq!(module.nodes.len(), 2);
+        assert_eq!(module.loc(), ASTLoc { start: 0, end: 24 });
+    }
+
+    #[test]
+    fn import_and_use() {
+        let module =
+            parse("import { A, B as C } from \"./widgets\"\nuse std::list\nuse a::b as c").unwrap();
+        let [ASTNodeScoped::Import(import), ASTNodeScoped::Use(list), ASTNodeScoped::Use(c)] =
+            module.nodes.as_slice()
+        else {
+            panic!("unexpected nodes {:?}", module.nodes);
+        };
+
+        assert_eq!(import.source, "./widgets");
+        let names: Vec<_> = import.names.iter().map(|n| n.local_name()).collect();
+        assert_eq!(names, ["A", "C"]);
+        assert_eq!(import.names[1].loc(), ASTLoc { start: 12, end: 18 });
+        assert_eq!(import.loc(), ASTLoc { start: 0, end: 37 });
+
+        assert_eq!(list.path, ["std", "list"]);
+        assert_eq!(list.local_name(), "list");
+        assert_eq!(c.path, ["a", "b"]);
+        assert_eq!(c.local_name(), "c");
+    }
+
+    #[test]
+    fn visibility() {
+        let module =
+            parse("pub type A = Int\ntype B = A\npub use a::b\npub import { C } from \"./c\"")
+                .unwrap();
+        let visibility: Vec<_> = module.nodes.iter().map(|n| n.visibility()).collect();
+        assert_eq!(
+            visibility,
+            [
+                ASTVisibility::Public,
+                ASTVisibility::Private,
+                ASTVisibility::Public,
+                ASTVisibility::Public
+            ]
+        );
+        assert_eq!(module.nodes[0].loc(), ASTLoc { start: 0, end: 16 });
+        assert_eq!(
+            parse("pub pub type A = Int"),
+            Err(ParseError::UnexpectedToken)
+        );
+    }
+
+    #[test]
+    fn invalid_imports() {
+        assert_eq!(
+            parse("import { A } from widgets"),
+            Err(ParseError::UnexpectedToken)
+        );
+        assert_eq!(
+            parse("import A from \"./a\""),
+            Err(ParseError::UnexpectedToken)
+        );
+        assert_eq!(parse("use std::"), Err(ParseError::SyntaxError));
+    }
+
+    #[test]
+    fn invalid_type_declarations() {
+        assert_eq!(parse("type = Int"), Err(ParseError::UnexpectedToken));
+        assert_eq!(parse("type A = "), Err(ParseError::SyntaxError));
+        assert_eq!(
+            parse("type A = { x Int }"),
+            Err(ParseError::UnexpectedToken)
+        );
+    }
+
+    #[test]
+    fn fn_with_effect_row() {
+        let module =
+            parse("pub fn fetch(url: String) -> String emits Log + Http { log(url) }").unwrap();
+        let [ASTNodeScoped::Fn(item)] = module.nodes.as_slice() else {
+            panic!("unexpected nodes {:?}", module.nodes);
+        };
+        assert!(item.visibility.is_public());
+        assert_eq!(item.name, "fetch");
+        assert_eq!(item.params[0].name, "url");
+        let effects: Vec<_> = item.effects.iter().map(|e| named(e).0).collect();
+        assert_eq!(effects, ["Log", "Http"]);
+        assert_eq!(item.body.stmts.len(), 1);
+    }
+
+    #[test]
+    fn generic_items() {
+        let bounds = |params: &[ASTNodeTypeParam]| -> Vec<(String, Vec<String>)> {
+            params
+                .iter()
+                .map(|p| {
+                    let bounds = p.bounds.iter().map(|b| b.name.clone()).collect();
+                    (p.name.clone(), bounds)
+                })
+                .collect()
+        };
+        let module = parse(
+            "fn max<T: Eq + Ord, U>(a: T, b: T) -> T { a }
+view List<T>(items: [T]) {}
+type Sorted<T: Ord> = [T]",
+        )
+        .unwrap();
+        let [ASTNodeScoped::Fn(f), ASTNodeScoped::View(view), ASTNodeScoped::Type(ty)] =
+            module.nodes.as_slice()
+        else {
+            panic!("unexpected nodes {:?}", module.nodes);
+        };
+        assert_eq!(
+            bounds(&f.type_params),
+            [
+                ("T".to_string(), vec!["Eq".to_string(), "Ord".to_string()]),
+                ("U".to_string(), vec![])
+            ]
+        );
+        assert_eq!(f.type_params[0].loc(), ASTLoc { start: 7, end: 18 });
+        assert_eq!(f.params.len(), 2);
+        assert_eq!(bounds(&view.type_params), [("T".to_string(), vec![])]);
+        assert_eq!(view.params[0].name, "items");
+        assert_eq!(
+            bounds(&ty.params),
+            [("T".to_string(), vec!["Ord".to_string()])]
+        );
+
+        assert_eq!(parse("fn f<T: >() {}"), Err(ParseError::UnexpectedToken));
+    }
+
+    #[test]
+    fn effect_declaration() {
+        let module = parse("effect Log {\n  fn log(msg: String)\n  fn level() -> Int\n}").unwrap();
+        let [ASTNodeScoped::Effect(item)] = module.nodes.as_slice() else {
+            panic!("unexpected nodes {:?}", module.nodes);
+        };
+        assert_eq!(item.name, "Log");
+        let ops: Vec<_> = item.ops.iter().map(|op| op.name.as_str()).collect();
+        assert_eq!(ops, ["log", "level"]);
+        assert!(item.ops[0].ret.is_none());
+        assert!(item.ops[1].ret.is_some());
+        assert_eq!(item.loc(), ASTLoc { start: 0, end: 56 });
+        assert_eq!(
+            parse("effect Log { log() }"),
+            Err(ParseError::UnexpectedToken)
+        );
+    }
+
+    #[test]
+    fn item_attributes() {
+        let module = parse("@allow(unused, img-alt)\n@inline\npub fn f() { 1 }").unwrap();
+        let item = &module.nodes[0];
+        let attributes: Vec<_> = item
+            .attributes()
+            .iter()
+            .map(|attribute| {
+                let args: Vec<_> = attribute.args.iter().map(|arg| arg.name.as_str()).collect();
+                (attribute.name.as_str(), args)
+            })
+            .collect();
+        assert_eq!(
+            attributes,
+            [("allow", vec!["unused", "img-alt"]), ("inline", vec![])]
+        );
+        assert_eq!(item.attributes()[0].loc(), ASTLoc { start: 0, end: 23 });
+        assert_eq!(item.loc(), ASTLoc { start: 0, end: 48 });
+
+        assert_eq!(
+            parse("@allow(unused fn f() {}"),
+            Err(ParseError::UnexpectedToken)
+        );
+    }
+
+    #[test]
+    fn view_with_handler() {
+        let module = parse(
+            "view Counter(start: Int) {
+              let count = start
+              const LABEL = \"count\"
+              with Log {
+                fn log(msg) { print(msg) }
+              }
+              <div#root class=\"counter\" hidden>
+                <span>{LABEL}</span>
+                <button type=\"button\" onclick={increment(count)}>\"+\"</>
+              </div>
+            }",
+        )
+        .unwrap();
+        let [ASTNodeScoped::View(view)] = module.nodes.as_slice() else {
+            panic!("unexpected nodes {:?}", module.nodes);
+        };
+        assert_eq!(view.name, "Counter");
+        assert_eq!(view.params.len(), 1);
+        let [ASTNodeStmt::Let(_), ASTNodeStmt::Const(_), ASTNodeStmt::With(with), ASTNodeStmt::Element(root)] =
+            view.body.stmts.as_slice()
+        else {
+            panic!("unexpected statements {:?}", view.body.stmts);
+        };
+        assert_eq!(named(&with.effect).0, "Log");
+        assert_eq!(with.ops[0].name, "log");
+
+        assert_eq!(root.name, "div");
+        assert_eq!(root.anchor.as_deref(), Some("root"));
+        let attributes: Vec<_> = root.attributes.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(attributes, ["class", "hidden"]);
+        assert!(root.attributes[1].value.is_none());
+        assert_eq!(root.children.len(), 2);
+        let ASTNodeViewChild::Element(button) = &root.children[1] else {
+            panic!("expected an element");
+        };
+        assert!(matches!(&button.children[..], [ASTNodeViewChild::Text(_)]));
+        assert_eq!(button.attributes[0].name, "type");
+
+        assert_eq!(
+            parse("view A { <div></span> }"),
+            Err(ParseError::SyntaxError)
+        );
+    }
+
+    #[test]
+    fn expressions() {
+        let module = parse("const X = -a + b * c == d || !e.f(1, 2)").unwrap();
+        let [ASTNodeScoped::Const(item)] = module.nodes.as_slice() else {
+            panic!("unexpected nodes {:?}", module.nodes);
+        };
+        let ASTExprKind::Binary { op, lhs, rhs } = &item.value.kind else {
+            panic!("expected a binary expression");
+        };
+        assert_eq!(*op, ASTBinaryOp::Or);
+        assert!(matches!(
+            &rhs.kind,
+            ASTExprKind::Unary {
+                op: ASTUnaryOp::Not,
+                ..
+            }
+        ));
+        let ASTExprKind::Binary { op, lhs, .. } = &lhs.kind else {
+            panic!("expected a comparison");
+        };
+        assert_eq!(*op, ASTBinaryOp::Eq);
+        let ASTExprKind::Binary { op, lhs, rhs } = &lhs.kind else {
+            panic!("expected an addition");
+        };
+        assert_eq!(*op, ASTBinaryOp::Add);
+        assert!(matches!(
+            &lhs.kind,
+            ASTExprKind::Unary {
+                op: ASTUnaryOp::Neg,
+                ..
+            }
+        ));
+        assert!(matches!(
+            &rhs.kind,
+            ASTExprKind::Binary {
+                op: ASTBinaryOp::Mul,
+                ..
+            }
+        ));
+
+        let module =
+            parse("const P = { x: 1, y: [a, b] }\nconst Q = if a < 1 { 1 } else { 2 }").unwrap();
+        assert!(matches!(&module.nodes[0], ASTNodeScoped::Const(c)
+            if matches!(&c.value.kind, ASTExprKind::Record(fields) if fields.len() == 2)));
+        assert!(matches!(&module.nodes[1], ASTNodeScoped::Const(c)
+            if matches!(&c.value.kind, ASTExprKind::If { els: Some(_), .. })));
+
+        let module = parse("fn f() { a.b = 1 effect { } }").unwrap();
+        let ASTNodeScoped::Fn(item) = &module.nodes[0] else {
+            panic!("expected a function");
+        };
+        assert!(matches!(&item.body.stmts[..],
+            [ASTNodeStmt::Assign(assign), ASTNodeStmt::Effect(_)]
+            if assign.target_name() == Some("a")));
+        assert_eq!(parse("fn f() { g() = 1 }"), Err(ParseError::SyntaxError));
+    }
+
+    #[test]
+    fn for_and_when() {
+        let module = parse(
+            "fn f(shapes) {
+              for (i, Circle(r)) in shapes { draw(i, r) }
+              when shape {
+                Circle(r) -> r * r,
+                Empty -> 0
+                nil -> { 0 }
+                other -> area(other)
+                _ -> 1
+              }
+            }",
+        )
+        .unwrap();
+        let ASTNodeScoped::Fn(item) = &module.nodes[0] else {
+            panic!("expected a function");
+        };
+        let [ASTNodeStmt::Expr(for_expr), ASTNodeStmt::Expr(when_expr)] = &item.body.stmts[..]
+        else {
+            panic!("unexpected statements {:?}", item.body.stmts);
+        };
+
+        let ASTExprKind::For { pattern, iter, .. } = &for_expr.kind else {
+            panic!("expected a for loop");
+        };
+        let bindings: Vec<_> = pattern.bindings().into_iter().map(|(n, _)| n).collect();
+        assert_eq!(bindings, ["i", "r"]);
+        assert!(matches!(&iter.kind, ASTExprKind::Identifier(name) if name == "shapes"));
+
+        let ASTExprKind::When { arms, .. } = &when_expr.kind else {
+            panic!("expected a when expression");
+        };
+        let kinds: Vec<_> = arms.iter().map(|arm| &arm.pattern.kind).collect();
+        assert!(matches!(
+            kinds[..],
+            [
+                ASTPatternKind::Variant { .. },
+                ASTPatternKind::Variant { fields, .. },
+                ASTPatternKind::Nil,
+                ASTPatternKind::Binding(_),
+                ASTPatternKind::Wildcard,
+            ] if fields.is_empty()
+        ));
+        assert!(matches!(arms[2].body.kind, ASTExprKind::Block(_)));
 
-    fn consume_token(&self) -> Option<TokenResult> {
-        self.tokenizer.borrow_mut().next()
+        assert_eq!(
+            parse("fn f() { for x of xs { } }"),
+            Err(ParseError::UnexpectedToken)
+        );
     }
 }
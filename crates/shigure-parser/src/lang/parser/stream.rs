@@ -0,0 +1,267 @@
+use crate::lang::{
+    ast::{ASTLoc, Token, TokenContent},
+    syntax::{Checkpoint, GreenNode, GreenToken, SyntaxKind, TreeBuilder},
+    tokenizer::{TokenResult, Tokenizer},
+};
+use std::sync::Arc;
+
+/// A token lexed ahead of the parser, with the trivia before it.
+#[derive(Debug)]
+struct Lexed {
+    trivia: Vec<Token>,
+    /// `None` at the end of the input, where `trivia` holds the trailing
+    /// whitespace and comments.
+    token: Option<TokenResult>,
+}
+
+/// A position of a [`TokenStream`] to [`rewind`](TokenStream::rewind) to.
+#[derive(Debug, Clone)]
+pub struct StreamCheckpoint {
+    pos: usize,
+    trivia_recorded: bool,
+    /// The syntax tree at the checkpoint, if one is being built.
+    tree: Option<TreeBuilder>,
+}
+
+/// The tokens of a [`Tokenizer`] with lookahead, for the parsers.
+///
+/// Any number of tokens can be looked at before they are consumed with
+/// [`peek_nth`](TokenStream::peek_nth), and parsing can return to an earlier
+/// position with [`checkpoint`](TokenStream::checkpoint) and
+/// [`rewind`](TokenStream::rewind). Lexing stops after the first tokenizer
+/// error.
+///
+/// While the root node of a syntax tree is open, every consumed token is
+/// recorded into the tree together with the whitespace and comments before
+/// it.
+///
+/// ```
+/// use shigure_parser::lang::{
+///     ast::TokenContent,
+///     parser::stream::TokenStream,
+///     tokenizer::Tokenizer,
+/// };
+///
+/// let mut stream = TokenStream::new(Tokenizer::new("a(b)"));
+/// let start = stream.checkpoint();
+/// assert_eq!(stream.peek_content_nth(1), Some(&TokenContent::ParenthesisLeft));
+/// assert_eq!(stream.by_ref().count(), 4);
+///
+/// stream.rewind(start);
+/// assert_eq!(stream.next().unwrap().unwrap().con, TokenContent::Identifier("a".into()));
+/// ```
+pub struct TokenStream<'a> {
+    tokenizer: Tokenizer<'a>,
+    /// Every token lexed so far. Consumed tokens are kept for rewinding.
+    tokens: Vec<Lexed>,
+    /// The index of the next token in `tokens`.
+    pos: usize,
+    /// Whether the trivia before the next token has been added to the tree.
+    trivia_recorded: bool,
+    tree: TreeBuilder,
+}
+
+impl<'a> TokenStream<'a> {
+    pub fn new(tokenizer: Tokenizer<'a>) -> Self {
+        Self {
+            tokenizer,
+            tokens: Vec::new(),
+            pos: 0,
+            trivia_recorded: false,
+            tree: TreeBuilder::default(),
+        }
+    }
+
+    /// Lexes tokens until the one at `index` or the end of the input.
+    fn fill(&mut self, index: usize) {
+        while self.tokens.len() <= index {
+            let done = match self.tokens.last() {
+                Some(last) => !matches!(last.token, Some(Ok(_))),
+                None => false,
+            };
+            if done {
+                return;
+            }
+            let mut trivia = Vec::new();
+            let token = match self.tree.is_building() {
+                true => self.tokenizer.lex_next(Some(&mut trivia)),
+                false => self.tokenizer.lex_next(None),
+            };
+            self.tokens.push(Lexed { trivia, token });
+        }
+    }
+
+    /// Returns the token `n` tokens after the next one without consuming
+    /// anything; `peek_nth(0)` is the next token.
+    pub fn peek_nth(&mut self, n: usize) -> Option<&TokenResult> {
+        let index = self.pos + n;
+        self.fill(index);
+        self.tokens.get(index)?.token.as_ref()
+    }
+
+    pub fn peek(&mut self) -> Option<&TokenResult> {
+        self.peek_nth(0)
+    }
+
+    /// Returns the content of the token `n` tokens after the next one, unless
+    /// it is an error or the end of the input.
+    pub fn peek_content_nth(&mut self, n: usize) -> Option<&TokenContent> {
+        match self.peek_nth(n) {
+            Some(Ok(token)) => Some(&token.con),
+            _ => None,
+        }
+    }
+
+    /// The location of the last consumed token, or of the text the tokenizer
+    /// failed on if that was consumed instead, which is where a parse error
+    /// is reported.
+    pub fn last_loc(&self) -> ASTLoc {
+        let Some(index) = self.pos.checked_sub(1) else {
+            return ASTLoc::default();
+        };
+        match &self.tokens[index].token {
+            Some(Ok(token)) => token.loc.into(),
+            _ => self
+                .tokenizer
+                .error_loc()
+                .map(ASTLoc::from)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Returns a position to [`rewind`](TokenStream::rewind) to.
+    ///
+    /// Rewinding also restores the syntax tree, so a checkpoint taken while
+    /// building one copies the tree; it is meant for bounded speculative
+    /// parsing rather than for every token.
+    pub fn checkpoint(&self) -> StreamCheckpoint {
+        StreamCheckpoint {
+            pos: self.pos,
+            trivia_recorded: self.trivia_recorded,
+            tree: self.tree.is_building().then(|| self.tree.clone()),
+        }
+    }
+
+    /// Returns to `checkpoint`, so the tokens consumed since are read again.
+    pub fn rewind(&mut self, checkpoint: StreamCheckpoint) {
+        self.pos = checkpoint.pos;
+        self.trivia_recorded = checkpoint.trivia_recorded;
+        if let Some(tree) = checkpoint.tree {
+            self.tree = tree;
+        }
+    }
+
+    /// Adds the trivia before the next token to the tree, once.
+    fn record_trivia(&mut self) {
+        if self.trivia_recorded || !self.tree.is_building() {
+            return;
+        }
+        self.fill(self.pos);
+        if let Some(lexed) = self.tokens.get(self.pos) {
+            for trivia in &lexed.trivia {
+                self.tree.trivia(GreenToken::new(
+                    trivia.con.clone(),
+                    self.tokenizer.text_of(trivia),
+                ));
+            }
+        }
+        self.trivia_recorded = true;
+    }
+
+    /// Starts the root node of a syntax tree. The root contains every token
+    /// consumed until [`TokenStream::finish_tree`], so it has to be started
+    /// before the first token is lexed.
+    pub(crate) fn start_node(&mut self, kind: SyntaxKind) {
+        debug_assert!(self.tokens.is_empty(), "tokens were lexed before the tree");
+        self.tree.start_node(kind);
+    }
+
+    /// Marks the position before the next token and the trivia preceding
+    /// it, to wrap what is parsed from there in a node with
+    /// [`TokenStream::wrap_node`].
+    pub(crate) fn node_checkpoint(&mut self) -> Checkpoint {
+        self.record_trivia();
+        self.tree.checkpoint()
+    }
+
+    /// Wraps the tokens and nodes consumed since `checkpoint` in a node.
+    pub(crate) fn wrap_node(&mut self, checkpoint: Checkpoint, kind: SyntaxKind) {
+        self.tree.start_node_at(checkpoint, kind);
+        self.tree.finish_node();
+    }
+
+    /// Finishes the root node after the last token, and returns the tree.
+    pub(crate) fn finish_tree(&mut self) -> Option<Arc<GreenNode>> {
+        self.record_trivia();
+        self.tree.finish_node();
+        self.tree.take_finished()
+    }
+}
+
+impl Iterator for TokenStream<'_> {
+    type Item = TokenResult;
+
+    /// Consumes the next token.
+    fn next(&mut self) -> Option<TokenResult> {
+        self.fill(self.pos);
+        let token = self.tokens.get(self.pos)?.token.clone()?;
+        if let Ok(token) = &token {
+            self.record_trivia();
+            if self.tree.is_building() {
+                let text = self.tokenizer.text_of(token);
+                self.tree.token(GreenToken::new(token.con.clone(), text));
+            }
+        }
+        self.pos += 1;
+        self.trivia_recorded = false;
+        Some(token)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn contents(stream: &mut TokenStream) -> Vec<TokenContent> {
+        stream.map(|res| res.unwrap().con).collect()
+    }
+
+    #[test]
+    fn peeks_ahead_without_consuming() {
+        let mut stream = TokenStream::new(Tokenizer::new("let x = 1"));
+        assert_eq!(stream.peek_content_nth(0), Some(&TokenContent::Let));
+        assert_eq!(
+            stream.peek_content_nth(2),
+            Some(&TokenContent::AssignmentOp)
+        );
+        assert_eq!(stream.peek_content_nth(4), None);
+        assert_eq!(contents(&mut stream).len(), 4);
+        assert!(stream.peek().is_none());
+    }
+
+    #[test]
+    fn rewinds_tokens_and_tree() {
+        let source = "a /* x */ b  c";
+        let mut stream = TokenStream::new(Tokenizer::new(source));
+        stream.start_node(SyntaxKind::Module);
+        stream.next();
+        let checkpoint = stream.checkpoint();
+        stream.next();
+        stream.next();
+        stream.rewind(checkpoint);
+
+        assert_eq!(contents(&mut stream).len(), 2);
+        let tree = stream.finish_tree().unwrap();
+        assert_eq!(tree.to_string(), source);
+    }
+
+    #[test]
+    fn stops_after_an_error() {
+        let mut stream = TokenStream::new(Tokenizer::new("a \"unterminated"));
+        assert!(stream.next().unwrap().is_ok());
+        assert!(matches!(stream.peek_nth(0), Some(Err(_))));
+        assert!(stream.next().unwrap().is_err());
+        assert!(stream.next().is_none());
+        assert!(stream.peek_nth(3).is_none());
+    }
+}
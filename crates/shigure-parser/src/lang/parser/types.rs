@@ -0,0 +1,255 @@
+use crate::lang::{
+    ast::{
+        ASTLoc, ASTNodeFnSignature, ASTNodeParam, ASTNodeType, ASTNodeTypeField, ASTTypeKind,
+        Spanned, TokenContent,
+    },
+    parser::{stream::TokenStream, ParseError, ParseResult, Parser, TokenConsumer},
+    syntax::{Checkpoint, SyntaxKind},
+};
+
+/// Parses a type expression.
+///
+/// ```text
+/// type    := fn "(" [type {"," type}] ")" "->" type [emits row] | primary {"?"}
+/// row     := type {"+" type}
+/// primary := Name ["<" type {"," type} ">"]
+///          | "[" type "]"
+///          | "(" [type {"," type} [","]] ")"
+///          | "{" [Name ":" type {"," Name ":" type} [","]] "}"
+/// ```
+pub struct TypeParser<'s, 'a> {
+    stream: &'s mut TokenStream<'a>,
+}
+
+impl Parser<ASTNodeType> for TypeParser<'_, '_> {
+    fn parse_all(&mut self) -> ParseResult<ASTNodeType> {
+        self.parse_type()
+    }
+}
+
+impl<'a> TokenConsumer<'a> for TypeParser<'_, 'a> {
+    fn stream(&mut self) -> &mut TokenStream<'a> {
+        self.stream
+    }
+}
+
+impl<'s, 'a> TypeParser<'s, 'a> {
+    pub fn new(stream: &'s mut TokenStream<'a>) -> Self {
+        TypeParser { stream }
+    }
+
+    fn parse_type(&mut self) -> ParseResult<ASTNodeType> {
+        let checkpoint = self.checkpoint();
+        if let Some(token) = self.consume_if(&TokenContent::FnKeyword) {
+            let ty = self.parse_fn_type(token.loc.into())?;
+            self.wrap_node(checkpoint, SyntaxKind::Type);
+            return Ok(ty);
+        }
+
+        let token = self.consume_token_or_err()?;
+        let loc: ASTLoc = token.loc.into();
+        let ty = match token.con {
+            TokenContent::Identifier(name) => {
+                return self.parse_named_rest(name.to_string(), loc, checkpoint);
+            }
+            TokenContent::SquareBracketLeft => {
+                let inner = self.parse_type()?;
+                let end = self.expect_token(&TokenContent::SquareBracketRight)?;
+                ASTNodeType::new(loc.to(end.loc.into()), ASTTypeKind::List(Box::new(inner)))
+            }
+            TokenContent::ParenthesisLeft => self.parse_parenthesized(loc)?,
+            TokenContent::BraceLeft => {
+                let (fields, end) = self.parse_record_fields()?;
+                ASTNodeType::new(loc.to(end), ASTTypeKind::Record(fields))
+            }
+            _ => return Err(ParseError::UnexpectedToken),
+        };
+        self.wrap_node(checkpoint, SyntaxKind::Type);
+
+        Ok(self.parse_optional_suffix(ty, checkpoint))
+    }
+
+    /// Continues parsing a named type whose name has been consumed already
+    /// after `checkpoint`.
+    fn parse_named_rest(
+        &mut self,
+        name: String,
+        loc: ASTLoc,
+        checkpoint: Checkpoint,
+    ) -> ParseResult<ASTNodeType> {
+        let mut args = Vec::new();
+        let mut loc = loc;
+        if self
+            .consume_if(&TokenContent::TagAngleBracketLeft)
+            .is_some()
+        {
+            loop {
+                args.push(self.parse_type()?);
+                if self.consume_if(&TokenContent::Comma).is_none() {
+                    break;
+                }
+            }
+            let end = self.expect_token(&TokenContent::TagAngleBracketRight)?;
+            loc = loc.to(end.loc.into());
+        }
+
+        let ty = ASTNodeType::new(loc, ASTTypeKind::Named { name, args });
+        self.wrap_node(checkpoint, SyntaxKind::Type);
+        Ok(self.parse_optional_suffix(ty, checkpoint))
+    }
+
+    /// Parses fields of a record type after its opening brace. Returns the
+    /// fields and the location of the closing brace.
+    pub(crate) fn parse_record_fields(&mut self) -> ParseResult<(Vec<ASTNodeTypeField>, ASTLoc)> {
+        let mut fields = Vec::new();
+        loop {
+            if let Some(end) = self.consume_if(&TokenContent::BraceRight) {
+                return Ok((fields, end.loc.into()));
+            }
+
+            let checkpoint = self.checkpoint();
+            let (name, loc) = self.expect_identifier()?;
+            self.expect_token(&TokenContent::Colon)?;
+            let ty = self.parse_type()?;
+            fields.push(ASTNodeTypeField::new(loc.to(ty.loc()), name, ty));
+            self.wrap_node(checkpoint, SyntaxKind::TypeField);
+
+            if self.consume_if(&TokenContent::Comma).is_none() {
+                let end = self.expect_token(&TokenContent::BraceRight)?;
+                return Ok((fields, end.loc.into()));
+            }
+        }
+    }
+
+    /// Parses `fn(A, B) -> C emits E` after the `fn` keyword.
+    fn parse_fn_type(&mut self, loc: ASTLoc) -> ParseResult<ASTNodeType> {
+        self.expect_token(&TokenContent::ParenthesisLeft)?;
+        let (params, _, _) = self.parse_type_list(&TokenContent::ParenthesisRight)?;
+        self.expect_token(&TokenContent::Arrow)?;
+        let ret = self.parse_type()?;
+        let effects = self.parse_effect_row()?;
+        let end = effects.last().unwrap_or(&ret).loc();
+
+        Ok(ASTNodeType::new(
+            loc.to(end),
+            ASTTypeKind::Fn {
+                params,
+                ret: Box::new(ret),
+                effects,
+            },
+        ))
+    }
+
+    /// Parses an optional effect row, `emits A + B`.
+    pub(crate) fn parse_effect_row(&mut self) -> ParseResult<Vec<ASTNodeType>> {
+        let mut effects = Vec::new();
+        if self.consume_if(&TokenContent::Emits).is_some() {
+            loop {
+                effects.push(self.parse_type()?);
+                if self.consume_if(&TokenContent::AddOp).is_none() {
+                    break;
+                }
+            }
+        }
+        Ok(effects)
+    }
+
+    /// Parses a parenthesized parameter list, `(a: A, b)`, and returns it with
+    /// the location of the whole list.
+    pub(crate) fn parse_params(&mut self) -> ParseResult<(Vec<ASTNodeParam>, ASTLoc)> {
+        self.node(SyntaxKind::ParamList, |p| p.parse_param_list())
+    }
+
+    fn parse_param_list(&mut self) -> ParseResult<(Vec<ASTNodeParam>, ASTLoc)> {
+        let open: ASTLoc = self
+            .expect_token(&TokenContent::ParenthesisLeft)?
+            .loc
+            .into();
+        let mut params = Vec::new();
+        loop {
+            if let Some(close) = self.consume_if(&TokenContent::ParenthesisRight) {
+                return Ok((params, open.to(close.loc.into())));
+            }
+
+            let param = self.node(SyntaxKind::Param, |p| {
+                let (name, loc) = p.expect_identifier()?;
+                let ty = match p.consume_if(&TokenContent::Colon) {
+                    Some(_) => Some(p.parse_type()?),
+                    None => None,
+                };
+                let loc = ty.as_ref().map_or(loc, |ty| loc.to(ty.loc()));
+                Ok(ASTNodeParam::new(loc, name, ty))
+            })?;
+            params.push(param);
+
+            if self.consume_if(&TokenContent::Comma).is_none() {
+                let close = self.expect_token(&TokenContent::ParenthesisRight)?;
+                return Ok((params, open.to(close.loc.into())));
+            }
+        }
+    }
+
+    /// Parses `(params) -> Ret emits Row` where the return type and the row
+    /// are optional.
+    pub(crate) fn parse_fn_signature(&mut self) -> ParseResult<ASTNodeFnSignature> {
+        let (params, mut loc) = self.parse_params()?;
+
+        let ret = match self.consume_if(&TokenContent::Arrow) {
+            Some(_) => Some(self.parse_type()?),
+            None => None,
+        };
+        let effects = self.parse_effect_row()?;
+        if let Some(end) = effects.last().or(ret.as_ref()) {
+            loc = loc.to(end.loc());
+        }
+
+        Ok(ASTNodeFnSignature::new(loc, params, ret, effects))
+    }
+
+    /// Parses a tuple, or a single parenthesized type, after `(`.
+    fn parse_parenthesized(&mut self, loc: ASTLoc) -> ParseResult<ASTNodeType> {
+        let (mut items, trailing_comma, end) =
+            self.parse_type_list(&TokenContent::ParenthesisRight)?;
+        if items.len() == 1 && !trailing_comma {
+            return Ok(items.remove(0));
+        }
+
+        Ok(ASTNodeType::new(loc.to(end), ASTTypeKind::Tuple(items)))
+    }
+
+    /// Parses comma separated types up to and including `close`. Returns the
+    /// types, whether the list ended with a trailing comma and the location of
+    /// `close`.
+    fn parse_type_list(
+        &mut self,
+        close: &TokenContent,
+    ) -> ParseResult<(Vec<ASTNodeType>, bool, ASTLoc)> {
+        let mut items = Vec::new();
+        let mut trailing_comma = false;
+        loop {
+            if let Some(end) = self.consume_if(close) {
+                return Ok((items, trailing_comma, end.loc.into()));
+            }
+            items.push(self.parse_type()?);
+            trailing_comma = self.consume_if(&TokenContent::Comma).is_some();
+            if !trailing_comma {
+                let end = self.expect_token(close)?;
+                return Ok((items, trailing_comma, end.loc.into()));
+            }
+        }
+    }
+
+    /// Wraps `ty`, which has been parsed since `checkpoint`, in optional types
+    /// for each following `?`.
+    fn parse_optional_suffix(&mut self, ty: ASTNodeType, checkpoint: Checkpoint) -> ASTNodeType {
+        let mut ty = ty;
+        while let Some(token) = self.consume_if(&TokenContent::QuestionMark) {
+            self.wrap_node(checkpoint, SyntaxKind::Type);
+            ty = ASTNodeType::new(
+                ty.loc().to(token.loc.into()),
+                ASTTypeKind::Optional(Box::new(ty)),
+            );
+        }
+        ty
+    }
+}
@@ -1,10 +1,14 @@
 use super::Parser;
 use crate::lang::{
-    ast::{ASTNodeViewElement, TokenContent},
-    parser::{ParseError, ParseResult},
-    tokenizer::{TokenResult, Tokenizer},
+    ast::{
+        ASTExprKind, ASTLoc, ASTNodeExpr, ASTNodeViewAttribute, ASTNodeViewChild,
+        ASTNodeViewElement, TokenContent, TokenLiteral,
+    },
+    parser::{expr::ExprParser, stream::TokenStream, ParseError, ParseResult, TokenConsumer},
+    syntax::{Checkpoint, SyntaxKind},
+    tokenizer::TokenResult,
 };
-use std::{cell::RefCell, rc::Rc};
+use std::mem;
 
 pub enum ViewParserResult {
     Continue,
@@ -16,33 +20,38 @@ pub enum ViewParserResult {
 enum ViewParserState {
     #[default]
     Ready,
-    PendingToken(TokenResult),
+    /// The first token of the element, and the checkpoint before it.
+    PendingToken(TokenResult, Checkpoint),
     PendingParseError(ParseError),
-    EOF,
+    Eof,
 }
 
 impl ViewParserState {
     fn is_ready(&self) -> bool {
-        match self {
-            ViewParserState::Ready => true,
-            _ => false,
-        }
+        matches!(self, ViewParserState::Ready)
     }
 }
 
-pub struct ViewParser<'a> {
-    tokenizer: Rc<RefCell<Tokenizer<'a>>>,
-    state: RefCell<ViewParserState>,
-    pending: RefCell<Option<ASTNodeViewElement>>,
+/// Parses one view element and its children.
+///
+/// ```text
+/// element := "<" Name [Anchor] {attr} ("/>" | ">" {child} "</" [Name] ">")
+/// attr    := (Name | Keyword) ["=" (StringLiteral | "{" expr "}")]
+/// child   := element | StringLiteral | "{" expr "}"
+/// ```
+pub struct ViewParser<'s, 'a> {
+    stream: &'s mut TokenStream<'a>,
+    state: ViewParserState,
+    pending: Option<ASTNodeViewElement>,
 }
 
-impl<'a> Parser<ASTNodeViewElement> for ViewParser<'a> {
-    fn parse_all(&self) -> ParseResult<ASTNodeViewElement> {
+impl Parser<ASTNodeViewElement> for ViewParser<'_, '_> {
+    fn parse_all(&mut self) -> ParseResult<ASTNodeViewElement> {
         loop {
             match self.advance() {
                 ViewParserResult::ParseError(err) => return Err(err),
                 ViewParserResult::Done => {
-                    return Ok(self.pending.take().expect("No pending result"));
+                    return self.pending.take().ok_or(ParseError::SyntaxError);
                 }
                 _ => {}
             }
@@ -50,58 +59,191 @@ impl<'a> Parser<ASTNodeViewElement> for ViewParser<'a> {
     }
 }
 
-impl<'a> ViewParser<'a> {
-    pub fn new(tokenizer: Rc<RefCell<Tokenizer<'a>>>) -> Self {
+impl<'a> TokenConsumer<'a> for ViewParser<'_, 'a> {
+    fn stream(&mut self) -> &mut TokenStream<'a> {
+        self.stream
+    }
+}
+
+impl<'s, 'a> ViewParser<'s, 'a> {
+    pub fn new(stream: &'s mut TokenStream<'a>) -> Self {
         ViewParser {
-            tokenizer,
-            state: RefCell::new(ViewParserState::default()),
-            pending: None.into(),
+            stream,
+            state: ViewParserState::default(),
+            pending: None,
         }
     }
 
-    fn parse_xml_tag(&self) -> ParseResult<ASTNodeViewElement> {
-        todo!()
+    /// Parses an element after its opening `<`, and wraps it in a node from
+    /// `checkpoint`.
+    fn parse_xml_tag(
+        &mut self,
+        start: ASTLoc,
+        checkpoint: Checkpoint,
+    ) -> ParseResult<ASTNodeViewElement> {
+        let element = self.parse_element(start)?;
+        self.wrap_node(checkpoint, SyntaxKind::Element);
+        Ok(element)
     }
 
-    fn set_pending_err(&self, err: ParseError) {
-        assert!(self
-            .state
-            .replace(ViewParserState::PendingParseError(err))
-            .is_ready());
+    fn parse_element(&mut self, start: ASTLoc) -> ParseResult<ASTNodeViewElement> {
+        let (name, _) = self.expect_identifier()?;
+        let anchor = match self.peek_content() {
+            Some(TokenContent::Anchor(anchor)) => {
+                self.consume_token_or_err()?;
+                Some(anchor.as_str().trim_start_matches('#').to_string())
+            }
+            _ => None,
+        };
+
+        let mut attributes = Vec::new();
+        loop {
+            let checkpoint = self.checkpoint();
+            let token = self.consume_token_or_err()?;
+            match token.con {
+                TokenContent::TagAngleSelfClosingRight => {
+                    return Ok(ASTNodeViewElement::new(
+                        start.to(token.loc.into()),
+                        name,
+                        anchor,
+                        attributes,
+                        Vec::new(),
+                    ));
+                }
+                TokenContent::TagAngleBracketRight => break,
+                TokenContent::Identifier(attr) => {
+                    attributes.push(self.parse_attribute(attr.to_string(), token.loc.into())?);
+                    self.wrap_node(checkpoint, SyntaxKind::Attribute);
+                }
+                // Attributes may be named like keywords, e.g. `type` or `for`.
+                con => {
+                    let Some(attr) = con.keyword() else {
+                        return Err(ParseError::UnexpectedToken);
+                    };
+                    attributes.push(self.parse_attribute(attr.to_string(), token.loc.into())?);
+                    self.wrap_node(checkpoint, SyntaxKind::Attribute);
+                }
+            }
+        }
+
+        let mut children = Vec::new();
+        loop {
+            let checkpoint = self.checkpoint();
+            let token = self.consume_token_or_err()?;
+            let loc = ASTLoc::from(token.loc);
+            match token.con {
+                TokenContent::TagAngleClosingLeft => {
+                    if let Some(TokenContent::Identifier(closing)) = self.peek_content() {
+                        if closing != name.as_str() {
+                            return Err(ParseError::SyntaxError);
+                        }
+                        self.consume_token_or_err()?;
+                    }
+                    let end = self.expect_token(&TokenContent::TagAngleBracketRight)?;
+                    return Ok(ASTNodeViewElement::new(
+                        start.to(end.loc.into()),
+                        name,
+                        anchor,
+                        attributes,
+                        children,
+                    ));
+                }
+                TokenContent::TagAngleBracketLeft => {
+                    let element = self.parse_xml_tag(loc, checkpoint)?;
+                    children.push(ASTNodeViewChild::Element(element));
+                }
+                TokenContent::Literal(literal @ TokenLiteral::StringLiteral(_)) => {
+                    self.wrap_node(checkpoint, SyntaxKind::Expr);
+                    let text = ASTNodeExpr::new(loc, ASTExprKind::Literal(literal));
+                    children.push(ASTNodeViewChild::Text(text));
+                }
+                TokenContent::BraceLeft => {
+                    let (expr, _) = self.parse_braced_expr()?;
+                    children.push(ASTNodeViewChild::Expr(expr));
+                }
+                _ => return Err(ParseError::UnexpectedToken),
+            }
+        }
     }
 
-    fn set_state_from_parse_result<T>(&self, res: ParseResult<T>) {
-        if let Err(err) = res {
-            self.set_pending_err(err);
+    /// Parses the optional value of an attribute whose name has been consumed.
+    fn parse_attribute(&mut self, name: String, loc: ASTLoc) -> ParseResult<ASTNodeViewAttribute> {
+        if self.consume_if(&TokenContent::AssignmentOp).is_none() {
+            return Ok(ASTNodeViewAttribute::new(loc, name, None));
         }
+
+        let checkpoint = self.checkpoint();
+        let token = self.consume_token_or_err()?;
+        let (value, end) = match token.con {
+            TokenContent::Literal(literal @ TokenLiteral::StringLiteral(_)) => {
+                self.wrap_node(checkpoint, SyntaxKind::Expr);
+                let loc = ASTLoc::from(token.loc);
+                (ASTNodeExpr::new(loc, ASTExprKind::Literal(literal)), loc)
+            }
+            TokenContent::BraceLeft => self.parse_braced_expr()?,
+            _ => return Err(ParseError::UnexpectedToken),
+        };
+
+        Ok(ASTNodeViewAttribute::new(loc.to(end), name, Some(value)))
     }
 
-    fn parse_token(&self, res: TokenResult) {
-        todo!()
+    /// Parses `expr }` after an opening brace, and returns the expression with
+    /// the location of the closing brace.
+    fn parse_braced_expr(&mut self) -> ParseResult<(ASTNodeExpr, ASTLoc)> {
+        let expr = ExprParser::new(self.stream()).parse_all()?;
+        let end = self.expect_token(&TokenContent::BraceRight)?;
+        Ok((expr, end.loc.into()))
     }
 
-    fn advance(&self) -> ViewParserResult {
+    fn set_pending_err(&mut self, err: ParseError) {
+        assert!(mem::replace(&mut self.state, ViewParserState::PendingParseError(err)).is_ready());
+    }
+
+    fn set_state_from_parse_result(&mut self, res: ParseResult<ASTNodeViewElement>) {
+        match res {
+            Ok(element) => {
+                self.pending = Some(element);
+                assert!(mem::replace(&mut self.state, ViewParserState::Eof).is_ready());
+            }
+            Err(err) => self.set_pending_err(err),
+        }
+    }
+
+    fn parse_token(&mut self, res: TokenResult, checkpoint: Checkpoint) {
+        match res {
+            Ok(token) => match token.con {
+                TokenContent::TagAngleBracketLeft => {
+                    let res = self.parse_xml_tag(token.loc.into(), checkpoint);
+                    self.set_state_from_parse_result(res);
+                }
+                _ => self.set_pending_err(ParseError::UnexpectedToken),
+            },
+            Err(err) => self.set_pending_err(ParseError::TokenizeError(err)),
+        }
+    }
+
+    fn advance(&mut self) -> ViewParserResult {
         type State = ViewParserState;
 
-        match self.state.take() {
+        match mem::take(&mut self.state) {
             State::Ready => {
+                let checkpoint = self.checkpoint();
                 match self.consume_token() {
-                    Some(tok) => assert!(self.state.replace(State::PendingToken(tok)).is_ready()),
-                    None => assert!(self.state.replace(State::EOF).is_ready()),
+                    Some(tok) => {
+                        let state = State::PendingToken(tok, checkpoint);
+                        assert!(mem::replace(&mut self.state, state).is_ready());
+                    }
+                    None => assert!(mem::replace(&mut self.state, State::Eof).is_ready()),
                 };
 
                 ViewParserResult::Continue
             }
-            State::PendingToken(tok) => {
-                self.parse_token(tok);
+            State::PendingToken(tok, checkpoint) => {
+                self.parse_token(tok, checkpoint);
                 ViewParserResult::Continue
             }
             State::PendingParseError(err) => ViewParserResult::ParseError(err),
-            State::EOF => ViewParserResult::Done,
+            State::Eof => ViewParserResult::Done,
         }
     }
-
-    fn consume_token(&self) -> Option<TokenResult> {
-        self.tokenizer.borrow_mut().next()
-    }
 }
@@ -0,0 +1,195 @@
+//! Parsing all modules of a project in parallel.
+//!
+//! Parsers own their token stream and hold no shared state, so every module
+//! is parsed on its own on a pool of worker threads. Results are returned in
+//! the order of the given paths regardless of which worker finished first.
+
+use std::{
+    num::NonZeroUsize,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+use crate::lang::{
+    ast::{ASTLoc, ASTNodeModule},
+    diagnostic::Diagnostic,
+    modules::{unreadable_module, FileLoader},
+    parser::{module::ModuleParser, Parser},
+    syntax::SyntaxNode,
+};
+
+/// A module parsed by [`parse_project`].
+#[derive(Debug, Clone)]
+pub struct ParsedFile {
+    pub path: String,
+    /// The module, unless it could not be loaded. A module which failed to
+    /// parse is empty.
+    pub ast: Option<ASTNodeModule>,
+    pub syntax: Option<SyntaxNode>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Loads and parses the modules at `paths` on one worker per available
+/// core, and returns them in the order of `paths`.
+pub fn parse_project<L, P>(loader: &L, paths: &[P]) -> Vec<ParsedFile>
+where
+    L: FileLoader + Sync,
+    P: AsRef<str> + Sync,
+{
+    let workers = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+    parse_project_with(loader, paths, workers)
+}
+
+/// Same as [`parse_project`] with a given number of worker threads.
+pub fn parse_project_with<L, P>(loader: &L, paths: &[P], workers: NonZeroUsize) -> Vec<ParsedFile>
+where
+    L: FileLoader + Sync,
+    P: AsRef<str> + Sync,
+{
+    let workers = workers.get().min(paths.len());
+    if workers <= 1 {
+        return paths
+            .iter()
+            .map(|path| parse_file(loader, path.as_ref()))
+            .collect();
+    }
+
+    // Workers take the next unparsed path until none is left, so a few large
+    // modules do not hold up the others.
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<Option<ParsedFile>> = vec![None; paths.len()];
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut parsed = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(path) = paths.get(index) else {
+                            return parsed;
+                        };
+                        parsed.push((index, parse_file(loader, path.as_ref())));
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            for (index, file) in handle.join().expect("parser worker panicked") {
+                results[index] = Some(file);
+            }
+        }
+    });
+
+    results
+        .into_iter()
+        .map(|file| file.expect("every path is parsed"))
+        .collect()
+}
+
+fn parse_file<L: FileLoader>(loader: &L, path: &str) -> ParsedFile {
+    let unloaded = |diagnostic| ParsedFile {
+        path: path.to_string(),
+        ast: None,
+        syntax: None,
+        diagnostics: vec![diagnostic],
+    };
+    let source = match loader.load(path) {
+        Ok(Some(source)) => source,
+        Ok(None) => {
+            return unloaded(Diagnostic::error(
+                "unresolved-module",
+                path,
+                ASTLoc { start: 0, end: 0 },
+                format!("cannot find module `{}`", path),
+            ))
+        }
+        Err(err) => return unloaded(unreadable_module(path, &err)),
+    };
+
+    let mut diagnostics = Vec::new();
+    let (ast, syntax) = parse_module(path, &source, &mut diagnostics);
+    ParsedFile {
+        path: path.to_string(),
+        ast: Some(ast),
+        syntax,
+        diagnostics,
+    }
+}
+
+/// Parses the source of the module at `path`, and returns it with its syntax
+/// tree. A module which fails to parse is reported and returned empty.
+pub(crate) fn parse_module(
+    path: &str,
+    source: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> (ASTNodeModule, Option<SyntaxNode>) {
+    let mut parser = ModuleParser::from_str(source);
+    let mut ast = match parser.parse_all() {
+        Ok(ast) => ast,
+        Err(err) => {
+            diagnostics.push(Diagnostic::error(
+                "parse-error",
+                path,
+                parser.error_loc(),
+                format!("failed to parse module: {}", err),
+            ));
+            ASTNodeModule::new(ASTLoc { start: 0, end: 0 }, String::new(), Vec::new())
+        }
+    };
+    ast.name = path.to_string();
+    (ast, parser.syntax())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lang::{
+        modules::MemoryFileLoader,
+        parser::{stream::TokenStream, view::ViewParser},
+    };
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn parsers_are_send_and_sync() {
+        assert_send_sync::<ModuleParser<'static>>();
+        assert_send_sync::<ViewParser<'static, 'static>>();
+        assert_send_sync::<TokenStream<'static>>();
+        assert_send_sync::<ParsedFile>();
+    }
+
+    #[test]
+    fn parses_files_in_order() {
+        let mut loader = MemoryFileLoader::new();
+        let mut paths = Vec::new();
+        for i in 0..40 {
+            let path = format!("m{i}.rbn");
+            loader.insert(path.clone(), format!("pub fn f{i}() {{ {i} }}"));
+            paths.push(path);
+        }
+        loader.insert("broken.rbn", "fn (");
+        paths.push("broken.rbn".to_string());
+        paths.push("missing.rbn".to_string());
+
+        let sequential = parse_project_with(&loader, &paths, NonZeroUsize::MIN);
+        let parallel = parse_project_with(&loader, &paths, NonZeroUsize::new(4).unwrap());
+        assert_eq!(parallel.len(), paths.len());
+        for ((path, a), b) in paths.iter().zip(&parallel).zip(&sequential) {
+            assert_eq!(&a.path, path);
+            assert_eq!(a.ast, b.ast);
+        }
+        let syntax = parallel[7].syntax.as_ref().unwrap();
+        assert_eq!(syntax.to_string(), "pub fn f7() { 7 }");
+
+        assert!(parallel[0].diagnostics.is_empty());
+        assert_eq!(parallel[40].diagnostics[0].code, "parse-error");
+        assert_eq!(parallel[40].diagnostics[0].loc, ASTLoc { start: 3, end: 4 });
+        assert_eq!(
+            parallel[40].diagnostics[0].message,
+            "failed to parse module: unexpected token"
+        );
+        assert_eq!(parallel[41].diagnostics[0].code, "unresolved-module");
+        assert!(parallel[41].ast.is_none());
+    }
+}
@@ -0,0 +1,587 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::lang::{
+    ast::{
+        ASTExprKind, ASTItemView, ASTLoc, ASTNodeAssign, ASTNodeEffectBlock, ASTNodeExpr,
+        ASTNodeScoped, ASTNodeStmt, ASTNodeType, ASTNodeViewChild, ASTNodeViewElement, Spanned,
+    },
+    diagnostic::Diagnostic,
+    node_index::{Ast, NodeId, NodeRef},
+    resolve::{DefId, Resolution},
+    visit::{walk_effect_block, walk_expr, Visitor},
+};
+
+/// How a name visible in a view body changes over time.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ReactiveKind {
+    /// A parameter of the view, set by its parent.
+    Prop,
+    /// A `let` binding of the view body.
+    State,
+    /// A `const` binding of the view body, recomputed from its dependencies.
+    Derived,
+}
+
+/// A prop, state or derived value of a view.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ReactiveBinding {
+    /// The definition of the value in the [`Resolution`] of the module.
+    pub def: DefId,
+    pub name: String,
+    pub kind: ReactiveKind,
+    pub loc: ASTLoc,
+    /// The props and state a derived value is computed from. Empty for props
+    /// and state.
+    pub deps: BTreeSet<DefId>,
+}
+
+/// The part of a view an expression belongs to.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum SiteKind {
+    /// The value of a derived `const`.
+    Derived(DefId),
+    /// The value of an attribute of an element.
+    Attribute { element: String, name: String },
+    /// An expression child of an element.
+    Child { element: String },
+    /// An `effect { .. }` block.
+    Effect,
+    /// Any other statement of the view body.
+    Stmt,
+}
+
+/// An expression of a view and the reactive values it depends on.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DependencySite {
+    pub kind: SiteKind,
+    pub loc: ASTLoc,
+    /// The props and state read by the expression, including those read
+    /// through derived values.
+    pub reads: BTreeSet<DefId>,
+    /// The state assigned by the expression.
+    pub writes: BTreeSet<DefId>,
+}
+
+/// The reactive bindings of a view and the dependencies of its expressions.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ViewDependencies {
+    pub view: String,
+    pub bindings: Vec<ReactiveBinding>,
+    pub sites: Vec<DependencySite>,
+}
+
+impl ViewDependencies {
+    pub fn binding(&self, def: DefId) -> Option<&ReactiveBinding> {
+        self.bindings.iter().find(|binding| binding.def == def)
+    }
+
+    /// Returns the sites to update when the prop or state `def` changes.
+    pub fn readers_of(&self, def: DefId) -> impl Iterator<Item = &DependencySite> {
+        self.sites
+            .iter()
+            .filter(move |site| site.reads.contains(&def))
+    }
+}
+
+/// The result of [`analyze_module`].
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ModuleReactivity {
+    pub views: Vec<ViewDependencies>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ModuleReactivity {
+    pub fn view(&self, name: &str) -> Option<&ViewDependencies> {
+        self.views.iter().find(|view| view.view == name)
+    }
+}
+
+/// Records which props and state each expression of the views of the module
+/// at `path` reads. `ast` indexes the module and `res` resolves it, so a name
+/// refers to a reactive value exactly when the resolver says it does.
+///
+/// Only the statements directly in a view body declare reactive values; the
+/// bindings of nested blocks are ordinary locals. The initial value of a
+/// state is computed once and is not tracked.
+pub fn analyze_module(path: &str, ast: &Ast, res: &Resolution) -> ModuleReactivity {
+    let NodeRef::Module(module) = ast.get(ast.root()) else {
+        unreachable!("the root of a node index is a module");
+    };
+    let decls: HashMap<NodeId, DefId> = res
+        .definition_ids()
+        .filter_map(|(id, def)| Some((def.node?, id)))
+        .collect();
+
+    let mut result = ModuleReactivity::default();
+    for node in &module.nodes {
+        if let ASTNodeScoped::View(view) = node {
+            let mut analyzer = ViewAnalyzer {
+                path,
+                ast,
+                res,
+                decls: &decls,
+                bindings: Vec::new(),
+                sites: Vec::new(),
+                diagnostics: Vec::new(),
+            };
+            analyzer.analyze(view);
+            result.diagnostics.append(&mut analyzer.diagnostics);
+            result.views.push(ViewDependencies {
+                view: view.name.clone(),
+                bindings: analyzer.bindings,
+                sites: analyzer.sites,
+            });
+        }
+    }
+    let mut placement = EffectPlacement {
+        path,
+        diagnostics: &mut result.diagnostics,
+    };
+    placement.visit_module(module);
+    result
+}
+
+fn effect_outside_view(path: &str, loc: ASTLoc) -> Diagnostic {
+    Diagnostic::error(
+        "effect-outside-view",
+        path,
+        loc,
+        "`effect` blocks are only allowed directly in a view body",
+    )
+}
+
+/// Reports the `effect` blocks anywhere but directly in a view body.
+struct EffectPlacement<'a> {
+    path: &'a str,
+    diagnostics: &'a mut Vec<Diagnostic>,
+}
+
+impl<'ast> Visitor<'ast> for EffectPlacement<'_> {
+    fn visit_view(&mut self, item: &'ast ASTItemView) {
+        for stmt in &item.body.stmts {
+            match stmt {
+                ASTNodeStmt::Effect(effect) => walk_effect_block(self, effect),
+                stmt => self.visit_stmt(stmt),
+            }
+        }
+    }
+
+    fn visit_effect_block(&mut self, stmt: &'ast ASTNodeEffectBlock) {
+        self.diagnostics
+            .push(effect_outside_view(self.path, stmt.loc()));
+        walk_effect_block(self, stmt);
+    }
+}
+
+/// The reads and writes collected for one [`DependencySite`].
+#[derive(Default)]
+struct Deps {
+    reads: BTreeSet<DefId>,
+    writes: BTreeSet<DefId>,
+}
+
+struct ViewAnalyzer<'a, 'ast> {
+    path: &'a str,
+    ast: &'a Ast<'ast>,
+    res: &'a Resolution,
+    /// The definition declared by each declaring node.
+    decls: &'a HashMap<NodeId, DefId>,
+    bindings: Vec<ReactiveBinding>,
+    sites: Vec<DependencySite>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a, 'ast> ViewAnalyzer<'a, 'ast> {
+    /// The definition declared by `node`.
+    fn decl(&self, node: impl Into<NodeRef<'ast>>) -> Option<DefId> {
+        let id = self.ast.id(node).expect("node of the analyzed module");
+        self.decls.get(&id).copied()
+    }
+
+    fn analyze(&mut self, view: &'ast ASTItemView) {
+        for param in &view.params {
+            self.bind(self.decl(param), ReactiveKind::Prop, BTreeSet::new());
+        }
+        for stmt in &view.body.stmts {
+            match stmt {
+                ASTNodeStmt::Let(stmt) => {
+                    // The initial value is not tracked, but its nested blocks
+                    // are still checked.
+                    self.collect(|c| c.visit_expr(&stmt.value));
+                    self.bind(self.decl(stmt), ReactiveKind::State, BTreeSet::new());
+                }
+                ASTNodeStmt::Const(item) => {
+                    let deps = self.collect(|c| c.visit_expr(&item.value));
+                    if let Some(def) = self.decl(item) {
+                        self.push_site(SiteKind::Derived(def), item.value.loc(), &deps);
+                    }
+                    self.bind(self.decl(item), ReactiveKind::Derived, deps.reads);
+                }
+                ASTNodeStmt::Effect(effect) => {
+                    let deps = self.collect(|c| c.visit_block(&effect.body));
+                    if deps.reads.is_empty() {
+                        self.diagnostics.push(Diagnostic::warning(
+                            "untracked-effect",
+                            self.path,
+                            effect.loc(),
+                            "this effect reads no state and runs only once",
+                        ));
+                    }
+                    self.push_site(SiteKind::Effect, effect.loc(), &deps);
+                }
+                ASTNodeStmt::Element(element) => self.analyze_element(element),
+                ASTNodeStmt::Assign(assign) => {
+                    let deps = self.collect(|c| c.visit_assign(assign));
+                    self.push_site(SiteKind::Stmt, assign.loc(), &deps);
+                }
+                ASTNodeStmt::Expr(expr) => {
+                    let deps = self.collect(|c| c.visit_expr(expr));
+                    self.push_site(SiteKind::Stmt, expr.loc(), &deps);
+                }
+                ASTNodeStmt::With(with) => {
+                    let deps = self.collect(|c| c.visit_with(with));
+                    self.push_site(SiteKind::Stmt, with.loc(), &deps);
+                }
+            }
+        }
+    }
+
+    fn analyze_element(&mut self, element: &'ast ASTNodeViewElement) {
+        for attribute in &element.attributes {
+            if let Some(value) = &attribute.value {
+                let deps = self.collect(|c| c.visit_expr(value));
+                let kind = SiteKind::Attribute {
+                    element: element.name.clone(),
+                    name: attribute.name.clone(),
+                };
+                self.push_site(kind, value.loc(), &deps);
+            }
+        }
+        for child in &element.children {
+            match child {
+                ASTNodeViewChild::Element(child) => self.analyze_element(child),
+                ASTNodeViewChild::Expr(expr) => {
+                    let deps = self.collect(|c| c.visit_expr(expr));
+                    let kind = SiteKind::Child {
+                        element: element.name.clone(),
+                    };
+                    self.push_site(kind, expr.loc(), &deps);
+                }
+                ASTNodeViewChild::Text(_) => {}
+            }
+        }
+    }
+
+    /// Records the value declared as `def`. A value the resolver rejected,
+    /// e.g. because its name is defined twice, has no definition and is not
+    /// recorded.
+    fn bind(&mut self, def: Option<DefId>, kind: ReactiveKind, deps: BTreeSet<DefId>) {
+        let Some(def) = def else {
+            return;
+        };
+        let definition = self.res.definition(def);
+        self.bindings.push(ReactiveBinding {
+            def,
+            name: definition.name.clone(),
+            kind,
+            loc: definition.loc,
+            deps,
+        });
+    }
+
+    fn push_site(&mut self, kind: SiteKind, loc: ASTLoc, deps: &Deps) {
+        self.sites.push(DependencySite {
+            kind,
+            loc,
+            reads: deps.reads.clone(),
+            writes: deps.writes.clone(),
+        });
+    }
+
+    /// Collects the reads and writes of what `walk` visits.
+    fn collect(&mut self, walk: impl FnOnce(&mut Collector<'_, 'a, 'ast>)) -> Deps {
+        let mut collector = Collector {
+            analyzer: self,
+            deps: Deps::default(),
+        };
+        walk(&mut collector);
+        collector.deps
+    }
+}
+
+/// Walks the expressions of one [`DependencySite`].
+struct Collector<'c, 'a, 'ast> {
+    analyzer: &'c mut ViewAnalyzer<'a, 'ast>,
+    deps: Deps,
+}
+
+impl<'ast> Collector<'_, '_, 'ast> {
+    /// Returns the reactive value the identifier `expr` refers to. A local
+    /// binding of the same name shadows it, as the resolver decided.
+    fn lookup(&self, expr: &'ast ASTNodeExpr) -> Option<ReactiveBinding> {
+        let analyzer = &self.analyzer;
+        let id = analyzer.ast.id(expr)?;
+        let def = *analyzer.res.uses.get(id)?;
+        analyzer
+            .bindings
+            .iter()
+            .find(|binding| binding.def == def)
+            .cloned()
+    }
+}
+
+impl<'ast> Visitor<'ast> for Collector<'_, '_, 'ast> {
+    fn visit_assign(&mut self, assign: &'ast ASTNodeAssign) {
+        self.visit_expr(&assign.value);
+        let mut target = &assign.target;
+        while let ASTExprKind::Member { object, .. } = &target.kind {
+            target = object;
+        }
+        if !std::ptr::eq(target, &assign.target) {
+            self.visit_expr(target);
+        }
+
+        let Some(binding) = self.lookup(target) else {
+            return;
+        };
+        let name = &binding.name;
+        let (code, message, label) = match binding.kind {
+            ReactiveKind::State => {
+                self.deps.writes.insert(binding.def);
+                return;
+            }
+            ReactiveKind::Derived => (
+                "assign-to-derived",
+                format!("cannot assign to derived value `{}`", name),
+                format!("`{}` is derived here", name),
+            ),
+            ReactiveKind::Prop => (
+                "assign-to-prop",
+                format!("cannot assign to prop `{}`", name),
+                format!("`{}` is a prop of the view", name),
+            ),
+        };
+        let path = self.analyzer.path;
+        let diagnostic = Diagnostic::error(code, path, assign.loc(), message).with_label(
+            path,
+            binding.loc,
+            label,
+        );
+        self.analyzer.diagnostics.push(diagnostic);
+    }
+
+    fn visit_expr(&mut self, expr: &'ast ASTNodeExpr) {
+        let ASTExprKind::Identifier(_) = &expr.kind else {
+            return walk_expr(self, expr);
+        };
+        let Some(binding) = self.lookup(expr) else {
+            return;
+        };
+        match binding.kind {
+            ReactiveKind::Derived => self.deps.reads.extend(binding.deps),
+            _ => {
+                self.deps.reads.insert(binding.def);
+            }
+        }
+    }
+
+    fn visit_type(&mut self, _ty: &'ast ASTNodeType) {}
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lang::resolve::test::with_resolution;
+
+    fn analyze(input: &str) -> ModuleReactivity {
+        with_resolution(input, |ast, res| analyze_module("main.rbn", ast, res))
+    }
+
+    fn def(view: &ViewDependencies, name: &str) -> DefId {
+        let binding = view.bindings.iter().find(|binding| binding.name == name);
+        binding.expect("no such binding").def
+    }
+
+    /// The sorted names of the bindings in `set`.
+    fn names<'v>(view: &'v ViewDependencies, set: &BTreeSet<DefId>) -> Vec<&'v str> {
+        let mut names: Vec<_> = set
+            .iter()
+            .map(|&def| view.binding(def).unwrap().name.as_str())
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    #[test]
+    fn records_bindings_and_reads() {
+        let result = analyze(
+            "view Counter(step: Int) {
+              let count = 0
+              let label = \"count\"
+              const doubled = count * 2
+              const next = doubled + step
+              <div title={label}>
+                <span>{next}</span>
+                \"static\"
+              </div>
+            }",
+        );
+        assert_eq!(result.diagnostics, []);
+        let view = result.view("Counter").unwrap();
+
+        let kinds: Vec<_> = view
+            .bindings
+            .iter()
+            .map(|b| (b.name.as_str(), b.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                ("step", ReactiveKind::Prop),
+                ("count", ReactiveKind::State),
+                ("label", ReactiveKind::State),
+                ("doubled", ReactiveKind::Derived),
+                ("next", ReactiveKind::Derived),
+            ]
+        );
+        assert_eq!(
+            names(view, &view.binding(def(view, "next")).unwrap().deps),
+            ["count", "step"]
+        );
+
+        let [doubled, next, title, span] = view.sites.as_slice() else {
+            panic!("unexpected sites {:?}", view.sites);
+        };
+        assert_eq!(doubled.kind, SiteKind::Derived(def(view, "doubled")));
+        assert_eq!(names(view, &next.reads), ["count", "step"]);
+        assert_eq!(
+            title.kind,
+            SiteKind::Attribute {
+                element: "div".into(),
+                name: "title".into()
+            }
+        );
+        assert_eq!(names(view, &title.reads), ["label"]);
+        assert_eq!(
+            span.kind,
+            SiteKind::Child {
+                element: "span".into()
+            }
+        );
+        assert_eq!(names(view, &span.reads), ["count", "step"]);
+
+        let readers: Vec<_> = view
+            .readers_of(def(view, "label"))
+            .map(|site| &site.kind)
+            .collect();
+        assert_eq!(readers, [&title.kind]);
+    }
+
+    #[test]
+    fn effect_blocks() {
+        let result = analyze(
+            "view Clock {
+              let time = 0
+              let ticks = 0
+              effect {
+                let time = now()
+                ticks = ticks + 1
+                print(time)
+              }
+              effect { print(\"mounted\") }
+            }",
+        );
+        let view = result.view("Clock").unwrap();
+        let [effect, once] = view.sites.as_slice() else {
+            panic!("unexpected sites {:?}", view.sites);
+        };
+        assert_eq!(effect.kind, SiteKind::Effect);
+        // The local `time` shadows the state of the same name.
+        assert_eq!(names(view, &effect.reads), ["ticks"]);
+        assert_eq!(names(view, &effect.writes), ["ticks"]);
+        assert!(once.reads.is_empty());
+
+        let codes: Vec<_> = result.diagnostics.iter().map(|d| d.code).collect();
+        assert_eq!(codes, ["untracked-effect"]);
+    }
+
+    #[test]
+    fn handler_bodies() {
+        let result = analyze(
+            "view Logger(start: Int) {
+              let count = start
+              with Log {
+                fn log(msg) {
+                  start = 1
+                  print(count)
+                }
+              }
+              with Log { fn log(count) { print(count) } }
+            }",
+        );
+        let codes: Vec<_> = result.diagnostics.iter().map(|d| d.code).collect();
+        assert_eq!(codes, ["assign-to-prop"]);
+
+        let view = result.view("Logger").unwrap();
+        let [handler, shadowed] = view.sites.as_slice() else {
+            panic!("unexpected sites {:?}", view.sites);
+        };
+        assert_eq!(handler.kind, SiteKind::Stmt);
+        assert_eq!(names(view, &handler.reads), ["count"]);
+        // The parameter `count` shadows the state of the same name.
+        assert!(shadowed.reads.is_empty());
+    }
+
+    #[test]
+    fn misplaced_effect_blocks() {
+        let input = "fn f() {
+              let a = if true { effect { } 1 } else { 2 }
+              const B = if true { effect { } 1 } else { 2 }
+              print(if true { effect { } 1 } else { 2 })
+              with Log { fn log(msg) { effect { } } }
+            }
+            const C = if true { effect { } 1 } else { 2 }
+            view V() {
+              effect { effect { } }
+              if true { effect { } }
+              <p>{if true { effect { } 1 } else { 2 }}</p>
+            }";
+        let result = analyze(input);
+        let lines: Vec<_> = result
+            .diagnostics
+            .iter()
+            .filter(|d| d.code == "effect-outside-view")
+            .map(|d| input[..d.loc.start as usize].lines().count())
+            .collect();
+        assert_eq!(lines, [2, 3, 4, 5, 7, 9, 10, 11]);
+    }
+
+    #[test]
+    fn invalid_assignments() {
+        let result = analyze(
+            "view Form(value: String) {
+              let draft = value
+              const empty = draft == \"\"
+              effect {
+                value = draft
+                empty = true
+                draft = value
+              }
+            }
+            fn reset() { effect { } }",
+        );
+        let codes: Vec<_> = result.diagnostics.iter().map(|d| d.code).collect();
+        assert_eq!(
+            codes,
+            ["assign-to-prop", "assign-to-derived", "effect-outside-view"]
+        );
+        assert_eq!(
+            result.diagnostics[0].message,
+            "cannot assign to prop `value`"
+        );
+        assert_eq!(
+            result.diagnostics[1].labels[0].message,
+            "`empty` is derived here"
+        );
+    }
+}
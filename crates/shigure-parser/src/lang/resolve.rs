@@ -0,0 +1,892 @@
+//! Name resolution.
+//!
+//! [`Resolver`] links every name used in a module to the definition it
+//! refers to. Values and types live in separate namespaces: an identifier in
+//! an expression or the name of a variant in a pattern is looked up among
+//! the values, the name of a named type among the types.
+//!
+//! Scopes nest as the code does. The module scope holds the items of the
+//! module, which are visible everywhere in it regardless of their order.
+//! Functions, views, handler operations and type declarations open a scope
+//! for their parameters, and blocks, `for` loops and `when` arms open one for
+//! the names they bind. A `let` or `const` in a block is visible from the
+//! statement after it.
+//!
+//! Anchors live in a namespace of their own. The anchors of the elements of
+//! a view or function are visible in all of its body, so `#input.focus()`
+//! may come before the `<input#input>` it refers to. An anchor must be
+//! unique in its view, so it cannot be defined twice nor inside a `for`
+//! loop, which renders its element once per item.
+//!
+//! ```
+//! use shigure_parser::lang::{
+//!     node_index::Ast,
+//!     parser::{module::ModuleParser, Parser},
+//!     resolve::{DefKind, Resolver},
+//! };
+//!
+//! let module = ModuleParser::from_str("fn f(x: Int) { let y = x\n y }").parse_all().unwrap();
+//! let ast = Ast::new(&module);
+//! let resolution = Resolver::new("main.rbn", &ast).resolve();
+//! assert!(resolution.diagnostics.is_empty());
+//!
+//! let y = ast.ids().filter(|&id| resolution.uses.contains(id)).last().unwrap();
+//! let def = resolution.resolved(y).unwrap();
+//! assert_eq!((def.name.as_str(), def.kind), ("y", DefKind::Let));
+//! ```
+
+use std::collections::HashMap;
+
+use crate::lang::{
+    ast::{
+        ASTExprKind, ASTItemConst, ASTItemEffect, ASTItemFn, ASTItemImport, ASTItemType,
+        ASTItemUse, ASTItemView, ASTLoc, ASTNodeBlock, ASTNodeExpr, ASTNodeHandlerOp, ASTNodeLet,
+        ASTNodeModule, ASTNodeParam, ASTNodePattern, ASTNodeScoped, ASTNodeType, ASTNodeTypeParam,
+        ASTNodeViewElement, ASTNodeWhenArm, ASTPatternKind, ASTTypeBody, ASTTypeKind, Spanned,
+    },
+    diagnostic::Diagnostic,
+    node_index::{Ast, NodeId, NodeRef, SideTable},
+    visit::{self, Visitor},
+};
+
+/// The types every module can use without declaring or importing them.
+pub const PRIMITIVE_TYPES: &[&str] = &["Bool", "Float", "Int", "String"];
+
+/// The values every module can use without declaring or importing them.
+pub const BUILTIN_VALUES: &[&str] = &["false", "true"];
+
+/// Whether an element named `name` renders a view rather than a builtin
+/// element, which is the case when its name is capitalized, e.g. `Counter`.
+pub fn is_component(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_uppercase())
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Namespace {
+    Value,
+    Type,
+    Anchor,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum DefKind {
+    Const,
+    Effect,
+    /// An operation of an effect, callable by its name.
+    EffectOp,
+    Fn,
+    Type,
+    TypeParam,
+    /// A variant of a union type, which constructs and matches its values.
+    Variant,
+    View,
+    /// A name bound by an `import`, which may be a value or a type.
+    Import,
+    /// The name bound by a `use`.
+    Use,
+    Param,
+    Let,
+    /// A name bound by the pattern of a `for` loop or a `when` arm.
+    Binding,
+    /// A primitive type, a builtin value or a global provided to the
+    /// resolver.
+    Builtin,
+    /// The anchor of an element, e.g. `#input` in `<input#input>`.
+    Anchor,
+}
+
+impl DefKind {
+    /// The namespaces a definition of this kind is visible in.
+    pub fn namespaces(self) -> &'static [Namespace] {
+        match self {
+            DefKind::Type | DefKind::TypeParam => &[Namespace::Type],
+            DefKind::Anchor => &[Namespace::Anchor],
+            DefKind::Effect | DefKind::Import | DefKind::Use => {
+                &[Namespace::Value, Namespace::Type]
+            }
+            _ => &[Namespace::Value],
+        }
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            DefKind::Const => "constant",
+            DefKind::Effect => "effect",
+            DefKind::EffectOp => "effect operation",
+            DefKind::Fn => "function",
+            DefKind::Type => "type",
+            DefKind::TypeParam => "type parameter",
+            DefKind::Variant => "variant",
+            DefKind::View => "view",
+            DefKind::Import => "import",
+            DefKind::Use => "use",
+            DefKind::Param => "parameter",
+            DefKind::Let => "binding",
+            DefKind::Binding => "binding",
+            DefKind::Builtin => "builtin",
+            DefKind::Anchor => "anchor",
+        }
+    }
+
+    /// The indefinite article of [`DefKind::describe`].
+    pub fn article(self) -> &'static str {
+        match self {
+            DefKind::Effect | DefKind::EffectOp | DefKind::Import | DefKind::Anchor => "an",
+            _ => "a",
+        }
+    }
+}
+
+#[derive(Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy, Debug)]
+pub struct DefId(u32);
+
+impl DefId {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+#[derive(Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy, Debug)]
+pub struct ScopeId(u32);
+
+impl ScopeId {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// A declared name.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Definition {
+    pub name: String,
+    pub kind: DefKind,
+    /// The declaring node, e.g. the `let` statement or the pattern binding
+    /// the name. Builtins have none.
+    pub node: Option<NodeId>,
+    pub loc: ASTLoc,
+    pub scope: ScopeId,
+    /// The definition of an enclosing scope hidden by this one, other than a
+    /// builtin. Reported by the `shadowed-name` lint.
+    pub shadows: Option<DefId>,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ScopeKind {
+    /// The primitive types, builtin values and globals, enclosing the module
+    /// scope.
+    Prelude,
+    Module,
+    Fn,
+    View,
+    /// The parameters of an operation of an effect or of a handler.
+    EffectOp,
+    /// The parameters of a type declaration.
+    Type,
+    Block,
+    For,
+    WhenArm,
+}
+
+#[derive(Debug, Clone)]
+pub struct Scope {
+    pub kind: ScopeKind,
+    pub parent: Option<ScopeId>,
+    /// The node opening the scope. The prelude has none.
+    pub node: Option<NodeId>,
+    names: HashMap<(Namespace, String), DefId>,
+}
+
+impl Scope {
+    /// The definition of `name` in this scope, not looking at its parents.
+    pub fn get(&self, ns: Namespace, name: &str) -> Option<DefId> {
+        self.names.get(&(ns, name.to_string())).copied()
+    }
+}
+
+/// The scopes and definitions of a module, and what each name refers to.
+#[derive(Debug, Clone)]
+pub struct Resolution {
+    pub scopes: Vec<Scope>,
+    pub definitions: Vec<Definition>,
+    /// The definition each identifier expression, anchor reference, named
+    /// type, variant pattern and component element refers to. Names which
+    /// could not be resolved have none.
+    pub uses: SideTable<DefId>,
+    /// The scope opened by each node which opens one.
+    pub node_scopes: SideTable<ScopeId>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Resolution {
+    pub fn definition(&self, id: DefId) -> &Definition {
+        &self.definitions[id.index()]
+    }
+
+    pub fn definition_ids(&self) -> impl Iterator<Item = (DefId, &Definition)> {
+        self.definitions
+            .iter()
+            .enumerate()
+            .map(|(idx, def)| (DefId(idx as u32), def))
+    }
+
+    pub fn scope(&self, id: ScopeId) -> &Scope {
+        &self.scopes[id.index()]
+    }
+
+    /// The definition the name used at `node` refers to.
+    pub fn resolved(&self, node: NodeId) -> Option<&Definition> {
+        self.uses.get(node).map(|&id| self.definition(id))
+    }
+
+    /// Looks `name` up from `scope` outwards.
+    pub fn lookup(&self, scope: ScopeId, ns: Namespace, name: &str) -> Option<DefId> {
+        let mut scope = Some(scope);
+        while let Some(id) = scope {
+            let current = self.scope(id);
+            if let Some(def) = current.get(ns, name) {
+                return Some(def);
+            }
+            scope = current.parent;
+        }
+        None
+    }
+}
+
+/// Builds the scopes of a module and resolves the names used in it.
+///
+/// Unresolved names and names defined twice in one scope are errors. A
+/// definition shadowing one of an enclosing scope is recorded in
+/// [`Definition::shadows`], unless the shadowed one is a builtin.
+pub struct Resolver<'a, 'ast> {
+    path: String,
+    ast: &'a Ast<'ast>,
+    globals: Vec<String>,
+    res: Resolution,
+    /// The scope being resolved.
+    current: ScopeId,
+}
+
+impl<'a, 'ast> Resolver<'a, 'ast> {
+    pub fn new(path: impl Into<String>, ast: &'a Ast<'ast>) -> Self {
+        Self {
+            path: path.into(),
+            ast,
+            globals: Vec::new(),
+            res: Resolution {
+                scopes: Vec::new(),
+                definitions: Vec::new(),
+                uses: SideTable::new(),
+                node_scopes: SideTable::new(),
+                diagnostics: Vec::new(),
+            },
+            current: ScopeId(0),
+        }
+    }
+
+    /// Declares values provided by the runtime, such as `print`, in the
+    /// prelude.
+    pub fn with_globals<S: Into<String>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.globals.extend(names.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn resolve(mut self) -> Resolution {
+        self.push_scope(ScopeKind::Prelude, None);
+        for name in PRIMITIVE_TYPES {
+            self.builtin(name, Namespace::Type);
+        }
+        for name in BUILTIN_VALUES {
+            self.builtin(name, Namespace::Value);
+        }
+        for name in std::mem::take(&mut self.globals) {
+            self.builtin(&name, Namespace::Value);
+        }
+
+        let NodeRef::Module(module) = self.ast.get(self.ast.root()) else {
+            unreachable!("the root of a node index is a module");
+        };
+        self.visit_module(module);
+        self.res
+    }
+
+    fn builtin(&mut self, name: &str, ns: Namespace) {
+        let id = DefId(self.res.definitions.len() as u32);
+        self.res.definitions.push(Definition {
+            name: name.to_string(),
+            kind: DefKind::Builtin,
+            node: None,
+            loc: ASTLoc::default(),
+            scope: self.current,
+            shadows: None,
+        });
+        self.res.scopes[self.current.index()]
+            .names
+            .insert((ns, name.to_string()), id);
+    }
+
+    fn id(&self, node: impl Into<NodeRef<'ast>>) -> NodeId {
+        self.ast.id(node).expect("node of the resolved module")
+    }
+
+    fn push_scope(&mut self, kind: ScopeKind, node: Option<NodeId>) {
+        let id = ScopeId(self.res.scopes.len() as u32);
+        let parent = (!self.res.scopes.is_empty()).then_some(self.current);
+        self.res.scopes.push(Scope {
+            kind,
+            parent,
+            node,
+            names: HashMap::new(),
+        });
+        if let Some(node) = node {
+            self.res.node_scopes.insert(node, id);
+        }
+        self.current = id;
+    }
+
+    fn pop_scope(&mut self) {
+        self.current = self.res.scopes[self.current.index()]
+            .parent
+            .expect("the prelude is never popped");
+    }
+
+    /// Runs `f` in a new scope opened by `node`.
+    fn scoped(&mut self, kind: ScopeKind, node: NodeId, f: impl FnOnce(&mut Self)) {
+        self.push_scope(kind, Some(node));
+        f(self);
+        self.pop_scope();
+    }
+
+    fn in_module_scope(&self) -> bool {
+        self.res.scopes[self.current.index()].kind == ScopeKind::Module
+    }
+
+    /// Defines `name` in the current scope.
+    fn define(&mut self, name: &str, kind: DefKind, node: NodeId, loc: ASTLoc) {
+        if name == "_" {
+            return;
+        }
+        let id = DefId(self.res.definitions.len() as u32);
+        self.res.definitions.push(Definition {
+            name: name.to_string(),
+            kind,
+            node: Some(node),
+            loc,
+            scope: self.current,
+            shadows: None,
+        });
+
+        let mut duplicate = None;
+        let mut shadowed = None;
+        for &ns in kind.namespaces() {
+            let scope = &self.res.scopes[self.current.index()];
+            if let Some(previous) = scope.get(ns, name) {
+                duplicate = duplicate.or(Some(previous));
+                continue;
+            }
+            if let Some(parent) = scope.parent {
+                let outer = self.res.lookup(parent, ns, name);
+                shadowed = shadowed
+                    .or(outer.filter(|&outer| self.res.definition(outer).kind != DefKind::Builtin));
+            }
+            self.res.scopes[self.current.index()]
+                .names
+                .insert((ns, name.to_string()), id);
+        }
+
+        if let Some(previous) = duplicate {
+            let previous = self.res.definition(previous).loc;
+            if kind == DefKind::Anchor {
+                let diagnostic = Diagnostic::error(
+                    "duplicate-anchor",
+                    &self.path,
+                    loc,
+                    format!("the anchor `#{}` is used by more than one element", name),
+                )
+                .with_label(&self.path, previous, "first used here");
+                self.res.diagnostics.push(diagnostic);
+                return;
+            }
+            self.res.diagnostics.push(
+                Diagnostic::error(
+                    "duplicate-definition",
+                    &self.path,
+                    loc,
+                    format!("`{}` is defined more than once", name),
+                )
+                .with_label(&self.path, previous, "first defined here"),
+            );
+        } else {
+            self.res.definitions[id.index()].shadows = shadowed;
+        }
+    }
+
+    /// Records the definition `name` refers to at `node`, reporting it if
+    /// there is none.
+    fn use_name(&mut self, name: &str, ns: Namespace, node: NodeId, loc: ASTLoc) {
+        if let Some(def) = self.res.lookup(self.current, ns, name) {
+            self.res.uses.insert(node, def);
+            return;
+        }
+        let what = match ns {
+            Namespace::Value => "value",
+            Namespace::Type => "type",
+            Namespace::Anchor => {
+                self.res.diagnostics.push(Diagnostic::error(
+                    "unresolved-anchor",
+                    &self.path,
+                    loc,
+                    format!("no element has the anchor `#{}`", name),
+                ));
+                return;
+            }
+        };
+        self.res.diagnostics.push(Diagnostic::error(
+            "unresolved-name",
+            &self.path,
+            loc,
+            format!("cannot find {} `{}` in this scope", what, name),
+        ));
+    }
+
+    /// Defines the anchors of the elements in the body of `item`, so they are
+    /// visible before the elements.
+    fn declare_anchors(&mut self, item: NodeId) {
+        let mut stack = vec![(item, false)];
+        while let Some((id, in_loop)) = stack.pop() {
+            let node = self.ast.get(id);
+            let in_loop = in_loop || is_for(node);
+            if let NodeRef::Element(element) = node {
+                if let Some(anchor) = &element.anchor {
+                    self.define(anchor, DefKind::Anchor, id, element.loc());
+                    if in_loop {
+                        let message = format!(
+                            "the anchor `#{}` would be used by an element for every item",
+                            anchor
+                        );
+                        self.res.diagnostics.push(Diagnostic::error(
+                            "anchor-in-loop",
+                            &self.path,
+                            element.loc(),
+                            message,
+                        ));
+                    }
+                }
+            }
+            // Children are pushed in reverse, so anchors are defined in
+            // source order.
+            stack.extend(
+                self.ast
+                    .children(id)
+                    .iter()
+                    .rev()
+                    .map(|&child| (child, in_loop)),
+            );
+        }
+    }
+
+    /// Defines the items of the module, so they are visible before their
+    /// declarations.
+    fn declare_items(&mut self, module: &'ast ASTNodeModule) {
+        for node in &module.nodes {
+            match node {
+                ASTNodeScoped::Const(item) => {
+                    self.define(&item.name, DefKind::Const, self.id(item), item.loc())
+                }
+                ASTNodeScoped::Effect(item) => {
+                    self.define(&item.name, DefKind::Effect, self.id(item), item.loc());
+                    for op in &item.ops {
+                        self.define(&op.name, DefKind::EffectOp, self.id(op), op.loc());
+                    }
+                }
+                ASTNodeScoped::Fn(item) => {
+                    self.define(&item.name, DefKind::Fn, self.id(item), item.loc())
+                }
+                ASTNodeScoped::Import(item) => {
+                    for name in &item.names {
+                        let id = self.id(name);
+                        self.define(name.local_name(), DefKind::Import, id, name.loc());
+                    }
+                }
+                ASTNodeScoped::Type(item) => {
+                    self.define(&item.name, DefKind::Type, self.id(item), item.loc());
+                    if let ASTTypeBody::Union(variants) = &item.body {
+                        for variant in variants {
+                            let id = self.id(variant);
+                            self.define(&variant.name, DefKind::Variant, id, variant.loc());
+                        }
+                    }
+                }
+                ASTNodeScoped::Use(item) => {
+                    self.define(item.local_name(), DefKind::Use, self.id(item), item.loc())
+                }
+                ASTNodeScoped::View(item) => {
+                    self.define(&item.name, DefKind::View, self.id(item), item.loc())
+                }
+            }
+        }
+    }
+}
+
+impl<'ast> Visitor<'ast> for Resolver<'_, 'ast> {
+    fn visit_module(&mut self, module: &'ast ASTNodeModule) {
+        self.scoped(ScopeKind::Module, self.id(module), |r| {
+            r.declare_items(module);
+            visit::walk_module(r, module);
+        });
+    }
+
+    fn visit_const(&mut self, item: &'ast ASTItemConst) {
+        visit::walk_const(self, item);
+        if !self.in_module_scope() {
+            self.define(&item.name, DefKind::Const, self.id(item), item.loc());
+        }
+    }
+
+    fn visit_effect(&mut self, item: &'ast ASTItemEffect) {
+        for op in &item.ops {
+            self.scoped(ScopeKind::EffectOp, self.id(op), |r| r.visit_effect_op(op));
+        }
+    }
+
+    fn visit_fn(&mut self, item: &'ast ASTItemFn) {
+        self.scoped(ScopeKind::Fn, self.id(item), |r| {
+            r.declare_anchors(r.id(item));
+            visit::walk_fn(r, item)
+        });
+    }
+
+    fn visit_import(&mut self, _item: &'ast ASTItemImport) {}
+
+    fn visit_use(&mut self, _item: &'ast ASTItemUse) {}
+
+    fn visit_type_item(&mut self, item: &'ast ASTItemType) {
+        self.scoped(ScopeKind::Type, self.id(item), |r| {
+            visit::walk_type_item(r, item)
+        });
+    }
+
+    fn visit_type_param(&mut self, param: &'ast ASTNodeTypeParam) {
+        self.define(&param.name, DefKind::TypeParam, self.id(param), param.loc());
+    }
+
+    fn visit_view(&mut self, item: &'ast ASTItemView) {
+        self.scoped(ScopeKind::View, self.id(item), |r| {
+            r.declare_anchors(r.id(item));
+            visit::walk_view(r, item)
+        });
+    }
+
+    fn visit_param(&mut self, param: &'ast ASTNodeParam) {
+        visit::walk_param(self, param);
+        self.define(&param.name, DefKind::Param, self.id(param), param.loc());
+    }
+
+    fn visit_block(&mut self, block: &'ast ASTNodeBlock) {
+        self.scoped(ScopeKind::Block, self.id(block), |r| {
+            visit::walk_block(r, block)
+        });
+    }
+
+    fn visit_let(&mut self, stmt: &'ast ASTNodeLet) {
+        visit::walk_let(self, stmt);
+        self.define(&stmt.name, DefKind::Let, self.id(stmt), stmt.loc());
+    }
+
+    fn visit_element(&mut self, element: &'ast ASTNodeViewElement) {
+        if is_component(&element.name) {
+            let id = self.id(element);
+            match self
+                .res
+                .lookup(self.current, Namespace::Value, &element.name)
+            {
+                Some(def) => {
+                    self.res.uses.insert(id, def);
+                }
+                None => self.res.diagnostics.push(Diagnostic::error(
+                    "unresolved-name",
+                    &self.path,
+                    element.loc(),
+                    format!("cannot find view `{}` in this scope", element.name),
+                )),
+            }
+        }
+        visit::walk_element(self, element);
+    }
+
+    fn visit_handler_op(&mut self, op: &'ast ASTNodeHandlerOp) {
+        self.scoped(ScopeKind::EffectOp, self.id(op), |r| {
+            visit::walk_handler_op(r, op)
+        });
+    }
+
+    fn visit_expr(&mut self, expr: &'ast ASTNodeExpr) {
+        match &expr.kind {
+            ASTExprKind::Identifier(name) => {
+                self.use_name(name, Namespace::Value, self.id(expr), expr.loc())
+            }
+            ASTExprKind::Anchor(name) => {
+                self.use_name(name, Namespace::Anchor, self.id(expr), expr.loc())
+            }
+            ASTExprKind::For {
+                pattern,
+                iter,
+                body,
+            } => {
+                self.visit_expr(iter);
+                self.scoped(ScopeKind::For, self.id(expr), |r| {
+                    r.visit_pattern(pattern);
+                    r.visit_block(body);
+                });
+            }
+            _ => visit::walk_expr(self, expr),
+        }
+    }
+
+    fn visit_when_arm(&mut self, arm: &'ast ASTNodeWhenArm) {
+        self.scoped(ScopeKind::WhenArm, self.id(arm), |r| {
+            visit::walk_when_arm(r, arm)
+        });
+    }
+
+    fn visit_pattern(&mut self, pattern: &'ast ASTNodePattern) {
+        match &pattern.kind {
+            ASTPatternKind::Binding(name) => {
+                self.define(name, DefKind::Binding, self.id(pattern), pattern.loc())
+            }
+            ASTPatternKind::Variant { name, .. } => {
+                let id = self.id(pattern);
+                self.use_name(name, Namespace::Value, id, pattern.loc());
+            }
+            _ => {}
+        }
+        visit::walk_pattern(self, pattern);
+    }
+
+    fn visit_type(&mut self, ty: &'ast ASTNodeType) {
+        if let ASTTypeKind::Named { name, .. } = &ty.kind {
+            self.use_name(name, Namespace::Type, self.id(ty), ty.loc());
+        }
+        visit::walk_type(self, ty);
+    }
+}
+
+fn is_for(node: NodeRef) -> bool {
+    matches!(node, NodeRef::Expr(expr) if matches!(expr.kind, ASTExprKind::For { .. }))
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use crate::lang::parser::{module::ModuleParser, Parser};
+
+    /// Parses `source` as the module `main.rbn`, resolves it with the global
+    /// `print`, and passes the indexed module and its resolution to `f`.
+    pub(crate) fn with_resolution<T>(source: &str, f: impl FnOnce(&Ast, &Resolution) -> T) -> T {
+        let module = ModuleParser::from_str(source)
+            .parse_all()
+            .expect("failed to parse");
+        let ast = Ast::new(&module);
+        let resolution = Resolver::new("main.rbn", &ast)
+            .with_globals(["print"])
+            .resolve();
+        f(&ast, &resolution)
+    }
+
+    fn resolve(source: &str) -> Vec<Diagnostic> {
+        with_resolution(source, |_, resolution| resolution.diagnostics.clone())
+    }
+
+    fn codes(diagnostics: &[Diagnostic]) -> Vec<(&str, &str)> {
+        diagnostics
+            .iter()
+            .map(|d| (d.code, d.message.split('`').nth(1).unwrap_or_default()))
+            .collect()
+    }
+
+    #[test]
+    fn resolves_nested_scopes() {
+        let source = "import { Item } from \"./item\"
+type Shape = Circle(Float) | Rect(Float, Float)
+effect Log { fn log(msg: String) }
+fn area(shape: Shape) -> Float emits Log {
+  log(\"area\")
+  when shape {
+    Circle(r) -> r * r
+    Rect(w, h) -> w * h
+  }
+}
+view List(items: [Item]) {
+  let total = 0
+  for item in items {
+    print(item)
+  }
+  <p>{total}</p>
+}
+const AREA = area(Circle(1.0))
+";
+        with_resolution(source, |ast, resolution| {
+            assert_eq!(resolution.diagnostics, []);
+
+            let uses: Vec<_> = ast
+                .ids()
+                .filter_map(|id| resolution.resolved(id))
+                .map(|def| (def.name.as_str(), def.kind))
+                .collect();
+            assert_eq!(
+                uses,
+                [
+                    ("Float", DefKind::Builtin),
+                    ("Float", DefKind::Builtin),
+                    ("Float", DefKind::Builtin),
+                    ("String", DefKind::Builtin),
+                    ("Shape", DefKind::Type),
+                    ("Float", DefKind::Builtin),
+                    ("Log", DefKind::Effect),
+                    ("log", DefKind::EffectOp),
+                    ("shape", DefKind::Param),
+                    ("Circle", DefKind::Variant),
+                    ("r", DefKind::Binding),
+                    ("r", DefKind::Binding),
+                    ("Rect", DefKind::Variant),
+                    ("w", DefKind::Binding),
+                    ("h", DefKind::Binding),
+                    ("Item", DefKind::Import),
+                    ("items", DefKind::Param),
+                    ("print", DefKind::Builtin),
+                    ("item", DefKind::Binding),
+                    ("total", DefKind::Let),
+                    ("area", DefKind::Fn),
+                    ("Circle", DefKind::Variant),
+                ]
+            );
+
+            let for_loop = ast
+            .ids()
+            .find(|&id| matches!(ast.get(id), NodeRef::Expr(e) if matches!(e.kind, ASTExprKind::For { .. })))
+            .unwrap();
+            let scope = resolution.node_scopes[for_loop];
+            assert_eq!(resolution.scope(scope).kind, ScopeKind::For);
+            let item = resolution.lookup(scope, Namespace::Value, "item").unwrap();
+            assert_eq!(resolution.definition(item).kind, DefKind::Binding);
+            assert!(resolution.lookup(scope, Namespace::Type, "item").is_none());
+        });
+    }
+
+    #[test]
+    fn resolves_type_params() {
+        let source = "fn first<T>(items: [T]) -> T? { let x: T? = nil\n x }
+view List<T>(items: [T]) {}
+type Pair<T> = (T, U)";
+        with_resolution(source, |ast, resolution| {
+            let params: Vec<_> = ast
+                .ids()
+                .filter_map(|id| resolution.resolved(id))
+                .filter(|def| def.kind == DefKind::TypeParam)
+                .map(|def| def.loc)
+                .collect();
+            assert_eq!(
+                params,
+                [
+                    ASTLoc { start: 9, end: 10 },
+                    ASTLoc { start: 9, end: 10 },
+                    ASTLoc { start: 9, end: 10 },
+                    ASTLoc { start: 63, end: 64 },
+                    ASTLoc { start: 91, end: 92 },
+                ]
+            );
+            assert_eq!(codes(&resolution.diagnostics), [("unresolved-name", "U")]);
+        });
+    }
+
+    #[test]
+    fn reports_unresolved_names() {
+        let diagnostics = resolve(
+            "fn f(x: Count) {
+  let y = y + x
+  when x { Missing(z) -> z }
+  for i in [1] { }
+  i
+  <Missing><div /></Missing>
+}",
+        );
+        assert_eq!(
+            codes(&diagnostics),
+            [
+                ("unresolved-name", "Count"),
+                ("unresolved-name", "y"),
+                ("unresolved-name", "Missing"),
+                ("unresolved-name", "i"),
+                ("unresolved-name", "Missing"),
+            ]
+        );
+        assert_eq!(
+            diagnostics[1].loc,
+            ASTLoc { start: 27, end: 28 },
+            "the use of `y` before its definition"
+        );
+    }
+
+    #[test]
+    fn reports_duplicates() {
+        let diagnostics = resolve(
+            "fn a() { 1 }
+view a() { }
+type T<U, U> = U
+fn b(x, x) {
+  let b = 1
+  for (y, y) in [] { }
+  when x { print -> 1 }
+}",
+        );
+        assert_eq!(
+            codes(&diagnostics),
+            [
+                ("duplicate-definition", "a"),
+                ("duplicate-definition", "U"),
+                ("duplicate-definition", "x"),
+                ("duplicate-definition", "y"),
+            ]
+        );
+        assert!(diagnostics[0].is_error());
+        assert_eq!(diagnostics[0].labels[0].loc, ASTLoc { start: 0, end: 12 });
+    }
+
+    #[test]
+    fn resolves_anchors() {
+        let source = "view Form() {
+  effect { #name.focus() }
+  <form#form>
+    <input#name />
+    <input#name />
+  </form>
+  for item in [] { <li#item>{item}</li> }
+  <button onclick={#missing.click}>\"Go\"</button>
+}
+fn other() { #form }";
+        let diagnostics = resolve(source);
+        assert_eq!(
+            codes(&diagnostics),
+            [
+                ("duplicate-anchor", "#name"),
+                ("anchor-in-loop", "#item"),
+                ("unresolved-anchor", "#missing"),
+                ("unresolved-anchor", "#form"),
+            ]
+        );
+        let first = source.find("<input#name").unwrap() as u32;
+        assert_eq!(diagnostics[0].labels[0].loc.start, first);
+
+        with_resolution(source, |ast, resolution| {
+            let focus = ast
+                .ids()
+                .find(|&id| match ast.get(id) {
+                    NodeRef::Expr(expr) => expr.kind == ASTExprKind::Anchor("name".into()),
+                    _ => false,
+                })
+                .unwrap();
+            let def = resolution.resolved(focus).unwrap();
+            assert_eq!(def.kind, DefKind::Anchor);
+            assert_eq!(def.loc.start, first);
+        });
+    }
+}
@@ -0,0 +1,229 @@
+//! The versioned serialized form of tokens and ASTs, available with the
+//! `serde` feature.
+//!
+//! Tokens and AST nodes serialize with the names of their Rust fields and
+//! variants. Structs become maps, unit variants become strings, and other
+//! variants become a map with the variant name as the only key, so
+//! `const A = 1` serializes to JSON as
+//!
+//! ```json
+//! {
+//!   "version": 5,
+//!   "data": {
+//!     "loc": { "start": 0, "end": 11 },
+//!     "name": "",
+//!     "nodes": [
+//!       {
+//!         "Const": {
+//!           "loc": { "start": 0, "end": 11 },
+//!           "attributes": [],
+//!           "visibility": "Private",
+//!           "name": "A",
+//!           "ty": null,
+//!           "value": {
+//!             "loc": { "start": 10, "end": 11 },
+//!             "kind": { "Literal": { "NumberLiteral": "1" } }
+//!           }
+//!         }
+//!       }
+//!     ]
+//!   }
+//! }
+//! ```
+//!
+//! Wrap serialized values in a [`Document`], which records the
+//! [`SCHEMA_VERSION`] they were written with.
+
+use serde::{Deserialize, Serialize};
+
+/// The version of the serialized form. It is incremented whenever a token or
+/// a node serializes differently, e.g. when a field or variant is added,
+/// renamed or removed.
+///
+/// | Version | Changes |
+/// |---------|---------|
+/// | 1 | The initial form. `For` and `When` expressions, patterns and the `in` keyword were added to it later without a bump. |
+/// | 2 | `is_const` on fns. |
+/// | 3 | The `Anchor` expression. |
+/// | 4 | Item attributes. |
+/// | 5 | Type parameters on fns and views, and bounds on type parameters. |
+///
+/// Every version so far only added fields and variants, and the added fields
+/// default to their empty value when missing, so documents written with any
+/// earlier version still read.
+pub const SCHEMA_VERSION: u32 = 5;
+
+/// A serialized value together with the schema version it was written with.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct Document<T> {
+    pub version: u32,
+    pub data: T,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SchemaError {
+    /// The document was written with a newer version of the schema, or with
+    /// none.
+    UnsupportedVersion(u32),
+}
+
+impl<T> Document<T> {
+    /// Wraps `data` with the current schema version.
+    pub fn new(data: T) -> Self {
+        Self {
+            version: SCHEMA_VERSION,
+            data,
+        }
+    }
+
+    /// Returns the data if the document was written with the current schema
+    /// version or an earlier one.
+    pub fn into_data(self) -> Result<T, SchemaError> {
+        match self.version {
+            1..=SCHEMA_VERSION => Ok(self.data),
+            version => Err(SchemaError::UnsupportedVersion(version)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lang::{
+        ast::{ASTNodeModule, ASTNodeScoped, Token},
+        parser::{module::ModuleParser, Parser},
+        tokenizer::{TokenResult, Tokenizer},
+    };
+
+    const SOURCE: &str = "import { Button as B } from \"./button\"
+use std::list as l
+
+type Shape<T: Num> = Circle(T) | Rect({ w: T, h: T })
+type Point = { x: Float, y: Float? }
+
+effect Log {
+  fn log(msg: String) -> ()
+}
+
+pub fn area(shape: Shape<Float>, f: fn(Int) -> Int emits Log) -> Float emits Log {
+  log(\"area\")
+  if !done { [1, 2.5] } else { (shape.w * -2, nil) }
+}
+
+pub view Counter(start: Int) {
+  const STEP = 1
+  let count: Int = start
+  with Log {
+    fn log(msg: String) { print(msg) }
+  }
+  effect { log(count) }
+  count = count + STEP
+  <button#inc onclick={increment} disabled>\"Add\" {count}</button>
+}
+";
+
+    fn round_trip<T>(value: &T) -> T
+    where
+        T: Serialize + for<'de> Deserialize<'de>,
+    {
+        let json = serde_json::to_string(&Document::new(value)).unwrap();
+        let document: Document<T> = serde_json::from_str(&json).unwrap();
+        document.into_data().unwrap()
+    }
+
+    #[test]
+    fn round_trips_module() {
+        let module = ModuleParser::from_str(SOURCE).parse_all().unwrap();
+        assert_eq!(round_trip(&module), module);
+    }
+
+    #[test]
+    fn round_trips_tokens() {
+        let tokens: Vec<TokenResult> = Tokenizer::new(SOURCE).collect();
+        assert!(tokens.len() > 100);
+        assert_eq!(round_trip(&tokens), tokens);
+
+        let tokens: Vec<Token> = tokens.into_iter().map(Result::unwrap).collect();
+        assert_eq!(round_trip(&tokens), tokens);
+    }
+
+    #[test]
+    fn stable_schema() {
+        let module = ModuleParser::from_str("const A = 1").parse_all().unwrap();
+        let expected = serde_json::json!({
+            "version": 5,
+            "data": {
+                "loc": { "start": 0, "end": 11 },
+                "name": "",
+                "nodes": [{
+                    "Const": {
+                        "loc": { "start": 0, "end": 11 },
+                        "attributes": [],
+                        "visibility": "Private",
+                        "name": "A",
+                        "ty": null,
+                        "value": {
+                            "loc": { "start": 10, "end": 11 },
+                            "kind": { "Literal": { "NumberLiteral": "1" } }
+                        }
+                    }
+                }]
+            }
+        });
+        assert_eq!(
+            serde_json::to_value(Document::new(&module)).unwrap(),
+            expected
+        );
+
+        let token = serde_json::json!({
+            "loc": { "starts_at": 0, "len": 5 },
+            "con": { "Identifier": "count" }
+        });
+        let token: Token = serde_json::from_value(token).unwrap();
+        assert_eq!(Tokenizer::new("count").next(), Some(Ok(token)));
+    }
+
+    #[test]
+    fn reads_earlier_versions() {
+        let json = r#"{
+            "version": 1,
+            "data": {
+                "loc": { "start": 0, "end": 29 },
+                "name": "",
+                "nodes": [{
+                    "Fn": {
+                        "loc": { "start": 0, "end": 29 },
+                        "visibility": "Private",
+                        "name": "id",
+                        "params": [],
+                        "ret": null,
+                        "effects": [],
+                        "body": { "loc": { "start": 8, "end": 29 }, "stmts": [] }
+                    }
+                }]
+            }
+        }"#;
+        let document: Document<ASTNodeModule> = serde_json::from_str(json).unwrap();
+        let module = document.into_data().unwrap();
+        let ASTNodeScoped::Fn(item) = &module.nodes[0] else {
+            panic!("expected a fn, got {:?}", module.nodes[0]);
+        };
+        assert!(item.attributes.is_empty());
+        assert!(!item.is_const);
+        assert!(item.type_params.is_empty());
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        for version in [0, SCHEMA_VERSION + 1] {
+            let json = format!(
+                r#"{{ "version": {version}, "data": {{ "loc": {{ "start": 0, "end": 0 }}, "name": "", "nodes": [] }} }}"#
+            );
+            let document: Document<ASTNodeModule> = serde_json::from_str(&json).unwrap();
+            assert_eq!(
+                document.into_data(),
+                Err(SchemaError::UnsupportedVersion(version))
+            );
+        }
+    }
+}
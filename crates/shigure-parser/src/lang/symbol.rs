@@ -0,0 +1,203 @@
+//! Interned strings.
+//!
+//! Tokens refer to their text through a [`Symbol`], a handle into a global
+//! interner. Interning a string which was interned before does not allocate,
+//! so tokenizing a source allocates once per distinct name or literal rather
+//! than once per token, and symbols are copied and compared as integers.
+//!
+//! Reading the string of a symbol takes no lock, so the parallel parse
+//! workers do not wait on each other to print or compare names. Interning
+//! only takes a write lock for strings seen for the first time.
+//!
+//! Interned strings are never freed, so the interner grows with the number
+//! of distinct strings, not with the number of sources parsed. They are
+//! copied into large shared chunks rather than allocated one by one.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{LazyLock, Mutex, OnceLock, RwLock},
+};
+
+/// A string interned in the global interner.
+#[derive(Eq, PartialEq, Hash, Clone, Copy)]
+pub struct Symbol(u32);
+
+/// The number of strings in the first segment of [`STRINGS`]. Each further
+/// segment is twice as large as the one before.
+const FIRST_SEGMENT: usize = 1024;
+
+/// Enough segments for every `u32` symbol.
+const SEGMENTS: usize = 23;
+
+/// The size of a chunk interned strings are copied into.
+const CHUNK: usize = 64 * 1024;
+
+/// The string of each symbol, by index. Segments are allocated once and
+/// never move, and each entry is written once, so reads need no lock.
+static STRINGS: [OnceLock<Box<[OnceLock<&'static str>]>>; SEGMENTS] =
+    [const { OnceLock::new() }; SEGMENTS];
+
+/// The symbol of each interned string.
+static IDS: LazyLock<RwLock<HashMap<&'static str, Symbol>>> = LazyLock::new(Default::default);
+
+/// The unused end of the chunk strings are currently copied into.
+static ARENA: Mutex<&'static mut [u8]> = Mutex::new(&mut []);
+
+/// The segment holding `index`, and the offset of `index` in it.
+fn slot(index: usize) -> (usize, usize) {
+    let segment = (index / FIRST_SEGMENT + 1).ilog2() as usize;
+    (segment, index - FIRST_SEGMENT * ((1 << segment) - 1))
+}
+
+/// Copies `string` into the arena.
+fn alloc(string: &str) -> &'static str {
+    let len = string.len();
+    let mut arena = ARENA.lock().unwrap();
+    let bytes: &'static mut [u8] = if len > CHUNK / 4 {
+        Box::leak(string.as_bytes().into())
+    } else {
+        if arena.len() < len {
+            *arena = Box::leak(vec![0; CHUNK].into_boxed_slice());
+        }
+        let (bytes, rest) = std::mem::take(&mut *arena).split_at_mut(len);
+        *arena = rest;
+        bytes.copy_from_slice(string.as_bytes());
+        bytes
+    };
+    std::str::from_utf8(bytes).expect("copied from a string")
+}
+
+impl Symbol {
+    /// Returns the symbol of `string`, interning it on first use.
+    pub fn intern(string: &str) -> Symbol {
+        if let Some(&symbol) = IDS.read().unwrap().get(string) {
+            return symbol;
+        }
+
+        let mut ids = IDS.write().unwrap();
+        if let Some(&symbol) = ids.get(string) {
+            return symbol;
+        }
+        let string = alloc(string);
+        let index = ids.len();
+        let (segment, offset) = slot(index);
+        let strings = STRINGS[segment].get_or_init(|| {
+            (0..FIRST_SEGMENT << segment)
+                .map(|_| OnceLock::new())
+                .collect()
+        });
+        strings[offset]
+            .set(string)
+            .expect("symbols are assigned once");
+        let symbol = Symbol(index as u32);
+        ids.insert(string, symbol);
+        symbol
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        let (segment, offset) = slot(self.0 as usize);
+        STRINGS[segment]
+            .get()
+            .and_then(|strings| strings[offset].get())
+            .expect("symbols are interned before use")
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(string: &str) -> Self {
+        Symbol::intern(string)
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(string: String) -> Self {
+        Symbol::intern(&string)
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Symbols serialize as their string, so serialized data does not depend on
+/// the order strings were interned in.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Symbol {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Symbol {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let string = String::deserialize(deserializer)?;
+        Ok(Symbol::intern(&string))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn interns_once() {
+        let a = Symbol::intern("interned-name");
+        let b = Symbol::intern(&String::from("interned-name"));
+        assert_eq!(a, b);
+        assert_eq!(a.as_str(), "interned-name");
+        assert!(std::ptr::eq(a.as_str(), b.as_str()));
+
+        let c = Symbol::intern("another-name");
+        assert_ne!(a, c);
+        assert_eq!(c, "another-name");
+        assert_eq!(format!("{c} {c:?}"), "another-name \"another-name\"");
+    }
+
+    #[test]
+    fn slots() {
+        assert_eq!(slot(0), (0, 0));
+        assert_eq!(slot(FIRST_SEGMENT - 1), (0, FIRST_SEGMENT - 1));
+        assert_eq!(slot(FIRST_SEGMENT), (1, 0));
+        assert_eq!(slot(3 * FIRST_SEGMENT), (2, 0));
+        assert_eq!(slot(u32::MAX as usize).0, SEGMENTS - 1);
+    }
+
+    #[test]
+    fn interns_across_threads() {
+        let names: Vec<_> = (0..2000).map(|n| format!("thread-name-{n}")).collect();
+        let symbols: Vec<Vec<Symbol>> = std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..4)
+                .map(|_| scope.spawn(|| names.iter().map(|n| Symbol::intern(n)).collect()))
+                .collect();
+            workers.into_iter().map(|w| w.join().unwrap()).collect()
+        });
+        for worker in &symbols {
+            assert_eq!(worker, &symbols[0]);
+        }
+        for (symbol, name) in symbols[0].iter().zip(&names) {
+            assert_eq!(symbol.as_str(), name);
+        }
+    }
+}
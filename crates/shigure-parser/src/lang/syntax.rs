@@ -0,0 +1,586 @@
+use std::{fmt, sync::Arc};
+
+use crate::lang::{
+    ast::{ASTLoc, TokenContent},
+    symbol::Symbol,
+};
+
+/// The kind of a node of the concrete syntax tree.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum SyntaxKind {
+    Module,
+    /// `@name(args)` before an item, inside the node of the item.
+    ItemAttribute,
+    Import,
+    ImportName,
+    Use,
+    TypeItem,
+    TypeParam,
+    Variant,
+    TypeField,
+    /// Any type expression, including the ones nested in another type.
+    Type,
+    Fn,
+    ParamList,
+    Param,
+    Effect,
+    EffectOp,
+    View,
+    Const,
+    Block,
+    Let,
+    With,
+    HandlerOp,
+    EffectBlock,
+    Assign,
+    Element,
+    Attribute,
+    /// Any expression, including the operands of another expression.
+    Expr,
+    RecordField,
+    WhenArm,
+    Pattern,
+}
+
+/// A token of the green tree. Unlike the tokens returned by the
+/// [`Tokenizer`](crate::lang::tokenizer::Tokenizer), it keeps the exact text
+/// of the source, and whitespace and comments are tokens as well.
+///
+/// The text is interned like the names in [`TokenContent`], so building a
+/// tree allocates once per distinct token text rather than once per token.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct GreenToken {
+    con: TokenContent,
+    text: Symbol,
+}
+
+impl GreenToken {
+    pub fn new(con: TokenContent, text: &str) -> Self {
+        Self {
+            con,
+            text: Symbol::intern(text),
+        }
+    }
+
+    pub fn con(&self) -> &TokenContent {
+        &self.con
+    }
+
+    pub fn text(&self) -> &str {
+        self.text.as_str()
+    }
+
+    pub fn len(&self) -> u32 {
+        self.text().len() as u32
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text().is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum GreenElement {
+    Node(Arc<GreenNode>),
+    Token(Arc<GreenToken>),
+}
+
+impl GreenElement {
+    pub fn len(&self) -> u32 {
+        match self {
+            GreenElement::Node(node) => node.len(),
+            GreenElement::Token(token) => token.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// An immutable node of the syntax tree. A green node knows its kind, its
+/// children and the length of its text, but not where it is in the tree, so
+/// unchanged subtrees can be shared between versions of a tree.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct GreenNode {
+    kind: SyntaxKind,
+    len: u32,
+    children: Vec<GreenElement>,
+}
+
+impl GreenNode {
+    pub fn new(kind: SyntaxKind, children: Vec<GreenElement>) -> Self {
+        let len = children.iter().map(GreenElement::len).sum();
+        Self {
+            kind,
+            len,
+            children,
+        }
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.kind
+    }
+
+    pub fn len(&self) -> u32 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn children(&self) -> &[GreenElement] {
+        &self.children
+    }
+
+    fn write_text(&self, out: &mut impl fmt::Write) -> fmt::Result {
+        for child in &self.children {
+            match child {
+                GreenElement::Node(node) => node.write_text(out)?,
+                GreenElement::Token(token) => out.write_str(token.text())?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for GreenNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_text(f)
+    }
+}
+
+#[derive(Debug)]
+struct SyntaxNodeData {
+    green: Arc<GreenNode>,
+    parent: Option<SyntaxNode>,
+    /// The index of the node in the children of its parent.
+    index: usize,
+    offset: u32,
+}
+
+/// A node of the red tree: a [`GreenNode`] together with its absolute offset
+/// and its parent. Red nodes are created on demand while walking down from
+/// the root.
+#[derive(Debug, Clone)]
+pub struct SyntaxNode(Arc<SyntaxNodeData>);
+
+impl PartialEq for SyntaxNode {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0.green, &other.0.green) && self.0.offset == other.0.offset
+    }
+}
+
+impl Eq for SyntaxNode {}
+
+impl SyntaxNode {
+    pub fn new_root(green: Arc<GreenNode>) -> Self {
+        Self(Arc::new(SyntaxNodeData {
+            green,
+            parent: None,
+            index: 0,
+            offset: 0,
+        }))
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.0.green.kind
+    }
+
+    pub fn green(&self) -> &Arc<GreenNode> {
+        &self.0.green
+    }
+
+    pub fn parent(&self) -> Option<&SyntaxNode> {
+        self.0.parent.as_ref()
+    }
+
+    /// Returns the node itself followed by its parent, its parent's parent,
+    /// and so on up to the root.
+    pub fn ancestors(&self) -> impl Iterator<Item = SyntaxNode> {
+        std::iter::successors(Some(self.clone()), |node| node.parent().cloned())
+    }
+
+    /// The index of the node in the children of its parent.
+    pub fn index(&self) -> usize {
+        self.0.index
+    }
+
+    /// The byte offset of the node in the source.
+    pub fn offset(&self) -> u32 {
+        self.0.offset
+    }
+
+    /// The range of the node in the source, including the whitespace and
+    /// comments inside of it.
+    pub fn loc(&self) -> ASTLoc {
+        ASTLoc {
+            start: self.0.offset,
+            end: self.0.offset + self.0.green.len,
+        }
+    }
+
+    pub fn children_with_tokens(&self) -> Vec<SyntaxElement> {
+        let mut offset = self.0.offset;
+        let mut children = Vec::with_capacity(self.0.green.children.len());
+        for (index, child) in self.0.green.children.iter().enumerate() {
+            children.push(match child {
+                GreenElement::Node(green) => {
+                    SyntaxElement::Node(SyntaxNode(Arc::new(SyntaxNodeData {
+                        green: green.clone(),
+                        parent: Some(self.clone()),
+                        index,
+                        offset,
+                    })))
+                }
+                GreenElement::Token(green) => SyntaxElement::Token(SyntaxToken {
+                    green: green.clone(),
+                    parent: self.clone(),
+                    index,
+                    offset,
+                }),
+            });
+            offset += child.len();
+        }
+        children
+    }
+
+    pub fn children(&self) -> Vec<SyntaxNode> {
+        self.children_with_tokens()
+            .into_iter()
+            .filter_map(|child| match child {
+                SyntaxElement::Node(node) => Some(node),
+                SyntaxElement::Token(_) => None,
+            })
+            .collect()
+    }
+
+    /// Returns every token below the node in source order.
+    pub fn tokens(&self) -> Vec<SyntaxToken> {
+        let mut tokens = Vec::new();
+        for child in self.children_with_tokens() {
+            match child {
+                SyntaxElement::Node(node) => tokens.append(&mut node.tokens()),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+        tokens
+    }
+
+    /// Returns the range of the node without leading and trailing trivia.
+    pub fn trimmed_loc(&self) -> ASTLoc {
+        let tokens = self.tokens();
+        let mut significant = tokens.iter().filter(|token| !token.is_trivia());
+        match (significant.next(), significant.next_back()) {
+            (Some(first), Some(last)) => first.loc().to(last.loc()),
+            (Some(first), None) => first.loc(),
+            _ => ASTLoc {
+                start: self.offset(),
+                end: self.offset(),
+            },
+        }
+    }
+
+    /// Returns the innermost node covering `loc`.
+    pub fn covering_node(&self, loc: ASTLoc) -> SyntaxNode {
+        let mut node = self.clone();
+        'descend: loop {
+            for child in node.children() {
+                let range = child.loc();
+                if range.start <= loc.start && loc.end <= range.end {
+                    node = child;
+                    continue 'descend;
+                }
+            }
+            return node;
+        }
+    }
+}
+
+impl fmt::Display for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.green.write_text(f)
+    }
+}
+
+/// A token of the red tree.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SyntaxToken {
+    green: Arc<GreenToken>,
+    parent: SyntaxNode,
+    index: usize,
+    offset: u32,
+}
+
+impl SyntaxToken {
+    pub fn con(&self) -> &TokenContent {
+        &self.green.con
+    }
+
+    pub fn text(&self) -> &str {
+        self.green.text()
+    }
+
+    pub fn parent(&self) -> &SyntaxNode {
+        &self.parent
+    }
+
+    /// The index of the token in the children of its parent.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn loc(&self) -> ASTLoc {
+        ASTLoc {
+            start: self.offset,
+            end: self.offset + self.green.len(),
+        }
+    }
+
+    /// Whether the token is whitespace or a comment.
+    pub fn is_trivia(&self) -> bool {
+        self.green.con.is_trivia()
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+/// A position in the children of the innermost open node of a
+/// [`TreeBuilder`], at which a node can be started after its first children
+/// have been added.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) struct Checkpoint {
+    depth: usize,
+    index: usize,
+}
+
+/// Builds a green tree from the tokens consumed by the parsers.
+///
+/// Trivia are buffered until the next token or node is added, so whitespace
+/// and comments between two nodes belong to their parent rather than to the
+/// node which follows them.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TreeBuilder {
+    stack: Vec<(SyntaxKind, Vec<GreenElement>)>,
+    trivia: Vec<GreenElement>,
+    finished: Option<Arc<GreenNode>>,
+}
+
+impl TreeBuilder {
+    pub(crate) fn is_building(&self) -> bool {
+        !self.stack.is_empty()
+    }
+
+    pub(crate) fn trivia(&mut self, token: GreenToken) {
+        if self.is_building() {
+            self.trivia.push(GreenElement::Token(Arc::new(token)));
+        }
+    }
+
+    pub(crate) fn token(&mut self, token: GreenToken) {
+        self.flush_trivia();
+        if let Some((_, children)) = self.stack.last_mut() {
+            children.push(GreenElement::Token(Arc::new(token)));
+        }
+    }
+
+    fn flush_trivia(&mut self) {
+        if let Some((_, children)) = self.stack.last_mut() {
+            children.append(&mut self.trivia);
+        }
+    }
+
+    pub(crate) fn start_node(&mut self, kind: SyntaxKind) {
+        self.flush_trivia();
+        self.stack.push((kind, Vec::new()));
+    }
+
+    pub(crate) fn checkpoint(&mut self) -> Checkpoint {
+        self.flush_trivia();
+        Checkpoint {
+            depth: self.stack.len(),
+            index: self.stack.last().map_or(0, |(_, children)| children.len()),
+        }
+    }
+
+    /// Starts a node whose first children are the ones added to the current
+    /// node since `checkpoint`.
+    pub(crate) fn start_node_at(&mut self, checkpoint: Checkpoint, kind: SyntaxKind) {
+        assert_eq!(checkpoint.depth, self.stack.len(), "stale checkpoint");
+        let children = match self.stack.last_mut() {
+            Some((_, children)) => children.split_off(checkpoint.index),
+            None => Vec::new(),
+        };
+        self.stack.push((kind, children));
+    }
+
+    /// Finishes the innermost open node. Trivia buffered at the end of the
+    /// root node are added to it.
+    pub(crate) fn finish_node(&mut self) {
+        if self.stack.len() == 1 {
+            self.flush_trivia();
+        }
+        let Some((kind, children)) = self.stack.pop() else {
+            return;
+        };
+        let node = Arc::new(GreenNode::new(kind, children));
+        match self.stack.last_mut() {
+            Some((_, children)) => children.push(GreenElement::Node(node)),
+            None => self.finished = Some(node),
+        }
+    }
+
+    pub(crate) fn take_finished(&mut self) -> Option<Arc<GreenNode>> {
+        self.finished.take()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashSet;
+
+    use crate::lang::{
+        ast::Spanned,
+        node_index::Ast,
+        parser::{module::ModuleParser, Parser},
+    };
+
+    const SOURCE: &str = "// widgets
+import { Button } from \"./button\"
+
+/* the entry view */
+pub view App(title: String) {
+  let count = 0 // state
+  <div#root class=\"app\">
+    {title}
+    <Button label={count + 1} />
+  </div>
+}
+";
+
+    fn parse(input: &str) -> SyntaxNode {
+        let mut parser = ModuleParser::from_str(input);
+        parser.parse_all().expect("failed to parse");
+        parser.syntax().expect("no syntax tree")
+    }
+
+    #[test]
+    fn round_trip() {
+        let root = parse(SOURCE);
+        assert_eq!(root.to_string(), SOURCE);
+        assert_eq!(
+            root.loc(),
+            ASTLoc {
+                start: 0,
+                end: SOURCE.len() as u32
+            }
+        );
+
+        for input in ["", "  \n", "type A = Int // trailing", "const S = \"é\"\n"] {
+            assert_eq!(parse(input).to_string(), input);
+        }
+    }
+
+    #[test]
+    fn structure() {
+        let root = parse(SOURCE);
+        assert_eq!(root.kind(), SyntaxKind::Module);
+        let kinds: Vec<_> = root.children().iter().map(SyntaxNode::kind).collect();
+        assert_eq!(kinds, [SyntaxKind::Import, SyntaxKind::View]);
+
+        // Comments between items belong to the module.
+        let comments: Vec<_> = root
+            .children_with_tokens()
+            .into_iter()
+            .filter_map(|child| match child {
+                SyntaxElement::Token(token) if token.con() == &TokenContent::Comment => {
+                    Some(token.text().to_string())
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(comments, ["// widgets", "/* the entry view */"]);
+
+        let view = &root.children()[1];
+        assert_eq!(view.parent(), Some(&root));
+        assert_eq!(&SOURCE[view.loc().start as usize..][..8], "pub view");
+
+        let offset = SOURCE.find("count + 1").unwrap() as u32;
+        let expr = root.covering_node(ASTLoc {
+            start: offset,
+            end: offset + 9,
+        });
+        assert_eq!(expr.kind(), SyntaxKind::Expr);
+        assert_eq!(expr.to_string(), "count + 1");
+        let ancestors: Vec<_> = expr.ancestors().map(|node| node.kind()).collect();
+        assert_eq!(
+            ancestors,
+            [
+                SyntaxKind::Expr,
+                SyntaxKind::Attribute,
+                SyntaxKind::Element,
+                SyntaxKind::Element,
+                SyntaxKind::Block,
+                SyntaxKind::View,
+                SyntaxKind::Module
+            ]
+        );
+    }
+
+    /// Every node of the AST has a node of the syntax tree with the same kind
+    /// and span.
+    #[test]
+    fn ast_matches_syntax() {
+        let source = "import { Button as B } from \"./button\"
+use std::list as l
+type Shape<T: Eq> = Circle(T) | Rect({ width: T, height: T })
+effect Log { fn log(msg: String) -> () }
+@allow(unused)
+pub const fn area(s: Shape<Float>, f: fn(Int) -> Int emits Log) -> Float? {
+  let n: [Int] = [1, -2, (3 + 4) * 5]
+  const K = { a: 1, b: (\"x\", true) }
+  with Log { fn log(msg) { print(msg) } }
+  for (i, x) in n { n = n }
+  when s { Circle(r) -> r.x  nil -> 1.0  _ -> { 2.0 } }
+  if n != nil { f(1) } else if true { 1 } else { 2 }
+}
+view App(title: String) {
+  effect { log(title) }
+  <div#root class=\"app\" hidden>
+    \"text\" {title}
+    <B label={#root} />
+  </div>
+}
+";
+        let mut parser = ModuleParser::from_str(source);
+        let module = parser.parse_all().unwrap();
+        let root = parser.syntax().unwrap();
+
+        let mut nodes = HashSet::new();
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            let loc = node.trimmed_loc();
+            nodes.insert((node.kind(), loc.start, loc.end));
+            stack.extend(node.children());
+        }
+
+        let ast = Ast::new(&module);
+        for id in ast.ids() {
+            let node = ast.get(id);
+            let loc = node.loc();
+            assert!(
+                nodes.contains(&(node.syntax_kind(), loc.start, loc.end)),
+                "no {:?} node at {:?}: {:?}",
+                node.syntax_kind(),
+                &source[loc.start as usize..loc.end as usize],
+                node
+            );
+        }
+    }
+}
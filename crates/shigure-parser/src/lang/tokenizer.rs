@@ -1,7 +1,8 @@
-use crate::lang::ast::*;
-use std::{cell::RefCell, iter::Peekable, str::Chars};
+use crate::lang::{ast::*, symbol::Symbol};
+use std::{fmt, iter::Peekable, str::Chars};
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TokenizerErr {
     UnterminatedStringLiteral,
     UnexpectedToken,
@@ -9,66 +10,91 @@ pub enum TokenizerErr {
     InvalidElementIdentifier,
 }
 
+impl fmt::Display for TokenizerErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TokenizerErr::UnterminatedStringLiteral => "unterminated string literal",
+            TokenizerErr::UnexpectedToken => "unexpected character",
+            TokenizerErr::EmptyElementIdentifier => "empty element identifier",
+            TokenizerErr::InvalidElementIdentifier => "invalid element identifier",
+        })
+    }
+}
+
+impl std::error::Error for TokenizerErr {}
+
 pub type TokenResult = Result<Token, TokenizerErr>;
 pub type TokenizationResult = Result<(), TokenizerErr>;
 
+/// Splits a source into tokens. Token locations are byte offsets into the
+/// source.
+///
+/// Whitespace and comments are skipped. The parsers read tokens through a
+/// [`TokenStream`](crate::lang::parser::stream::TokenStream), which also
+/// collects them to build the syntax tree.
 pub struct Tokenizer<'a> {
+    input: &'a str,
     itr: Peekable<Chars<'a>>,
-    pending: RefCell<Option<Token>>,
+    pending: Option<Token>,
     current_idx: u32,
-    full_idx_count: u32,
     current: Option<char>,
+    /// The text which could not be lexed, after an error.
+    error: Option<TokenLoc>,
 }
 
-const MAX_IDX_VALUE: u32 = u32::MAX;
-
 impl<'a> Tokenizer<'a> {
     pub fn new(input: &'a str) -> Self {
         let mut itr = input.chars().peekable();
-        if let Some(char0) = itr.next() {
-            Self {
-                itr,
-                pending: RefCell::new(None),
-                current_idx: 0,
-                full_idx_count: 0,
-                current: Some(char0),
-            }
-        } else {
-            panic!("tokenizer may got zero size string")
+        let current = itr.next();
+        Self {
+            input,
+            itr,
+            pending: None,
+            current_idx: 0,
+            current,
+            error: None,
         }
     }
 
-    fn lex_number_literal(&mut self) -> TokenResult {
-        let mut loc = TokenLoc {
-            starts_at: self.current_idx,
-            len: 0,
-        };
+    pub fn input(&self) -> &'a str {
+        self.input
+    }
+
+    /// The location of the text which could not be lexed, once lexing
+    /// stopped at an error.
+    pub(crate) fn error_loc(&self) -> Option<TokenLoc> {
+        self.error
+    }
+
+    /// The source text of a token lexed by this tokenizer.
+    pub(crate) fn text_of(&self, token: &Token) -> &'a str {
+        let end = token.loc.starts_at + token.loc.len;
+        self.text(token.loc.starts_at, end)
+    }
 
-        let mut literal = String::new();
-        let mut len = 0;
+    fn lex_number_literal(&mut self) -> TokenResult {
+        let starts_at = self.current_idx;
+        let mut fraction = false;
         while let Some(c) = self.current {
-            if c.is_digit(10) {
-                literal.push(c);
-                len += 1;
+            let is_point =
+                c == '.' && !fraction && self.itr.peek().is_some_and(|next| next.is_ascii_digit());
+            if c.is_ascii_digit() || is_point {
+                fraction |= is_point;
                 self.consume_char();
             } else {
                 break;
             }
         }
 
-        loc.len = len;
+        let literal = Symbol::intern(self.text(starts_at, self.current_idx));
         Ok(Token {
-            loc,
+            loc: self.loc_from(starts_at),
             con: TokenContent::Literal(TokenLiteral::NumberLiteral(literal)),
         })
     }
 
     fn lex_string_literal(&mut self) -> TokenResult {
-        let mut literal = String::from("\"");
-        let mut loc = TokenLoc {
-            starts_at: self.current_idx,
-            len: 0,
-        };
+        let starts_at = self.current_idx;
 
         if self.current != Some('"') {
             return Err(TokenizerErr::UnexpectedToken);
@@ -78,12 +104,11 @@ impl<'a> Tokenizer<'a> {
 
         while let Some(c) = self.current {
             self.consume_char();
-            literal.push(c);
 
             if c == '"' {
-                loc.len = self.current_idx - loc.starts_at;
+                let literal = Symbol::intern(self.text(starts_at, self.current_idx));
                 return Ok(Token {
-                    loc,
+                    loc: self.loc_from(starts_at),
                     con: TokenContent::Literal(TokenLiteral::StringLiteral(literal)),
                 });
             }
@@ -92,129 +117,63 @@ impl<'a> Tokenizer<'a> {
         Err(TokenizerErr::UnterminatedStringLiteral)
     }
 
-    fn lex_reserved(&mut self) -> Option<TokenResult> {
-        let mut word = String::new();
-        let mut loc = TokenLoc {
-            starts_at: self.current_idx,
-            len: 0,
-        };
-        while let Some(c) = self.current {
-            if c.is_alphabetic() {
-                word.push(c);
-                self.consume_char();
-                if let Ok(con) = TokenContent::try_from(word.as_str()) {
-                    loc.len = self.current_idx - loc.starts_at + 1;
-                    return Some(Ok(Token { loc, con }));
-                };
-            } else {
-                self.pending.replace(Some(Token {
-                    loc,
-                    con: TokenContent::Identifier(word),
-                }));
-
-                return None;
-            }
-        }
-
-        self.pending.replace(Some(Token {
-            loc,
-            con: TokenContent::Identifier(word),
-        }));
-
-        None
-    }
-
-    fn lex_identifier(&mut self) -> TokenResult {
-        let mut word = String::new();
-        let mut loc = TokenLoc {
-            starts_at: self.current_idx,
-            len: 0,
-        };
-
-        if let Some(pending) = self.pending.take() {
-            loc = pending.loc;
-
-            match &pending.con {
-                TokenContent::Identifier(s) => {
-                    word = s.to_string();
-                }
-                _ => {
-                    return Err(TokenizerErr::UnexpectedToken);
-                }
-            }
-        };
-
+    /// Lexes a word, slicing it from the input.
+    fn lex_word(&mut self) -> &'a str {
+        let starts_at = self.current_idx;
         while let Some(c) = self.current {
             if c.is_whitespace() {
                 break;
             }
             match c {
-                'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '$' | '-' => {
-                    word.push(c);
-                    self.consume_char();
-                }
-                _ => {
-                    if word.chars().last() == Some('-') {
-                        return Err(TokenizerErr::UnexpectedToken);
-                    };
-                    break;
-                }
+                // `-` only joins words, as in `x-attribute-name`; `a-1` and
+                // `a->b` are not identifiers
+                '-' if !self.itr.peek().is_some_and(|next| next.is_alphabetic()) => break,
+                'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '$' | '-' => self.consume_char(),
+                _ => break,
             }
         }
+        self.text(starts_at, self.current_idx)
+    }
 
-        loc.len = self.current_idx - loc.starts_at;
-
+    fn lex_identifier(&mut self) -> TokenResult {
+        let starts_at = self.current_idx;
+        let word = self.lex_word();
         Ok(Token {
-            loc,
-            con: TokenContent::Identifier(word),
+            loc: self.loc_from(starts_at),
+            con: TokenContent::Identifier(Symbol::intern(word)),
         })
     }
 
+    /// Lex a word and turn it into a reserved keyword if it is one.
     fn lex_alphabetical_chars(&mut self) -> TokenResult {
-        if let Some(token) = self.lex_reserved() {
-            return token;
-        } else {
-            return self.lex_identifier();
-        }
+        let starts_at = self.current_idx;
+        let word = self.lex_word();
+        let con = TokenContent::try_from(word)
+            .unwrap_or_else(|()| TokenContent::Identifier(Symbol::intern(word)));
+        Ok(Token {
+            loc: self.loc_from(starts_at),
+            con,
+        })
     }
 
     fn lex_anchor(&mut self) -> TokenResult {
-        if let Some(c) = self.current {
-            let mut loc = TokenLoc {
-                starts_at: self.current_idx,
-                len: 0,
-            };
-            if c != '#' {
-                return Err(TokenizerErr::InvalidElementIdentifier);
-            }
-
-            let mut identifier = String::new();
-
-            identifier.push(c);
-            loc.len += 1;
-
-            while let Some(c) = self.advance() {
-                if c.is_whitespace() {
-                    break;
-                } else if c.is_alphabetic() {
-                    identifier.push(c);
-                } else {
-                    break;
-                }
-
-                loc.len += 1;
-            }
+        let starts_at = self.current_idx;
+        if self.current != Some('#') {
+            return Err(TokenizerErr::InvalidElementIdentifier);
+        }
+        while self.advance().is_some_and(char::is_alphabetic) {}
 
-            if identifier.is_empty() {
-                return Err(TokenizerErr::EmptyElementIdentifier);
-            }
+        Ok(Token {
+            loc: self.loc_from(starts_at),
+            con: TokenContent::Anchor(Symbol::intern(self.text(starts_at, self.current_idx))),
+        })
+    }
 
-            return Ok(Token {
-                loc,
-                con: TokenContent::Anchor(identifier),
-            });
-        } else {
-            return Err(TokenizerErr::InvalidElementIdentifier);
+    /// The location from `starts_at` to the current character.
+    fn loc_from(&self, starts_at: u32) -> TokenLoc {
+        TokenLoc {
+            starts_at,
+            len: self.current_idx - starts_at,
         }
     }
 
@@ -224,18 +183,59 @@ impl<'a> Tokenizer<'a> {
     }
 
     fn consume_char(&mut self) {
-        self.current_idx += 1;
+        if let Some(c) = self.current {
+            self.current_idx += c.len_utf8() as u32;
+        }
+        self.current = self.itr.next();
+    }
+
+    fn text(&self, starts_at: u32, end: u32) -> &'a str {
+        &self.input[starts_at as usize..end as usize]
+    }
 
-        if self.current_idx == MAX_IDX_VALUE {
-            self.full_idx_count += 1;
-            self.current_idx = 0;
+    fn lex_whitespace(&mut self) -> Token {
+        let starts_at = self.current_idx;
+        while self.current.is_some_and(char::is_whitespace) {
+            self.consume_char();
+        }
+        Token {
+            loc: self.loc_from(starts_at),
+            con: TokenContent::Whitespace,
         }
+    }
 
-        self.current = self.itr.next();
+    /// Lexes a `// line comment` or a `/* block comment */`, if one starts at
+    /// the current character.
+    fn lex_comment(&mut self) -> Option<Token> {
+        let starts_at = self.current_idx;
+        match self.itr.peek() {
+            Some('/') => {
+                while self.current.is_some_and(|c| c != '\n') {
+                    self.consume_char();
+                }
+            }
+            Some('*') => {
+                self.consume_char();
+                self.consume_char();
+                let mut star = false;
+                while let Some(c) = self.current {
+                    self.consume_char();
+                    if star && c == '/' {
+                        break;
+                    }
+                    star = c == '*';
+                }
+            }
+            _ => return None,
+        }
+        Some(Token {
+            loc: self.loc_from(starts_at),
+            con: TokenContent::Comment,
+        })
     }
 
     fn set_pending(&mut self, token: Token) -> TokenizationResult {
-        assert!(self.pending.replace(Some(token)).is_none());
+        assert!(self.pending.replace(token).is_none());
         Ok(())
     }
 
@@ -246,6 +246,29 @@ impl<'a> Tokenizer<'a> {
         }
     }
 
+    /// Lexes an operator which is `double` when the current character is
+    /// followed by `second`, and `single` otherwise.
+    fn lex_operator(
+        &mut self,
+        second: char,
+        double: TokenContent,
+        single: TokenContent,
+    ) -> TokenizationResult {
+        let starts_at = self.current_idx;
+        if self.advance() == Some(second) {
+            self.consume_char();
+            self.set_pending(Token {
+                loc: TokenLoc { starts_at, len: 2 },
+                con: double,
+            })
+        } else {
+            self.set_pending(Token {
+                loc: TokenLoc { starts_at, len: 1 },
+                con: single,
+            })
+        }
+    }
+
     fn tokenize_char(&mut self, c: char) -> TokenizationResult {
         match c {
             'a'..='z' | 'A'..='Z' => {
@@ -262,60 +285,53 @@ impl<'a> Tokenizer<'a> {
             }
             '<' => {
                 let starts_at = self.current_idx;
-                if let Some('/') = self.advance() {
-                    let loc = TokenLoc { starts_at, len: 2 };
-                    let con = TokenContent::TagAngleClosingLeft;
+                match self.advance() {
+                    Some('/') => {
+                        let loc = TokenLoc { starts_at, len: 2 };
+                        let con = TokenContent::TagAngleClosingLeft;
 
-                    self.consume_char();
+                        self.consume_char();
 
-                    return self.set_pending(Token { loc, con });
-                } else {
-                    // ViewElement starting tag
-                    self.set_pending(Token {
+                        self.set_pending(Token { loc, con })
+                    }
+                    Some('=') => {
+                        self.consume_char();
+                        self.set_pending(Token {
+                            loc: TokenLoc { starts_at, len: 2 },
+                            con: TokenContent::LessEqualOp,
+                        })
+                    }
+                    Some(c) if c.is_whitespace() => self.set_pending(Token {
+                        loc: TokenLoc { starts_at, len: 1 },
+                        con: TokenContent::LessThanOp,
+                    }),
+                    // ViewElement starting tag, or generic arguments
+                    _ => self.set_pending(Token {
                         loc: TokenLoc { starts_at, len: 1 },
                         con: TokenContent::TagAngleBracketLeft,
-                    })
+                    }),
                 }
             }
             '>' => {
-                // ViewElement starting tag
-                let loc = TokenLoc {
-                    starts_at: self.current_idx,
-                    len: 1,
-                };
-                self.consume_char();
-                self.set_pending(Token {
-                    loc,
-                    con: TokenContent::TagAngleBracketRight,
-                })
+                // ViewElement starting tag, or a comparison
+                self.lex_operator(
+                    '=',
+                    TokenContent::GreaterEqualOp,
+                    TokenContent::TagAngleBracketRight,
+                )
             }
             '/' => {
                 // Self-closing ViewElement tag
-                if let Some('>') = self.advance() {
-                    let loc = TokenLoc {
-                        starts_at: self.current_idx - 1,
-                        len: 2,
-                    };
-                    let con = TokenContent::TagAngleSelfClosingRight;
-
-                    self.consume_char();
-
-                    self.set_pending(Token { loc, con })
-                } else {
-                    Err(TokenizerErr::UnexpectedToken)
-                }
-            }
-            '=' => {
-                let loc = TokenLoc {
-                    starts_at: self.current_idx,
-                    len: 1,
-                };
-                self.consume_char();
-                self.set_pending(Token {
-                    loc,
-                    con: TokenContent::AssignmentOp,
-                })
+                self.lex_operator(
+                    '>',
+                    TokenContent::TagAngleSelfClosingRight,
+                    TokenContent::DivOp,
+                )
             }
+            '=' => self.lex_operator('=', TokenContent::EqualOp, TokenContent::AssignmentOp),
+            '!' => self.lex_operator('=', TokenContent::NotEqualOp, TokenContent::NotOp),
+            '&' => self.lex_operator('&', TokenContent::LogicalAndOp, TokenContent::BitwiseAndOp),
+            '|' => self.lex_operator('|', TokenContent::LogicalOrOp, TokenContent::BitwiseOrOp),
             '[' => {
                 let loc = TokenLoc {
                     starts_at: self.current_idx,
@@ -346,34 +362,64 @@ impl<'a> Tokenizer<'a> {
                 let res = self.lex_anchor();
                 self.set_pending_or_err(res)
             }
-            _ => Err(TokenizerErr::UnexpectedToken),
+            ':' => self.lex_operator(':', TokenContent::DoubleColon, TokenContent::Colon),
+            '-' => self.lex_operator('>', TokenContent::Arrow, TokenContent::SubOp),
+            _ => {
+                let con = TokenContent::try_from(c).map_err(|_| TokenizerErr::UnexpectedToken)?;
+                let loc = TokenLoc {
+                    starts_at: self.current_idx,
+                    len: 1,
+                };
+                self.consume_char();
+                self.set_pending(Token { loc, con })
+            }
         }
     }
 
-    pub fn next(&mut self) -> Option<TokenResult> {
+    /// Lexes the next token. The whitespace and comments before it are
+    /// appended to `trivia` if given, and skipped otherwise. After the last
+    /// token or an error, a final call collects the trivia at the end of the
+    /// input and returns `None`.
+    pub(crate) fn lex_next(&mut self, mut trivia: Option<&mut Vec<Token>>) -> Option<TokenResult> {
         while let Some(c) = self.current {
-            if c.is_whitespace() {
-                self.consume_char();
+            let skipped = match c {
+                c if c.is_whitespace() => Some(self.lex_whitespace()),
+                '/' => self.lex_comment(),
+                _ => None,
+            };
+            if let Some(token) = skipped {
+                if let Some(trivia) = trivia.as_mut() {
+                    trivia.push(token);
+                }
                 continue;
             }
 
-            match self.tokenize_char(c) {
-                Ok(..) => {
-                    if let Some(token) = self.pending.take() {
-                        return Some(Ok(token));
-                    } else {
-                        panic!("no pending token")
-                    }
-                }
+            let starts_at = self.current_idx;
+            return match self.tokenize_char(c) {
+                Ok(()) => match self.pending.take() {
+                    Some(token) => Some(Ok(token)),
+                    None => panic!("no pending token"),
+                },
                 Err(err) => {
-                    return Some(Err(err));
+                    // Lexing stops at the first error.
+                    self.error = Some(self.loc_from(starts_at));
+                    self.current = None;
+                    Some(Err(err))
                 }
-            }
+            };
         }
         None
     }
 }
 
+impl Iterator for Tokenizer<'_> {
+    type Item = TokenResult;
+
+    fn next(&mut self) -> Option<TokenResult> {
+        self.lex_next(None)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -402,9 +448,7 @@ mod test {
 
         pub fn run(&self) -> TesterResult {
             let mut tokenizer = Tokenizer::new(self.query);
-            let mut expected_itr = self.expected.clone().into_iter();
-
-            while let Some(expected) = expected_itr.next() {
+            for expected in self.expected.clone() {
                 if let Some(token) = tokenizer.next() {
                     match token {
                         Ok(token) => {
@@ -461,7 +505,7 @@ mod test {
                     starts_at: 0,
                     len: 2,
                 },
-                con: TokenContent::Literal(TokenLiteral::NumberLiteral("91".to_string())),
+                con: TokenContent::Literal(TokenLiteral::NumberLiteral("91".into())),
             }],
             "91",
         )
@@ -479,14 +523,14 @@ mod test {
                         starts_at: 0,
                         len: 1,
                     },
-                    con: TokenContent::Identifier("x".to_string()),
+                    con: TokenContent::Identifier("x".into()),
                 },
                 Token {
                     loc: TokenLoc {
                         starts_at: 2,
                         len: 2,
                     },
-                    con: TokenContent::Literal(TokenLiteral::NumberLiteral("91".to_string())),
+                    con: TokenContent::Literal(TokenLiteral::NumberLiteral("91".into())),
                 }
             ],
             "x 91",
@@ -538,9 +582,7 @@ mod test {
                     starts_at: 0,
                     len: 14,
                 },
-                con: TokenContent::Literal(TokenLiteral::StringLiteral(
-                    "\"hello, world\"".to_string()
-                )),
+                con: TokenContent::Literal(TokenLiteral::StringLiteral("\"hello, world\"".into())),
             }],
             "\"hello, world\"",
         )
@@ -768,4 +810,325 @@ mod test {
 
         tester.run_all();
     }
+
+    #[test]
+    fn keyword_prefixed_identifiers() {
+        assert!(Tester::new(
+            "keyword prefixed identifiers",
+            vec![
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 0,
+                        len: 4,
+                    },
+                    con: TokenContent::Type,
+                },
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 5,
+                        len: 6,
+                    },
+                    con: TokenContent::Identifier("format".into()),
+                },
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 12,
+                        len: 5,
+                    },
+                    con: TokenContent::Identifier("typed".into()),
+                },
+            ],
+            "type format typed",
+        )
+        .run()
+        .is_ok());
+    }
+
+    #[test]
+    fn type_punctuation() {
+        assert!(Tester::new(
+            "type punctuation",
+            vec![
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 0,
+                        len: 1,
+                    },
+                    con: TokenContent::ParenthesisLeft,
+                },
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 1,
+                        len: 1,
+                    },
+                    con: TokenContent::Identifier("a".into()),
+                },
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 2,
+                        len: 1,
+                    },
+                    con: TokenContent::Colon,
+                },
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 3,
+                        len: 1,
+                    },
+                    con: TokenContent::Comma,
+                },
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 4,
+                        len: 1,
+                    },
+                    con: TokenContent::ParenthesisRight,
+                },
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 5,
+                        len: 2,
+                    },
+                    con: TokenContent::Arrow,
+                },
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 7,
+                        len: 1,
+                    },
+                    con: TokenContent::Identifier("T".into()),
+                },
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 8,
+                        len: 1,
+                    },
+                    con: TokenContent::QuestionMark,
+                },
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 10,
+                        len: 1,
+                    },
+                    con: TokenContent::BitwiseOrOp,
+                },
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 12,
+                        len: 1,
+                    },
+                    con: TokenContent::BraceLeft,
+                },
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 13,
+                        len: 1,
+                    },
+                    con: TokenContent::BraceRight,
+                },
+            ],
+            "(a:,)->T? | {}",
+        )
+        .run()
+        .is_ok());
+    }
+
+    #[test]
+    fn operators() {
+        assert!(Tester::new(
+            "operators",
+            vec![
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 0,
+                        len: 1,
+                    },
+                    con: TokenContent::SubOp,
+                },
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 1,
+                        len: 1,
+                    },
+                    con: TokenContent::Identifier("a".into()),
+                },
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 3,
+                        len: 2,
+                    },
+                    con: TokenContent::LessEqualOp,
+                },
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 6,
+                        len: 1,
+                    },
+                    con: TokenContent::Identifier("b".into()),
+                },
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 8,
+                        len: 1,
+                    },
+                    con: TokenContent::LessThanOp,
+                },
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 10,
+                        len: 1,
+                    },
+                    con: TokenContent::Identifier("c".into()),
+                },
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 12,
+                        len: 2,
+                    },
+                    con: TokenContent::LogicalAndOp,
+                },
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 15,
+                        len: 1,
+                    },
+                    con: TokenContent::NotOp,
+                },
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 16,
+                        len: 1,
+                    },
+                    con: TokenContent::Identifier("d".into()),
+                },
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 18,
+                        len: 2,
+                    },
+                    con: TokenContent::NotEqualOp,
+                },
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 21,
+                        len: 3,
+                    },
+                    con: TokenContent::Literal(TokenLiteral::NumberLiteral("1.5".into())),
+                },
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 25,
+                        len: 1,
+                    },
+                    con: TokenContent::MulOp,
+                },
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 27,
+                        len: 1,
+                    },
+                    con: TokenContent::Identifier("e".into()),
+                },
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 29,
+                        len: 1,
+                    },
+                    con: TokenContent::DivOp,
+                },
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 31,
+                        len: 1,
+                    },
+                    con: TokenContent::Literal(TokenLiteral::NumberLiteral("2".into())),
+                },
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 33,
+                        len: 1,
+                    },
+                    con: TokenContent::RemOp,
+                },
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 35,
+                        len: 1,
+                    },
+                    con: TokenContent::Identifier("f".into()),
+                },
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 37,
+                        len: 2,
+                    },
+                    con: TokenContent::GreaterEqualOp,
+                },
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 40,
+                        len: 1,
+                    },
+                    con: TokenContent::Identifier("g".into()),
+                },
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 42,
+                        len: 2,
+                    },
+                    con: TokenContent::LogicalOrOp,
+                },
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 45,
+                        len: 1,
+                    },
+                    con: TokenContent::Identifier("h".into()),
+                },
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 47,
+                        len: 2,
+                    },
+                    con: TokenContent::EqualOp,
+                },
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 50,
+                        len: 1,
+                    },
+                    con: TokenContent::Identifier("i".into()),
+                },
+            ],
+            "-a <= b < c && !d != 1.5 * e / 2 % f >= g || h == i",
+        )
+        .run()
+        .is_ok());
+    }
+
+    #[test]
+    fn comments_and_byte_offsets() {
+        let tokens: Vec<_> = Tokenizer::new("\"é\" // comment\n/* a * b */ x")
+            .map(|res| res.ok().unwrap())
+            .collect();
+        assert_eq!(
+            tokens,
+            [
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 0,
+                        len: 4,
+                    },
+                    con: TokenContent::Literal(TokenLiteral::StringLiteral("\"é\"".into())),
+                },
+                Token {
+                    loc: TokenLoc {
+                        starts_at: 28,
+                        len: 1,
+                    },
+                    con: TokenContent::Identifier("x".into()),
+                },
+            ]
+        );
+    }
 }
@@ -0,0 +1,2649 @@
+//! Static type checking.
+//!
+//! [`TypeChecker`] checks a module bidirectionally: where the type an
+//! expression must have is known, e.g. from an annotation, a parameter or
+//! another branch of an `if`, the expression is checked against it and the
+//! expectation is pushed into its parts, so `let p: Point = { x: 1.0, y: 2.0 }`
+//! reports a wrong field rather than two unrelated record types. Elsewhere the
+//! type of an expression is inferred from its parts.
+//!
+//! Missing annotations are inferred by unification: `let` and `const`
+//! without a type take the type of their value, and parameters and return
+//! types of functions without one are inferred from how they are used.
+//! Generic declarations are instantiated with fresh type variables wherever
+//! they are used, and their type arguments are inferred the same way.
+
+use std::{collections::HashMap, fmt};
+
+use crate::lang::{
+    ast::{
+        ASTBinaryOp, ASTExprKind, ASTItemType, ASTItemView, ASTLoc, ASTNodeBlock, ASTNodeExpr,
+        ASTNodeModule, ASTNodeParam, ASTNodePattern, ASTNodeScoped, ASTNodeStmt, ASTNodeType,
+        ASTNodeTypeParam, ASTNodeViewAttribute, ASTNodeViewChild, ASTNodeViewElement,
+        ASTNodeWhenArm, ASTNodeWith, ASTPatternKind, ASTTypeBody, ASTTypeKind, ASTUnaryOp, Spanned,
+        TokenLiteral,
+    },
+    diagnostic::{suggest, Diagnostic},
+    html::{self, AttrType},
+    node_index::{Ast, NodeId, NodeRef, SideTable},
+    resolve::{is_component, DefId, DefKind, Resolution},
+};
+
+/// A type variable, standing for a type being inferred.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy, Debug)]
+pub struct TypeVar(u32);
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum Type {
+    Int,
+    Float,
+    String,
+    Bool,
+    /// `()`, `(Int, String)`
+    Tuple(Vec<Type>),
+    /// `[T]`
+    List(Box<Type>),
+    /// `T?`
+    Optional(Box<Type>),
+    /// `{ x: Float, y: Float }`, with its fields sorted by name.
+    Record(Vec<(String, Type)>),
+    /// `fn(Int) -> Bool emits Log`
+    Fn {
+        params: Vec<Type>,
+        ret: Box<Type>,
+        /// The effects performed by calling it, each a [`Type::Effect`],
+        /// sorted by definition.
+        ///
+        /// The row is displayed but not unified: two function types with the
+        /// same parameters and return type are the same type whatever their
+        /// rows, and the [`EffectChecker`](crate::lang::effects::EffectChecker)
+        /// reports a function bound where its effects are not declared. Rows
+        /// have no variables, so a generic function cannot be generic over
+        /// the effects of a function it takes.
+        effects: Vec<Type>,
+    },
+    /// A declared record, union or alias, e.g. `Option<Int>`.
+    Named {
+        name: String,
+        def: DefId,
+        args: Vec<Type>,
+    },
+    /// A generic parameter of a declaration. Inside the declaration it only
+    /// unifies with itself, and its values only support the operators its
+    /// bounds allow.
+    Param {
+        name: String,
+        def: DefId,
+    },
+    /// The type of the name of an effect, whose operations are its members.
+    Effect {
+        name: String,
+        def: DefId,
+    },
+    /// A reference to an element through its anchor, e.g. `#input` for
+    /// `<input#input>`.
+    Element(String),
+    Var(TypeVar),
+    /// The type of an expression which cannot be typed, e.g. a name imported
+    /// from another module or an operator applied to an invalid operand. It is compatible with every type, so an error is
+    /// reported once rather than wherever the expression is used.
+    Unknown,
+}
+
+impl Type {
+    pub fn unit() -> Self {
+        Type::Tuple(Vec::new())
+    }
+
+    fn record(mut fields: Vec<(String, Type)>) -> Self {
+        fields.sort_by(|a, b| a.0.cmp(&b.0));
+        Type::Record(fields)
+    }
+
+    /// Replaces the generic parameters `params` by `args`.
+    pub fn substitute(&self, params: &[DefId], args: &[Type]) -> Type {
+        let sub = |ty: &Type| ty.substitute(params, args);
+        match self {
+            Type::Param { def, .. } => match params.iter().position(|p| p == def) {
+                Some(idx) => args[idx].clone(),
+                None => self.clone(),
+            },
+            Type::Tuple(items) => Type::Tuple(items.iter().map(sub).collect()),
+            Type::List(item) => Type::List(Box::new(sub(item))),
+            Type::Optional(item) => Type::Optional(Box::new(sub(item))),
+            Type::Record(fields) => Type::Record(
+                fields
+                    .iter()
+                    .map(|(name, ty)| (name.clone(), sub(ty)))
+                    .collect(),
+            ),
+            Type::Fn {
+                params: p,
+                ret,
+                effects,
+            } => Type::Fn {
+                params: p.iter().map(sub).collect(),
+                ret: Box::new(sub(ret)),
+                effects: effects.clone(),
+            },
+            Type::Named { name, def, args: a } => Type::Named {
+                name: name.clone(),
+                def: *def,
+                args: a.iter().map(sub).collect(),
+            },
+            _ => self.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn list(f: &mut fmt::Formatter<'_>, items: &[Type]) -> fmt::Result {
+            for (idx, item) in items.iter().enumerate() {
+                if idx > 0 {
+                    f.write_str(", ")?;
+                }
+                write!(f, "{}", item)?;
+            }
+            Ok(())
+        }
+
+        match self {
+            Type::Int => f.write_str("Int"),
+            Type::Float => f.write_str("Float"),
+            Type::String => f.write_str("String"),
+            Type::Bool => f.write_str("Bool"),
+            Type::Tuple(items) => {
+                f.write_str("(")?;
+                list(f, items)?;
+                f.write_str(")")
+            }
+            Type::List(item) => write!(f, "[{}]", item),
+            Type::Optional(item) => match **item {
+                Type::Fn { .. } => write!(f, "({})?", item),
+                _ => write!(f, "{}?", item),
+            },
+            Type::Record(fields) => {
+                f.write_str("{ ")?;
+                for (idx, (name, ty)) in fields.iter().enumerate() {
+                    if idx > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}: {}", name, ty)?;
+                }
+                f.write_str(" }")
+            }
+            Type::Fn {
+                params,
+                ret,
+                effects,
+            } => {
+                f.write_str("fn(")?;
+                list(f, params)?;
+                write!(f, ") -> {}", ret)?;
+                for (idx, effect) in effects.iter().enumerate() {
+                    f.write_str(if idx == 0 { " emits " } else { " + " })?;
+                    write!(f, "{}", effect)?;
+                }
+                Ok(())
+            }
+            Type::Named { name, args, .. } => {
+                f.write_str(name)?;
+                if !args.is_empty() {
+                    f.write_str("<")?;
+                    list(f, args)?;
+                    f.write_str(">")?;
+                }
+                Ok(())
+            }
+            Type::Param { name, .. } | Type::Effect { name, .. } => f.write_str(name),
+            Type::Element(tag) => write!(f, "Element<{}>", tag),
+            Type::Var(_) | Type::Unknown => f.write_str("_"),
+        }
+    }
+}
+
+/// A constraint on the type arguments of a generic parameter, e.g. `Ord` in
+/// `T: Ord`.
+///
+/// The bounds form a chain, each implying the ones before it: numbers can be
+/// ordered, and ordered values can be compared for equality.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Bound {
+    /// Values compared with `==` and `!=`.
+    Eq,
+    /// Values compared with `<`, `<=`, `>` and `>=`.
+    Ord,
+    /// Numbers, supporting arithmetic.
+    Num,
+}
+
+impl Bound {
+    const ALL: [Bound; 3] = [Bound::Eq, Bound::Ord, Bound::Num];
+
+    pub fn from_name(name: &str) -> Option<Bound> {
+        Bound::ALL.into_iter().find(|bound| bound.as_str() == name)
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Bound::Eq => "Eq",
+            Bound::Ord => "Ord",
+            Bound::Num => "Num",
+        }
+    }
+
+    /// Whether every type satisfying `self` satisfies `other`.
+    pub fn implies(self, other: Bound) -> bool {
+        self >= other
+    }
+}
+
+impl fmt::Display for Bound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The types of a module.
+#[derive(Debug, Clone, Default)]
+pub struct Typing {
+    /// The type of every expression.
+    pub types: SideTable<Type>,
+    /// The type of every value declared in the module: constants,
+    /// functions, effect operations, parameters and bindings.
+    pub bindings: HashMap<DefId, Type>,
+    /// The type arguments of every use of a generic function or view, by the
+    /// identifier or element using it.
+    pub instances: SideTable<Vec<Type>>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Typing {
+    pub fn type_of(&self, node: NodeId) -> Option<&Type> {
+        self.types.get(node)
+    }
+}
+
+#[derive(Debug, Clone)]
+enum DeclBody {
+    Alias(Type),
+    Record(Vec<(String, Type)>),
+    Union(Vec<VariantDecl>),
+}
+
+#[derive(Debug, Clone)]
+struct VariantDecl {
+    def: DefId,
+    fields: Vec<Type>,
+}
+
+/// A `type` declaration, with the types of its body in terms of its
+/// parameters.
+#[derive(Debug, Clone)]
+struct TypeDecl {
+    name: String,
+    params: Vec<DefId>,
+    body: DeclBody,
+}
+
+/// A bound a type argument must satisfy, checked once inference is done.
+#[derive(Debug, Clone)]
+struct Obligation {
+    ty: Type,
+    bound: Bound,
+    /// The parameter the type is an argument for.
+    param: DefId,
+    /// Where the generic declaration is used.
+    loc: ASTLoc,
+}
+
+/// The type an expression is checked against.
+#[derive(Debug, Clone, Copy)]
+struct Expected<'t> {
+    ty: &'t Type,
+    /// The annotation the expectation comes from, if any.
+    origin: Option<ASTLoc>,
+}
+
+impl<'t> Expected<'t> {
+    fn new(ty: &'t Type) -> Self {
+        Self { ty, origin: None }
+    }
+}
+
+/// Infers and checks the types of a resolved module.
+///
+/// Types are not nullable unless they are optional: `nil` is only a value of
+/// `T?`, and the members of a `T?` cannot be accessed until a check against
+/// `nil` narrows it to `T`.
+///
+/// Mismatches are reported with the expected and the found type, and with
+/// the annotation the expected type comes from. Names the resolver could
+/// not resolve are typed as unknown and not reported again.
+pub struct TypeChecker<'a, 'ast> {
+    path: String,
+    ast: &'a Ast<'ast>,
+    res: &'a Resolution,
+    /// The definition declared by each declaring node.
+    decls: HashMap<NodeId, DefId>,
+    type_decls: HashMap<DefId, TypeDecl>,
+    /// The type parameters of each generic function and view.
+    generics: HashMap<DefId, Vec<DefId>>,
+    /// The bounds of each type parameter, with where they are written.
+    bounds: HashMap<DefId, Vec<(Bound, ASTLoc)>>,
+    obligations: Vec<Obligation>,
+    /// The type each type variable stands for, once known.
+    vars: Vec<Option<Type>>,
+    /// The types of optional bindings where they are known not to be nil.
+    narrowed: HashMap<DefId, Type>,
+    /// The elements enclosing the element being checked, innermost last.
+    enclosing: Vec<Enclosing>,
+    typing: Typing,
+}
+
+impl<'a, 'ast> TypeChecker<'a, 'ast> {
+    pub fn new(path: impl Into<String>, ast: &'a Ast<'ast>, res: &'a Resolution) -> Self {
+        let decls = res
+            .definition_ids()
+            .filter_map(|(id, def)| Some((def.node?, id)))
+            .collect();
+        Self {
+            path: path.into(),
+            ast,
+            res,
+            decls,
+            type_decls: HashMap::new(),
+            generics: HashMap::new(),
+            bounds: HashMap::new(),
+            obligations: Vec::new(),
+            vars: Vec::new(),
+            narrowed: HashMap::new(),
+            enclosing: Vec::new(),
+            typing: Typing::default(),
+        }
+    }
+
+    pub fn check(mut self) -> Typing {
+        let NodeRef::Module(module) = self.ast.get(self.ast.root()) else {
+            unreachable!("the root of a node index is a module");
+        };
+        self.declare_types(module);
+        self.declare_values(module);
+
+        // Generic functions are checked first, so that the types inferred for
+        // them are known wherever they are instantiated.
+        let (generic, rest): (Vec<_>, Vec<_>) = module
+            .nodes
+            .iter()
+            .partition(|node| matches!(node, ASTNodeScoped::Fn(f) if !f.type_params.is_empty()));
+        for node in generic.into_iter().chain(rest) {
+            match node {
+                ASTNodeScoped::Const(item) => {
+                    let ty = self.binding(self.id(item));
+                    let origin = item.ty.as_ref().map(|ty| ty.loc());
+                    self.check_expr(&item.value, Expected { ty: &ty, origin });
+                }
+                ASTNodeScoped::Fn(item) => {
+                    let Type::Fn { ret, .. } = self.binding(self.id(item)) else {
+                        unreachable!("functions are declared with a function type");
+                    };
+                    let origin = item.ret.as_ref().map(|ty| ty.loc());
+                    self.check_block(&item.body, Some(Expected { ty: &ret, origin }));
+                }
+                ASTNodeScoped::View(item) => {
+                    self.check_block(&item.body, None);
+                }
+                _ => {}
+            }
+        }
+        self.check_obligations();
+
+        let mut typing = std::mem::take(&mut self.typing);
+        let ids: Vec<_> = typing.types.iter().map(|(id, _)| id).collect();
+        for id in ids {
+            let ty = self.zonk(&typing.types[id]);
+            typing.types.insert(id, ty);
+        }
+        for ty in typing.bindings.values_mut() {
+            *ty = self.zonk(ty);
+        }
+        let ids: Vec<_> = typing.instances.iter().map(|(id, _)| id).collect();
+        for id in ids {
+            let args = typing.instances[id].iter().map(|t| self.zonk(t)).collect();
+            typing.instances.insert(id, args);
+        }
+        typing
+    }
+
+    fn id(&self, node: impl Into<NodeRef<'ast>>) -> NodeId {
+        self.ast.id(node).expect("node of the checked module")
+    }
+
+    /// The definition declared by `node`.
+    fn decl(&self, node: impl Into<NodeRef<'ast>>) -> Option<DefId> {
+        self.decls.get(&self.id(node)).copied()
+    }
+
+    /// The type of the value declared by `node`.
+    fn binding(&self, node: NodeId) -> Type {
+        self.decls
+            .get(&node)
+            .and_then(|def| self.typing.bindings.get(def))
+            .cloned()
+            .unwrap_or(Type::Unknown)
+    }
+
+    fn bind(&mut self, node: impl Into<NodeRef<'ast>>, ty: Type) {
+        if let Some(def) = self.decl(node) {
+            self.typing.bindings.insert(def, ty);
+        }
+    }
+
+    fn fresh(&mut self) -> Type {
+        self.vars.push(None);
+        Type::Var(TypeVar(self.vars.len() as u32 - 1))
+    }
+
+    fn error(&mut self, code: &'static str, loc: ASTLoc, message: String) {
+        self.typing
+            .diagnostics
+            .push(Diagnostic::error(code, &self.path, loc, message));
+    }
+
+    /// Records the declarations of all `type` items, so that types can refer
+    /// to each other regardless of their order.
+    fn declare_types(&mut self, module: &'ast ASTNodeModule) {
+        let items: Vec<(DefId, &'ast ASTItemType)> = module
+            .nodes
+            .iter()
+            .filter_map(|node| match node {
+                ASTNodeScoped::Type(item) => Some((self.decl(item)?, item)),
+                _ => None,
+            })
+            .collect();
+
+        for &(def, item) in &items {
+            let params = self.declare_type_params(&item.params);
+            let decl = TypeDecl {
+                name: item.name.clone(),
+                params,
+                body: DeclBody::Alias(Type::Unknown),
+            };
+            self.type_decls.insert(def, decl);
+        }
+
+        for (def, item) in items {
+            let body = match &item.body {
+                ASTTypeBody::Alias(ty) => DeclBody::Alias(self.lower_type(ty)),
+                ASTTypeBody::Record(fields) => {
+                    let fields = fields
+                        .iter()
+                        .map(|field| (field.name.clone(), self.lower_type(&field.ty)))
+                        .collect();
+                    let Type::Record(fields) = Type::record(fields) else {
+                        unreachable!();
+                    };
+                    DeclBody::Record(fields)
+                }
+                ASTTypeBody::Union(variants) => DeclBody::Union(
+                    variants
+                        .iter()
+                        .filter_map(|variant| {
+                            let fields = variant.fields.iter().map(|f| self.lower_type(f));
+                            Some(VariantDecl {
+                                fields: fields.collect(),
+                                def: self.decl(variant)?,
+                            })
+                        })
+                        .collect(),
+                ),
+            };
+            self.type_decls.get_mut(&def).expect("declared").body = body;
+        }
+    }
+
+    /// Gives every value declared by an item its annotated type, or a type
+    /// variable to infer it.
+    fn declare_values(&mut self, module: &'ast ASTNodeModule) {
+        for node in &module.nodes {
+            match node {
+                ASTNodeScoped::Const(item) => {
+                    let ty = self.annotation(item.ty.as_ref());
+                    self.bind(item, ty);
+                }
+                ASTNodeScoped::Fn(item) => {
+                    self.declare_generics(item, &item.type_params);
+                    let params = self.declare_params(&item.params);
+                    let ret = self.annotation(item.ret.as_ref());
+                    let ret = Box::new(ret);
+                    let effects = self.lower_row(&item.effects);
+                    self.bind(
+                        item,
+                        Type::Fn {
+                            params,
+                            ret,
+                            effects,
+                        },
+                    );
+                }
+                ASTNodeScoped::Effect(item) => {
+                    let effects = match self.decl(item) {
+                        Some(def) => vec![Type::Effect {
+                            name: item.name.clone(),
+                            def,
+                        }],
+                        None => Vec::new(),
+                    };
+                    for op in &item.ops {
+                        let params = self.declare_params(&op.params);
+                        let ret = match &op.ret {
+                            Some(ty) => self.lower_type(ty),
+                            None => Type::unit(),
+                        };
+                        let ret = Box::new(ret);
+                        let effects = effects.clone();
+                        self.bind(
+                            op,
+                            Type::Fn {
+                                params,
+                                ret,
+                                effects,
+                            },
+                        );
+                    }
+                }
+                ASTNodeScoped::View(item) => {
+                    self.declare_generics(item, &item.type_params);
+                    self.declare_params(&item.params);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Records the type parameters of the function or view `item`, if it is
+    /// generic.
+    fn declare_generics(
+        &mut self,
+        item: impl Into<NodeRef<'ast>>,
+        type_params: &'ast [ASTNodeTypeParam],
+    ) {
+        let params = self.declare_type_params(type_params);
+        if let (false, Some(def)) = (params.is_empty(), self.decl(item)) {
+            self.generics.insert(def, params);
+        }
+    }
+
+    /// Records the bounds of type parameters, reporting unknown ones, and
+    /// returns their definitions.
+    fn declare_type_params(&mut self, params: &'ast [ASTNodeTypeParam]) -> Vec<DefId> {
+        let mut defs = Vec::new();
+        for param in params {
+            let mut bounds = Vec::new();
+            for bound in &param.bounds {
+                match Bound::from_name(&bound.name) {
+                    Some(known) => bounds.push((known, bound.loc())),
+                    None => {
+                        let message = did_you_mean(
+                            format!("unknown bound `{}`", bound.name),
+                            suggest(&bound.name, Bound::ALL.map(Bound::as_str)),
+                        );
+                        self.error("unknown-bound", bound.loc(), message);
+                    }
+                }
+            }
+            if let Some(def) = self.decl(param) {
+                self.bounds.insert(def, bounds);
+                defs.push(def);
+            }
+        }
+        defs
+    }
+
+    fn declare_params(&mut self, params: &'ast [ASTNodeParam]) -> Vec<Type> {
+        params
+            .iter()
+            .map(|param| {
+                let ty = self.annotation(param.ty.as_ref());
+                self.bind(param, ty.clone());
+                ty
+            })
+            .collect()
+    }
+
+    /// The annotated type, or a type variable to infer it.
+    fn annotation(&mut self, ty: Option<&'ast ASTNodeType>) -> Type {
+        match ty {
+            Some(ty) => self.lower_type(ty),
+            None => self.fresh(),
+        }
+    }
+
+    /// The effects of the row `effects`. Names of anything but an effect are
+    /// reported by the effect checker and left out.
+    fn lower_row(&self, effects: &'ast [ASTNodeType]) -> Vec<Type> {
+        let mut row: Vec<_> = (effects.iter())
+            .filter_map(|ty| self.res.uses.get(self.id(ty)).copied())
+            .filter(|&def| {
+                matches!(
+                    self.res.definition(def).kind,
+                    DefKind::Effect | DefKind::Import | DefKind::Use
+                )
+            })
+            .collect();
+        row.sort();
+        row.dedup();
+        (row.into_iter())
+            .map(|def| Type::Effect {
+                name: self.res.definition(def).name.clone(),
+                def,
+            })
+            .collect()
+    }
+
+    /// The type written as `ty`.
+    fn lower_type(&mut self, ty: &'ast ASTNodeType) -> Type {
+        match &ty.kind {
+            ASTTypeKind::Named { name, args } => {
+                let Some(&def_id) = self.res.uses.get(self.id(ty)) else {
+                    return Type::Unknown;
+                };
+                match self.res.definition(def_id).kind {
+                    DefKind::Builtin => match name.as_str() {
+                        "Int" => Type::Int,
+                        "Float" => Type::Float,
+                        "String" => Type::String,
+                        "Bool" => Type::Bool,
+                        _ => Type::Unknown,
+                    },
+                    DefKind::Type => {
+                        let expected = self.type_decls.get(&def_id).map_or(0, |d| d.params.len());
+                        let args: Vec<_> = match args.is_empty() {
+                            true => (0..expected).map(|_| self.fresh()).collect(),
+                            false => args.iter().map(|arg| self.lower_type(arg)).collect(),
+                        };
+                        if args.len() != expected {
+                            self.error(
+                                "wrong-type-argument-count",
+                                ty.loc(),
+                                format!(
+                                    "`{}` takes {} type argument{}, found {}",
+                                    name,
+                                    expected,
+                                    if expected == 1 { "" } else { "s" },
+                                    args.len()
+                                ),
+                            );
+                            return Type::Unknown;
+                        }
+                        if let Some(decl) = self.type_decls.get(&def_id) {
+                            let params = decl.params.clone();
+                            self.require_bounds(&params, &args, ty.loc());
+                        }
+                        Type::Named {
+                            name: name.clone(),
+                            def: def_id,
+                            args,
+                        }
+                    }
+                    DefKind::TypeParam => Type::Param {
+                        name: name.clone(),
+                        def: def_id,
+                    },
+                    DefKind::Effect => Type::Effect {
+                        name: name.clone(),
+                        def: def_id,
+                    },
+                    _ => Type::Unknown,
+                }
+            }
+            ASTTypeKind::Fn {
+                params,
+                ret,
+                effects,
+            } => Type::Fn {
+                params: params.iter().map(|p| self.lower_type(p)).collect(),
+                ret: Box::new(self.lower_type(ret)),
+                effects: self.lower_row(effects),
+            },
+            ASTTypeKind::Optional(item) => Type::Optional(Box::new(self.lower_type(item))),
+            ASTTypeKind::List(item) => Type::List(Box::new(self.lower_type(item))),
+            ASTTypeKind::Tuple(items) => {
+                Type::Tuple(items.iter().map(|item| self.lower_type(item)).collect())
+            }
+            ASTTypeKind::Record(fields) => Type::record(
+                fields
+                    .iter()
+                    .map(|field| (field.name.clone(), self.lower_type(&field.ty)))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Fresh type arguments for the generic type `def`.
+    fn instantiate(&mut self, def: DefId) -> Vec<Type> {
+        let count = self.type_decls.get(&def).map_or(0, |d| d.params.len());
+        (0..count).map(|_| self.fresh()).collect()
+    }
+
+    /// Fresh type arguments for the generic function or view `def` used by
+    /// the node `user`, with its type parameters. Returns `None` if `def` is
+    /// not generic.
+    ///
+    /// The arguments are inferred from the arguments or props `user` gives,
+    /// and recorded in [`Typing::instances`] for monomorphization. Their
+    /// bounds are checked once they are known.
+    fn instantiate_generic(&mut self, def: DefId, user: NodeId) -> Option<(Vec<DefId>, Vec<Type>)> {
+        let params = self.generics.get(&def)?.clone();
+        let args: Vec<_> = params.iter().map(|_| self.fresh()).collect();
+        self.require_bounds(&params, &args, self.ast.get(user).loc());
+        self.typing.instances.insert(user, args.clone());
+        Some((params, args))
+    }
+
+    /// Requires each of `args` to satisfy the bounds of its parameter.
+    fn require_bounds(&mut self, params: &[DefId], args: &[Type], loc: ASTLoc) {
+        for (&param, ty) in params.iter().zip(args) {
+            for &(bound, _) in self.bounds.get(&param).into_iter().flatten() {
+                self.obligations.push(Obligation {
+                    ty: ty.clone(),
+                    bound,
+                    param,
+                    loc,
+                });
+            }
+        }
+    }
+
+    /// Whether the type `ty` satisfies `bound`. Type variables left unknown
+    /// satisfy every bound, as any type could be chosen for them.
+    fn satisfies(&self, ty: &Type, bound: Bound) -> bool {
+        match self.expand(ty) {
+            Type::Int | Type::Float | Type::Var(_) | Type::Unknown => true,
+            Type::String | Type::Bool => bound == Bound::Eq,
+            Type::Tuple(items) => {
+                bound == Bound::Eq && items.iter().all(|t| self.satisfies(t, bound))
+            }
+            Type::List(item) | Type::Optional(item) => {
+                bound == Bound::Eq && self.satisfies(&item, bound)
+            }
+            Type::Record(fields) => {
+                bound == Bound::Eq && fields.iter().all(|(_, t)| self.satisfies(t, bound))
+            }
+            // A declared type is compared by its variants and fields, which
+            // only contain values of its arguments or of other declared types.
+            Type::Named { args, .. } => {
+                bound == Bound::Eq && args.iter().all(|t| self.satisfies(t, bound))
+            }
+            Type::Param { def, .. } => self.has_bound(def, bound),
+            Type::Fn { .. } | Type::Effect { .. } | Type::Element(_) => false,
+        }
+    }
+
+    /// Whether the type parameter `param` is declared with a bound implying
+    /// `bound`.
+    fn has_bound(&self, param: DefId, bound: Bound) -> bool {
+        self.bounds
+            .get(&param)
+            .is_some_and(|bounds| bounds.iter().any(|&(b, _)| b.implies(bound)))
+    }
+
+    /// Reports the type arguments which do not satisfy the bounds of their
+    /// parameters.
+    fn check_obligations(&mut self) {
+        for obligation in std::mem::take(&mut self.obligations) {
+            if self.satisfies(&obligation.ty, obligation.bound) {
+                continue;
+            }
+            let ty = self.zonk(&obligation.ty);
+            let param = self.res.definition(obligation.param);
+            let bound_loc = self.bounds[&obligation.param]
+                .iter()
+                .find(|&&(b, _)| b == obligation.bound)
+                .map_or(param.loc, |&(_, loc)| loc);
+            let diagnostic = Diagnostic::error(
+                "unsatisfied-bound",
+                &self.path,
+                obligation.loc,
+                format!(
+                    "`{}` does not satisfy the bound `{}` of `{}`",
+                    ty, obligation.bound, param.name
+                ),
+            )
+            .with_label(&self.path, bound_loc, "required by this bound");
+            self.typing.diagnostics.push(diagnostic);
+        }
+    }
+
+    /// The type of the value `def` refers to, used by `expr`.
+    fn value_type(&mut self, def: DefId, expr: &'ast ASTNodeExpr) -> Type {
+        let definition = self.res.definition(def);
+        match definition.kind {
+            DefKind::Fn if self.generics.contains_key(&def) => {
+                let ty = self.zonk(&self.typing.bindings[&def]);
+                let Some((params, args)) = self.instantiate_generic(def, self.id(expr)) else {
+                    unreachable!("generic functions are instantiated");
+                };
+                ty.substitute(&params, &args)
+            }
+            DefKind::Variant => {
+                let Some((union, fields)) = self.variant(def) else {
+                    return Type::Unknown;
+                };
+                let name = self.type_decls[&union].name.clone();
+                let args = self.instantiate(union);
+                let params = self.type_decls[&union].params.clone();
+                self.require_bounds(&params, &args, expr.loc());
+                let ty = Type::Named {
+                    name,
+                    def: union,
+                    args: args.clone(),
+                };
+                match fields.is_empty() {
+                    true => ty,
+                    false => Type::Fn {
+                        params: fields
+                            .iter()
+                            .map(|f| f.substitute(&params, &args))
+                            .collect(),
+                        ret: Box::new(ty),
+                        effects: Vec::new(),
+                    },
+                }
+            }
+            DefKind::Effect => Type::Effect {
+                name: definition.name.clone(),
+                def,
+            },
+            DefKind::Builtin => match definition.name.as_str() {
+                "true" | "false" => Type::Bool,
+                _ => Type::Unknown,
+            },
+            _ => self
+                .typing
+                .bindings
+                .get(&def)
+                .cloned()
+                .unwrap_or(Type::Unknown),
+        }
+    }
+
+    /// The union declaring the variant `def`, and the fields of the variant.
+    fn variant(&self, def: DefId) -> Option<(DefId, Vec<Type>)> {
+        let node = self.res.definition(def).node?;
+        let union = *self.decls.get(&self.ast.parent(node)?)?;
+        let DeclBody::Union(variants) = &self.type_decls.get(&union)?.body else {
+            return None;
+        };
+        let variant = variants.iter().find(|v| v.def == def)?;
+        Some((union, variant.fields.clone()))
+    }
+
+    /// Resolves type variables bound at the top of `ty` and expands aliases.
+    fn expand(&self, ty: &Type) -> Type {
+        let mut ty = ty.clone();
+        // Bounds cyclic aliases such as `type A = A`.
+        for _ in 0..64 {
+            ty = match ty {
+                Type::Var(TypeVar(var)) => match &self.vars[var as usize] {
+                    Some(bound) => bound.clone(),
+                    None => return ty,
+                },
+                Type::Named { def, ref args, .. } => match self.type_decls.get(&def) {
+                    Some(TypeDecl {
+                        params,
+                        body: DeclBody::Alias(target),
+                        ..
+                    }) => target.substitute(params, args),
+                    _ => return ty,
+                },
+                _ => return ty,
+            };
+        }
+        Type::Unknown
+    }
+
+    /// Replaces all bound type variables in `ty` by their types.
+    fn zonk(&self, ty: &Type) -> Type {
+        match ty {
+            Type::Var(TypeVar(var)) => match &self.vars[*var as usize] {
+                Some(bound) => self.zonk(bound),
+                None => ty.clone(),
+            },
+            Type::Tuple(items) => Type::Tuple(items.iter().map(|t| self.zonk(t)).collect()),
+            Type::List(item) => Type::List(Box::new(self.zonk(item))),
+            Type::Optional(item) => Type::Optional(Box::new(self.zonk(item))),
+            Type::Record(fields) => Type::Record(
+                fields
+                    .iter()
+                    .map(|(name, ty)| (name.clone(), self.zonk(ty)))
+                    .collect(),
+            ),
+            Type::Fn {
+                params,
+                ret,
+                effects,
+            } => Type::Fn {
+                params: params.iter().map(|t| self.zonk(t)).collect(),
+                ret: Box::new(self.zonk(ret)),
+                effects: effects.clone(),
+            },
+            Type::Named { name, def, args } => Type::Named {
+                name: name.clone(),
+                def: *def,
+                args: args.iter().map(|t| self.zonk(t)).collect(),
+            },
+            _ => ty.clone(),
+        }
+    }
+
+    fn occurs(&self, var: TypeVar, ty: &Type) -> bool {
+        match self.expand(ty) {
+            Type::Var(other) => other == var,
+            Type::Tuple(items) => items.iter().any(|t| self.occurs(var, t)),
+            Type::List(item) | Type::Optional(item) => self.occurs(var, &item),
+            Type::Record(fields) => fields.iter().any(|(_, t)| self.occurs(var, t)),
+            Type::Fn { params, ret, .. } => {
+                params.iter().any(|t| self.occurs(var, t)) || self.occurs(var, &ret)
+            }
+            Type::Named { args, .. } => args.iter().any(|t| self.occurs(var, t)),
+            _ => false,
+        }
+    }
+
+    /// The fields of a record or declared record type.
+    fn record_fields(&self, ty: &Type) -> Option<Vec<(String, Type)>> {
+        match self.expand(ty) {
+            Type::Record(fields) => Some(fields),
+            Type::Named { def, args, .. } => match self.type_decls.get(&def)? {
+                TypeDecl {
+                    params,
+                    body: DeclBody::Record(fields),
+                    ..
+                } => Some(
+                    fields
+                        .iter()
+                        .map(|(name, ty)| (name.clone(), ty.substitute(params, &args)))
+                        .collect(),
+                ),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Makes `a` and `b` the same type by binding type variables.
+    fn unify(&mut self, a: &Type, b: &Type) -> Result<(), ()> {
+        let (a, b) = (self.expand(a), self.expand(b));
+        match (&a, &b) {
+            (Type::Unknown, _) | (_, Type::Unknown) => Ok(()),
+            (Type::Var(x), Type::Var(y)) if x == y => Ok(()),
+            (Type::Var(var), ty) | (ty, Type::Var(var)) => {
+                if self.occurs(*var, ty) {
+                    return Err(());
+                }
+                self.vars[var.0 as usize] = Some(ty.clone());
+                Ok(())
+            }
+            (Type::Tuple(xs), Type::Tuple(ys)) => self.unify_all(xs, ys),
+            (Type::List(x), Type::List(y)) | (Type::Optional(x), Type::Optional(y)) => {
+                self.unify(x, y)
+            }
+            (Type::Record(xs), Type::Record(ys)) => self.unify_fields(xs, ys),
+            (
+                Type::Fn {
+                    params: xs, ret: x, ..
+                },
+                Type::Fn {
+                    params: ys, ret: y, ..
+                },
+            ) => {
+                self.unify_all(xs, ys)?;
+                self.unify(x, y)
+            }
+            (
+                Type::Named {
+                    def: x, args: xs, ..
+                },
+                Type::Named {
+                    def: y, args: ys, ..
+                },
+            ) if x == y => self.unify_all(xs, ys),
+            (Type::Named { .. }, Type::Record(ys)) => match self.record_fields(&a) {
+                Some(xs) => self.unify_fields(&xs, ys),
+                None => Err(()),
+            },
+            (Type::Record(xs), Type::Named { .. }) => match self.record_fields(&b) {
+                Some(ys) => self.unify_fields(xs, &ys),
+                None => Err(()),
+            },
+            (Type::Param { def: x, .. }, Type::Param { def: y, .. })
+            | (Type::Effect { def: x, .. }, Type::Effect { def: y, .. }) => match x == y {
+                true => Ok(()),
+                false => Err(()),
+            },
+            (Type::Element(x), Type::Element(y)) => match x == y {
+                true => Ok(()),
+                false => Err(()),
+            },
+            (Type::Int, Type::Int)
+            | (Type::Float, Type::Float)
+            | (Type::String, Type::String)
+            | (Type::Bool, Type::Bool) => Ok(()),
+            _ => Err(()),
+        }
+    }
+
+    fn unify_all(&mut self, xs: &[Type], ys: &[Type]) -> Result<(), ()> {
+        if xs.len() != ys.len() {
+            return Err(());
+        }
+        for (x, y) in xs.iter().zip(ys) {
+            self.unify(x, y)?;
+        }
+        Ok(())
+    }
+
+    fn unify_fields(&mut self, xs: &[(String, Type)], ys: &[(String, Type)]) -> Result<(), ()> {
+        if xs.len() != ys.len() || xs.iter().zip(ys).any(|(x, y)| x.0 != y.0) {
+            return Err(());
+        }
+        for ((_, x), (_, y)) in xs.iter().zip(ys) {
+            self.unify(x, y)?;
+        }
+        Ok(())
+    }
+
+    /// Checks that a value of type `found` at `loc` may be used where
+    /// `expected` is, reporting a mismatch otherwise.
+    ///
+    /// Besides equal types, a `T` may be used as a `T?` and a record literal
+    /// as a declared record with the same fields.
+    fn coerce(&mut self, expected: Expected, found: &Type, loc: ASTLoc) {
+        let compatible = match (self.expand(expected.ty), self.expand(found)) {
+            (Type::Optional(inner), found)
+                if !matches!(found, Type::Optional(_) | Type::Var(_) | Type::Unknown) =>
+            {
+                self.unify(&inner, &found)
+            }
+            _ => self.unify(expected.ty, found),
+        };
+        if compatible.is_err() {
+            let found = self.zonk(found);
+            self.mismatch(expected, found, loc);
+        }
+    }
+
+    fn mismatch(&mut self, expected: Expected, found: impl fmt::Display, loc: ASTLoc) {
+        let expected_ty = self.zonk(expected.ty);
+        let mut diagnostic = Diagnostic::error(
+            "type-mismatch",
+            &self.path,
+            loc,
+            format!(
+                "mismatched types: expected `{}`, found `{}`",
+                expected_ty, found
+            ),
+        );
+        if let Some(origin) = expected.origin {
+            diagnostic = diagnostic.with_label(
+                &self.path,
+                origin,
+                format!("expected `{}` because of this", expected_ty),
+            );
+        }
+        self.typing.diagnostics.push(diagnostic);
+    }
+
+    /// Checks the statements of a block and returns its type, the type of its
+    /// trailing expression or `()`.
+    fn check_block(&mut self, block: &'ast ASTNodeBlock, expected: Option<Expected>) -> Type {
+        let Some((last, stmts)) = block.stmts.split_last() else {
+            if let Some(expected) = expected {
+                self.coerce(expected, &Type::unit(), block.loc());
+            }
+            return Type::unit();
+        };
+        for stmt in stmts {
+            self.check_stmt(stmt);
+        }
+        match (last, expected) {
+            (ASTNodeStmt::Expr(expr), Some(expected)) => self.check_expr(expr, expected),
+            (ASTNodeStmt::Expr(expr), None) => self.infer_expr(expr),
+            (stmt, expected) => {
+                self.check_stmt(stmt);
+                if let Some(expected) = expected {
+                    self.coerce(expected, &Type::unit(), stmt.loc());
+                }
+                Type::unit()
+            }
+        }
+    }
+
+    fn check_stmt(&mut self, stmt: &'ast ASTNodeStmt) {
+        match stmt {
+            ASTNodeStmt::Let(stmt) => {
+                let ty = self.check_binding(stmt.ty.as_ref(), &stmt.value);
+                self.bind(stmt, ty);
+            }
+            ASTNodeStmt::Const(item) => {
+                let ty = self.check_binding(item.ty.as_ref(), &item.value);
+                self.bind(item, ty);
+            }
+            ASTNodeStmt::With(with) => self.check_with(with),
+            ASTNodeStmt::Effect(block) => {
+                self.check_block(&block.body, None);
+            }
+            ASTNodeStmt::Assign(assign) => {
+                // A binding assigned to may be nil again.
+                if let Some(def) = self.res.uses.get(self.id(&assign.target)) {
+                    self.narrowed.remove(def);
+                }
+                let target = self.infer_expr(&assign.target);
+                self.check_expr(&assign.value, Expected::new(&target));
+            }
+            ASTNodeStmt::Element(element) => self.check_element(element),
+            ASTNodeStmt::Expr(expr) => {
+                self.infer_expr(expr);
+            }
+        }
+    }
+
+    /// Checks the value of a `let` or `const` against its annotation, and
+    /// returns the type of the binding.
+    fn check_binding(&mut self, ty: Option<&'ast ASTNodeType>, value: &'ast ASTNodeExpr) -> Type {
+        match ty {
+            Some(ty) => {
+                let origin = Some(ty.loc());
+                let ty = self.lower_type(ty);
+                self.check_expr(value, Expected { ty: &ty, origin });
+                ty
+            }
+            None => self.infer_expr(value),
+        }
+    }
+
+    /// Gives the parameters of each handler operation the types of the
+    /// operation it handles, and checks its body against the return type.
+    fn check_with(&mut self, with: &'ast ASTNodeWith) {
+        let effect = self
+            .res
+            .resolved(self.id(&with.effect))
+            .filter(|def| def.kind == DefKind::Effect)
+            .and_then(|def| match self.ast.get(def.node?) {
+                NodeRef::Effect(effect) => Some(effect),
+                _ => None,
+            });
+
+        for op in &with.ops {
+            let signature = effect
+                .and_then(|effect| effect.ops.iter().find(|o| o.name == op.name))
+                .map(|declared| self.binding(self.id(declared)));
+            let (params, ret) = match signature {
+                Some(Type::Fn { params, ret, .. }) => (params, *ret),
+                _ => (Vec::new(), Type::Unknown),
+            };
+            for (idx, param) in op.params.iter().enumerate() {
+                let declared = params.get(idx).cloned().unwrap_or(Type::Unknown);
+                let ty = match &param.ty {
+                    Some(annotation) => {
+                        let origin = Some(annotation.loc());
+                        let ty = self.lower_type(annotation);
+                        self.coerce(Expected { ty: &ty, origin }, &declared, param.loc());
+                        ty
+                    }
+                    None => declared,
+                };
+                self.bind(param, ty);
+            }
+            self.check_block(&op.body, Some(Expected::new(&ret)));
+        }
+    }
+
+    fn check_element(&mut self, element: &'ast ASTNodeViewElement) {
+        let enclosing = match is_component(&element.name) {
+            true => {
+                match self.component(element) {
+                    Some(view) => self.check_props(element, view),
+                    None => self.infer_attributes(element),
+                }
+                Enclosing::Opaque
+            }
+            false => self.check_html(element),
+        };
+
+        self.enclosing.push(enclosing);
+        for child in &element.children {
+            match child {
+                ASTNodeViewChild::Element(element) => self.check_element(element),
+                ASTNodeViewChild::Text(expr) | ASTNodeViewChild::Expr(expr) => {
+                    self.infer_expr(expr);
+                }
+            }
+        }
+        self.enclosing.pop();
+    }
+
+    fn infer_attributes(&mut self, element: &'ast ASTNodeViewElement) {
+        for attribute in &element.attributes {
+            if let Some(value) = &attribute.value {
+                self.infer_expr(value);
+            }
+        }
+    }
+
+    /// Checks a builtin element against the HTML schema: its name and the
+    /// names of its attributes must be known, the values of its attributes
+    /// must have the right type, and it must be allowed where it is, so
+    /// `<p><div /></p>` is an error. Misspelled names come with a suggestion.
+    fn check_html(&mut self, element: &'ast ASTNodeViewElement) -> Enclosing {
+        if let Some(Enclosing::Foreign) = self.enclosing.last() {
+            self.infer_attributes(element);
+            return Enclosing::Foreign;
+        }
+        let Some(schema) = html::element(&element.name) else {
+            if !html::is_custom(&element.name) {
+                let message = did_you_mean(
+                    format!("unknown element `<{}>`", element.name),
+                    suggest(&element.name, html::element_names()).map(|name| format!("<{}>", name)),
+                );
+                self.error("unknown-element", element.loc(), message);
+            }
+            self.infer_attributes(element);
+            return Enclosing::Opaque;
+        };
+
+        let mut ancestors: Vec<_> = (self.enclosing.iter().rev())
+            .map_while(|enclosing| match enclosing {
+                Enclosing::Html(element) => Some(*element),
+                _ => None,
+            })
+            .collect();
+        ancestors.reverse();
+        if let Some(message) = html::misplaced(&ancestors, schema) {
+            self.error("invalid-nesting", element.loc(), message);
+        }
+        if schema.content == html::Content::Void && !element.children.is_empty() {
+            let message = format!("`<{}>` cannot have children", element.name);
+            self.error("invalid-nesting", element.loc(), message);
+        }
+
+        if schema.content == html::Content::Foreign {
+            self.infer_attributes(element);
+            return Enclosing::Foreign;
+        }
+        for attribute in &element.attributes {
+            match schema.attribute(&attribute.name) {
+                Some(ty) => self.check_attribute(schema, attribute, ty),
+                None => {
+                    let message = did_you_mean(
+                        format!("`<{}>` has no attribute `{}`", schema.name, attribute.name),
+                        suggest(&attribute.name, schema.attribute_names()),
+                    );
+                    self.error("unknown-attribute", attribute.loc(), message);
+                    if let Some(value) = &attribute.value {
+                        self.infer_expr(value);
+                    }
+                }
+            }
+        }
+        Enclosing::Html(schema)
+    }
+
+    fn check_attribute(
+        &mut self,
+        schema: &html::Element,
+        attribute: &'ast ASTNodeViewAttribute,
+        ty: AttrType,
+    ) {
+        let Some(value) = &attribute.value else {
+            if !matches!(ty, AttrType::Bool | AttrType::Boolish) {
+                let message = format!(
+                    "the attribute `{}` of `<{}>` needs a value",
+                    attribute.name, schema.name
+                );
+                self.error("invalid-attribute-value", attribute.loc(), message);
+            }
+            return;
+        };
+        let keywords: &[&str] = match ty {
+            AttrType::Text => {
+                self.check_expr(value, Expected::new(&Type::String));
+                return;
+            }
+            AttrType::Bool => {
+                self.check_expr(value, Expected::new(&Type::Bool));
+                return;
+            }
+            AttrType::Int => {
+                self.check_expr(value, Expected::new(&Type::Int));
+                return;
+            }
+            AttrType::Float => {
+                self.check_expr(value, Expected::new(&Type::Float));
+                return;
+            }
+            AttrType::Handler => {
+                let found = self.infer_expr(value);
+                if !matches!(
+                    self.expand(&found),
+                    Type::Fn { .. } | Type::Var(_) | Type::Unknown
+                ) {
+                    let found = self.zonk(&found);
+                    let message =
+                        format!("mismatched types: expected a function, found `{}`", found);
+                    self.error("type-mismatch", value.loc(), message);
+                }
+                return;
+            }
+            AttrType::Boolish => {
+                let found = self.infer_expr(value);
+                if matches!(self.expand(&found), Type::Bool | Type::Unknown) {
+                    return;
+                }
+                self.coerce(Expected::new(&Type::String), &found, value.loc());
+                &["true", "false"]
+            }
+            AttrType::Enum(keywords) => {
+                self.check_expr(value, Expected::new(&Type::String));
+                keywords
+            }
+        };
+
+        let ASTExprKind::Literal(literal @ TokenLiteral::StringLiteral(_)) = &value.kind else {
+            return;
+        };
+        let keyword = literal.unquoted();
+        if !keywords.contains(&keyword) {
+            let message = format!(
+                "`{}` is not a valid `{}` of `<{}>`",
+                keyword, attribute.name, schema.name
+            );
+            let message = match suggest(keyword, keywords.iter().copied()) {
+                Some(suggestion) => did_you_mean(message, Some(suggestion)),
+                None => {
+                    let keywords: Vec<_> = keywords.iter().map(|k| format!("`{}`", k)).collect();
+                    format!("{}; expected one of {}", message, keywords.join(", "))
+                }
+            };
+            self.error("invalid-attribute-value", value.loc(), message);
+        }
+    }
+
+    /// The view rendered by `element`, if it is a component declared in this
+    /// module. Reports components naming something else than a view.
+    fn component(&mut self, element: &'ast ASTNodeViewElement) -> Option<&'ast ASTItemView> {
+        let definition = self.res.resolved(self.id(element))?;
+        match (
+            definition.kind,
+            definition.node.map(|node| self.ast.get(node)),
+        ) {
+            (DefKind::View, Some(NodeRef::View(view))) => Some(view),
+            (DefKind::Import | DefKind::Use | DefKind::Builtin, _) => None,
+            (kind, _) => {
+                let message = format!(
+                    "expected a view, found the {} `{}`",
+                    kind.describe(),
+                    definition.name
+                );
+                self.error("not-a-view", element.loc(), message);
+                None
+            }
+        }
+    }
+
+    /// Checks the attributes of `element` against the props of `view`. Every
+    /// attribute must be a parameter of the view and is checked against its
+    /// type, a bare attribute being `true`. Every parameter whose type is not
+    /// optional must be given.
+    fn check_props(&mut self, element: &'ast ASTNodeViewElement, view: &'ast ASTItemView) {
+        let generic = self
+            .decl(view)
+            .and_then(|def| self.instantiate_generic(def, self.id(element)));
+        let prop_type = |c: &mut Self, param: &'ast ASTNodeParam| {
+            let ty = c.binding(c.id(param));
+            match &generic {
+                Some((params, args)) => c.zonk(&ty).substitute(params, args),
+                None => ty,
+            }
+        };
+
+        for attribute in &element.attributes {
+            let Some(param) = view.params.iter().find(|p| p.name == attribute.name) else {
+                let message = format!("`{}` has no prop `{}`", view.name, attribute.name);
+                self.error("unknown-prop", attribute.loc(), message);
+                if let Some(value) = &attribute.value {
+                    self.infer_expr(value);
+                }
+                continue;
+            };
+            let ty = prop_type(self, param);
+            let origin = param.ty.as_ref().map(|ty| ty.loc());
+            let expected = Expected { ty: &ty, origin };
+            match &attribute.value {
+                Some(value) => {
+                    self.check_expr(value, expected);
+                }
+                None => self.coerce(expected, &Type::Bool, attribute.loc()),
+            }
+        }
+
+        for param in &view.params {
+            if element.attributes.iter().any(|a| a.name == param.name) {
+                continue;
+            }
+            let ty = prop_type(self, param);
+            if !matches!(self.expand(&ty), Type::Optional(_)) {
+                let ty = self.zonk(&ty);
+                self.error(
+                    "missing-prop",
+                    element.loc(),
+                    format!(
+                        "missing prop `{}` of type `{}` for `{}`",
+                        param.name, ty, view.name
+                    ),
+                );
+            }
+        }
+    }
+
+    /// Checks `expr` against the expected type and returns its type.
+    fn check_expr(&mut self, expr: &'ast ASTNodeExpr, expected: Expected) -> Type {
+        let target = self.expand(expected.ty);
+        let ty = match (&expr.kind, &target) {
+            (ASTExprKind::Nil, Type::Optional(_)) => expected.ty.clone(),
+            (ASTExprKind::Nil, target) if !matches!(target, Type::Var(_) | Type::Unknown) => {
+                self.mismatch(expected, "nil", expr.loc());
+                Type::Optional(Box::new(expected.ty.clone()))
+            }
+            (ASTExprKind::Record(fields), _) if self.record_fields(&target).is_some() => {
+                let mut declared = self.record_fields(&target).unwrap_or_default();
+                for field in fields {
+                    match declared.iter().position(|(name, _)| *name == field.name) {
+                        Some(idx) => {
+                            let (_, ty) = declared.remove(idx);
+                            let expected = Expected {
+                                ty: &ty,
+                                origin: expected.origin,
+                            };
+                            self.check_expr(&field.value, expected);
+                        }
+                        None => {
+                            let ty = self.zonk(expected.ty);
+                            self.error(
+                                "unknown-field",
+                                field.loc(),
+                                format!("no field `{}` in type `{}`", field.name, ty),
+                            );
+                            self.infer_expr(&field.value);
+                        }
+                    }
+                }
+                for (name, _) in declared {
+                    let ty = self.zonk(expected.ty);
+                    self.error(
+                        "missing-field",
+                        expr.loc(),
+                        format!("missing field `{}` of type `{}`", name, ty),
+                    );
+                }
+                expected.ty.clone()
+            }
+            (ASTExprKind::List(items), Type::List(item)) => {
+                let expected = Expected {
+                    ty: item,
+                    origin: expected.origin,
+                };
+                for item in items {
+                    self.check_expr(item, expected);
+                }
+                target.clone()
+            }
+            (ASTExprKind::Tuple(items), Type::Tuple(tys)) if items.len() == tys.len() => {
+                for (item, ty) in items.iter().zip(tys) {
+                    let expected = Expected {
+                        ty,
+                        origin: expected.origin,
+                    };
+                    self.check_expr(item, expected);
+                }
+                target.clone()
+            }
+            (ASTExprKind::If { cond, then, els }, _) => {
+                self.check_if(expr, cond, then, els.as_deref(), Some(expected))
+            }
+            (ASTExprKind::Block(block), _) => self.check_block(block, Some(expected)),
+            (ASTExprKind::When { subject, arms }, _) => {
+                self.check_when(subject, arms, Some(expected))
+            }
+            _ => {
+                let found = self.infer_expr(expr);
+                self.coerce(expected, &found, expr.loc());
+                return found;
+            }
+        };
+        self.typing.types.insert(self.id(expr), ty.clone());
+        ty
+    }
+
+    /// Infers the type of `expr`.
+    fn infer_expr(&mut self, expr: &'ast ASTNodeExpr) -> Type {
+        let ty = self.infer_kind(expr);
+        self.typing.types.insert(self.id(expr), ty.clone());
+        ty
+    }
+
+    fn infer_kind(&mut self, expr: &'ast ASTNodeExpr) -> Type {
+        match &expr.kind {
+            ASTExprKind::Literal(literal) => literal_type(literal),
+            ASTExprKind::Nil => Type::Optional(Box::new(self.fresh())),
+            ASTExprKind::Identifier(_) => match self.res.uses.get(self.id(expr)) {
+                Some(def) if self.narrowed.contains_key(def) => self.narrowed[def].clone(),
+                Some(&def) => self.value_type(def, expr),
+                None => Type::Unknown,
+            },
+            ASTExprKind::Anchor(_) => {
+                let element = self.res.resolved(self.id(expr)).and_then(|def| def.node);
+                match element.map(|node| self.ast.get(node)) {
+                    Some(NodeRef::Element(element)) => Type::Element(element.name.clone()),
+                    _ => Type::Unknown,
+                }
+            }
+            ASTExprKind::Call { callee, args } => {
+                let callee_ty = self.infer_expr(callee);
+                match self.expand(&callee_ty) {
+                    Type::Fn { params, ret, .. } if params.len() == args.len() => {
+                        for (arg, param) in args.iter().zip(&params) {
+                            self.check_expr(arg, Expected::new(param));
+                        }
+                        *ret
+                    }
+                    Type::Fn { params, ret, .. } => {
+                        self.error(
+                            "wrong-argument-count",
+                            expr.loc(),
+                            format!(
+                                "expected {} argument{}, found {}",
+                                params.len(),
+                                if params.len() == 1 { "" } else { "s" },
+                                args.len()
+                            ),
+                        );
+                        for arg in args {
+                            self.infer_expr(arg);
+                        }
+                        *ret
+                    }
+                    Type::Var(_) => {
+                        let params = args.iter().map(|arg| self.infer_expr(arg)).collect();
+                        let ret = self.fresh();
+                        let ty = Type::Fn {
+                            params,
+                            ret: Box::new(ret.clone()),
+                            effects: Vec::new(),
+                        };
+                        self.coerce(Expected::new(&callee_ty), &ty, callee.loc());
+                        ret
+                    }
+                    Type::Unknown => {
+                        for arg in args {
+                            self.infer_expr(arg);
+                        }
+                        Type::Unknown
+                    }
+                    Type::Optional(_) => {
+                        let callee_ty = self.zonk(&callee_ty);
+                        self.error(
+                            "possibly-nil",
+                            callee.loc(),
+                            format!(
+                                "cannot call a value of type `{}`, which may be nil",
+                                callee_ty
+                            ),
+                        );
+                        for arg in args {
+                            self.infer_expr(arg);
+                        }
+                        Type::Unknown
+                    }
+                    other => {
+                        let other = self.zonk(&other);
+                        self.error(
+                            "not-callable",
+                            callee.loc(),
+                            format!("`{}` is not a function", other),
+                        );
+                        for arg in args {
+                            self.infer_expr(arg);
+                        }
+                        Type::Unknown
+                    }
+                }
+            }
+            ASTExprKind::Member { object, name } => {
+                let object_ty = self.infer_expr(object);
+                self.member_type(&object_ty, name, expr.loc())
+            }
+            ASTExprKind::Binary { op, lhs, rhs } => self.infer_binary(*op, lhs, rhs),
+            ASTExprKind::Unary { op, expr: operand } => match op {
+                ASTUnaryOp::Not => {
+                    self.check_expr(operand, Expected::new(&Type::Bool));
+                    Type::Bool
+                }
+                ASTUnaryOp::Neg => {
+                    let ty = self.infer_expr(operand);
+                    match self.expect_operand(&ty, ASTBinaryOp::Sub, operand.loc()) {
+                        true => ty,
+                        false => Type::Unknown,
+                    }
+                }
+            },
+            ASTExprKind::If { cond, then, els } => {
+                self.check_if(expr, cond, then, els.as_deref(), None)
+            }
+            ASTExprKind::Block(block) => self.check_block(block, None),
+            ASTExprKind::For {
+                pattern,
+                iter,
+                body,
+            } => {
+                let iter_ty = self.infer_expr(iter);
+                let item = match self.expand(&iter_ty) {
+                    Type::List(item) => *item,
+                    Type::Unknown => Type::Unknown,
+                    Type::Var(_) => {
+                        let item = self.fresh();
+                        let list = Type::List(Box::new(item.clone()));
+                        self.coerce(Expected::new(&iter_ty), &list, iter.loc());
+                        item
+                    }
+                    other => {
+                        let other = self.zonk(&other);
+                        self.error(
+                            "not-iterable",
+                            iter.loc(),
+                            format!("cannot iterate over a value of type `{}`", other),
+                        );
+                        Type::Unknown
+                    }
+                };
+                self.check_pattern(pattern, &item);
+                self.check_block(body, None);
+                Type::unit()
+            }
+            ASTExprKind::When { subject, arms } => self.check_when(subject, arms, None),
+            ASTExprKind::Record(fields) => Type::record(
+                fields
+                    .iter()
+                    .map(|field| (field.name.clone(), self.infer_expr(&field.value)))
+                    .collect(),
+            ),
+            ASTExprKind::List(items) => {
+                let Some((first, rest)) = items.split_first() else {
+                    return Type::List(Box::new(self.fresh()));
+                };
+                let item = self.infer_expr(first);
+                for other in rest {
+                    self.check_expr(other, Expected::new(&item));
+                }
+                Type::List(Box::new(item))
+            }
+            ASTExprKind::Tuple(items) => {
+                Type::Tuple(items.iter().map(|item| self.infer_expr(item)).collect())
+            }
+        }
+    }
+
+    /// Checks an `if` expression against `expected`, or infers its type from
+    /// its first branch. The bindings the condition proves not to be nil are
+    /// narrowed in the branch it guards.
+    fn check_if(
+        &mut self,
+        expr: &'ast ASTNodeExpr,
+        cond: &'ast ASTNodeExpr,
+        then: &'ast ASTNodeBlock,
+        els: Option<&'ast ASTNodeExpr>,
+        expected: Option<Expected>,
+    ) -> Type {
+        self.check_expr(cond, Expected::new(&Type::Bool));
+        let (holds, fails) = (self.nil_facts(cond, true), self.nil_facts(cond, false));
+        let ty = self.narrowed(holds, |c| c.check_block(then, expected));
+        match (els, expected) {
+            (Some(els), expected) => {
+                let expected = expected.unwrap_or(Expected::new(&ty));
+                self.narrowed(fails, |c| c.check_expr(els, expected));
+            }
+            (None, Some(expected)) => self.coerce(expected, &Type::unit(), expr.loc()),
+            (None, None) => return Type::unit(),
+        }
+        match expected {
+            Some(expected) => expected.ty.clone(),
+            None => ty,
+        }
+    }
+
+    /// Checks the arms of a `when` expression against `expected`, or against
+    /// the type of the first arm. Arms following a `nil` arm and arms whose
+    /// pattern only matches values see an optional subject without nil.
+    fn check_when(
+        &mut self,
+        subject: &'ast ASTNodeExpr,
+        arms: &'ast [ASTNodeWhenArm],
+        expected: Option<Expected>,
+    ) -> Type {
+        let subject_ty = self.infer_expr(subject);
+        let non_nil = match self.expand(&subject_ty) {
+            Type::Optional(inner) => Some(*inner),
+            _ => None,
+        };
+        let fact = self.non_nil(subject);
+        let origin = expected.and_then(|expected| expected.origin);
+        let mut result = expected.map(|expected| expected.ty.clone());
+        let mut nil_matched = false;
+
+        for arm in arms {
+            let matches_values = matches!(
+                arm.pattern.kind,
+                ASTPatternKind::Literal(_)
+                    | ASTPatternKind::Variant { .. }
+                    | ASTPatternKind::Tuple(_)
+            );
+            let narrow = nil_matched || matches_values;
+            let pattern_ty = match &non_nil {
+                Some(inner) if narrow => inner.clone(),
+                _ => subject_ty.clone(),
+            };
+            self.check_pattern(&arm.pattern, &pattern_ty);
+
+            let facts = fact.clone().filter(|_| narrow).into_iter().collect();
+            let ty = self.narrowed(facts, |c| match &result {
+                Some(ty) => c.check_expr(&arm.body, Expected { ty, origin }),
+                None => c.infer_expr(&arm.body),
+            });
+            result.get_or_insert(ty);
+            nil_matched |= matches!(arm.pattern.kind, ASTPatternKind::Nil);
+        }
+        result.unwrap_or_else(Type::unit)
+    }
+
+    /// Runs `f` with the bindings in `facts` narrowed to their types.
+    fn narrowed<R>(&mut self, facts: Vec<(DefId, Type)>, f: impl FnOnce(&mut Self) -> R) -> R {
+        let saved: Vec<_> = facts
+            .iter()
+            .map(|(def, _)| (*def, self.narrowed.get(def).cloned()))
+            .collect();
+        self.narrowed.extend(facts);
+        let result = f(self);
+        for (def, previous) in saved {
+            match previous {
+                Some(ty) => self.narrowed.insert(def, ty),
+                None => self.narrowed.remove(&def),
+            };
+        }
+        result
+    }
+
+    /// The optional bindings the checked condition `cond` proves not to be
+    /// nil when it evaluates to `holds`, with their types without nil.
+    fn nil_facts(&self, cond: &'ast ASTNodeExpr, holds: bool) -> Vec<(DefId, Type)> {
+        match &cond.kind {
+            ASTExprKind::Binary {
+                op: op @ (ASTBinaryOp::Eq | ASTBinaryOp::NotEq),
+                lhs,
+                rhs,
+            } => {
+                let subject = match (&lhs.kind, &rhs.kind) {
+                    (_, ASTExprKind::Nil) => lhs,
+                    (ASTExprKind::Nil, _) => rhs,
+                    _ => return Vec::new(),
+                };
+                match (*op == ASTBinaryOp::NotEq) == holds {
+                    true => self.non_nil(subject).into_iter().collect(),
+                    false => Vec::new(),
+                }
+            }
+            ASTExprKind::Binary {
+                op: op @ (ASTBinaryOp::And | ASTBinaryOp::Or),
+                lhs,
+                rhs,
+            } if (*op == ASTBinaryOp::And) == holds => {
+                let mut facts = self.nil_facts(lhs, holds);
+                facts.extend(self.nil_facts(rhs, holds));
+                facts
+            }
+            ASTExprKind::Unary {
+                op: ASTUnaryOp::Not,
+                expr,
+            } => self.nil_facts(expr, !holds),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The binding named by the checked expression `expr` with its type
+    /// without nil, if it is an optional binding.
+    fn non_nil(&self, expr: &'ast ASTNodeExpr) -> Option<(DefId, Type)> {
+        let ASTExprKind::Identifier(_) = expr.kind else {
+            return None;
+        };
+        let id = self.id(expr);
+        let def = *self.res.uses.get(id)?;
+        match self.expand(self.typing.types.get(id)?) {
+            Type::Optional(inner) => Some((def, *inner)),
+            _ => None,
+        }
+    }
+
+    fn member_type(&mut self, object: &Type, name: &str, loc: ASTLoc) -> Type {
+        if let Type::Optional(inner) = self.expand(object) {
+            let object = self.zonk(object);
+            self.error(
+                "possibly-nil",
+                loc,
+                format!(
+                    "cannot access `{}` on a value of type `{}`, which may be nil",
+                    name, object
+                ),
+            );
+            return match self.expand(&inner) {
+                Type::Var(_) => Type::Unknown,
+                _ => self.member_type(&inner, name, loc),
+            };
+        }
+        if let Some(fields) = self.record_fields(object) {
+            if let Some((_, ty)) = fields.into_iter().find(|(field, _)| field == name) {
+                return ty;
+            }
+        } else {
+            match self.expand(object) {
+                Type::Unknown | Type::Var(_) => return Type::Unknown,
+                Type::Effect { def, .. } => {
+                    let op =
+                        self.res
+                            .definition(def)
+                            .node
+                            .and_then(|node| match self.ast.get(node) {
+                                NodeRef::Effect(effect) => {
+                                    effect.ops.iter().find(|op| op.name == name)
+                                }
+                                _ => None,
+                            });
+                    if let Some(op) = op {
+                        return self.binding(self.id(op));
+                    }
+                }
+                Type::Element(tag) => {
+                    if let Some(ty) = element_member(&tag, name) {
+                        return ty;
+                    }
+                }
+                _ => {}
+            }
+        }
+        let object = self.zonk(object);
+        self.error(
+            "unknown-field",
+            loc,
+            format!("no field `{}` on type `{}`", name, object),
+        );
+        Type::Unknown
+    }
+
+    fn infer_binary(
+        &mut self,
+        op: ASTBinaryOp,
+        lhs: &'ast ASTNodeExpr,
+        rhs: &'ast ASTNodeExpr,
+    ) -> Type {
+        match op {
+            ASTBinaryOp::And | ASTBinaryOp::Or => {
+                self.check_expr(lhs, Expected::new(&Type::Bool));
+                let facts = self.nil_facts(lhs, op == ASTBinaryOp::And);
+                self.narrowed(facts, |c| c.check_expr(rhs, Expected::new(&Type::Bool)));
+                Type::Bool
+            }
+            ASTBinaryOp::Eq | ASTBinaryOp::NotEq => {
+                let ty = self.infer_expr(lhs);
+                if let Type::Param { def, .. } = self.expand(&ty) {
+                    self.expect_bound(&ty, def, op, Bound::Eq, lhs.loc());
+                }
+                self.check_expr(rhs, Expected::new(&ty));
+                Type::Bool
+            }
+            ASTBinaryOp::Lt | ASTBinaryOp::LtEq | ASTBinaryOp::Gt | ASTBinaryOp::GtEq => {
+                self.infer_operands(op, lhs, rhs);
+                Type::Bool
+            }
+            ASTBinaryOp::Add
+            | ASTBinaryOp::Sub
+            | ASTBinaryOp::Mul
+            | ASTBinaryOp::Div
+            | ASTBinaryOp::Rem => self.infer_operands(op, lhs, rhs),
+        }
+    }
+
+    /// Checks the operands of an arithmetic or comparison operator and
+    /// returns their type. After an invalid left operand, the right one is
+    /// only inferred and the type is [`Type::Unknown`], so the operator is
+    /// reported once rather than again wherever its result is used.
+    fn infer_operands(
+        &mut self,
+        op: ASTBinaryOp,
+        lhs: &'ast ASTNodeExpr,
+        rhs: &'ast ASTNodeExpr,
+    ) -> Type {
+        let ty = self.infer_expr(lhs);
+        match self.expect_operand(&ty, op, lhs.loc()) {
+            true => {
+                self.check_expr(rhs, Expected::new(&ty));
+                ty
+            }
+            false => {
+                self.infer_expr(rhs);
+                Type::Unknown
+            }
+        }
+    }
+
+    /// Reports an operand of an arithmetic or comparison operator which is
+    /// not a number, or a string for `+`, and returns whether it was valid.
+    /// A value of a type parameter must be bounded by `Num` for arithmetic,
+    /// or by `Ord` for comparisons.
+    fn expect_operand(&mut self, ty: &Type, op: ASTBinaryOp, loc: ASTLoc) -> bool {
+        match self.expand(ty) {
+            Type::Int | Type::Float | Type::Var(_) | Type::Unknown => true,
+            Type::String if op == ASTBinaryOp::Add => true,
+            Type::Param { def, .. } => {
+                let bound = match op {
+                    ASTBinaryOp::Lt | ASTBinaryOp::LtEq | ASTBinaryOp::Gt | ASTBinaryOp::GtEq => {
+                        Bound::Ord
+                    }
+                    _ => Bound::Num,
+                };
+                self.expect_bound(ty, def, op, bound, loc)
+            }
+            other => {
+                let other = self.zonk(&other);
+                self.error(
+                    "invalid-operand",
+                    loc,
+                    format!(
+                        "cannot apply `{}` to a value of type `{}`",
+                        op.as_str(),
+                        other
+                    ),
+                );
+                false
+            }
+        }
+    }
+
+    /// Reports an operand of `op` whose type is the parameter `param`, if
+    /// the parameter is not declared with `bound`, and returns whether it is.
+    fn expect_bound(
+        &mut self,
+        ty: &Type,
+        param: DefId,
+        op: ASTBinaryOp,
+        bound: Bound,
+        loc: ASTLoc,
+    ) -> bool {
+        if self.has_bound(param, bound) {
+            return true;
+        }
+        let ty = self.zonk(ty);
+        self.error(
+            "invalid-operand",
+            loc,
+            format!(
+                "cannot apply `{}` to a value of type `{}`, which is not bounded by `{}`",
+                op.as_str(),
+                ty,
+                bound
+            ),
+        );
+        false
+    }
+
+    /// Checks that `pattern` can match a value of type `ty`, and gives its
+    /// bindings their types.
+    fn check_pattern(&mut self, pattern: &'ast ASTNodePattern, ty: &Type) {
+        match &pattern.kind {
+            ASTPatternKind::Wildcard => {}
+            ASTPatternKind::Binding(_) => self.bind(pattern, ty.clone()),
+            ASTPatternKind::Literal(literal) => {
+                let found = literal_type(literal);
+                self.coerce(Expected::new(ty), &found, pattern.loc());
+            }
+            ASTPatternKind::Nil => {
+                let found = Type::Optional(Box::new(self.fresh()));
+                self.coerce(Expected::new(ty), &found, pattern.loc());
+            }
+            ASTPatternKind::Variant { name, fields } => {
+                let variant = self
+                    .res
+                    .uses
+                    .get(self.id(pattern))
+                    .filter(|&&def| self.res.definition(def).kind == DefKind::Variant)
+                    .and_then(|&def| self.variant(def));
+                let Some((union, declared)) = variant else {
+                    for field in fields {
+                        self.check_pattern(field, &Type::Unknown);
+                    }
+                    return;
+                };
+
+                let args = self.instantiate(union);
+                let found = Type::Named {
+                    name: self.type_decls[&union].name.clone(),
+                    def: union,
+                    args: args.clone(),
+                };
+                self.coerce(Expected::new(ty), &found, pattern.loc());
+                if fields.len() != declared.len() {
+                    self.error(
+                        "wrong-field-count",
+                        pattern.loc(),
+                        format!(
+                            "`{}` has {} field{}, found {}",
+                            name,
+                            declared.len(),
+                            if declared.len() == 1 { "" } else { "s" },
+                            fields.len()
+                        ),
+                    );
+                }
+                let params = self.type_decls[&union].params.clone();
+                for (idx, field) in fields.iter().enumerate() {
+                    let field_ty = declared
+                        .get(idx)
+                        .map_or(Type::Unknown, |ty| ty.substitute(&params, &args));
+                    self.check_pattern(field, &field_ty);
+                }
+            }
+            ASTPatternKind::Tuple(items) => {
+                let tys = match self.expand(ty) {
+                    Type::Tuple(tys) if tys.len() == items.len() => tys,
+                    _ => {
+                        let tys: Vec<_> = items.iter().map(|_| self.fresh()).collect();
+                        self.coerce(Expected::new(ty), &Type::Tuple(tys.clone()), pattern.loc());
+                        tys
+                    }
+                };
+                for (item, ty) in items.iter().zip(&tys) {
+                    self.check_pattern(item, ty);
+                }
+            }
+        }
+    }
+}
+
+/// An element enclosing the element being checked.
+#[derive(Clone, Copy)]
+enum Enclosing {
+    Html(&'static html::Element),
+    /// A component or a custom element, whose children may be placed
+    /// anywhere.
+    Opaque,
+    /// An element of another vocabulary, e.g. `svg`, whose content is not
+    /// checked.
+    Foreign,
+}
+
+/// Appends the suggestion to replace a misspelled name by `suggestion` to
+/// `message`.
+fn did_you_mean(message: String, suggestion: Option<impl fmt::Display>) -> String {
+    match suggestion {
+        Some(suggestion) => format!("{}; did you mean `{}`?", message, suggestion),
+        None => message,
+    }
+}
+
+fn literal_type(literal: &TokenLiteral) -> Type {
+    match literal {
+        TokenLiteral::StringLiteral(_) => Type::String,
+        TokenLiteral::NumberLiteral(_) if literal.content().contains('.') => Type::Float,
+        TokenLiteral::NumberLiteral(_) => Type::Int,
+    }
+}
+
+/// The type of the member `name` of an element with the tag `tag`,
+/// referenced through its anchor.
+fn element_member(tag: &str, name: &str) -> Option<Type> {
+    let method = || Type::Fn {
+        params: Vec::new(),
+        ret: Box::new(Type::unit()),
+        effects: Vec::new(),
+    };
+    let field = matches!(tag, "input" | "textarea" | "select");
+    let ty = match name {
+        "focus" | "blur" | "click" | "scrollIntoView" => method(),
+        "select" if matches!(tag, "input" | "textarea") => method(),
+        "value" if field => Type::String,
+        "checked" if tag == "input" => Type::Bool,
+        "disabled" if field || tag == "button" => Type::Bool,
+        _ => return None,
+    };
+    Some(ty)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lang::resolve::test::with_resolution;
+
+    struct Checked {
+        /// The type of each binding by name, as written.
+        bindings: HashMap<String, String>,
+        diagnostics: Vec<Diagnostic>,
+    }
+
+    fn check(source: &str) -> Checked {
+        with_resolution(source, |ast, resolution| {
+            assert_eq!(resolution.diagnostics, [], "failed to resolve");
+            let typing = TypeChecker::new("main.rbn", ast, resolution).check();
+
+            for id in ast.ids() {
+                if let NodeRef::Expr(_) = ast.get(id) {
+                    assert!(typing.types.contains(id), "untyped {:?}", ast.get(id));
+                }
+            }
+            let bindings = typing
+                .bindings
+                .iter()
+                .map(|(&def, ty)| (resolution.definition(def).name.clone(), ty.to_string()))
+                .collect();
+            Checked {
+                bindings,
+                diagnostics: typing.diagnostics,
+            }
+        })
+    }
+
+    fn codes(diagnostics: &[Diagnostic]) -> Vec<&'static str> {
+        diagnostics.iter().map(|d| d.code).collect()
+    }
+
+    #[test]
+    fn infers_bindings() {
+        let checked = check(
+            "const LIMIT = 10
+fn scale(x: Float, by) { x * by }
+fn main() {
+  let a = LIMIT + 1
+  let s = \"a\" + \"b\"
+  let xs = [1.0, scale(2.0, 3.0)]
+  let p = { y: true, x: \"a\" }
+  let f = scale
+  let t = (a, if a > 1 { s } else { \"c\" })
+  for (i, c) in [(1, \"a\")] { }
+}",
+        );
+        assert_eq!(checked.diagnostics, []);
+        for (name, ty) in [
+            ("LIMIT", "Int"),
+            ("scale", "fn(Float, Float) -> Float"),
+            ("by", "Float"),
+            ("a", "Int"),
+            ("s", "String"),
+            ("xs", "[Float]"),
+            ("p", "{ x: String, y: Bool }"),
+            ("f", "fn(Float, Float) -> Float"),
+            ("t", "(Int, String)"),
+            ("i", "Int"),
+            ("c", "String"),
+        ] {
+            assert_eq!(checked.bindings[name], ty, "type of `{}`", name);
+        }
+    }
+
+    #[test]
+    fn instantiates_generics() {
+        let checked = check(
+            "type Option<T> = Some(T) | None
+type Pair<A, B> = { first: A, second: B }
+type Name = String
+fn main() {
+  let a = Some(1)
+  let b: Option<Name> = None
+  let p: Pair<Int, Name> = { first: 1, second: \"x\" }
+  let s = p.second
+  let n = when a {
+    Some(x) -> x
+    None -> 0
+  }
+}",
+        );
+        assert_eq!(checked.diagnostics, []);
+        for (name, ty) in [
+            ("a", "Option<Int>"),
+            ("b", "Option<Name>"),
+            ("p", "Pair<Int, Name>"),
+            ("s", "Name"),
+            ("x", "Int"),
+            ("n", "Int"),
+        ] {
+            assert_eq!(checked.bindings[name], ty, "type of `{}`", name);
+        }
+    }
+
+    #[test]
+    fn checks_effect_rows() {
+        let checked = check(
+            "effect Log { fn log(msg: String) }
+fn noisy() emits Log { log(\"noise\") }
+fn quiet() { }
+fn pure(k: fn() -> ()) { k() }
+fn run(k: fn() -> () emits Log) emits Log { k() }
+fn main() {
+  let op = log
+  let fs: [fn() -> ()] = [noisy]
+  run(quiet)
+  run(noisy)
+  pure(quiet)
+  pure(noisy)
+}",
+        );
+        // Passing `noisy` to `pure` is left to the effect checker.
+        assert_eq!(checked.diagnostics, []);
+        assert_eq!(checked.bindings["fs"], "[fn() -> ()]");
+        assert_eq!(checked.bindings["op"], "fn(String) -> () emits Log");
+        assert_eq!(checked.bindings["noisy"], "fn() -> () emits Log");
+    }
+
+    #[test]
+    fn infers_type_arguments() {
+        let checked = check(
+            "fn first<T>(items: [T]) -> T? { nil }
+fn map<T, U>(items: [T], f: fn(T) -> U) -> [U] { [] }
+fn max<T: Ord>(a: T, b: T) -> T { if a > b { a } else { b } }
+fn show(n: Int) -> String { \"n\" }
+view List<T>(items: [T], label: fn(T) -> String) { <ul></ul> }
+view Page() {
+  let ints = first([1, 2])
+  let strings = first([\"x\"])
+  let shown = map([1, 2], show)
+  let larger = max(1.5, 2.0)
+  <List items={[1]} label={show} />
+  <List items={[true]} label={show} />
+}",
+        );
+        assert_eq!(codes(&checked.diagnostics), ["type-mismatch"]);
+        assert_eq!(
+            checked.diagnostics[0].message,
+            "mismatched types: expected `fn(Bool) -> String`, found `fn(Int) -> String`"
+        );
+        for (name, ty) in [
+            ("first", "fn([T]) -> T?"),
+            ("ints", "Int?"),
+            ("strings", "String?"),
+            ("shown", "[String]"),
+            ("larger", "Float"),
+        ] {
+            assert_eq!(checked.bindings[name], ty, "type of `{}`", name);
+        }
+    }
+
+    #[test]
+    fn checks_bounds() {
+        let checked = check(
+            "type Sorted<T: Ord> = [T]
+fn max<T: Ord>(a: T, b: T) -> T { if a > b { a } else { b } }
+fn sum<T: Num>(xs: [T], zero: T) -> T { zero + zero }
+fn top<T: Num>(a: T, b: T) -> T { max(a, b) }
+fn same<T>(a: T, b: T) -> Bool { a == b }
+fn less<T: Eq>(a: T, b: T) -> Bool { a < b }
+fn wrap<T: Nmu>(a: T) -> T { a }
+fn main() {
+  let a = max(\"a\", \"b\")
+  let b = sum([true], false)
+  let c: Sorted<Bool> = []
+  let d = max((1, 2.5), (3, 4.5))
+}",
+        );
+        let messages: Vec<_> = (checked.diagnostics.iter())
+            .map(|d| (d.code, d.message.as_str()))
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                ("unknown-bound", "unknown bound `Nmu`; did you mean `Num`?"),
+                (
+                    "invalid-operand",
+                    "cannot apply `==` to a value of type `T`, which is not bounded by `Eq`"
+                ),
+                (
+                    "invalid-operand",
+                    "cannot apply `<` to a value of type `T`, which is not bounded by `Ord`"
+                ),
+                (
+                    "unsatisfied-bound",
+                    "`String` does not satisfy the bound `Ord` of `T`"
+                ),
+                (
+                    "unsatisfied-bound",
+                    "`Bool` does not satisfy the bound `Num` of `T`"
+                ),
+                (
+                    "unsatisfied-bound",
+                    "`Bool` does not satisfy the bound `Ord` of `T`"
+                ),
+                (
+                    "unsatisfied-bound",
+                    "`(Int, Float)` does not satisfy the bound `Ord` of `T`"
+                ),
+            ]
+        );
+        assert_eq!(
+            checked.diagnostics[3].labels[0].message,
+            "required by this bound"
+        );
+    }
+
+    #[test]
+    fn reports_invalid_operands_once() {
+        let checked = check(
+            "fn f(x: Int?) -> Int { x + 1 }
+fn g(s: Bool) -> Int { -s }
+fn h(b: Bool) -> Bool { b < 1 && true }
+fn main() {
+  let n: String = true * 2
+}",
+        );
+        let messages: Vec<_> = (checked.diagnostics.iter())
+            .map(|d| (d.code, d.message.as_str()))
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                (
+                    "invalid-operand",
+                    "cannot apply `+` to a value of type `Int?`"
+                ),
+                (
+                    "invalid-operand",
+                    "cannot apply `-` to a value of type `Bool`"
+                ),
+                (
+                    "invalid-operand",
+                    "cannot apply `<` to a value of type `Bool`"
+                ),
+                (
+                    "invalid-operand",
+                    "cannot apply `*` to a value of type `Bool`"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_mismatches() {
+        let source = "type Point = { x: Float, y: Float }
+fn add(a: Int, b: Int) -> Int { a + b }
+fn main() {
+  let p: Point = { x: 1.0, y: \"no\" }
+  let q: Point = { x: 1.0, z: 2.0 }
+  let n = add(1)
+  let m = add(1, 2.5)
+  let s = -\"text\"
+  if 1 { }
+  n(2)
+  p.z
+}
+fn name() -> String { 1 }";
+        let checked = check(source);
+        assert_eq!(
+            codes(&checked.diagnostics),
+            [
+                "type-mismatch",
+                "unknown-field",
+                "missing-field",
+                "wrong-argument-count",
+                "type-mismatch",
+                "invalid-operand",
+                "type-mismatch",
+                "not-callable",
+                "unknown-field",
+                "type-mismatch",
+            ]
+        );
+
+        let field = &checked.diagnostics[0];
+        assert_eq!(
+            field.message,
+            "mismatched types: expected `Float`, found `String`"
+        );
+        assert_eq!(
+            &source[field.loc.start as usize..field.loc.end as usize],
+            "\"no\""
+        );
+        assert_eq!(field.labels[0].message, "expected `Float` because of this");
+        assert_eq!(
+            checked.diagnostics[2].message,
+            "missing field `y` of type `Point`"
+        );
+        assert_eq!(
+            checked.diagnostics[3].message,
+            "expected 2 arguments, found 1"
+        );
+        assert_eq!(
+            checked.diagnostics[6].message,
+            "mismatched types: expected `Bool`, found `Int`"
+        );
+        let ret = checked.diagnostics.last().unwrap();
+        assert_eq!(
+            ret.message,
+            "mismatched types: expected `String`, found `Int`"
+        );
+        let label = ret.labels[0].loc;
+        assert_eq!(&source[label.start as usize..label.end as usize], "String");
+    }
+
+    #[test]
+    fn effects_and_optionals() {
+        let checked = check(
+            "effect Log { fn log(msg: String) -> Int }
+fn run() emits Log {
+  let n = log(\"a\")
+  let m = Log.log(\"b\")
+  let o: Int? = n + m
+  let none: String? = nil
+  o == nil
+}
+fn main() {
+  with Log { fn log(msg) { msg } }
+  let b: Bool = run()
+}",
+        );
+        assert_eq!(checked.bindings["n"], "Int");
+        assert_eq!(checked.bindings["m"], "Int");
+        assert_eq!(checked.bindings["o"], "Int?");
+        assert_eq!(checked.bindings["msg"], "String");
+        assert_eq!(checked.bindings["run"], "fn() -> Bool emits Log");
+        assert_eq!(codes(&checked.diagnostics), ["type-mismatch"]);
+        assert_eq!(
+            checked.diagnostics[0].message,
+            "mismatched types: expected `Int`, found `String`"
+        );
+    }
+
+    #[test]
+    fn types_anchors() {
+        let checked = check(
+            "view Form() {
+  let focus = #name.focus
+  let text = #name.value
+  let button = #submit
+  effect { #submit.value }
+  <input#name />
+  <button#submit disabled={#name.value == \"\"} />
+}",
+        );
+        assert_eq!(checked.bindings["focus"], "fn() -> ()");
+        assert_eq!(checked.bindings["text"], "String");
+        assert_eq!(checked.bindings["button"], "Element<button>");
+        assert_eq!(codes(&checked.diagnostics), ["unknown-field"]);
+        assert_eq!(
+            checked.diagnostics[0].message,
+            "no field `value` on type `Element<button>`"
+        );
+    }
+
+    #[test]
+    fn checks_props() {
+        let checked = check(
+            "import { Link } from \"./link\"
+const Title = \"x\"
+view Counter(start: Int, label: String?, compact: Bool) { }
+view App() {
+  <Counter start={1} compact />
+  <Counter start=\"1\" label={nil} compact={false} size={2} />
+  <Counter label=\"x\" />
+  <Title />
+  <Link to=\"/\" />
+  <div class=\"x\" />
+}",
+        );
+        assert_eq!(
+            codes(&checked.diagnostics),
+            [
+                "type-mismatch",
+                "unknown-prop",
+                "missing-prop",
+                "missing-prop",
+                "not-a-view",
+            ]
+        );
+        let messages: Vec<_> = checked.diagnostics.iter().map(|d| &d.message).collect();
+        assert_eq!(
+            messages,
+            [
+                "mismatched types: expected `Int`, found `String`",
+                "`Counter` has no prop `size`",
+                "missing prop `start` of type `Int` for `Counter`",
+                "missing prop `compact` of type `Bool` for `Counter`",
+                "expected a view, found the constant `Title`",
+            ]
+        );
+        assert_eq!(
+            checked.diagnostics[0].labels[0].message,
+            "expected `Int` because of this"
+        );
+    }
+
+    #[test]
+    fn checks_html() {
+        let checked = check(
+            "fn save() { }
+view Form(count: Int) {
+  <form onsubmit={save}>
+    <p class=\"intro\"><div /></p>
+    <ul><li><a href=\"/\" target=\"_blank\">\"Home\"</a></li><span /></ul>
+    <dvi />
+    <input type=\"chekbox\" checked aria-hidden={true} plaecholder=\"Name\" />
+    <button type=\"submit\" disabled={count} onclick={count} tabindex={count}>
+      <a href />
+    </button>
+    <img src=\"/a.png\" aria-live=\"loud\">\"alt\"</img>
+    <my-widget anything=\"x\"><td /></my-widget>
+    <svg viewBox=\"0 0 1 1\"><path d=\"M0\" /></svg>
+  </form>
+}",
+        );
+        let messages: Vec<_> = (checked.diagnostics.iter())
+            .map(|d| (d.code, d.message.as_str()))
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                ("invalid-nesting", "`<div>` cannot be inside `<p>`"),
+                ("invalid-nesting", "`<span>` cannot be inside `<ul>`"),
+                (
+                    "unknown-element",
+                    "unknown element `<dvi>`; did you mean `<div>`?"
+                ),
+                (
+                    "invalid-attribute-value",
+                    "`chekbox` is not a valid `type` of `<input>`; did you mean `checkbox`?"
+                ),
+                (
+                    "unknown-attribute",
+                    "`<input>` has no attribute `plaecholder`; did you mean `placeholder`?"
+                ),
+                (
+                    "type-mismatch",
+                    "mismatched types: expected `Bool`, found `Int`"
+                ),
+                (
+                    "type-mismatch",
+                    "mismatched types: expected a function, found `Int`"
+                ),
+                ("invalid-nesting", "`<a>` cannot be inside `<button>`"),
+                (
+                    "invalid-attribute-value",
+                    "the attribute `href` of `<a>` needs a value"
+                ),
+                ("invalid-nesting", "`<img>` cannot have children"),
+                (
+                    "invalid-attribute-value",
+                    "`loud` is not a valid `aria-live` of `<img>`; \
+                     expected one of `assertive`, `off`, `polite`"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn narrows_optionals() {
+        let source = "type User = { name: String }
+fn find(id: Int) -> User? { nil }
+fn main() {
+  let user = find(1)
+  let a = user.name
+  if user != nil { let b = user.name }
+  if !(user == nil) { let c = user.name } else { let f = user.name }
+  let d = user != nil && user.name == \"x\"
+  let e = user == nil || user.name == \"x\"
+  let n = when user {
+    nil -> \"guest\"
+    u -> u.name
+  }
+  if user != nil {
+    user = find(2)
+    user.name
+  }
+  let count: Int = nil
+  find(2).name
+}";
+        let checked = check(source);
+        assert_eq!(checked.bindings["user"], "User?");
+        assert_eq!(checked.bindings["b"], "String");
+        assert_eq!(checked.bindings["c"], "String");
+        assert_eq!(checked.bindings["u"], "User");
+        assert_eq!(checked.bindings["n"], "String");
+
+        let reported: Vec<_> = checked
+            .diagnostics
+            .iter()
+            .map(|d| (d.code, &source[d.loc.start as usize..d.loc.end as usize]))
+            .collect();
+        assert_eq!(
+            reported,
+            [
+                ("possibly-nil", "user.name"),
+                ("possibly-nil", "user.name"),
+                ("possibly-nil", "user.name"),
+                ("type-mismatch", "nil"),
+                ("possibly-nil", "find(2).name"),
+            ]
+        );
+        assert_eq!(
+            checked.diagnostics[0].message,
+            "cannot access `name` on a value of type `User?`, which may be nil"
+        );
+        assert_eq!(
+            checked.diagnostics[3].message,
+            "mismatched types: expected `Int`, found `nil`"
+        );
+    }
+}
@@ -0,0 +1,1003 @@
+//! Traversal of the AST.
+//!
+//! [`Visitor`] walks a tree by shared reference and [`VisitorMut`] by mutable
+//! reference. Every method of both traits defaults to the matching `walk_*`
+//! function, which visits the children of the node in source order, so an
+//! implementation only overrides the nodes it cares about. An overriding
+//! method calls the `walk_*` function itself to keep descending.
+//!
+//! ```
+//! use shigure_parser::lang::{
+//!     ast::{ASTExprKind, ASTNodeExpr},
+//!     parser::{module::ModuleParser, Parser},
+//!     visit::{walk_expr, Visitor},
+//! };
+//!
+//! #[derive(Default)]
+//! struct Names<'ast>(Vec<&'ast str>);
+//!
+//! impl<'ast> Visitor<'ast> for Names<'ast> {
+//!     fn visit_expr(&mut self, expr: &'ast ASTNodeExpr) {
+//!         if let ASTExprKind::Identifier(name) = &expr.kind {
+//!             self.0.push(name);
+//!         }
+//!         walk_expr(self, expr);
+//!     }
+//! }
+//!
+//! let module = ModuleParser::from_str("const A = b + c(d)").parse_all().unwrap();
+//! let mut names = Names::default();
+//! names.visit_module(&module);
+//! assert_eq!(names.0, ["b", "c", "d"]);
+//! ```
+
+use crate::lang::ast::{
+    ASTExprKind, ASTItemConst, ASTItemEffect, ASTItemFn, ASTItemImport, ASTItemType, ASTItemUse,
+    ASTItemView, ASTNodeAssign, ASTNodeBlock, ASTNodeEffectBlock, ASTNodeEffectOp, ASTNodeExpr,
+    ASTNodeHandlerOp, ASTNodeImportName, ASTNodeLet, ASTNodeModule, ASTNodeParam, ASTNodePattern,
+    ASTNodeRecordField, ASTNodeScoped, ASTNodeStmt, ASTNodeType, ASTNodeTypeField,
+    ASTNodeTypeParam, ASTNodeTypeVariant, ASTNodeViewAttribute, ASTNodeViewChild,
+    ASTNodeViewElement, ASTNodeWhenArm, ASTNodeWith, ASTPatternKind, ASTTypeBody, ASTTypeKind,
+};
+
+/// Visits the nodes of an AST by shared reference.
+///
+/// The `'ast` lifetime lets a visitor keep references into the tree it
+/// walks.
+pub trait Visitor<'ast> {
+    fn visit_module(&mut self, module: &'ast ASTNodeModule) {
+        walk_module(self, module);
+    }
+
+    fn visit_item(&mut self, item: &'ast ASTNodeScoped) {
+        walk_item(self, item);
+    }
+
+    fn visit_const(&mut self, item: &'ast ASTItemConst) {
+        walk_const(self, item);
+    }
+
+    fn visit_effect(&mut self, item: &'ast ASTItemEffect) {
+        walk_effect(self, item);
+    }
+
+    fn visit_effect_op(&mut self, op: &'ast ASTNodeEffectOp) {
+        walk_effect_op(self, op);
+    }
+
+    fn visit_fn(&mut self, item: &'ast ASTItemFn) {
+        walk_fn(self, item);
+    }
+
+    fn visit_import(&mut self, item: &'ast ASTItemImport) {
+        walk_import(self, item);
+    }
+
+    fn visit_import_name(&mut self, _name: &'ast ASTNodeImportName) {}
+
+    fn visit_type_item(&mut self, item: &'ast ASTItemType) {
+        walk_type_item(self, item);
+    }
+
+    fn visit_type_param(&mut self, _param: &'ast ASTNodeTypeParam) {}
+
+    fn visit_type_field(&mut self, field: &'ast ASTNodeTypeField) {
+        walk_type_field(self, field);
+    }
+
+    fn visit_type_variant(&mut self, variant: &'ast ASTNodeTypeVariant) {
+        walk_type_variant(self, variant);
+    }
+
+    fn visit_use(&mut self, _item: &'ast ASTItemUse) {}
+
+    fn visit_view(&mut self, item: &'ast ASTItemView) {
+        walk_view(self, item);
+    }
+
+    fn visit_param(&mut self, param: &'ast ASTNodeParam) {
+        walk_param(self, param);
+    }
+
+    fn visit_block(&mut self, block: &'ast ASTNodeBlock) {
+        walk_block(self, block);
+    }
+
+    fn visit_stmt(&mut self, stmt: &'ast ASTNodeStmt) {
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_let(&mut self, stmt: &'ast ASTNodeLet) {
+        walk_let(self, stmt);
+    }
+
+    fn visit_with(&mut self, stmt: &'ast ASTNodeWith) {
+        walk_with(self, stmt);
+    }
+
+    fn visit_handler_op(&mut self, op: &'ast ASTNodeHandlerOp) {
+        walk_handler_op(self, op);
+    }
+
+    fn visit_effect_block(&mut self, stmt: &'ast ASTNodeEffectBlock) {
+        walk_effect_block(self, stmt);
+    }
+
+    fn visit_assign(&mut self, stmt: &'ast ASTNodeAssign) {
+        walk_assign(self, stmt);
+    }
+
+    fn visit_element(&mut self, element: &'ast ASTNodeViewElement) {
+        walk_element(self, element);
+    }
+
+    fn visit_attribute(&mut self, attr: &'ast ASTNodeViewAttribute) {
+        walk_attribute(self, attr);
+    }
+
+    fn visit_view_child(&mut self, child: &'ast ASTNodeViewChild) {
+        walk_view_child(self, child);
+    }
+
+    fn visit_expr(&mut self, expr: &'ast ASTNodeExpr) {
+        walk_expr(self, expr);
+    }
+
+    fn visit_record_field(&mut self, field: &'ast ASTNodeRecordField) {
+        walk_record_field(self, field);
+    }
+
+    fn visit_when_arm(&mut self, arm: &'ast ASTNodeWhenArm) {
+        walk_when_arm(self, arm);
+    }
+
+    fn visit_pattern(&mut self, pattern: &'ast ASTNodePattern) {
+        walk_pattern(self, pattern);
+    }
+
+    fn visit_type(&mut self, ty: &'ast ASTNodeType) {
+        walk_type(self, ty);
+    }
+}
+
+pub fn walk_module<'ast, V: Visitor<'ast> + ?Sized>(v: &mut V, module: &'ast ASTNodeModule) {
+    for item in &module.nodes {
+        v.visit_item(item);
+    }
+}
+
+pub fn walk_item<'ast, V: Visitor<'ast> + ?Sized>(v: &mut V, item: &'ast ASTNodeScoped) {
+    match item {
+        ASTNodeScoped::Const(i) => v.visit_const(i),
+        ASTNodeScoped::Effect(i) => v.visit_effect(i),
+        ASTNodeScoped::Fn(i) => v.visit_fn(i),
+        ASTNodeScoped::Import(i) => v.visit_import(i),
+        ASTNodeScoped::Type(i) => v.visit_type_item(i),
+        ASTNodeScoped::Use(i) => v.visit_use(i),
+        ASTNodeScoped::View(i) => v.visit_view(i),
+    }
+}
+
+pub fn walk_const<'ast, V: Visitor<'ast> + ?Sized>(v: &mut V, item: &'ast ASTItemConst) {
+    if let Some(ty) = &item.ty {
+        v.visit_type(ty);
+    }
+    v.visit_expr(&item.value);
+}
+
+pub fn walk_effect<'ast, V: Visitor<'ast> + ?Sized>(v: &mut V, item: &'ast ASTItemEffect) {
+    for op in &item.ops {
+        v.visit_effect_op(op);
+    }
+}
+
+pub fn walk_effect_op<'ast, V: Visitor<'ast> + ?Sized>(v: &mut V, op: &'ast ASTNodeEffectOp) {
+    for param in &op.params {
+        v.visit_param(param);
+    }
+    if let Some(ret) = &op.ret {
+        v.visit_type(ret);
+    }
+}
+
+pub fn walk_fn<'ast, V: Visitor<'ast> + ?Sized>(v: &mut V, item: &'ast ASTItemFn) {
+    for param in &item.type_params {
+        v.visit_type_param(param);
+    }
+    for param in &item.params {
+        v.visit_param(param);
+    }
+    if let Some(ret) = &item.ret {
+        v.visit_type(ret);
+    }
+    for effect in &item.effects {
+        v.visit_type(effect);
+    }
+    v.visit_block(&item.body);
+}
+
+pub fn walk_import<'ast, V: Visitor<'ast> + ?Sized>(v: &mut V, item: &'ast ASTItemImport) {
+    for name in &item.names {
+        v.visit_import_name(name);
+    }
+}
+
+pub fn walk_type_item<'ast, V: Visitor<'ast> + ?Sized>(v: &mut V, item: &'ast ASTItemType) {
+    for param in &item.params {
+        v.visit_type_param(param);
+    }
+    match &item.body {
+        ASTTypeBody::Alias(ty) => v.visit_type(ty),
+        ASTTypeBody::Record(fields) => {
+            for field in fields {
+                v.visit_type_field(field);
+            }
+        }
+        ASTTypeBody::Union(variants) => {
+            for variant in variants {
+                v.visit_type_variant(variant);
+            }
+        }
+    }
+}
+
+pub fn walk_type_field<'ast, V: Visitor<'ast> + ?Sized>(v: &mut V, field: &'ast ASTNodeTypeField) {
+    v.visit_type(&field.ty);
+}
+
+pub fn walk_type_variant<'ast, V: Visitor<'ast> + ?Sized>(
+    v: &mut V,
+    variant: &'ast ASTNodeTypeVariant,
+) {
+    for field in &variant.fields {
+        v.visit_type(field);
+    }
+}
+
+pub fn walk_view<'ast, V: Visitor<'ast> + ?Sized>(v: &mut V, item: &'ast ASTItemView) {
+    for param in &item.type_params {
+        v.visit_type_param(param);
+    }
+    for param in &item.params {
+        v.visit_param(param);
+    }
+    v.visit_block(&item.body);
+}
+
+pub fn walk_param<'ast, V: Visitor<'ast> + ?Sized>(v: &mut V, param: &'ast ASTNodeParam) {
+    if let Some(ty) = &param.ty {
+        v.visit_type(ty);
+    }
+}
+
+pub fn walk_block<'ast, V: Visitor<'ast> + ?Sized>(v: &mut V, block: &'ast ASTNodeBlock) {
+    for stmt in &block.stmts {
+        v.visit_stmt(stmt);
+    }
+}
+
+pub fn walk_stmt<'ast, V: Visitor<'ast> + ?Sized>(v: &mut V, stmt: &'ast ASTNodeStmt) {
+    match stmt {
+        ASTNodeStmt::Let(s) => v.visit_let(s),
+        ASTNodeStmt::Const(s) => v.visit_const(s),
+        ASTNodeStmt::With(s) => v.visit_with(s),
+        ASTNodeStmt::Effect(s) => v.visit_effect_block(s),
+        ASTNodeStmt::Assign(s) => v.visit_assign(s),
+        ASTNodeStmt::Element(s) => v.visit_element(s),
+        ASTNodeStmt::Expr(s) => v.visit_expr(s),
+    }
+}
+
+pub fn walk_let<'ast, V: Visitor<'ast> + ?Sized>(v: &mut V, stmt: &'ast ASTNodeLet) {
+    if let Some(ty) = &stmt.ty {
+        v.visit_type(ty);
+    }
+    v.visit_expr(&stmt.value);
+}
+
+pub fn walk_with<'ast, V: Visitor<'ast> + ?Sized>(v: &mut V, stmt: &'ast ASTNodeWith) {
+    v.visit_type(&stmt.effect);
+    for op in &stmt.ops {
+        v.visit_handler_op(op);
+    }
+}
+
+pub fn walk_handler_op<'ast, V: Visitor<'ast> + ?Sized>(v: &mut V, op: &'ast ASTNodeHandlerOp) {
+    for param in &op.params {
+        v.visit_param(param);
+    }
+    v.visit_block(&op.body);
+}
+
+pub fn walk_effect_block<'ast, V: Visitor<'ast> + ?Sized>(
+    v: &mut V,
+    stmt: &'ast ASTNodeEffectBlock,
+) {
+    v.visit_block(&stmt.body);
+}
+
+pub fn walk_assign<'ast, V: Visitor<'ast> + ?Sized>(v: &mut V, stmt: &'ast ASTNodeAssign) {
+    v.visit_expr(&stmt.target);
+    v.visit_expr(&stmt.value);
+}
+
+pub fn walk_element<'ast, V: Visitor<'ast> + ?Sized>(v: &mut V, element: &'ast ASTNodeViewElement) {
+    for attr in &element.attributes {
+        v.visit_attribute(attr);
+    }
+    for child in &element.children {
+        v.visit_view_child(child);
+    }
+}
+
+pub fn walk_attribute<'ast, V: Visitor<'ast> + ?Sized>(
+    v: &mut V,
+    attr: &'ast ASTNodeViewAttribute,
+) {
+    if let Some(value) = &attr.value {
+        v.visit_expr(value);
+    }
+}
+
+pub fn walk_view_child<'ast, V: Visitor<'ast> + ?Sized>(v: &mut V, child: &'ast ASTNodeViewChild) {
+    match child {
+        ASTNodeViewChild::Element(element) => v.visit_element(element),
+        ASTNodeViewChild::Text(expr) | ASTNodeViewChild::Expr(expr) => v.visit_expr(expr),
+    }
+}
+
+pub fn walk_expr<'ast, V: Visitor<'ast> + ?Sized>(v: &mut V, expr: &'ast ASTNodeExpr) {
+    match &expr.kind {
+        ASTExprKind::Literal(_)
+        | ASTExprKind::Nil
+        | ASTExprKind::Identifier(_)
+        | ASTExprKind::Anchor(_) => {}
+        ASTExprKind::Call { callee, args } => {
+            v.visit_expr(callee);
+            for arg in args {
+                v.visit_expr(arg);
+            }
+        }
+        ASTExprKind::Member { object, .. } => v.visit_expr(object),
+        ASTExprKind::Binary { lhs, rhs, .. } => {
+            v.visit_expr(lhs);
+            v.visit_expr(rhs);
+        }
+        ASTExprKind::Unary { expr, .. } => v.visit_expr(expr),
+        ASTExprKind::If { cond, then, els } => {
+            v.visit_expr(cond);
+            v.visit_block(then);
+            if let Some(els) = els {
+                v.visit_expr(els);
+            }
+        }
+        ASTExprKind::Block(block) => v.visit_block(block),
+        ASTExprKind::For {
+            pattern,
+            iter,
+            body,
+        } => {
+            v.visit_pattern(pattern);
+            v.visit_expr(iter);
+            v.visit_block(body);
+        }
+        ASTExprKind::When { subject, arms } => {
+            v.visit_expr(subject);
+            for arm in arms {
+                v.visit_when_arm(arm);
+            }
+        }
+        ASTExprKind::Record(fields) => {
+            for field in fields {
+                v.visit_record_field(field);
+            }
+        }
+        ASTExprKind::List(items) | ASTExprKind::Tuple(items) => {
+            for item in items {
+                v.visit_expr(item);
+            }
+        }
+    }
+}
+
+pub fn walk_record_field<'ast, V: Visitor<'ast> + ?Sized>(
+    v: &mut V,
+    field: &'ast ASTNodeRecordField,
+) {
+    v.visit_expr(&field.value);
+}
+
+pub fn walk_when_arm<'ast, V: Visitor<'ast> + ?Sized>(v: &mut V, arm: &'ast ASTNodeWhenArm) {
+    v.visit_pattern(&arm.pattern);
+    v.visit_expr(&arm.body);
+}
+
+pub fn walk_pattern<'ast, V: Visitor<'ast> + ?Sized>(v: &mut V, pattern: &'ast ASTNodePattern) {
+    match &pattern.kind {
+        ASTPatternKind::Variant { fields: items, .. } | ASTPatternKind::Tuple(items) => {
+            for item in items {
+                v.visit_pattern(item);
+            }
+        }
+        ASTPatternKind::Wildcard
+        | ASTPatternKind::Binding(_)
+        | ASTPatternKind::Literal(_)
+        | ASTPatternKind::Nil => {}
+    }
+}
+
+pub fn walk_type<'ast, V: Visitor<'ast> + ?Sized>(v: &mut V, ty: &'ast ASTNodeType) {
+    match &ty.kind {
+        ASTTypeKind::Named { args, .. } => {
+            for arg in args {
+                v.visit_type(arg);
+            }
+        }
+        ASTTypeKind::Fn {
+            params,
+            ret,
+            effects,
+        } => {
+            for param in params {
+                v.visit_type(param);
+            }
+            v.visit_type(ret);
+            for effect in effects {
+                v.visit_type(effect);
+            }
+        }
+        ASTTypeKind::Optional(inner) | ASTTypeKind::List(inner) => v.visit_type(inner),
+        ASTTypeKind::Tuple(items) => {
+            for item in items {
+                v.visit_type(item);
+            }
+        }
+        ASTTypeKind::Record(fields) => {
+            for field in fields {
+                v.visit_type_field(field);
+            }
+        }
+    }
+}
+
+/// Visits the nodes of an AST by mutable reference, e.g. to rewrite them in
+/// place.
+///
+/// The methods mirror [`Visitor`]; the walk functions carry a `_mut` suffix.
+pub trait VisitorMut {
+    fn visit_module_mut(&mut self, module: &mut ASTNodeModule) {
+        walk_module_mut(self, module);
+    }
+
+    fn visit_item_mut(&mut self, item: &mut ASTNodeScoped) {
+        walk_item_mut(self, item);
+    }
+
+    fn visit_const_mut(&mut self, item: &mut ASTItemConst) {
+        walk_const_mut(self, item);
+    }
+
+    fn visit_effect_mut(&mut self, item: &mut ASTItemEffect) {
+        walk_effect_mut(self, item);
+    }
+
+    fn visit_effect_op_mut(&mut self, op: &mut ASTNodeEffectOp) {
+        walk_effect_op_mut(self, op);
+    }
+
+    fn visit_fn_mut(&mut self, item: &mut ASTItemFn) {
+        walk_fn_mut(self, item);
+    }
+
+    fn visit_import_mut(&mut self, item: &mut ASTItemImport) {
+        walk_import_mut(self, item);
+    }
+
+    fn visit_import_name_mut(&mut self, _name: &mut ASTNodeImportName) {}
+
+    fn visit_type_item_mut(&mut self, item: &mut ASTItemType) {
+        walk_type_item_mut(self, item);
+    }
+
+    fn visit_type_param_mut(&mut self, _param: &mut ASTNodeTypeParam) {}
+
+    fn visit_type_field_mut(&mut self, field: &mut ASTNodeTypeField) {
+        walk_type_field_mut(self, field);
+    }
+
+    fn visit_type_variant_mut(&mut self, variant: &mut ASTNodeTypeVariant) {
+        walk_type_variant_mut(self, variant);
+    }
+
+    fn visit_use_mut(&mut self, _item: &mut ASTItemUse) {}
+
+    fn visit_view_mut(&mut self, item: &mut ASTItemView) {
+        walk_view_mut(self, item);
+    }
+
+    fn visit_param_mut(&mut self, param: &mut ASTNodeParam) {
+        walk_param_mut(self, param);
+    }
+
+    fn visit_block_mut(&mut self, block: &mut ASTNodeBlock) {
+        walk_block_mut(self, block);
+    }
+
+    fn visit_stmt_mut(&mut self, stmt: &mut ASTNodeStmt) {
+        walk_stmt_mut(self, stmt);
+    }
+
+    fn visit_let_mut(&mut self, stmt: &mut ASTNodeLet) {
+        walk_let_mut(self, stmt);
+    }
+
+    fn visit_with_mut(&mut self, stmt: &mut ASTNodeWith) {
+        walk_with_mut(self, stmt);
+    }
+
+    fn visit_handler_op_mut(&mut self, op: &mut ASTNodeHandlerOp) {
+        walk_handler_op_mut(self, op);
+    }
+
+    fn visit_effect_block_mut(&mut self, stmt: &mut ASTNodeEffectBlock) {
+        walk_effect_block_mut(self, stmt);
+    }
+
+    fn visit_assign_mut(&mut self, stmt: &mut ASTNodeAssign) {
+        walk_assign_mut(self, stmt);
+    }
+
+    fn visit_element_mut(&mut self, element: &mut ASTNodeViewElement) {
+        walk_element_mut(self, element);
+    }
+
+    fn visit_attribute_mut(&mut self, attr: &mut ASTNodeViewAttribute) {
+        walk_attribute_mut(self, attr);
+    }
+
+    fn visit_view_child_mut(&mut self, child: &mut ASTNodeViewChild) {
+        walk_view_child_mut(self, child);
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut ASTNodeExpr) {
+        walk_expr_mut(self, expr);
+    }
+
+    fn visit_record_field_mut(&mut self, field: &mut ASTNodeRecordField) {
+        walk_record_field_mut(self, field);
+    }
+
+    fn visit_when_arm_mut(&mut self, arm: &mut ASTNodeWhenArm) {
+        walk_when_arm_mut(self, arm);
+    }
+
+    fn visit_pattern_mut(&mut self, pattern: &mut ASTNodePattern) {
+        walk_pattern_mut(self, pattern);
+    }
+
+    fn visit_type_mut(&mut self, ty: &mut ASTNodeType) {
+        walk_type_mut(self, ty);
+    }
+}
+
+pub fn walk_module_mut<V: VisitorMut + ?Sized>(v: &mut V, module: &mut ASTNodeModule) {
+    for item in &mut module.nodes {
+        v.visit_item_mut(item);
+    }
+}
+
+pub fn walk_item_mut<V: VisitorMut + ?Sized>(v: &mut V, item: &mut ASTNodeScoped) {
+    match item {
+        ASTNodeScoped::Const(i) => v.visit_const_mut(i),
+        ASTNodeScoped::Effect(i) => v.visit_effect_mut(i),
+        ASTNodeScoped::Fn(i) => v.visit_fn_mut(i),
+        ASTNodeScoped::Import(i) => v.visit_import_mut(i),
+        ASTNodeScoped::Type(i) => v.visit_type_item_mut(i),
+        ASTNodeScoped::Use(i) => v.visit_use_mut(i),
+        ASTNodeScoped::View(i) => v.visit_view_mut(i),
+    }
+}
+
+pub fn walk_const_mut<V: VisitorMut + ?Sized>(v: &mut V, item: &mut ASTItemConst) {
+    if let Some(ty) = &mut item.ty {
+        v.visit_type_mut(ty);
+    }
+    v.visit_expr_mut(&mut item.value);
+}
+
+pub fn walk_effect_mut<V: VisitorMut + ?Sized>(v: &mut V, item: &mut ASTItemEffect) {
+    for op in &mut item.ops {
+        v.visit_effect_op_mut(op);
+    }
+}
+
+pub fn walk_effect_op_mut<V: VisitorMut + ?Sized>(v: &mut V, op: &mut ASTNodeEffectOp) {
+    for param in &mut op.params {
+        v.visit_param_mut(param);
+    }
+    if let Some(ret) = &mut op.ret {
+        v.visit_type_mut(ret);
+    }
+}
+
+pub fn walk_fn_mut<V: VisitorMut + ?Sized>(v: &mut V, item: &mut ASTItemFn) {
+    for param in &mut item.type_params {
+        v.visit_type_param_mut(param);
+    }
+    for param in &mut item.params {
+        v.visit_param_mut(param);
+    }
+    if let Some(ret) = &mut item.ret {
+        v.visit_type_mut(ret);
+    }
+    for effect in &mut item.effects {
+        v.visit_type_mut(effect);
+    }
+    v.visit_block_mut(&mut item.body);
+}
+
+pub fn walk_import_mut<V: VisitorMut + ?Sized>(v: &mut V, item: &mut ASTItemImport) {
+    for name in &mut item.names {
+        v.visit_import_name_mut(name);
+    }
+}
+
+pub fn walk_type_item_mut<V: VisitorMut + ?Sized>(v: &mut V, item: &mut ASTItemType) {
+    for param in &mut item.params {
+        v.visit_type_param_mut(param);
+    }
+    match &mut item.body {
+        ASTTypeBody::Alias(ty) => v.visit_type_mut(ty),
+        ASTTypeBody::Record(fields) => {
+            for field in fields {
+                v.visit_type_field_mut(field);
+            }
+        }
+        ASTTypeBody::Union(variants) => {
+            for variant in variants {
+                v.visit_type_variant_mut(variant);
+            }
+        }
+    }
+}
+
+pub fn walk_type_field_mut<V: VisitorMut + ?Sized>(v: &mut V, field: &mut ASTNodeTypeField) {
+    v.visit_type_mut(&mut field.ty);
+}
+
+pub fn walk_type_variant_mut<V: VisitorMut + ?Sized>(v: &mut V, variant: &mut ASTNodeTypeVariant) {
+    for field in &mut variant.fields {
+        v.visit_type_mut(field);
+    }
+}
+
+pub fn walk_view_mut<V: VisitorMut + ?Sized>(v: &mut V, item: &mut ASTItemView) {
+    for param in &mut item.type_params {
+        v.visit_type_param_mut(param);
+    }
+    for param in &mut item.params {
+        v.visit_param_mut(param);
+    }
+    v.visit_block_mut(&mut item.body);
+}
+
+pub fn walk_param_mut<V: VisitorMut + ?Sized>(v: &mut V, param: &mut ASTNodeParam) {
+    if let Some(ty) = &mut param.ty {
+        v.visit_type_mut(ty);
+    }
+}
+
+pub fn walk_block_mut<V: VisitorMut + ?Sized>(v: &mut V, block: &mut ASTNodeBlock) {
+    for stmt in &mut block.stmts {
+        v.visit_stmt_mut(stmt);
+    }
+}
+
+pub fn walk_stmt_mut<V: VisitorMut + ?Sized>(v: &mut V, stmt: &mut ASTNodeStmt) {
+    match stmt {
+        ASTNodeStmt::Let(s) => v.visit_let_mut(s),
+        ASTNodeStmt::Const(s) => v.visit_const_mut(s),
+        ASTNodeStmt::With(s) => v.visit_with_mut(s),
+        ASTNodeStmt::Effect(s) => v.visit_effect_block_mut(s),
+        ASTNodeStmt::Assign(s) => v.visit_assign_mut(s),
+        ASTNodeStmt::Element(s) => v.visit_element_mut(s),
+        ASTNodeStmt::Expr(s) => v.visit_expr_mut(s),
+    }
+}
+
+pub fn walk_let_mut<V: VisitorMut + ?Sized>(v: &mut V, stmt: &mut ASTNodeLet) {
+    if let Some(ty) = &mut stmt.ty {
+        v.visit_type_mut(ty);
+    }
+    v.visit_expr_mut(&mut stmt.value);
+}
+
+pub fn walk_with_mut<V: VisitorMut + ?Sized>(v: &mut V, stmt: &mut ASTNodeWith) {
+    v.visit_type_mut(&mut stmt.effect);
+    for op in &mut stmt.ops {
+        v.visit_handler_op_mut(op);
+    }
+}
+
+pub fn walk_handler_op_mut<V: VisitorMut + ?Sized>(v: &mut V, op: &mut ASTNodeHandlerOp) {
+    for param in &mut op.params {
+        v.visit_param_mut(param);
+    }
+    v.visit_block_mut(&mut op.body);
+}
+
+pub fn walk_effect_block_mut<V: VisitorMut + ?Sized>(v: &mut V, stmt: &mut ASTNodeEffectBlock) {
+    v.visit_block_mut(&mut stmt.body);
+}
+
+pub fn walk_assign_mut<V: VisitorMut + ?Sized>(v: &mut V, stmt: &mut ASTNodeAssign) {
+    v.visit_expr_mut(&mut stmt.target);
+    v.visit_expr_mut(&mut stmt.value);
+}
+
+pub fn walk_element_mut<V: VisitorMut + ?Sized>(v: &mut V, element: &mut ASTNodeViewElement) {
+    for attr in &mut element.attributes {
+        v.visit_attribute_mut(attr);
+    }
+    for child in &mut element.children {
+        v.visit_view_child_mut(child);
+    }
+}
+
+pub fn walk_attribute_mut<V: VisitorMut + ?Sized>(v: &mut V, attr: &mut ASTNodeViewAttribute) {
+    if let Some(value) = &mut attr.value {
+        v.visit_expr_mut(value);
+    }
+}
+
+pub fn walk_view_child_mut<V: VisitorMut + ?Sized>(v: &mut V, child: &mut ASTNodeViewChild) {
+    match child {
+        ASTNodeViewChild::Element(element) => v.visit_element_mut(element),
+        ASTNodeViewChild::Text(expr) | ASTNodeViewChild::Expr(expr) => v.visit_expr_mut(expr),
+    }
+}
+
+pub fn walk_expr_mut<V: VisitorMut + ?Sized>(v: &mut V, expr: &mut ASTNodeExpr) {
+    match &mut expr.kind {
+        ASTExprKind::Literal(_)
+        | ASTExprKind::Nil
+        | ASTExprKind::Identifier(_)
+        | ASTExprKind::Anchor(_) => {}
+        ASTExprKind::Call { callee, args } => {
+            v.visit_expr_mut(callee);
+            for arg in args {
+                v.visit_expr_mut(arg);
+            }
+        }
+        ASTExprKind::Member { object, .. } => v.visit_expr_mut(object),
+        ASTExprKind::Binary { lhs, rhs, .. } => {
+            v.visit_expr_mut(lhs);
+            v.visit_expr_mut(rhs);
+        }
+        ASTExprKind::Unary { expr, .. } => v.visit_expr_mut(expr),
+        ASTExprKind::If { cond, then, els } => {
+            v.visit_expr_mut(cond);
+            v.visit_block_mut(then);
+            if let Some(els) = els {
+                v.visit_expr_mut(els);
+            }
+        }
+        ASTExprKind::Block(block) => v.visit_block_mut(block),
+        ASTExprKind::For {
+            pattern,
+            iter,
+            body,
+        } => {
+            v.visit_pattern_mut(pattern);
+            v.visit_expr_mut(iter);
+            v.visit_block_mut(body);
+        }
+        ASTExprKind::When { subject, arms } => {
+            v.visit_expr_mut(subject);
+            for arm in arms {
+                v.visit_when_arm_mut(arm);
+            }
+        }
+        ASTExprKind::Record(fields) => {
+            for field in fields {
+                v.visit_record_field_mut(field);
+            }
+        }
+        ASTExprKind::List(items) | ASTExprKind::Tuple(items) => {
+            for item in items {
+                v.visit_expr_mut(item);
+            }
+        }
+    }
+}
+
+pub fn walk_record_field_mut<V: VisitorMut + ?Sized>(v: &mut V, field: &mut ASTNodeRecordField) {
+    v.visit_expr_mut(&mut field.value);
+}
+
+pub fn walk_when_arm_mut<V: VisitorMut + ?Sized>(v: &mut V, arm: &mut ASTNodeWhenArm) {
+    v.visit_pattern_mut(&mut arm.pattern);
+    v.visit_expr_mut(&mut arm.body);
+}
+
+pub fn walk_pattern_mut<V: VisitorMut + ?Sized>(v: &mut V, pattern: &mut ASTNodePattern) {
+    match &mut pattern.kind {
+        ASTPatternKind::Variant { fields: items, .. } | ASTPatternKind::Tuple(items) => {
+            for item in items {
+                v.visit_pattern_mut(item);
+            }
+        }
+        ASTPatternKind::Wildcard
+        | ASTPatternKind::Binding(_)
+        | ASTPatternKind::Literal(_)
+        | ASTPatternKind::Nil => {}
+    }
+}
+
+pub fn walk_type_mut<V: VisitorMut + ?Sized>(v: &mut V, ty: &mut ASTNodeType) {
+    match &mut ty.kind {
+        ASTTypeKind::Named { args, .. } => {
+            for arg in args {
+                v.visit_type_mut(arg);
+            }
+        }
+        ASTTypeKind::Fn {
+            params,
+            ret,
+            effects,
+        } => {
+            for param in params {
+                v.visit_type_mut(param);
+            }
+            v.visit_type_mut(ret);
+            for effect in effects {
+                v.visit_type_mut(effect);
+            }
+        }
+        ASTTypeKind::Optional(inner) | ASTTypeKind::List(inner) => v.visit_type_mut(inner),
+        ASTTypeKind::Tuple(items) => {
+            for item in items {
+                v.visit_type_mut(item);
+            }
+        }
+        ASTTypeKind::Record(fields) => {
+            for field in fields {
+                v.visit_type_field_mut(field);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lang::parser::{module::ModuleParser, Parser};
+
+    const SOURCE: &str = "type Pair<T> = { first: T, second: [T?] }
+type Shape = Circle(Float) | Rect(Pair<Float>)
+
+effect Log {
+  fn log(msg: String)
+}
+
+fn area(shape: Shape) -> Float emits Log {
+  log(\"area\")
+  if done { 1 } else { size(shape) }
+}
+
+view Counter(start: Int) {
+  let count: Int = start
+  with Log {
+    fn log(msg: String) { print(msg) }
+  }
+  effect { log(count) }
+  count = count + step
+  <button onclick={increment} title=\"add\">{label}</button>
+}
+";
+
+    fn parse(input: &str) -> ASTNodeModule {
+        ModuleParser::from_str(input)
+            .parse_all()
+            .expect("failed to parse")
+    }
+
+    #[derive(Default)]
+    struct Collector<'ast> {
+        names: Vec<&'ast str>,
+        types: Vec<&'ast str>,
+    }
+
+    impl<'ast> Visitor<'ast> for Collector<'ast> {
+        fn visit_expr(&mut self, expr: &'ast ASTNodeExpr) {
+            if let ASTExprKind::Identifier(name) = &expr.kind {
+                self.names.push(name);
+            }
+            walk_expr(self, expr);
+        }
+
+        fn visit_type(&mut self, ty: &'ast ASTNodeType) {
+            if let ASTTypeKind::Named { name, .. } = &ty.kind {
+                self.types.push(name);
+            }
+            walk_type(self, ty);
+        }
+    }
+
+    #[test]
+    fn visits_all_nodes() {
+        let module = parse(SOURCE);
+        let mut collector = Collector::default();
+        collector.visit_module(&module);
+        assert_eq!(
+            collector.names,
+            [
+                "log",
+                "done",
+                "size",
+                "shape",
+                "start",
+                "print",
+                "msg",
+                "log",
+                "count",
+                "count",
+                "count",
+                "step",
+                "increment",
+                "label",
+            ]
+        );
+        assert_eq!(
+            collector.types,
+            [
+                "T", "T", "Float", "Pair", "Float", "String", "Shape", "Float", "Log", "Int",
+                "Int", "Log", "String",
+            ]
+        );
+    }
+
+    #[test]
+    fn overrides_stop_descent() {
+        struct TopLevel(usize);
+
+        impl<'ast> Visitor<'ast> for TopLevel {
+            fn visit_block(&mut self, _block: &'ast ASTNodeBlock) {
+                self.0 += 1;
+            }
+        }
+
+        let module = parse(SOURCE);
+        let mut visitor = TopLevel(0);
+        visitor.visit_module(&module);
+        // The bodies of `area` and `Counter`, but none of the nested blocks.
+        assert_eq!(visitor.0, 2);
+    }
+
+    #[test]
+    fn rewrites_in_place() {
+        struct Rename;
+
+        impl VisitorMut for Rename {
+            fn visit_expr_mut(&mut self, expr: &mut ASTNodeExpr) {
+                if let ASTExprKind::Identifier(name) = &mut expr.kind {
+                    if name == "count" {
+                        *name = "total".to_string();
+                    }
+                }
+                walk_expr_mut(self, expr);
+            }
+
+            fn visit_let_mut(&mut self, stmt: &mut ASTNodeLet) {
+                if stmt.name == "count" {
+                    stmt.name = "total".to_string();
+                }
+                walk_let_mut(self, stmt);
+            }
+        }
+
+        let mut module = parse(SOURCE);
+        Rename.visit_module_mut(&mut module);
+        assert_eq!(module, parse(&SOURCE.replace("count", "total")));
+    }
+}